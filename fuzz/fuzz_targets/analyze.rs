@@ -0,0 +1,11 @@
+//! 任意字符串 -> `QuanpinPreeditor::analyze`（含模糊音），不应 panic。
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rime_core::engine::Analyzer;
+use rime_pinyin::{FuzzyRules, QuanpinPreeditor};
+
+fuzz_target!(|input: &str| {
+    let _ = QuanpinPreeditor::new().analyze(input);
+    let _ = QuanpinPreeditor::new().with_fuzzy(FuzzyRules::default_set()).analyze(input);
+});
@@ -0,0 +1,41 @@
+//! 任意字节解码为事件序列驱动 `Session::handle`，不应 panic。
+#![no_main]
+
+use std::sync::{Arc, OnceLock};
+
+use libfuzzer_sys::fuzz_target;
+use rime_core::{engine::Engine, key_event::InputEvent, session::Session};
+use rime_dict::TsvDictionary;
+use rime_pinyin::QuanpinPreeditor;
+
+fn dict() -> Arc<TsvDictionary> {
+    static DICT: OnceLock<Arc<TsvDictionary>> = OnceLock::new();
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../rime_cli/asset/dict.tsv");
+    Arc::clone(DICT.get_or_init(|| Arc::new(TsvDictionary::from_path(path).expect("加载词典"))))
+}
+
+/// 每个字节对应一个事件：高 3 位选类型，低 5 位作参数。
+fn decode(b: u8) -> InputEvent {
+    let arg = b & 0x1F;
+    match b >> 5 {
+        0..=3 => InputEvent::Char(if arg < 26 { char::from(b'a' + arg) } else { '\'' }),
+        4 => InputEvent::Select(usize::from(arg)),
+        5 => InputEvent::Backspace,
+        6 => match arg % 6 {
+            0 => InputEvent::Space,
+            1 => InputEvent::Enter,
+            2 => InputEvent::NextPage,
+            3 => InputEvent::PrevPage,
+            4 => InputEvent::MoveLeft,
+            _ => InputEvent::MoveRight,
+        },
+        _ => InputEvent::Clear,
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut session = Session::new(Engine::new(dict(), QuanpinPreeditor::new()));
+    for &b in data {
+        session.handle(decode(b));
+    }
+});
@@ -0,0 +1,10 @@
+//! 任意字节 -> `TsvDictionary::from_tsv_str_lenient`，不应 panic。
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rime_dict::TsvDictionary;
+
+fuzz_target!(|data: &[u8]| {
+    let s = String::from_utf8_lossy(data);
+    let _ = TsvDictionary::from_tsv_str_lenient(&s);
+});
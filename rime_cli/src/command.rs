@@ -0,0 +1,291 @@
+//! REPL 冒号命令：`:cmd args...` 的解析与执行。
+//!
+//! 新命令只需：在 `Command` 加一个变体、在 `COMMANDS` 登记帮助、在 `parse`/`run` 各加一个分支。
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use rime_core::{
+    dictionary::{Dictionary, MutableDictionary},
+    engine::Analyzer,
+    history::UserHistory,
+    key_event::InputEvent,
+    learn::LearnedPhrases,
+};
+use rime_dict::Profile;
+use rime_pinyin::QuanpinPreeditor;
+
+use crate::CliSession;
+
+/// REPL 的可变状态（命令与选词循环共享）。
+#[derive(Debug, Default)]
+pub struct CliState {
+    /// 本次运行已上屏的文本
+    pub committed: Vec<String>,
+    /// 用户词典文件；`:add` 的词条会写入这里
+    pub user_dict: Option<PathBuf>,
+    /// `--profile`：`:add` 后保存用户层（此时不用 `user_dict`）
+    pub profile: Option<Profile>,
+    /// `--debug`：打印组合的诊断信息（候选的分数组成由引擎附带）
+    pub debug: bool,
+    /// `--profile`：用户词频，退出时写回用户目录（重新加载后的引擎也用这一份）
+    pub history: Option<UserHistory>,
+    /// 学到的词及其文件（见 `main::learned_path`），退出时写回（重新加载后的引擎也用这一份）
+    pub learned: Option<(PathBuf, LearnedPhrases)>,
+    /// `--watch`：文件变化后重新加载
+    #[cfg(feature = "watch")]
+    pub watch: Option<crate::watch::Watch>,
+}
+
+/// 命令执行后 REPL 的去向。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// 已解析的命令。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `:q` / `:quit` / `:exit`
+    Quit,
+    /// `:help`
+    Help,
+    /// `:history [n]`：最近 n 条上屏（默认全部）
+    History(Option<usize>),
+    /// `:add <词> <拼音> [weight]`：加词；省略 weight 时排到该拼音的首位
+    Add { text: String, key: String, weight: Option<i32> },
+    /// `:snapshot <拼音>`：打印该输入的单行 `UiState` 快照（便于贴进 bug 报告）
+    Snapshot(String),
+    /// `:trad`：切换繁体输出（需配置 `cli.convert_table`）
+    Traditional,
+    /// `:schema`：切换到下一个输入方案
+    Schema,
+    /// `:en`：切换拼音/英文模式
+    Mode,
+    /// `:pinyin <词>`：反查词典中该词的全部编码
+    Pinyin(String),
+}
+
+/// 命令解析错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// 未知命令
+    Unknown(String),
+    /// 参数错误
+    BadArgs { command: &'static str, message: String },
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "未知命令 `:{name}`，输入 :help 查看可用命令"),
+            Self::BadArgs { command, message } => write!(f, ":{command} {message}"),
+        }
+    }
+}
+
+/// 帮助表：(命令, 参数, 说明)。
+const COMMANDS: &[(&str, &str, &str)] = &[
+    ("q", "", "退出（同 :quit / :exit）"),
+    ("help", "", "显示本帮助"),
+    ("history", "[n]", "显示最近 n 条上屏（默认全部）"),
+    ("add", "<词> <拼音> [weight]", "加词（省略 weight 时排在首位）"),
+    ("snapshot", "<拼音>", "打印该输入的单行状态快照"),
+    ("pinyin", "<词>", "反查读音（多音时按权重降序；词典中没有该词时逐字反查）"),
+    ("trad", "", "切换繁体输出（需配置 cli.convert_table）"),
+    ("schema", "", "切换到下一个输入方案"),
+    ("en", "", "切换拼音/英文模式（英文模式下每行原样上屏）"),
+];
+
+/// 解析一行输入；不以 `:` 开头时返回 None（按拼音处理）。
+pub fn parse(line: &str) -> Option<Result<Command, CommandError>> {
+    let body = line.trim().strip_prefix(':')?;
+    let mut words = body.split_whitespace();
+    let name = words.next().unwrap_or("");
+    let args: Vec<&str> = words.collect();
+
+    let cmd = match name {
+        "q" | "quit" | "exit" => no_args("q", &args).map(|_| Command::Quit),
+        "help" | "h" | "?" => no_args("help", &args).map(|_| Command::Help),
+        "history" => match args.as_slice() {
+            [] => Ok(Command::History(None)),
+            [n] => n.parse::<usize>().map(|n| Command::History(Some(n))).map_err(|_| CommandError::BadArgs {
+                command: "history",
+                message: format!("参数须为非负整数，实际为 `{n}`"),
+            }),
+            _ => Err(CommandError::BadArgs {
+                command: "history",
+                message: "最多一个参数".to_string(),
+            }),
+        },
+        "add" => match args.as_slice() {
+            [text, key] => Ok(Command::Add {
+                text: text.to_string(),
+                key: key.to_ascii_lowercase(),
+                weight: None,
+            }),
+            [text, key, w] => w
+                .parse::<i32>()
+                .map(|w| Command::Add {
+                    text: text.to_string(),
+                    key: key.to_ascii_lowercase(),
+                    weight: Some(w),
+                })
+                .map_err(|_| CommandError::BadArgs {
+                    command: "add",
+                    message: format!("weight 须为整数，实际为 `{w}`"),
+                }),
+            _ => Err(CommandError::BadArgs {
+                command: "add",
+                message: "用法：:add <词> <拼音> [weight]，例如 :add 拼音词 pinyinci".to_string(),
+            }),
+        },
+        "snapshot" => match args.as_slice() {
+            [key] => Ok(Command::Snapshot(key.to_ascii_lowercase())),
+            _ => Err(CommandError::BadArgs {
+                command: "snapshot",
+                message: "需要一个拼音参数，例如 :snapshot nihao".to_string(),
+            }),
+        },
+        "pinyin" => match args.as_slice() {
+            [text] => Ok(Command::Pinyin(text.to_string())),
+            _ => Err(CommandError::BadArgs {
+                command: "pinyin",
+                message: "需要一个词，例如 :pinyin 你好".to_string(),
+            }),
+        },
+        "trad" => no_args("trad", &args).map(|_| Command::Traditional),
+        "schema" => no_args("schema", &args).map(|_| Command::Schema),
+        "en" => no_args("en", &args).map(|_| Command::Mode),
+        other => Err(CommandError::Unknown(other.to_string())),
+    };
+    Some(cmd)
+}
+
+fn no_args(command: &'static str, args: &[&str]) -> Result<(), CommandError> {
+    if args.is_empty() {
+        Ok(())
+    } else {
+        Err(CommandError::BadArgs {
+            command,
+            message: "不接受参数".to_string(),
+        })
+    }
+}
+
+impl Command {
+    /// 执行命令。
+    pub fn run(&self, session: &mut CliSession, state: &mut CliState, out: &mut impl Write) -> io::Result<Flow> {
+        match self {
+            Command::Quit => return Ok(Flow::Quit),
+            Command::Help => {
+                for (name, args, desc) in COMMANDS {
+                    let usage = if args.is_empty() { format!(":{name}") } else { format!(":{name} {args}") };
+                    writeln!(out, "  {usage:<24} {desc}")?;
+                }
+            }
+            Command::History(n) => {
+                let skip = n.map_or(0, |n| state.committed.len().saturating_sub(n));
+                if state.committed.is_empty() {
+                    writeln!(out, "(暂无上屏记录)")?;
+                }
+                for (i, text) in state.committed.iter().enumerate().skip(skip) {
+                    writeln!(out, "  {}. {text}", i + 1)?;
+                }
+            }
+            Command::Add { text, key, weight } => {
+                // 只接受能完整切成音节的拼音（不退化为简拼）
+                let segments = QuanpinPreeditor::new().initials_fallback(false).analyze(key).segment;
+                if segments.is_empty() {
+                    writeln!(out, ":add 拼音 `{key}` 无法切分为音节")?;
+                    return Ok(Flow::Continue);
+                }
+                let key = segments.concat();
+                // 默认比当前首选高 1，保证新词排在第一位
+                let weight = weight.unwrap_or_else(|| {
+                    let top = session.engine().compose(&key).candidate_list.iter().map(|c| c.weight).max();
+                    top.map_or(0, |w| w.saturating_add(1))
+                });
+                let Some(engine) = session.engine_mut() else {
+                    writeln!(out, ":add 引擎被其他会话共享，无法修改词典")?;
+                    return Ok(Flow::Continue);
+                };
+                let added = engine.dictionary_mut().insert(&key, text, weight);
+                let verb = if added { "已添加" } else { "已更新" };
+                writeln!(out, "{verb}：{text} {} (weight {weight})", segments.join(" "))?;
+                if let Some(profile) = &mut state.profile {
+                    let path = profile.paths().user_dict.display().to_string();
+                    match profile.save(session.engine().dictionary()) {
+                        Ok(_) => writeln!(out, "已写入 {path}")?,
+                        Err(e) => writeln!(out, "写入 {path} 失败：{e}")?,
+                    }
+                } else if let Some(path) = &state.user_dict {
+                    match save_user_entry(path, text, &key, weight) {
+                        Ok(()) => writeln!(out, "已写入 {}", path.display())?,
+                        Err(e) => writeln!(out, "写入 {} 失败：{e}", path.display())?,
+                    }
+                }
+            }
+            Command::Snapshot(key) => writeln!(out, "{}", session.engine().compose(key).to_snapshot_string())?,
+            Command::Pinyin(text) => {
+                let dictionary = session.engine().dictionary();
+                let keys = dictionary.reverse_lookup(text);
+                if !keys.is_empty() {
+                    writeln!(out, "{text}：{}", keys.join(", "))?;
+                } else if text.chars().count() == 1 {
+                    writeln!(out, ":pinyin 词典中没有 `{text}`")?;
+                } else {
+                    for ch in text.chars() {
+                        let keys = dictionary.reverse_lookup(ch.encode_utf8(&mut [0; 4]));
+                        writeln!(out, "  {ch}：{}", if keys.is_empty() { "-".to_string() } else { keys.join(", ") })?;
+                    }
+                }
+            }
+            Command::Traditional => {
+                session.handle(InputEvent::ToggleTraditional);
+                if !session.last_consumed() {
+                    writeln!(out, ":trad 未配置转换表（cli.convert_table）")?;
+                } else if session.is_traditional() {
+                    writeln!(out, "繁体输出：开")?;
+                } else {
+                    writeln!(out, "繁体输出：关")?;
+                }
+            }
+            Command::Schema => {
+                let (ui, _) = session.handle(InputEvent::NextSchema);
+                if session.last_consumed() {
+                    writeln!(out, "输入方案：{}", ui.schema)?;
+                } else {
+                    writeln!(out, ":schema 只有一个输入方案（{}）", ui.schema)?;
+                }
+            }
+            Command::Mode => {
+                let (ui, _) = session.handle(InputEvent::ToggleMode);
+                writeln!(out, "输入模式：{}", if ui.english { "英文" } else { "拼音" })?;
+            }
+        }
+        Ok(Flow::Continue)
+    }
+}
+
+/// 把 `(text, key)` 写入用户词典：已有同一词条时替换该行，否则追加。
+fn save_user_entry(path: &Path, text: &str, key: &str, weight: i32) -> io::Result<()> {
+    let old = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut lines: Vec<&str> = old
+        .lines()
+        .filter(|line| {
+            let mut it = line.split('\t').map(str::trim);
+            (it.next(), it.next()) != (Some(text), Some(key))
+        })
+        .collect();
+    let entry = format!("{text}\t{key}\t{weight}");
+    lines.push(&entry);
+    fs::write(path, lines.join("\n") + "\n")
+}
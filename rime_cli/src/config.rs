@@ -0,0 +1,411 @@
+//! 配置文件（TOML 子集）解析。
+//!
+//! 为保持 std-only，这里只实现本 CLI 用到的 TOML 子集：
+//! - `[table]` / `[[array_table]]`
+//! - `key = value`，value 支持字符串、整数、布尔
+//! - `#` 注释
+//!
+//! 示例见 `asset/config.toml`。
+use std::{
+    env,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use rime_core::{
+    config::{Annotation, DedupMode, EngineConfig, Orientation, PreeditStyle, SentenceSelect},
+    history::DEFAULT_HISTORY_BOOST,
+};
+use rime_pinyin::{DEFAULT_MAX_INPUT_LEN, FuzzyRules};
+
+use crate::{render::ColorMode, scheme::Scheme};
+
+/// 词典配置（`[[dictionary]]`）。
+#[derive(Debug, Clone)]
+pub struct DictionaryConfig {
+    /// 词典路径（相对路径以配置文件所在目录为基准）
+    pub path: PathBuf,
+    /// 优先级（越大越先加载）
+    pub priority: i32,
+}
+
+/// 切分器配置（`[analyzer]`）。
+#[derive(Debug, Clone)]
+pub struct AnalyzerConfig {
+    /// 输入方案
+    pub scheme: Scheme,
+    /// 无法切分时是否退化为简拼
+    pub initials_fallback: bool,
+    /// 全拼接受声调数字（`tone = "strip"`）：`1`-`5` 断开音节后忽略
+    pub tone_digits: bool,
+    /// 模糊音规则（空表示关闭）
+    pub fuzzy: FuzzyRules,
+    /// 超过此字节数的输入不切分
+    pub max_input_len: usize,
+    /// 码表方案单个编码的最大长度；None 为不限
+    pub max_code_length: Option<usize>,
+    /// 全拼整段组词时另外尝试的切分方案数（0 为只用最优切分）
+    pub alternatives: usize,
+    /// 全拼的音节表（`syllable<TAB>freq`）；None 为内置表
+    pub syllabary: Option<PathBuf>,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            scheme: Scheme::Quanpin,
+            initials_fallback: true,
+            tone_digits: false,
+            fuzzy: FuzzyRules::default(),
+            max_input_len: DEFAULT_MAX_INPUT_LEN,
+            max_code_length: None,
+            alternatives: 0,
+            syllabary: None,
+        }
+    }
+}
+
+/// CLI 偏好（`[cli]`）。
+#[derive(Debug, Clone)]
+pub struct CliPreference {
+    /// 选词键，第 n 个字符选择第 n 个候选
+    pub select_keys: String,
+    /// 每次展示的候选数量；引擎每页的候选数不超过它与选词键数（见 `load_config`）
+    pub page_size: u8,
+    /// 上屏结果以 JSON 行输出
+    pub json: bool,
+    /// 是否着色（auto/always/never）
+    pub color: ColorMode,
+    /// 用户词典（`:add` 写入；存在时以最高优先级加载）
+    pub user_dict: Option<PathBuf>,
+    /// 简繁转换表（OpenCC 文本格式）；设置后可用 `:trad` 切换繁体输出
+    pub convert_table: Option<PathBuf>,
+    /// 英文词表（`word<TAB>frequency`）；设置后大写字母开头或无法切分的输入给出英文单词补全
+    pub english_words: Option<PathBuf>,
+    /// 二元语言模型（`word_a<TAB>word_b<TAB>log_prob`）；设置后组句按相邻两词的搭配加分
+    pub bigram_model: Option<PathBuf>,
+    /// `--profile` 时用户词频每次选择的加分（0 为只记录不加分）
+    pub history_boost: i32,
+    /// 用户词频的半衰期（天）：这么久没选过的词按一半次数加分（0 为不衰减）
+    pub history_half_life: u32,
+}
+
+impl Default for CliPreference {
+    fn default() -> Self {
+        Self {
+            select_keys: "123456789".to_string(),
+            page_size: 9,
+            json: false,
+            color: ColorMode::Auto,
+            user_dict: None,
+            convert_table: None,
+            english_words: None,
+            bigram_model: None,
+            history_boost: DEFAULT_HISTORY_BOOST,
+            history_half_life: 30,
+        }
+    }
+}
+
+/// 完整 CLI 配置。
+#[derive(Debug, Clone, Default)]
+pub struct CliConfig {
+    pub engine: EngineConfig,
+    pub dictionaries: Vec<DictionaryConfig>,
+    pub analyzer: AnalyzerConfig,
+    pub cli: CliPreference,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "字符串",
+            Value::Int(_) => "整数",
+            Value::Bool(_) => "布尔",
+        }
+    }
+}
+
+impl CliConfig {
+    /// 从文件加载配置。
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let s = fs::read_to_string(path).map_err(|e| io::Error::new(e.kind(), format!("读取配置 {} 失败：{e}", path.display())))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        Self::from_toml_str(&s, base).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+
+    /// 从 TOML 文本加载配置；`base_dir` 用于解析词典的相对路径。
+    pub fn from_toml_str(s: &str, base_dir: &Path) -> io::Result<Self> {
+        let mut config = Self::default();
+        let mut table = String::new();
+
+        for (idx, raw) in s.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix("[[").and_then(|x| x.strip_suffix("]]")) {
+                table = name.trim().to_string();
+                if table != "dictionary" {
+                    return Err(invalid(line_no, &table, "未知的数组表"));
+                }
+                config.dictionaries.push(DictionaryConfig {
+                    path: PathBuf::new(),
+                    priority: 0,
+                });
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+                table = name.trim().to_string();
+                if !matches!(table.as_str(), "engine" | "analyzer" | "cli") {
+                    return Err(invalid(line_no, &table, "未知的配置段"));
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(invalid(line_no, line, "缺少 `=`"));
+            };
+            let key = key.trim();
+            let full_key = if table.is_empty() { key.to_string() } else { format!("{table}.{key}") };
+            let value = parse_value(value.trim()).map_err(|msg| invalid(line_no, &full_key, &msg))?;
+            config.apply(&table, key, value, base_dir).map_err(|msg| invalid(line_no, &full_key, &msg))?;
+        }
+
+        for d in &config.dictionaries {
+            if d.path.as_os_str().is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "配置项 `dictionary.path` 缺失"));
+            }
+        }
+        Ok(config)
+    }
+
+    fn apply(&mut self, table: &str, key: &str, value: Value, base_dir: &Path) -> Result<(), String> {
+        match (table, key) {
+            ("engine", "limit") => self.engine.candidate_limit = as_int(&value, 1, 9)? as u8,
+            ("engine", "max_word_length") => self.engine.max_word_length = as_int(&value, 1, 255)? as u8,
+            ("engine", "per_span_limit") => {
+                let n = as_int(&value, 1, i64::from(u16::MAX))? as usize;
+                (self.engine.beam_span_limit, self.engine.word_candidate_limit) = (n, n);
+            }
+            ("engine", "beam_span_limit") => self.engine.beam_span_limit = as_int(&value, 1, i64::from(u16::MAX))? as usize,
+            ("engine", "beam_width") => self.engine.beam_width = Some(as_int(&value, 1, i64::from(u16::MAX))? as usize),
+            ("engine", "word_candidate_limit") => self.engine.word_candidate_limit = as_int(&value, 1, i64::from(u16::MAX))? as usize,
+            ("engine", "final_limit") => self.engine.final_limit = Some(as_int(&value, 1, i64::from(u32::MAX))? as usize),
+            ("engine", "max_lookups") => self.engine.max_lookups = Some(as_int(&value, 1, i64::from(u32::MAX))? as usize),
+            ("engine", "prefix_completion") => self.engine.prefix_completion = as_bool(&value)?,
+            ("engine", "wildcard") => {
+                let mut chars = as_str(&value)?.chars();
+                self.engine.wildcard = match (chars.next(), chars.next()) {
+                    (None, _) => None,
+                    (Some(c), None) => Some(c),
+                    _ => return Err("通配符只能是单个字符（留空关闭）".to_string()),
+                };
+            }
+            ("engine", "auto_commit_length") => self.engine.auto_commit_length = Some(as_int(&value, 1, 16)? as usize),
+            ("engine", "group_by_source") => self.engine.group_by_source = as_bool(&value)?,
+            ("engine", "dedup_mode") => {
+                self.engine.dedup_mode = match as_str(&value)? {
+                    "text_and_span" => DedupMode::ByTextAndSpan,
+                    "text_only" => DedupMode::ByTextOnly,
+                    _ => return Err("取值只能是 text_and_span/text_only".to_string()),
+                }
+            }
+            ("engine", "sentence_select") => {
+                self.engine.sentence_select = match as_str(&value)? {
+                    "whole_sentence" => SentenceSelect::WholeSentence,
+                    "first_word" => SentenceSelect::FirstWord,
+                    _ => return Err("取值只能是 whole_sentence/first_word".to_string()),
+                }
+            }
+            ("engine", "variant_dedup") => self.engine.variant_dedup = as_bool(&value)?,
+            ("engine", "preedit_style") => self.engine.preedit_style = parse_preedit_style(as_str(&value)?).ok_or("取值只能是 pinyin/preview")?,
+            ("engine", "orientation") => {
+                self.engine.orientation = match as_str(&value)? {
+                    "vertical" => Orientation::Vertical,
+                    "horizontal" => Orientation::Horizontal,
+                    _ => return Err("取值只能是 vertical/horizontal".to_string()),
+                }
+            }
+            ("engine", "annotation") => {
+                self.engine.annotation = match as_str(&value)? {
+                    "off" => Annotation::Off,
+                    "ambiguous" => Annotation::Ambiguous,
+                    "all" => Annotation::All,
+                    _ => return Err("取值只能是 off/ambiguous/all".to_string()),
+                }
+            }
+            ("dictionary", "path") => {
+                let path = PathBuf::from(as_str(&value)?);
+                self.last_dictionary().path = if path.is_relative() { base_dir.join(path) } else { path };
+            }
+            ("dictionary", "priority") => self.last_dictionary().priority = as_int(&value, i64::from(i32::MIN), i64::from(i32::MAX))? as i32,
+            ("analyzer", "scheme") => {
+                self.analyzer.scheme = Scheme::parse(as_str(&value)?).ok_or_else(|| format!("未知方案，可选：{}", Scheme::NAMES.join("/")))?;
+            }
+            ("analyzer", "initials_fallback") => self.analyzer.initials_fallback = as_bool(&value)?,
+            ("analyzer", "tone") => {
+                self.analyzer.tone_digits = match as_str(&value)? {
+                    "off" => false,
+                    "strip" => true,
+                    _ => return Err("取值只能是 off/strip".to_string()),
+                }
+            }
+            ("analyzer", "max_input_len") => self.analyzer.max_input_len = as_int(&value, 1, i64::from(u32::MAX))? as usize,
+            ("analyzer", "max_code_length") => self.analyzer.max_code_length = Some(as_int(&value, 1, 16)? as usize),
+            ("analyzer", "alternatives") => self.analyzer.alternatives = as_int(&value, 0, 8)? as usize,
+            ("analyzer", "fuzzy") => self.analyzer.fuzzy = FuzzyRules::parse(as_str(&value)?).map_err(|e| e.to_string())?,
+            ("analyzer", "syllabary") => {
+                let path = PathBuf::from(as_str(&value)?);
+                self.analyzer.syllabary = Some(if path.is_relative() { base_dir.join(path) } else { path });
+            }
+            ("cli", "select_keys") => {
+                let keys = as_str(&value)?;
+                if keys.is_empty() || keys.chars().count() > 9 {
+                    return Err("选词键数量必须在 1-9 之间".to_string());
+                }
+                self.cli.select_keys = keys.to_string();
+                // serve 模式下作为候选标签告诉前端（`UiState::layout`）
+                self.engine.select_labels = keys.to_string();
+            }
+            ("cli", "page_size") => self.cli.page_size = as_int(&value, 1, 9)? as u8,
+            ("cli", "json") => self.cli.json = as_bool(&value)?,
+            ("cli", "history_boost") => self.cli.history_boost = as_int(&value, 0, i64::from(i32::MAX))? as i32,
+            ("cli", "history_half_life") => self.cli.history_half_life = as_int(&value, 0, 36500)? as u32,
+            ("cli", "color") => self.cli.color = ColorMode::parse(as_str(&value)?).ok_or("取值只能是 auto/always/never")?,
+            ("cli", "user_dict") => {
+                let path = PathBuf::from(as_str(&value)?);
+                self.cli.user_dict = Some(if path.is_relative() { base_dir.join(path) } else { path });
+            }
+            ("cli", "convert_table") => {
+                let path = PathBuf::from(as_str(&value)?);
+                self.cli.convert_table = Some(if path.is_relative() { base_dir.join(path) } else { path });
+            }
+            ("cli", "english_words") => {
+                let path = PathBuf::from(as_str(&value)?);
+                self.cli.english_words = Some(if path.is_relative() { base_dir.join(path) } else { path });
+            }
+            ("cli", "bigram_model") => {
+                let path = PathBuf::from(as_str(&value)?);
+                self.cli.bigram_model = Some(if path.is_relative() { base_dir.join(path) } else { path });
+            }
+            _ => return Err("未知的配置项".to_string()),
+        }
+        Ok(())
+    }
+
+    fn last_dictionary(&mut self) -> &mut DictionaryConfig {
+        self.dictionaries.last_mut().expect("[[dictionary]] 已创建")
+    }
+
+    /// 按优先级从高到低排列的词典路径。
+    pub fn dictionary_paths(&self) -> Vec<PathBuf> {
+        let mut dicts = self.dictionaries.clone();
+        dicts.sort_by_key(|d| std::cmp::Reverse(d.priority));
+        dicts.into_iter().map(|d| d.path).collect()
+    }
+}
+
+/// 默认配置位置：`$XDG_CONFIG_HOME/rime_rs/config.toml` 或 `~/.config/rime_rs/config.toml`（存在时才使用）。
+pub fn default_config_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    let path = dir.join("rime_rs").join("config.toml");
+    path.is_file().then_some(path)
+}
+
+/// 解析内嵌组合串样式（配置与 `--preedit-style` 共用）：`pinyin` / `preview`。
+pub fn parse_preedit_style(s: &str) -> Option<PreeditStyle> {
+    match s {
+        "pinyin" => Some(PreeditStyle::Pinyin),
+        "preview" => Some(PreeditStyle::Preview),
+        _ => None,
+    }
+}
+
+fn invalid(line_no: usize, key: &str, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("配置第 {line_no} 行 `{key}`：{msg}"))
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_str = false;
+    let mut escaped = false;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '"' if !escaped => in_str = !in_str,
+            '#' if !in_str => return &line[..i],
+            _ => {}
+        }
+        escaped = in_str && ch == '\\' && !escaped;
+    }
+    line
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    if s.is_empty() {
+        return Err("缺少值".to_string());
+    }
+    if let Some(inner) = s.strip_prefix('"') {
+        let inner = inner.strip_suffix('"').ok_or("字符串缺少结尾引号")?;
+        return unescape(inner).map(Value::Str);
+    }
+    match s {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+    s.replace('_', "").parse::<i64>().map(Value::Int).map_err(|_| format!("无法解析的值 `{s}`"))
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut it = s.chars();
+    while let Some(ch) = it.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match it.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            other => return Err(format!("不支持的转义 `\\{}`", other.map(String::from).unwrap_or_default())),
+        }
+    }
+    Ok(out)
+}
+
+fn as_int(v: &Value, min: i64, max: i64) -> Result<i64, String> {
+    match v {
+        Value::Int(n) if (min..=max).contains(n) => Ok(*n),
+        Value::Int(n) => Err(format!("取值 {n} 超出范围 {min}..={max}")),
+        other => Err(format!("期望整数，实际为{}", other.type_name())),
+    }
+}
+
+fn as_bool(v: &Value) -> Result<bool, String> {
+    match v {
+        Value::Bool(b) => Ok(*b),
+        other => Err(format!("期望布尔，实际为{}", other.type_name())),
+    }
+}
+
+fn as_str(v: &Value) -> Result<&str, String> {
+    match v {
+        Value::Str(s) => Ok(s),
+        other => Err(format!("期望字符串，实际为{}", other.type_name())),
+    }
+}
@@ -0,0 +1,165 @@
+//! `rime_cli import`：把 Rime 的 `*.dict.yaml` 转换为本项目的 TSV 词典。
+//!
+//! - YAML 头（`---` 到 `...`）只读取 `columns`，其余字段忽略
+//! - 正文每行 `text<TAB>code<TAB>weight`（列顺序以 `columns` 为准），code 为空格分隔的音节
+//! - key 按 `KeyNormalization` 规整（`import` 命令用 `KeyNormalization::pinyin()`：`ni3 hao3`、`lü` -> `nihao`、`lv`）；规整后仍含 a-z 以外字符的行跳过
+//! - weight：整数原样保留；百分比 `p%` 换算为 `round(p * 100)`（`100%` -> 10000）；缺省为 0
+//!
+//! 逐行流式处理，输入文件大小不受内存限制。
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use rime_dict::KeyNormalization;
+
+/// 最多逐条报告多少个被跳过的行（其余只计数）。
+const MAX_REPORTED_SKIPS: usize = 20;
+
+/// 转换统计。
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    /// 写出的词条数
+    pub written: usize,
+    /// 跳过的行：(行号, 原因)，最多保留 `MAX_REPORTED_SKIPS` 条
+    pub skipped: Vec<(usize, String)>,
+    /// 跳过的总行数
+    pub skipped_total: usize,
+}
+
+impl ImportReport {
+    pub(crate) fn skip(&mut self, line_no: usize, reason: impl Into<String>) {
+        self.skipped_total += 1;
+        if self.skipped.len() < MAX_REPORTED_SKIPS {
+            self.skipped.push((line_no, reason.into()));
+        }
+    }
+}
+
+/// 正文列位置。
+#[derive(Debug, Clone, Copy)]
+struct Columns {
+    text: usize,
+    code: usize,
+    weight: Option<usize>,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            text: 0,
+            code: 1,
+            weight: Some(2),
+        }
+    }
+}
+
+/// 转换文件：`output` 为 None 时写到 stdout。
+pub fn import_file(input: &Path, output: Option<&Path>, keys: KeyNormalization) -> io::Result<ImportReport> {
+    let reader = BufReader::new(File::open(input)?);
+    match output {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            let report = convert(reader, &mut writer, keys)?;
+            writer.flush()?;
+            Ok(report)
+        }
+        None => convert(reader, io::stdout().lock(), keys),
+    }
+}
+
+/// 从 `reader` 读取 dict.yaml，按 `keys` 规整编码，写出 TSV 到 `writer`。
+pub fn convert(reader: impl BufRead, mut writer: impl Write, keys: KeyNormalization) -> io::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut columns = Columns::default();
+    // 没有 YAML 头的文件直接视为正文
+    let mut in_header = false;
+    let mut header_seen = false;
+
+    writeln!(writer, "# text\tkey\tweight")?;
+    for (idx, line) in reader.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line?;
+        let trimmed = line.trim_end();
+
+        if !header_seen && trimmed == "---" {
+            in_header = true;
+            header_seen = true;
+            continue;
+        }
+        if in_header {
+            if trimmed == "..." {
+                in_header = false;
+            } else if let Some(list) = trimmed.trim_start().strip_prefix("columns:") {
+                columns = parse_columns(list).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("第 {line_no} 行：columns 必须包含 text 与 code")))?;
+            }
+            continue;
+        }
+        header_seen = true;
+
+        if trimmed.trim().is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = trimmed.split('\t').collect();
+        let text = fields.get(columns.text).map(|s| s.trim()).unwrap_or("");
+        let code = fields.get(columns.code).map(|s| s.trim()).unwrap_or("");
+        if text.is_empty() {
+            report.skip(line_no, "缺少文本");
+            continue;
+        }
+        if code.is_empty() {
+            report.skip(line_no, "缺少编码");
+            continue;
+        }
+        let Some(key) = normalize_code(code, keys) else {
+            report.skip(line_no, format!("编码 `{code}` 含 a-z 以外的字符"));
+            continue;
+        };
+        let weight = match columns.weight.and_then(|i| fields.get(i)).map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            None => 0,
+            Some(w) => match parse_weight(w) {
+                Some(w) => w,
+                None => {
+                    report.skip(line_no, format!("无法解析的权重 `{w}`"));
+                    continue;
+                }
+            },
+        };
+        writeln!(writer, "{text}\t{key}\t{weight}")?;
+        report.written += 1;
+    }
+    Ok(report)
+}
+
+/// `[text, code, weight]` -> 列位置。
+fn parse_columns(list: &str) -> Option<Columns> {
+    let names: Vec<&str> = list
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|s| s.trim().trim_matches(|c| c == '"' || c == '\''))
+        .collect();
+    let find = |name: &str| names.iter().position(|n| *n == name);
+    Some(Columns {
+        text: find("text")?,
+        code: find("code")?,
+        weight: find("weight"),
+    })
+}
+
+/// 按 `keys` 规整；规整后只接受非空的 a-z 串，否则返回 None。
+pub(crate) fn normalize_code(code: &str, keys: KeyNormalization) -> Option<String> {
+    let key = keys.normalize(code);
+    (!key.is_empty() && key.bytes().all(|b| b.is_ascii_lowercase())).then(|| key.into_owned())
+}
+
+/// 整数或百分比；超出 i32 的值截断到 i32 范围。
+fn parse_weight(w: &str) -> Option<i32> {
+    let value = match w.strip_suffix('%') {
+        Some(p) => p.trim().parse::<f64>().ok().filter(|p| p.is_finite())? * 100.0,
+        None => w.parse::<f64>().ok().filter(|p| p.is_finite())?,
+    };
+    Some(value.round().clamp(f64::from(i32::MIN), f64::from(i32::MAX)) as i32)
+}
@@ -1,45 +1,446 @@
+mod command;
+mod config;
+mod import;
+mod render;
+mod scheme;
+mod userdb;
+#[cfg(feature = "watch")]
+mod watch;
+
 use std::{
     env,
-    io::{self, Write},
+    fs::File,
+    io::{self, BufRead, IsTerminal, Write},
+    iter,
     path::PathBuf,
+    sync::Arc,
 };
 
+use command::{CliState, Flow};
+use config::{CliConfig, CliPreference};
 use rime_core::{
-    engine::Engine,
-    key_event::{Action, InputEvent},
+    bigram::BigramModel,
+    blacklist::Blacklist,
+    config::PreeditStyle,
+    engine::{Analyzer, Engine},
+    explain::Explanation,
+    history::UserHistory,
+    host::{Host, drive},
+    learn::{DEFAULT_MAX_SYLLABLES, LearnedPhrases},
+    key_event::{Action, InputEvent, RejectReason},
+    metrics::SessionMetrics,
+    model::UiState,
+    processor::{EngineFacade, InputPolicy},
     session::Session,
+    session_manager::SessionManager,
 };
-use rime_dict::TsvDictionary;
-use rime_pinyin::QuanpinPreeditor;
+use rime_dict::{BLACKLIST_FILE, BigramModelFile, BlacklistFile, ConversionTable, DictionaryStack, EnglishTranslator, HISTORY_FILE, KeyNormalization, LEARNED_FILE, LearnedPhrasesFile, MergePolicy, Profile, TsvDictionary, UserHistoryFile};
+use rime_pinyin::FuzzyRules;
+use rime_server::protocol;
+use render::{ColorMode, Renderer};
+use scheme::Scheme;
+
+/// CLI 使用的引擎类型：analyzer 在运行时按方案选择。
+type CliEngine = Engine<DictionaryStack, Box<dyn Analyzer>>;
+
+/// CLI 使用的会话类型。
+type CliSession = Session<DictionaryStack, Box<dyn Analyzer>>;
+
+/// 子命令。
+#[derive(Debug, Clone, Default)]
+enum Mode {
+    /// 交互式 REPL（默认）
+    #[default]
+    Repl,
+    /// `serve`：JSON-RPC 服务模式
+    Serve,
+    /// `import <dict.yaml> [-o <out.tsv>]`：转换 Rime 词典
+    Import { input: PathBuf, output: Option<PathBuf> },
+    /// `compile -o <out.bin>`：把加载的词典写成二进制编译词典
+    Compile { output: Option<PathBuf> },
+    /// `merge <a.tsv> <b.tsv> [--policy ..] [-o <out.tsv>]`：合并两份词典并混合权重
+    Merge { inputs: [PathBuf; 2], policy: MergePolicy, output: Option<PathBuf> },
+    /// `import-userdb <x.userdb.txt> [-o <history.tsv>]`：把 librime 用户词典备份导入用户词频
+    ImportUserdb { input: PathBuf, output: Option<PathBuf> },
+    /// `export-userdb <history.tsv> [--name <db_name>] [-o <x.userdb.txt>]`：把用户词频写成 librime 用户词典备份
+    ExportUserdb { input: PathBuf, name: Option<String>, output: Option<PathBuf> },
+}
+
+/// 命令行参数；优先级高于配置文件。
+#[derive(Debug, Clone, Default)]
+struct Args {
+    mode: Mode,
+    config: Option<PathBuf>,
+    dict: Option<PathBuf>,
+    user_dict: Option<PathBuf>,
+    profile: Option<PathBuf>,
+    limit: Option<u8>,
+    json: bool,
+    stats: bool,
+    no_prompt: bool,
+    trace: bool,
+    /// `--debug`：候选附带分数组成
+    debug: bool,
+    color: Option<ColorMode>,
+    scheme: Option<Scheme>,
+    fuzzy: Option<FuzzyRules>,
+    preedit_style: Option<PreeditStyle>,
+    /// `--punct`：标点映射表（见 `rime_core::punct`）
+    punct: Option<PathBuf>,
+    /// `--watch`：配置或词典文件变化后重新加载
+    #[cfg(feature = "watch")]
+    watch: bool,
+}
 
 fn main() -> io::Result<()> {
-    let dict_path = parse_args().unwrap_or_else(default_dict_path);
-    let dict = TsvDictionary::from_path(&dict_path)?;
-    let preeditor = QuanpinPreeditor::new();
-    let engine = Engine::new(dict, preeditor).candidate_limit(9);
+    let args = parse_args()?;
+    if args.trace {
+        tracing_subscriber::fmt()
+            .with_writer(io::stderr)
+            .with_max_level(tracing_subscriber::filter::LevelFilter::DEBUG)
+            .init();
+    }
+    if let Mode::Import { input, output } = &args.mode {
+        let report = import::import_file(input, output.as_deref(), KeyNormalization::pinyin())?;
+        for (line_no, reason) in &report.skipped {
+            eprintln!("跳过第 {line_no} 行：{reason}");
+        }
+        eprintln!("写入 {} 条，跳过 {} 行", report.written, report.skipped_total);
+        return Ok(());
+    }
+    if let Mode::ImportUserdb { input, output } | Mode::ExportUserdb { input, output, .. } = &args.mode {
+        let report = match &args.mode {
+            Mode::ExportUserdb { name, .. } => userdb::export_file(input, output.as_deref(), name.as_deref().unwrap_or(userdb::DEFAULT_DB_NAME))?,
+            _ => userdb::import_file(input, output.as_deref())?,
+        };
+        for (line_no, reason) in &report.skipped {
+            eprintln!("跳过第 {line_no} 行：{reason}");
+        }
+        eprintln!("写入 {} 条，跳过 {} 行", report.written, report.skipped_total);
+        return Ok(());
+    }
+    if let Mode::Merge { inputs: [a, b], policy, output } = &args.mode {
+        let merged = TsvDictionary::from_path(a)?.merge(&TsvDictionary::from_path(b)?, *policy);
+        match output {
+            Some(path) => {
+                let mut w = io::BufWriter::new(File::create(path)?);
+                merged.write_tsv(&mut w)?;
+                w.flush()?;
+                eprintln!("已写入 {}", path.display());
+            }
+            None => merged.write_tsv(io::BufWriter::new(io::stdout().lock()))?,
+        }
+        return Ok(());
+    }
+    let config = load_config(&args)?;
+    if let Mode::Compile { output } = &args.mode {
+        if args.profile.is_some() {
+            return Err(invalid_arg("compile 不支持 --profile".to_string()));
+        }
+        let output = output.as_ref().ok_or_else(|| invalid_arg("compile 需要 -o <out.bin>".to_string()))?;
+        let analyzer = config.analyzer.scheme.build(&config.analyzer)?;
+        let dict = TsvDictionary::from_paths_with(&dictionary_paths(&args, &config), &*analyzer)?;
+        dict.compile_to(output)?;
+        eprintln!("已写入 {}", output.display());
+        return Ok(());
+    }
 
-    let mut committed: Vec<String> = Vec::new();
-    let mut session = Session::new(engine);
-    repl(&mut session, &dict_path, &mut committed)
+    let history = args.profile.as_ref().map(|dir| UserHistory::load_from(dir.join(HISTORY_FILE))).transpose()?;
+    let learned_file = learned_path(&args, &config);
+    let learned = learned_file.as_ref().map(LearnedPhrases::load_from).transpose()?.unwrap_or_default();
+    let (engine, profile, dict_paths) = load_engine(&args, &config, history.as_ref(), &learned)?;
+    let mut state = match profile {
+        Some(profile) => CliState { profile: Some(profile), ..CliState::default() },
+        None => CliState { user_dict: config.cli.user_dict.clone(), ..CliState::default() },
+    };
+    state.debug = args.debug;
+    state.history = history;
+    state.learned = learned_file.map(|path| (path, learned.clone()));
+    #[cfg(feature = "watch")]
+    if args.watch {
+        state.watch = Some(watch::Watch::start(args.clone(), &config, &dict_paths, state.history.clone(), learned));
+    }
+    if matches!(args.mode, Mode::Serve) {
+        let mut manager = SessionManager::new(engine);
+        #[cfg(feature = "watch")]
+        if let Some(watch) = &mut state.watch {
+            let served = protocol::serve_with(&mut manager, io::stdin().lock(), io::stdout().lock(), |m| watch.reload_manager(m)).map(|_| ());
+            return served.and(save_user_data(&args, &state));
+        }
+        let served = protocol::serve(&mut manager, io::stdin().lock(), io::stdout().lock()).map(|_| ());
+        return served.and(save_user_data(&args, &state));
+    }
+
+    // 临时英文以大写字母开头，须保留输入的大小写
+    let policy = InputPolicy { keep_case: config.cli.english_words.is_some(), ..config.analyzer.scheme.input_policy(&config.analyzer) };
+    let mut session = Session::new(engine).with_input_policy(policy).with_phrase_learning(DEFAULT_MAX_SYLLABLES);
+    if let Some(path) = &args.punct {
+        session = session.with_punctuation(rime_dict::punct::from_path(path)?);
+    }
+    if let Some(dir) = &args.profile {
+        session = session.with_blacklist(Blacklist::load_from(dir.join(BLACKLIST_FILE))?);
+    }
+    if args.stats {
+        session = session.with_metrics();
+    }
+    // stdin 不是终端（管道/重定向）时不打印提示，逐行直接上屏首选
+    let interactive = !args.no_prompt && io::stdin().is_terminal();
+    if interactive {
+        repl(&mut session, &dict_paths, &config.cli, &mut state)?;
+    } else {
+        convert_lines(&mut session, io::stdin().lock(), &mut io::stdout().lock(), &config.cli, &mut state)?;
+    }
+    if let Some(metrics) = session.metrics() {
+        print_stats(&metrics);
+    }
+    if let Some(dir) = &args.profile {
+        session.blacklist().save_to(dir.join(BLACKLIST_FILE))?;
+    }
+    save_user_data(&args, &state)
 }
 
-fn parse_args() -> Option<PathBuf> {
-    let mut args = env::args().skip(1);
+/// 退出时保存：`--profile` 的用户词频写回用户目录，学到的词写回 `learned_path`。
+fn save_user_data(args: &Args, state: &CliState) -> io::Result<()> {
+    if let (Some(dir), Some(history)) = (&args.profile, &state.history) {
+        history.save_to(dir.join(HISTORY_FILE))?;
+    }
+    match &state.learned {
+        // 没学到词时不新建文件
+        Some((path, learned)) if !learned.is_empty() || path.exists() => learned.save_to(path),
+        _ => Ok(()),
+    }
+}
+
+/// 学到的词（见 `rime_core::learn`）存放的文件：`--profile` 时在用户目录下，否则在 `cli.user_dict` 旁边；都没有时不保存。
+fn learned_path(args: &Args, config: &CliConfig) -> Option<PathBuf> {
+    match &args.profile {
+        Some(dir) => Some(dir.join(LEARNED_FILE)),
+        None => config.cli.user_dict.as_ref().map(|path| path.with_file_name(LEARNED_FILE)),
+    }
+}
+
+/// 读取配置文件（`--config` 或默认路径），再以命令行参数覆盖。
+fn load_config(args: &Args) -> io::Result<CliConfig> {
+    let mut config = match args.config.clone().or_else(config::default_config_path) {
+        Some(path) => CliConfig::from_path(path)?,
+        None => CliConfig::default(),
+    };
+    if let Some(limit) = args.limit {
+        config.engine.candidate_limit = limit;
+    }
+    if args.json {
+        config.cli.json = true;
+    }
+    if args.debug {
+        config.engine.explain = true;
+    }
+    if let Some(scheme) = args.scheme {
+        config.analyzer.scheme = scheme;
+    }
+    if let Some(fuzzy) = &args.fuzzy {
+        config.analyzer.fuzzy = fuzzy.clone();
+    }
+    if let Some(color) = args.color {
+        config.cli.color = color;
+    }
+    if let Some(style) = args.preedit_style {
+        config.engine.preedit_style = style;
+    }
+    if let Some(path) = &args.user_dict {
+        config.cli.user_dict = Some(path.clone());
+    }
+    // 每页只展示 page_size 个、选词键也只有这么多：引擎按展示的个数分页，翻页才不会跳过没展示的候选
+    let keys = u8::try_from(config.cli.select_keys.chars().count()).unwrap_or(u8::MAX);
+    config.engine.candidate_limit = config.engine.candidate_limit.min(config.cli.page_size).min(keys);
+    Ok(config)
+}
+
+/// 要加载的词典文件：`--dict`，或配置中的词典，都没有时用自带词典。
+///
+/// 不用 `--profile` 时，已存在的用户词典排在最前（优先级最高）；尚不存在时等第一次 :add 再创建。
+fn dictionary_paths(args: &Args, config: &CliConfig) -> Vec<PathBuf> {
+    let mut paths = match &args.dict {
+        Some(p) => vec![p.clone()],
+        None if !config.dictionaries.is_empty() => config.dictionary_paths(),
+        None => vec![default_dict_path()],
+    };
+    if args.profile.is_none()
+        && let Some(path) = config.cli.user_dict.as_ref().filter(|p| p.is_file())
+    {
+        paths.insert(0, path.clone());
+    }
+    paths
+}
+
+/// 按配置构造引擎（有 `history` 时开启用户词频，自动造词写入 `learned`）；返回引擎、`--profile` 打开的用户配置，以及实际加载的词典文件。
+fn load_engine(args: &Args, config: &CliConfig, history: Option<&UserHistory>, learned: &LearnedPhrases) -> io::Result<(CliEngine, Option<Profile>, Vec<PathBuf>)> {
+    let mut dict_paths = dictionary_paths(args, config);
+    // 词典与引擎共用同一个切分器
+    let analyzer = config.analyzer.scheme.build(&config.analyzer)?;
+    // --profile：用户词典在配置目录下，:add 后整体保存（不再使用 cli.user_dict）
+    let (dict, profile) = match &args.profile {
+        Some(dir) => {
+            let (profile, dict) = Profile::open_with(&dict_paths, dir, &*analyzer)?;
+            dict_paths.insert(0, profile.paths().user_dict.clone());
+            (dict, Some(profile))
+        }
+        None => (DictionaryStack::new(TsvDictionary::from_paths_with(&dict_paths, &*analyzer)?), None),
+    };
+    let mut engine = Engine::with_config(dict, analyzer, &config.engine).phrase_store(learned.clone());
+    if let Some(path) = &config.cli.convert_table {
+        engine = engine.converter(Arc::new(ConversionTable::from_path(path)?));
+    }
+    if let Some(path) = &config.cli.english_words {
+        engine = engine.english_translator(Arc::new(EnglishTranslator::from_path(path)?));
+    }
+    if let Some(path) = &config.cli.bigram_model {
+        engine = engine.bigram_model(Arc::new(BigramModel::from_path(path)?));
+    }
+    if let Some(history) = history {
+        let half_life = Some(u64::from(config.cli.history_half_life) * 24 * 60 * 60).filter(|&secs| secs > 0);
+        engine = engine.user_history(history.clone().with_half_life(half_life), config.cli.history_boost);
+    }
+    Ok((engine, profile, dict_paths))
+}
+
+/// 退出时的统计摘要（stderr，不影响管道输出）。
+fn print_stats(metrics: &SessionMetrics) {
+    eprintln!("---- stats ----");
+    eprintln!("上屏次数：{}（{} 字）", metrics.commits, metrics.committed_chars);
+    eprintln!("按键数：{}，每字按键数：{:.2}", metrics.keystrokes, metrics.keystrokes_per_char());
+    eprintln!("平均候选数：{:.1}", metrics.average_candidates());
+    eprintln!("选首选：{}，选其他：{}", metrics.first_choice(), metrics.other_choice());
+    let ranks: Vec<_> = metrics.ranks.iter().enumerate().filter(|(_, n)| **n > 0).map(|(i, n)| format!("{}:{n}", i + 1)).collect();
+    eprintln!("选词排名：{}", if ranks.is_empty() { "-".to_string() } else { ranks.join(" ") });
+    eprintln!("组合：上屏 {}，放弃 {}，平均长度 {:.1}", metrics.compositions_committed, metrics.compositions_abandoned, metrics.average_composition_len());
+    eprintln!("用时：{:.1}s", metrics.elapsed().as_secs_f64());
+}
+
+fn parse_args() -> io::Result<Args> {
+    let mut out = Args::default();
+    let mut args = env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("serve") => {
+            args.next();
+            out.mode = Mode::Serve;
+        }
+        Some("import") => {
+            args.next();
+            let input = args.next_if(|a| !a.starts_with('-')).ok_or_else(|| invalid_arg("import 缺少输入文件".to_string()))?;
+            out.mode = Mode::Import {
+                input: PathBuf::from(input),
+                output: None,
+            };
+        }
+        Some(command @ ("import-userdb" | "export-userdb")) => {
+            let export = command == "export-userdb";
+            args.next();
+            let input = PathBuf::from(args.next_if(|a| !a.starts_with('-')).ok_or_else(|| invalid_arg(format!("{} 缺少输入文件", if export { "export-userdb" } else { "import-userdb" })))?);
+            out.mode = if export { Mode::ExportUserdb { input, name: None, output: None } } else { Mode::ImportUserdb { input, output: None } };
+        }
+        Some("compile") => {
+            args.next();
+            out.mode = Mode::Compile { output: None };
+        }
+        Some("merge") => {
+            args.next();
+            let mut input = || args.next_if(|a| !a.starts_with('-')).map(PathBuf::from).ok_or_else(|| invalid_arg("merge 需要两个输入文件".to_string()));
+            out.mode = Mode::Merge {
+                inputs: [input()?, input()?],
+                policy: MergePolicy::Max,
+                output: None,
+            };
+        }
+        _ => {}
+    }
     while let Some(a) = args.next() {
-        if a == "--dict" {
-            if let Some(p) = args.next() {
-                return Some(PathBuf::from(p));
+        match a.as_str() {
+            "-o" | "--output" if matches!(out.mode, Mode::Import { .. } | Mode::Compile { .. } | Mode::Merge { .. } | Mode::ImportUserdb { .. } | Mode::ExportUserdb { .. }) => {
+                let v = PathBuf::from(expect_value(&a, args.next())?);
+                if let Mode::Import { output, .. } | Mode::Compile { output } | Mode::Merge { output, .. } | Mode::ImportUserdb { output, .. } | Mode::ExportUserdb { output, .. } = &mut out.mode {
+                    *output = Some(v);
+                }
             }
+            "--name" if matches!(out.mode, Mode::ExportUserdb { .. }) => {
+                let v = expect_value(&a, args.next())?;
+                if let Mode::ExportUserdb { name, .. } = &mut out.mode {
+                    *name = Some(v);
+                }
+            }
+            "--policy" if matches!(out.mode, Mode::Merge { .. }) => {
+                let v = expect_value(&a, args.next())?;
+                let parsed = parse_merge_policy(&v).ok_or_else(|| invalid_arg(format!("--policy 只能是 max/sum/weighted:<a>,<b>，实际为 `{v}`")))?;
+                if let Mode::Merge { policy, .. } = &mut out.mode {
+                    *policy = parsed;
+                }
+            }
+            "--config" => out.config = Some(PathBuf::from(expect_value(&a, args.next())?)),
+            "--dict" => out.dict = Some(PathBuf::from(expect_value(&a, args.next())?)),
+            "--user-dict" => out.user_dict = Some(PathBuf::from(expect_value(&a, args.next())?)),
+            "--profile" => out.profile = Some(PathBuf::from(expect_value(&a, args.next())?)),
+            "--punct" => out.punct = Some(PathBuf::from(expect_value(&a, args.next())?)),
+            "--limit" => {
+                let v = expect_value(&a, args.next())?;
+                let n = v.parse::<u8>().ok().filter(|n| (1..=9).contains(n));
+                out.limit = Some(n.ok_or_else(|| invalid_arg(format!("--limit 需要 1-9 的整数，实际为 `{v}`")))?);
+            }
+            "--json" => out.json = true,
+            "--stats" => out.stats = true,
+            "--no-prompt" => out.no_prompt = true,
+            "--trace" => out.trace = true,
+            "--debug" => out.debug = true,
+            #[cfg(feature = "watch")]
+            "--watch" => out.watch = true,
+            "--fuzzy" => {
+                // 不带参数（或紧跟其他选项）时使用默认规则集
+                let rules = match args.next_if(|v| !v.starts_with("--")) {
+                    Some(v) => FuzzyRules::parse(&v).map_err(|e| invalid_arg(e.to_string()))?,
+                    None => FuzzyRules::default_set(),
+                };
+                out.fuzzy = Some(rules);
+            }
+            "--scheme" => {
+                let v = expect_value(&a, args.next())?;
+                out.scheme = Some(Scheme::parse(&v).ok_or_else(|| invalid_arg(format!("未知方案 `{v}`，可选：{}", Scheme::NAMES.join("/"))))?);
+            }
+            "--color" => {
+                let v = expect_value(&a, args.next())?;
+                out.color = Some(ColorMode::parse(&v).ok_or_else(|| invalid_arg(format!("--color 只能是 auto/always/never，实际为 `{v}`")))?);
+            }
+            "--preedit-style" => {
+                let v = expect_value(&a, args.next())?;
+                out.preedit_style = Some(config::parse_preedit_style(&v).ok_or_else(|| invalid_arg(format!("--preedit-style 只能是 pinyin/preview，实际为 `{v}`")))?);
+            }
+            "--help" | "-h" => print_help(),
+            _ => return Err(invalid_arg(format!("未知参数 `{a}`"))),
         }
-        if a == "--help" || a == "-h" {
-            print_help();
+    }
+    Ok(out)
+}
+
+/// `max` / `sum` / `weighted:<self_factor>,<other_factor>`（如 `weighted:0.7,0.3`）。
+fn parse_merge_policy(s: &str) -> Option<MergePolicy> {
+    match s {
+        "max" => Some(MergePolicy::Max),
+        "sum" => Some(MergePolicy::Sum),
+        _ => {
+            let (a, b) = s.strip_prefix("weighted:")?.split_once(',')?;
+            let (self_factor, other_factor) = (a.trim().parse::<f64>().ok()?, b.trim().parse::<f64>().ok()?);
+            (self_factor.is_finite() && other_factor.is_finite()).then_some(MergePolicy::Weighted { self_factor, other_factor })
         }
     }
-    None
+}
+
+fn expect_value(flag: &str, value: Option<String>) -> io::Result<String> {
+    value.ok_or_else(|| invalid_arg(format!("{flag} 缺少参数值")))
+}
+
+fn invalid_arg(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
 }
 
 fn print_help() -> ! {
-    println!("用法：rime_cli [--dict <path>]\n交互：按行提交（回车确认一行拼音），随后输入 1-9 选择候选；直接回车默认选 1；输入 0 上屏原串；输入 q 放弃本次");
+    println!("用法：rime_cli [serve] [--config <path>] [--dict <path>] [--user-dict <path>] [--profile <dir>] [--scheme <name>] [--fuzzy [a=b,...]] [--limit <1-9>] [--json] [--stats] [--no-prompt] [--trace] [--debug] [--color auto|always|never] [--preedit-style pinyin|preview] [--punct <path>] [--watch]\n       rime_cli import <name.dict.yaml> [-o <out.tsv>]\n       rime_cli compile [--dict <path>] -o <out.bin>\n       rime_cli merge <a.tsv> <b.tsv> [--policy max|sum|weighted:<a>,<b>] [-o <out.tsv>]\n       rime_cli import-userdb <x.userdb.txt> [-o <history.tsv>]\n       rime_cli export-userdb <history.tsv> [--name <db_name>] [-o <x.userdb.txt>]\n配置：默认读取 $XDG_CONFIG_HOME/rime_rs/config.toml（示例见 asset/config.toml），命令行参数优先\nserve：按行读取 JSON-RPC 请求（create_session/process_key/get_ui/destroy_session/shutdown），每行输出一个响应\nimport：把 Rime dict.yaml 转换为 TSV 词典（流式处理，跳过的行输出到 stderr）\ncompile：把词典（含用户词典）写成二进制编译词典，供 rime_server --compiled 加载，或直接作为 --dict 使用（跳过解析，加载更快）\nmerge：合并两份 TSV 词典，同一词条的权重取较大者（max，默认）、相加（sum）或加权（weighted），不指定 -o 时写到 stdout\nimport-userdb / export-userdb：librime 用户词典文本备份与用户词频（--profile 下的 history.tsv）互转（上屏次数 c 即选择次数，在词典权重之上加分），-o 指向已有的文件时合并，跳过的行输出到 stderr\n非交互（stdin 不是终端或 --no-prompt）：每行拼音直接上屏首选，不打印提示\n--punct：标点映射表（每行 `标点<TAB>上屏文本`，示例见 asset/punct.tsv），行内的标点先上屏前面拼音的首选，再上屏对应的中文标点（serve 不适用）\n--debug：打印诊断信息（无法切分、没有候选、查询预算用尽等），每个候选附带分数组成（词典权重、组句长度加成与二元加分、去重等 filter），非交互模式输出到 stderr\n--watch（需以 feature watch 构建）：配置或词典文件变化后，在下一行输入（serve 为下一个请求）之前重新加载\n交互：按行提交（回车确认一行拼音），随后输入 1-9 选择候选；直接回车默认选 1；=/+ 下一页，- 上一页；输入 0 上屏原串；输入 q 放弃本次；:add <词> <拼音> [weight] 加词");
     std::process::exit(0);
 }
 
@@ -47,103 +448,148 @@ fn default_dict_path() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("asset").join("dict.tsv")
 }
 
-fn repl(session: &mut Session<TsvDictionary, QuanpinPreeditor>, dict_path: &PathBuf, committed: &mut Vec<String>) -> io::Result<()> {
+fn repl(session: &mut CliSession, dict_paths: &[PathBuf], pref: &CliPreference, state: &mut CliState) -> io::Result<()> {
     let mut out = io::stdout();
     let mut line = String::new();
-    writeln!(out, "rime-rs demo (全拼 CLI, std-only) | dict: {}", dict_path.display())?;
-    writeln!(out, "输入拼音后回车。输入 :q 退出。")?;
-    (&mut out).flush()?;
+    let renderer = Renderer::new(pref.color.enabled());
+    let preview = EngineFacade::preedit_style(&**session.engine()) == PreeditStyle::Preview;
+    let dicts: Vec<_> = dict_paths.iter().map(|p| p.display().to_string()).collect();
+    writeln!(out, "rime-rs demo (全拼 CLI, std-only) | dict: {}", dicts.join(", "))?;
+    writeln!(out, "输入拼音后回车。输入 :help 查看命令，:q 退出。")?;
+    out.flush()?;
 
     loop {
-        (&mut line).clear();
+        line.clear();
         print!("pinyin>");
         out.flush()?;
         if io::stdin().read_line(&mut line)? == 0 {
             break;
         }
+        #[cfg(feature = "watch")]
+        watch::reload_session(session, state, &mut out)?;
         let input = line.trim();
         if input.is_empty() {
             continue;
         }
         println!("--------------------");
         println!("input:{input}");
-        if input == ":q" || input == ":quit" || input == ":exit" {
-            break;
+        match command::parse(input) {
+            Some(Ok(cmd)) => {
+                if cmd.run(session, state, &mut out)? == Flow::Quit {
+                    break;
+                }
+                continue;
+            }
+            Some(Err(e)) => {
+                writeln!(out, "{e}")?;
+                continue;
+            }
+            None => {}
         }
-        let raw: String = sanitize_input(input);
-        if raw.is_empty() {
-            writeln!(out, "(忽略：只接受 a-z 和 ' )")?;
-            continue;
+        // feed into session (line-base)；中间状态不展示，只为最终状态组句。接受哪些字符由会话的 InputPolicy 决定
+        let (mut ui, actions) = session.handle_all(iter::once(InputEvent::Clear).chain(input.chars().map(InputEvent::Char)));
+        // 码表码满时输入途中已上屏的部分
+        let mut auto_committed = false;
+        for a in actions {
+            if let Action::Commit(s) = a {
+                emit_commit(&mut out, pref, state, s)?;
+                auto_committed = true;
+            }
         }
-
-        // feed into session (line-base)
-        (&mut *session).handle(InputEvent::Clear);
-        for ch in raw.chars() {
-            (&mut *session).handle(InputEvent::Char(ch));
+        if ui.raw_input.is_empty() {
+            if !auto_committed {
+                writeln!(out, "(忽略：只接受 a-z 和 ' )")?;
+            }
+            continue;
         }
 
         // selection loop: may require multiple steps (confirmed advances)
         loop {
-            let ui = session.ui_state();
-            writeln!(out, "> {}", ui.preedit)?;
-            if !ui.confirm_text.is_empty() {
-                writeln!(out, "  confirmed: {} ({} / {})", ui.confirm_text, ui.confirm, ui.caret)?;
-            } else {
-                writeln!(out, "  confirmed: (0 / {})", ui.caret)?;
+            writeln!(out, "> {}  ({} / {})", renderer.composition(&ui, preview), ui.confirm, ui.caret)?;
+            if state.debug {
+                for d in &ui.diagnostics {
+                    writeln!(out, "  ! {d}")?;
+                }
             }
 
             if ui.candidate_list.is_empty() {
                 // 无候选：直接上屏原串并清空
-                committed.push(ui.raw_input.clone());
-                writeln!(out, "commit: {}", ui.raw_input)?;
+                emit_commit(&mut out, pref, state, ui.raw_input.clone())?;
                 session.handle(InputEvent::Clear);
                 break;
             }
 
-            for (i, c) in ui.candidate_list.iter().enumerate() {
-                let n = i + 1;
-                let display_text = if ui.confirm_text.is_empty() { c.text.clone() } else { format!("{}{}", ui.confirm_text, c.text) };
-                match &c.comment {
-                    Some(comment) => writeln!(out, "{n}. {}\t({comment})", display_text)?,
-                    None => writeln!(out, "{n}. {}", display_text)?,
-                }
+            // 引擎的每页候选数已不超过 page_size 与选词键数（见 `load_config`）
+            let shown = ui.candidate_list.len();
+            // 直接回车选第 1 个，因此第 1 个即高亮候选
+            for l in renderer.candidates(&ui, &pref.select_keys, shown, 0) {
+                writeln!(out, "{l}")?;
+            }
+
+            if ui.page > 0 || ui.has_next_page {
+                let pages = session.page_count();
+                writeln!(out, "  page {}/{pages} (=/+ 下一页, - 上一页)", ui.page + 1)?;
             }
 
             line.clear();
-            print!("select [1-{}] (Enter=1, 0=raw, q=cancel)> ", ui.candidate_list.len().min(9));
-            (&mut out).flush()?;
+            let keys: String = pref.select_keys.chars().take(shown).collect();
+            print!("select [{keys}] (Enter=1, 0=raw, !n=delete, q=cancel)> ");
+            out.flush()?;
             if io::stdin().read_line(&mut line)? == 0 {
                 return Ok(());
             }
             let sel = line.trim();
-            // if sel == "q" || sel == "Q" {
-            //     writeln!(out, "(cancel)")?;
-            //     session.handle(InputEvent::Clear);
-            //     break;
-            // }
+            // 选词键里有 q 时按选词处理
+            if (sel == "q" || sel == "Q") && !pref.select_keys.contains(sel) {
+                writeln!(out, "(cancel)")?;
+                session.handle(InputEvent::Clear);
+                break;
+            }
             if sel == "0" {
-                let text = ui.raw_input.clone();
-                committed.push(text.clone());
-                writeln!(out, "commit: {text}")?;
+                emit_commit(&mut out, pref, state, ui.raw_input.clone())?;
                 session.handle(InputEvent::Clear);
                 break;
             }
 
-            let idx = if sel.is_empty() { Some(0usize) } else { sel.parse::<usize>().ok().and_then(|n| (1..=9).contains(&n).then_some(n - 1)) };
+            if sel == "=" || sel == "+" || sel == "-" {
+                let ev = if sel == "-" { InputEvent::PrevPage } else { InputEvent::NextPage };
+                ui = session.handle(ev).0;
+                continue;
+            }
+
+            // `!n`：删除第 n 个候选，此后不再出现
+            if let Some(key) = sel.strip_prefix('!') {
+                let Some(i) = select_index(key, &pref.select_keys).filter(|&i| i < shown) else {
+                    writeln!(out, "无效删除，请输入 ! 加 [{keys}] 之一")?;
+                    continue;
+                };
+                let text = ui.candidate_list[i].text.clone();
+                let (next, actions) = session.handle(InputEvent::DeleteCandidate(i));
+                ui = next;
+                match actions.first() {
+                    Some(Action::Rejected(reason)) => writeln!(out, "{}", reject_message(*reason))?,
+                    _ => writeln!(out, "已删除：{text}")?,
+                }
+                continue;
+            }
+
+            let idx = if sel.is_empty() { Some(0usize) } else { select_index(sel, &pref.select_keys).filter(|&i| i < shown) };
             let Some(i) = idx else {
-                writeln!(out, "无效选择，请输入 1-9 / 0 / q / 直接回车")?;
+                writeln!(out, "无效选择，请输入 [{keys}] / 0 / q / 直接回车")?;
                 continue;
             };
 
-            let (_ui2, actions) = session.handle(InputEvent::Select(i));
+            let (next, actions) = session.handle(InputEvent::Select(i));
+            ui = next;
             let mut committed_now = None;
             for a in actions {
-                let Action::Commit(s) = a;
-                committed_now = Some(s);
+                match a {
+                    Action::Commit(s) => committed_now = Some(s),
+                    Action::Rejected(reason) => writeln!(out, "{}", reject_message(reason))?,
+                }
             }
             if let Some(s) = committed_now {
-                committed.push(s.clone());
-                writeln!(out, "commit: {s}")?;
+                emit_commit(&mut out, pref, state, s)?;
                 break;
             }
         }
@@ -152,12 +598,137 @@ fn repl(session: &mut Session<TsvDictionary, QuanpinPreeditor>, dict_path: &Path
     Ok(())
 }
 
-fn sanitize_input(s: &str) -> String {
-    let mut out = String::new();
+/// 非交互模式的 `Host`：只记录上屏文本与最新快照。
+struct LineHost {
+    commit: Option<String>,
+    ui: Option<UiState>,
+}
+
+impl Host for LineHost {
+    fn commit_text(&mut self, text: &str) {
+        self.commit.get_or_insert_with(String::new).push_str(text);
+    }
+
+    fn update_preedit(&mut self, ui: &UiState) {
+        self.ui = Some(ui.clone());
+    }
+
+    fn show_candidates(&mut self, _ui: &UiState) {}
+
+    fn hide_candidates(&mut self) {}
+
+    fn forward_key(&mut self, _ev: &InputEvent) {}
+
+    fn rejected(&mut self, reason: RejectReason) {
+        eprintln!("{}", reject_message(reason));
+    }
+}
+
+/// 选词被拒绝时的提示。
+fn reject_message(reason: RejectReason) -> &'static str {
+    match reason {
+        RejectReason::NotComposing => "无法选择：当前没有输入",
+        RejectReason::NoSuchCandidate => "无法选择：没有这个候选",
+        RejectReason::SpanNotAtConfirm => "无法选择：该候选不接在已确认部分之后",
+        RejectReason::NotForgettable => "无法删除：多词的组句等不是词典词条的候选不能删除",
+    }
+}
+
+/// 非交互模式：每行拼音逐段选首选直到上屏，只输出上屏结果；冒号命令照常执行。
+fn convert_lines(session: &mut CliSession, input: impl BufRead, out: &mut impl Write, pref: &CliPreference, state: &mut CliState) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        // 重新加载的提示写到 stderr，不混入上屏结果
+        #[cfg(feature = "watch")]
+        watch::reload_session(session, state, &mut io::stderr())?;
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+        match command::parse(input) {
+            Some(Ok(cmd)) => {
+                if cmd.run(session, state, out)? == Flow::Quit {
+                    break;
+                }
+                continue;
+            }
+            Some(Err(e)) => {
+                writeln!(out, "{e}")?;
+                continue;
+            }
+            None => {}
+        }
+        let (ui, actions) = session.handle_all(iter::once(InputEvent::Clear).chain(input.chars().map(InputEvent::Char)));
+        // 码表码满时输入途中已上屏的部分，与本行最后的上屏合为一次输出
+        let auto: String = actions.into_iter().filter_map(|a| if let Action::Commit(s) = a { Some(s) } else { None }).collect();
+        if ui.raw_input.is_empty() {
+            if auto.is_empty() {
+                eprintln!("忽略 `{input}`：只接受 a-z 和 '");
+            } else {
+                emit_commit(out, pref, state, auto)?;
+            }
+            continue;
+        }
+        // --debug：诊断信息与首页候选的分数组成
+        if state.debug {
+            for d in &ui.diagnostics {
+                eprintln!("{input}\t!\t{d}");
+            }
+        }
+        for (c, explanation) in ui.candidate_list.iter().filter_map(|c| Some((c, Explanation::of(c)?))) {
+            eprintln!("{input}\t{}\t{explanation}", c.text);
+        }
+        let mut host = LineHost { commit: None, ui: Some(ui) };
+        // 每次选首选至少推进一段，段数 + 1 次内必然上屏
+        let steps = host.ui.as_ref().map_or(0, |ui| ui.segment.len());
+        for _ in 0..=steps {
+            match &host.ui {
+                _ if host.commit.is_some() => break,
+                Some(ui) if !ui.candidate_list.is_empty() => drive(session, &mut host, InputEvent::Space),
+                // 无候选：回车上屏已确认文本 + 剩余原串
+                _ => drive(session, &mut host, InputEvent::Enter),
+            }
+        }
+        let committed = auto + host.commit.as_deref().unwrap_or("");
+        session.handle(InputEvent::Clear);
+        if !committed.is_empty() {
+            emit_commit(out, pref, state, committed)?;
+        }
+    }
+    out.flush()
+}
+
+/// 选词输入（单个选词键）映射为候选下标。
+fn select_index(sel: &str, select_keys: &str) -> Option<usize> {
+    let mut chars = sel.chars();
+    let (Some(ch), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    select_keys.chars().position(|k| k == ch)
+}
+
+fn emit_commit(out: &mut impl Write, pref: &CliPreference, state: &mut CliState, text: String) -> io::Result<()> {
+    if pref.json {
+        writeln!(out, "{{\"commit\":{}}}", json_string(&text))?;
+    } else {
+        writeln!(out, "commit: {text}")?;
+    }
+    state.committed.push(text);
+    Ok(())
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
     for ch in s.chars() {
-        if ch.is_ascii_alphabetic() || ch == '\'' {
-            out.push(ch.to_ascii_lowercase());
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
         }
     }
+    out.push('"');
     out
 }
+
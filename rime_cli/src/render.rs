@@ -0,0 +1,147 @@
+//! 输出格式化：候选列对齐（按终端显示宽度计算）与 ANSI 着色。
+use std::io::IsTerminal;
+
+use rime_core::{explain::Explanation, model::UiState};
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const HIGHLIGHT: &str = "\x1b[1;7m";
+const CONFIRMED: &str = "\x1b[32m";
+const PENDING: &str = "\x1b[33m";
+
+/// `--color` 取值。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// stdout 是终端时着色
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(Self::Auto),
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    /// 解析为“是否着色”；`Auto` 时检测 stdout 是否为 tty。
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Auto => std::io::stdout().is_terminal(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// 候选与 preedit 的渲染器。
+pub struct Renderer {
+    color: bool,
+}
+
+impl Renderer {
+    pub fn new(color: bool) -> Self {
+        Self { color }
+    }
+
+    fn paint(&self, style: &str, s: &str) -> String {
+        if self.color && !s.is_empty() {
+            format!("{style}{s}{RESET}")
+        } else {
+            s.to_string()
+        }
+    }
+
+    /// 组合串：已确认文本 + 待确认的拼音段，两部分着不同颜色。
+    ///
+    /// `preview` 时待确认部分为首选预览（`UiState::composition`）。
+    pub fn composition(&self, ui: &UiState, preview: bool) -> String {
+        if preview && let Some(pending) = ui.composition.strip_prefix(ui.confirm_text.as_str()) {
+            return format!("{}{}", self.paint(CONFIRMED, &ui.confirm_text), self.paint(PENDING, pending));
+        }
+        let pending = ui.segment.get(ui.confirm..ui.caret).map(|s| s.join(" ")).unwrap_or_default();
+        let pending = if pending.is_empty() && ui.confirm_text.is_empty() { ui.preedit.clone() } else { pending };
+        let sep = if ui.confirm_text.is_empty() || pending.is_empty() { "" } else { " " };
+        format!("{}{sep}{}", self.paint(CONFIRMED, &ui.confirm_text), self.paint(PENDING, &pending))
+    }
+
+    /// 候选列表：编号、文本按最大显示宽度对齐，注释变暗，高亮候选反色；候选带分数组成（`--debug`）时下面多一行。
+    pub fn candidates(&self, ui: &UiState, keys: &str, shown: usize, highlighted: usize) -> Vec<String> {
+        let texts: Vec<String> = ui
+            .candidate_list
+            .iter()
+            .take(shown)
+            .map(|c| format!("{}{}", ui.confirm_text, c.text))
+            .collect();
+        let column = texts.iter().map(|t| display_width(t)).max().unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for ((c, text), key) in ui.candidate_list.iter().zip(&texts).zip(keys.chars()) {
+            let label = match c.comment {
+                Some(_) => format!("{key}. {}", pad_to_width(text, column)),
+                None => format!("{key}. {text}"),
+            };
+            let label = if lines.len() == highlighted { self.paint(HIGHLIGHT, &label) } else { label };
+            match &c.comment {
+                Some(comment) => lines.push(format!("{label}  {}", self.paint(DIM, &format!("({comment})")))),
+                None => lines.push(label),
+            }
+            if let Some(explanation) = Explanation::of(c) {
+                lines.push(format!("   {}", self.paint(DIM, &explanation.to_string())));
+            }
+        }
+        lines
+    }
+}
+
+/// 字符串在等宽终端中的显示宽度：CJK 等宽字符记 2 列，组合字符记 0 列。
+pub fn display_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// 用空格把 `s` 右侧补齐到 `width` 列（已超出时原样返回）。
+pub fn pad_to_width(s: &str, width: usize) -> String {
+    let w = display_width(s);
+    let mut out = s.to_string();
+    out.extend(std::iter::repeat_n(' ', width.saturating_sub(w)));
+    out
+}
+
+fn char_width(ch: char) -> usize {
+    let cp = ch as u32;
+    if cp == 0 || is_zero_width(cp) {
+        return 0;
+    }
+    if is_wide(cp) { 2 } else { 1 }
+}
+
+fn is_zero_width(cp: u32) -> bool {
+    matches!(cp,
+        0x0300..=0x036F // 组合附加符号
+        | 0x200B..=0x200F // 零宽空格/连接符/方向标记
+        | 0xFE00..=0xFE0F // 变体选择符
+        | 0xE0100..=0xE01EF)
+}
+
+fn is_wide(cp: u32) -> bool {
+    matches!(cp,
+        0x1100..=0x115F // 谚文字母
+        | 0x2E80..=0x303E // CJK 部首、符号与标点
+        | 0x3041..=0x33FF // 假名、注音、CJK 兼容
+        | 0x3400..=0x4DBF // CJK 扩展 A
+        | 0x4E00..=0x9FFF // CJK 统一汉字
+        | 0xA000..=0xA4CF // 彝文
+        | 0xAC00..=0xD7A3 // 谚文音节
+        | 0xF900..=0xFAFF // CJK 兼容汉字
+        | 0xFE30..=0xFE4F // CJK 兼容形式
+        | 0xFF00..=0xFF60 // 全角 ASCII
+        | 0xFFE0..=0xFFE6 // 全角符号
+        | 0x1F300..=0x1F64F // emoji
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD) // CJK 扩展 B 及之后
+}
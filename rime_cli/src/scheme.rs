@@ -0,0 +1,95 @@
+//! 输入方案选择：启动时按 `--scheme` 选定默认方案，其余方案可用 `:schema` 切换。
+use std::io;
+
+use rime_core::{engine::Analyzer, processor::InputPolicy, schema::AnalyzerSet};
+use rime_pinyin::{QuanpinPreeditor, ShuangpinPreeditor, ShuangpinScheme, TableAnalyzer, ZhuyinPreeditor};
+
+use crate::config::AnalyzerConfig;
+
+/// 可选输入方案。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scheme {
+    /// 全拼（默认）
+    #[default]
+    Quanpin,
+    /// 码表（仓颉等固定编码，配合码表词典使用）
+    Table,
+    /// 注音（大千键盘或注音符号，查拼音词典）
+    Zhuyin,
+    /// 双拼（小鹤、自然码或微软双拼，查拼音词典）
+    Shuangpin(ShuangpinScheme),
+}
+
+impl Scheme {
+    /// 所有可选方案的名字（用于帮助与报错）。
+    pub const NAMES: &'static [&'static str] = &["quanpin", "table", "zhuyin", "flypy", "ziranma", "mspy"];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "quanpin" => Some(Self::Quanpin),
+            "table" => Some(Self::Table),
+            "zhuyin" => Some(Self::Zhuyin),
+            "flypy" => Some(Self::Shuangpin(ShuangpinScheme::Flypy)),
+            "ziranma" => Some(Self::Shuangpin(ShuangpinScheme::Ziranma)),
+            "mspy" => Some(Self::Shuangpin(ShuangpinScheme::Microsoft)),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Quanpin => "quanpin",
+            Self::Table => "table",
+            Self::Zhuyin => "zhuyin",
+            Self::Shuangpin(ShuangpinScheme::Flypy) => "flypy",
+            Self::Shuangpin(ShuangpinScheme::Ziranma) => "ziranma",
+            Self::Shuangpin(ShuangpinScheme::Microsoft) => "mspy",
+        }
+    }
+
+    /// 构造包含全部方案的 analyzer，以该方案为默认；`Box<dyn Analyzer>` 让 `Engine`/`Session` 的类型与方案无关。
+    pub fn build(self, config: &AnalyzerConfig) -> io::Result<Box<dyn Analyzer>> {
+        let mut set = AnalyzerSet::new(self.name(), self.analyzer(config)?);
+        for s in Self::NAMES.iter().filter_map(|name| Self::parse(name)).filter(|&s| s != self) {
+            set = set.with_schema(s.name(), s.analyzer(config)?);
+        }
+        Ok(Box::new(set))
+    }
+
+    /// 会话接受哪些字符：按默认方案决定，运行中切换方案不变（注音的大千键盘要用到数字与标点，微软双拼要用到 `;`，
+    /// 全拼接受声调时要用到数字）。
+    pub fn input_policy(self, config: &AnalyzerConfig) -> InputPolicy {
+        match self {
+            Self::Quanpin => InputPolicy { digits: config.tone_digits, ..InputPolicy::default() },
+            Self::Table => InputPolicy::default(),
+            Self::Zhuyin => ZhuyinPreeditor::input_policy(),
+            Self::Shuangpin(scheme) => ShuangpinPreeditor::new(scheme).input_policy(),
+        }
+    }
+
+    /// 该方案单独的 analyzer；配置了音节表（`analyzer.syllabary`）时全拼从文件读入音节表，读入失败返回错误。
+    fn analyzer(self, config: &AnalyzerConfig) -> io::Result<Box<dyn Analyzer>> {
+        Ok(match self {
+            Self::Quanpin => {
+                let preeditor = match &config.syllabary {
+                    Some(path) => QuanpinPreeditor::from_syllabary_file(path)?,
+                    None => QuanpinPreeditor::new(),
+                };
+                Box::new(
+                    preeditor
+                        .initials_fallback(config.initials_fallback)
+                        .tone_digits(config.tone_digits)
+                        .with_fuzzy(config.fuzzy.clone())
+                        .max_input_len(config.max_input_len)
+                        .max_alternatives(config.alternatives),
+                )
+            }
+            Self::Table => match config.max_code_length {
+                Some(n) => Box::new(TableAnalyzer::new().max_code_length(n)),
+                None => Box::new(TableAnalyzer::new()),
+            },
+            Self::Zhuyin => Box::new(ZhuyinPreeditor::new()),
+            Self::Shuangpin(scheme) => Box::new(ShuangpinPreeditor::new(scheme)),
+        })
+    }
+}
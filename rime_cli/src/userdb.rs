@@ -0,0 +1,183 @@
+//! `rime_cli import-userdb` / `export-userdb`：与 librime 用户词典的文本备份（`*.userdb.txt`）互转。
+//!
+//! 文本格式：
+//! - 头部 `#@/<name><TAB><value>`（`db_name`、`db_type`、`tick` 等），其余 `#` 开头的行为注释
+//! - 正文每行 `code<TAB>text<TAB>c=<count> d=<dee> t=<tick>`；code 为空格分隔的音节（通常以空格结尾），
+//!   code 与 text 中的 `\ ` 表示空格、`\\` 表示反斜杠
+//!
+//! 导入导出的是用户词频（`rime_core::history`，`--profile` 下的 `history.tsv`），而不是词典：`c`（上屏次数）是选择次数，
+//! 候选在词典权重之上按次数加分，不会替换系统词典的权重。
+//!
+//! - 导入：code 按 `KeyNormalization::pinyin()` 规整；`c < 0`（已删除）与 `c = 0` 的行跳过，`d` 须能解析但不保留；
+//!   `t` 按 librime 的衰减（每 tick 乘以 e^(-1/200)）折算为距今的时长，与用户词频的半衰期（`DEFAULT_HALF_LIFE`）对应，
+//!   离头部 `tick`（没有时取最大的 `t`）越远，最近上屏时间越早；`-o` 指向已有的文件时合并进去
+//! - 导出：`c` 与 `d` 为选择次数，`t` 按同样的比例由最近上屏时间折算，头部 `tick` 为现在
+use std::{
+    f64::consts::LN_2,
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+};
+
+use rime_core::{
+    engine::Analyzer,
+    history::{DEFAULT_HALF_LIFE, UserHistory},
+};
+use rime_dict::{KeyNormalization, UserHistoryFile};
+use rime_pinyin::QuanpinPreeditor;
+
+use crate::import::{ImportReport, normalize_code};
+
+/// 导出时头部的 `db_name` 缺省值。
+pub const DEFAULT_DB_NAME: &str = "rime_rs";
+
+/// 导入：`output` 为 None 时写到 stdout，否则合并进 `output`（不存在时新建）。
+pub fn import_file(input: &Path, output: Option<&Path>) -> io::Result<ImportReport> {
+    let reader = BufReader::new(fs::File::open(input)?);
+    let history = match output {
+        Some(path) => UserHistory::load_from(path)?,
+        None => UserHistory::new(),
+    };
+    let report = import(reader, &history)?;
+    match output {
+        Some(path) => history.save_to(path)?,
+        None => io::stdout().lock().write_all(history.to_tsv().as_bytes())?,
+    }
+    Ok(report)
+}
+
+/// 导出：`input` 为用户词频文件（`history.tsv`），`output` 为 None 时写到 stdout。
+pub fn export_file(input: &Path, output: Option<&Path>, db_name: &str) -> io::Result<ImportReport> {
+    let history = UserHistory::from_tsv_str(&fs::read_to_string(input)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", input.display())))?;
+    match output {
+        Some(path) => {
+            let mut writer = io::BufWriter::new(fs::File::create(path)?);
+            let report = export(&history, &mut writer, db_name)?;
+            writer.flush()?;
+            Ok(report)
+        }
+        None => export(&history, io::stdout().lock(), db_name),
+    }
+}
+
+/// 每个 librime tick 相当于多少秒：librime 每 tick 衰减 e^(-1/200)，半衰期为 200·ln2 个 tick，对应 `half_life` 秒。
+fn seconds_per_tick(history: &UserHistory) -> f64 {
+    history.half_life().unwrap_or(DEFAULT_HALF_LIFE) as f64 / (200.0 * LN_2)
+}
+
+/// 从 `reader` 读取 userdb 文本，把各词条的选择次数记入 `history`。
+pub fn import(reader: impl BufRead, history: &UserHistory) -> io::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut header_tick = None;
+    let mut rows = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line?;
+        if let Some(tick) = line.strip_prefix("#@/tick\t") {
+            header_tick = tick.trim().parse::<u64>().ok();
+            continue;
+        }
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split('\t');
+        let (Some(code), Some(text), Some(values), None) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+            report.skip(line_no, "应为 code、text、属性三列");
+            continue;
+        };
+        let text = unescape(text);
+        if text.trim().is_empty() {
+            report.skip(line_no, "缺少文本");
+            continue;
+        }
+        let Some(key) = normalize_code(&unescape(code), KeyNormalization::pinyin()) else {
+            report.skip(line_no, format!("编码 `{code}` 含 a-z 以外的字符"));
+            continue;
+        };
+        let (count, tick) = match parse_values(values) {
+            Ok(values) => values,
+            Err(reason) => {
+                report.skip(line_no, reason);
+                continue;
+            }
+        };
+        if count < 0 {
+            report.skip(line_no, "已删除的词条（c < 0）");
+            continue;
+        }
+        if count == 0 {
+            report.skip(line_no, "没有上屏过（c = 0）");
+            continue;
+        }
+        rows.push((key, text, u32::try_from(count).unwrap_or(u32::MAX), tick));
+    }
+    let latest = header_tick.or_else(|| rows.iter().filter_map(|row| row.3).max()).unwrap_or(0);
+    let (now, per_tick) = (history.now(), seconds_per_tick(history));
+    for (key, text, count, tick) in rows {
+        let age = (latest.saturating_sub(tick.unwrap_or(latest)) as f64 * per_tick).round() as u64;
+        history.add(&key, &text, count, now.saturating_sub(age));
+        report.written += 1;
+    }
+    Ok(report)
+}
+
+/// 把 `history` 写成 userdb 文本到 `writer`；key 按全拼切成音节，切不开时原样作为一段。
+pub fn export(history: &UserHistory, mut writer: impl Write, db_name: &str) -> io::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let analyzer = QuanpinPreeditor::new().initials_fallback(false);
+    let entries = history.entries();
+    let (now, per_tick) = (history.now(), seconds_per_tick(history));
+    let ages: Vec<u64> = entries.iter().map(|e| (now.saturating_sub(e.last_used) as f64 / per_tick).round() as u64).collect();
+    // 最早的词条 t 为 1，其余按距今的 tick 数往后排
+    let tick = ages.iter().max().map_or(1, |&age| age + 1);
+    writeln!(writer, "# Rime user dictionary")?;
+    writeln!(writer, "#@/db_name\t{db_name}")?;
+    writeln!(writer, "#@/db_type\tuserdb")?;
+    writeln!(writer, "#@/tick\t{tick}")?;
+    for (e, age) in entries.iter().zip(ages) {
+        let segment = analyzer.analyze(&e.key).segment;
+        let code = if segment.concat() == *e.key { segment.join(" ") } else { e.key.to_string() };
+        writeln!(writer, "{code} \t{}\tc={} d={} t={}", escape(&e.text), e.count, e.count, tick - age)?;
+        report.written += 1;
+    }
+    Ok(report)
+}
+
+/// `c=42 d=0.5 t=123456` -> (c, t)；缺少 `c`、或 c/d/t 无法解析时返回原因。未知的属性忽略。
+fn parse_values(values: &str) -> Result<(i64, Option<u64>), String> {
+    let (mut count, mut tick) = (None, None);
+    for item in values.split_whitespace() {
+        let (name, value) = item.split_once('=').ok_or_else(|| format!("无法解析的属性 `{item}`"))?;
+        let ok = match name {
+            "c" => value.parse::<i64>().map(|c| count = Some(c)).is_ok(),
+            "d" => value.parse::<f64>().is_ok_and(f64::is_finite),
+            "t" => value.parse::<u64>().map(|t| tick = Some(t)).is_ok(),
+            _ => true,
+        };
+        if !ok {
+            return Err(format!("无法解析的属性 `{item}`"));
+        }
+    }
+    count.map(|c| (c, tick)).ok_or_else(|| "缺少属性 c".to_string())
+}
+
+/// `\ ` -> 空格，`\\` -> `\`；其余字符（含其他反斜杠序列）原样保留。
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ (' ' | '\\'))) => {
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `unescape` 的逆操作。
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(' ', "\\ ")
+}
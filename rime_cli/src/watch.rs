@@ -0,0 +1,98 @@
+//! `--watch`（feature `watch`）：配置或词典文件变化后重新加载，在两次输入之间替换引擎（REPL/非交互为两行之间，serve 为两个请求之间）。
+//!
+//! 重新加载按启动时的命令行参数重读配置、重建词典与引擎，失败时继续使用原引擎。
+//! 会话接受哪些字符（输入策略）与 `[cli]` 的显示偏好以启动时为准。
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use rime_core::{engine::Analyzer, history::UserHistory, learn::LearnedPhrases, session_manager::SessionManager};
+use rime_dict::{DictionaryStack, Profile};
+use rime_server::watch::FileWatcher;
+
+use crate::{Args, CliEngine, CliSession, command::CliState, config, config::CliConfig, load_config, load_engine};
+
+/// 连续写入在此时间内合并为一次重新加载。
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 一次重新加载：变化的文件，以及加载结果（配置、引擎、`--profile` 的用户配置）。
+type Reloaded = (Vec<PathBuf>, io::Result<(CliConfig, CliEngine, Option<Profile>)>);
+
+/// 监视中的文件及重新加载所需的命令行参数。
+#[derive(Debug)]
+pub struct Watch {
+    args: Args,
+    watcher: FileWatcher,
+    /// 用户词频：重新加载的引擎沿用同一份，不重读文件
+    history: Option<UserHistory>,
+    /// 自动造词学到的词：同样沿用，重新加载后不丢
+    learned: LearnedPhrases,
+}
+
+impl Watch {
+    pub fn start(args: Args, config: &CliConfig, dict_paths: &[PathBuf], history: Option<UserHistory>, learned: LearnedPhrases) -> Self {
+        let watcher = FileWatcher::spawn(watched_files(&args, config, dict_paths), DEBOUNCE);
+        Self { args, watcher, history, learned }
+    }
+
+    /// 有文件变化时重新加载；成功时改为监视新配置用到的文件。
+    fn reload(&mut self) -> Option<Reloaded> {
+        let changed = self.watcher.try_changed()?;
+        let loaded = load_config(&self.args).and_then(|config| {
+            let (engine, profile, dict_paths) = load_engine(&self.args, &config, self.history.as_ref(), &self.learned)?;
+            self.watcher = FileWatcher::spawn(watched_files(&self.args, &config, &dict_paths), DEBOUNCE);
+            Ok((config, engine, profile))
+        });
+        Some((changed, loaded))
+    }
+
+    /// serve 模式：每个请求之前调用（见 `protocol::serve_with`），返回是否替换了引擎。
+    pub fn reload_manager(&mut self, manager: &mut SessionManager<DictionaryStack, Box<dyn Analyzer>>) -> bool {
+        let Some((changed, loaded)) = self.reload() else {
+            return false;
+        };
+        match loaded {
+            Ok((_, engine, _)) => {
+                manager.set_engine(engine);
+                eprintln!("已重新加载：{}", names(&changed));
+                true
+            }
+            Err(e) => {
+                eprintln!("重新加载失败，继续使用原配置：{e}");
+                false
+            }
+        }
+    }
+}
+
+/// REPL 与非交互模式：读入一行之后、处理之前调用，提示写到 `out`。
+pub fn reload_session(session: &mut CliSession, state: &mut CliState, out: &mut impl Write) -> io::Result<()> {
+    let Some(watch) = &mut state.watch else {
+        return Ok(());
+    };
+    let Some((changed, loaded)) = watch.reload() else {
+        return Ok(());
+    };
+    match loaded {
+        Ok((config, engine, profile)) => {
+            session.set_engine(engine);
+            state.user_dict = if profile.is_some() { None } else { config.cli.user_dict };
+            state.profile = profile;
+            writeln!(out, "已重新加载：{}", names(&changed))
+        }
+        Err(e) => writeln!(out, "重新加载失败，继续使用原配置：{e}"),
+    }
+}
+
+/// 要监视的文件：配置文件、词典、英文词表、简繁转换表与音节表。
+fn watched_files(args: &Args, config: &CliConfig, dict_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let config_path = args.config.clone().or_else(config::default_config_path);
+    let extra = [&config.cli.english_words, &config.cli.convert_table, &config.analyzer.syllabary].into_iter().flatten().cloned();
+    config_path.into_iter().chain(dict_paths.iter().cloned()).chain(extra).collect()
+}
+
+fn names(paths: &[PathBuf]) -> String {
+    paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+}
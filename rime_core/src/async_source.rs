@@ -0,0 +1,41 @@
+//! `async_source`：异步候选来源（云输入、大模型等）与本地候选的合并。
+//!
+//! core 不依赖任何异步运行时：会话只负责编号与合并，异步部分由前端实现。
+//!
+//! 1. 会话开启异步来源（`Session::with_async_source`）后，组合（raw_input/confirm/caret）每变化一次，
+//!    编号加一；正在输入时以新编号调用 `AsyncCandidateSource::request_candidates`
+//! 2. 本地候选照常立即组句，按键不等待异步结果
+//! 3. 结果到达后前端调用 `Session::inject_candidates(id, ..)`：编号仍是当前组合的编号时与本地候选一起排序后分页，
+//!    返回新的 `UiState` 供前端重绘；组合已变化（过期）或没有新候选（重复）时返回 None
+
+use std::sync::Arc;
+
+/// 异步请求编号：会话内单调递增，组合每变化一次加一。
+pub type RequestId = u64;
+
+/// 发给异步来源的请求：当前要选词的部分 `segment[confirm..caret]`。
+#[derive(Debug, Clone)]
+pub struct CandidateRequest {
+    pub id: RequestId,
+    pub raw_input: String,
+    /// 切分结果（与 `UiState` 共享）
+    pub segment: Arc<[String]>,
+    pub confirm: usize,
+    pub caret: usize,
+}
+
+/// 异步候选来源：收到请求后自行（在别的线程/任务中）计算，完成时由前端调用 `Session::inject_candidates`。
+///
+/// 实现不应阻塞：通常只是把请求发进 channel 或启动一个任务。
+pub trait AsyncCandidateSource: Send + Sync {
+    fn request_candidates(&self, request: CandidateRequest);
+}
+
+impl<S> AsyncCandidateSource for Arc<S>
+where
+    S: AsyncCandidateSource + ?Sized,
+{
+    fn request_candidates(&self, request: CandidateRequest) {
+        (**self).request_candidates(request)
+    }
+}
@@ -0,0 +1,98 @@
+//! `bigram`：组句用的二元语言模型（`Engine::bigram_model` 开启）。
+//!
+//! 组句（`translator::DictTranslator`）扩展路径时，除各词的权重与长度加成外，再给相邻的两个词加上
+//! `(ln P(b|a) - floor) × scale` 分：常见的搭配（如「你好」后接「吗」）胜过权重更高但不搭的词。
+//!
+//! - 模型里没有的词对按 `floor` 计，即加 0 分；`floor` 缺省为模型中最小的对数概率，出现过的词对都不低于没出现过的
+//! - 只作用于组句路径内部相邻的词，单词候选与直查结果不受影响；没有模型时组句与之前完全一致
+//! - 可从 TSV 读入（`from_tsv_str`），core 不读写文件，读文件见 `rime_dict::bigram`
+
+use std::collections::HashMap;
+
+use crate::model::CandidateText;
+
+/// 对数概率每差 1（自然对数）折合的缺省分数，按自带词典的权重（词频，常用字为百万量级）取定；其他词典宜按其权重量级调整。
+pub const DEFAULT_BIGRAM_SCALE: f64 = 1_000_000.0;
+
+/// 二元语言模型：`word_a` 之后接 `word_b` 的对数概率。
+#[derive(Debug, Clone)]
+pub struct BigramModel {
+    /// word_a -> word_b -> ln P(word_b | word_a)
+    map: HashMap<CandidateText, HashMap<CandidateText, f64>>,
+    /// 模型里没有的词对的对数概率
+    floor: f64,
+    /// 对数概率每差 1 折合的分数
+    scale: f64,
+}
+
+impl Default for BigramModel {
+    fn default() -> Self {
+        Self { map: HashMap::new(), floor: 0.0, scale: DEFAULT_BIGRAM_SCALE }
+    }
+}
+
+impl BigramModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从 TSV 读入：每行 `word_a<TAB>word_b<TAB>log_prob`（自然对数，不大于 0）；空行与 `#` 开头的行忽略，
+    /// 同一 (word_a, word_b) 出现多次时只保留第一条。`floor` 取其中最小的对数概率。
+    ///
+    /// 某行格式不对时返回带行号的错误信息。
+    pub fn from_tsv_str(s: &str) -> Result<Self, String> {
+        let mut model = Self::new();
+        let mut floor = f64::INFINITY;
+        for (idx, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let (Some(a), Some(b), Some(log_prob), None) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+                return Err(format!("第 {} 行：应为 word_a、word_b、对数概率三列", idx + 1));
+            };
+            let (a, b) = (a.trim(), b.trim());
+            if a.is_empty() || b.is_empty() {
+                return Err(format!("第 {} 行：缺少 word_a/word_b", idx + 1));
+            }
+            let log_prob = log_prob.trim().parse::<f64>().ok().filter(|p| p.is_finite() && *p <= 0.0).ok_or_else(|| format!("第 {} 行：对数概率应为不大于 0 的数，实际为 `{log_prob}`", idx + 1))?;
+            model.map.entry(a.into()).or_default().entry(b.into()).or_insert(log_prob);
+            floor = floor.min(log_prob);
+        }
+        if floor.is_finite() {
+            model.floor = floor;
+        }
+        Ok(model)
+    }
+
+    /// 模型里没有的词对按此对数概率计（加 0 分）；低于它的词对减分。
+    pub fn floor(mut self, log_prob: f64) -> Self {
+        self.floor = log_prob;
+        self
+    }
+
+    /// 对数概率每差 1 折合多少分（缺省 `DEFAULT_BIGRAM_SCALE`）；为 0 时模型不起作用。
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// `a` 之后接 `b` 的对数概率；模型里没有时为 None。
+    pub fn log_prob(&self, a: &str, b: &str) -> Option<f64> {
+        self.map.get(a)?.get(b).copied()
+    }
+
+    /// 组句时 `a` 之后接 `b` 的加分：`(ln P(b|a) - floor) × scale`，四舍五入。
+    pub fn score(&self, a: &str, b: &str) -> i64 {
+        self.log_prob(a, b).map_or(0, |log_prob| ((log_prob - self.floor) * self.scale).round() as i64)
+    }
+
+    /// 词对的个数。
+    pub fn len(&self) -> usize {
+        self.map.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
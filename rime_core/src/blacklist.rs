@@ -0,0 +1,121 @@
+//! `blacklist`：删除（遗忘）的候选（`InputEvent::DeleteCandidate`，见 `Context::forget_candidate`）。
+//!
+//! 按 (编码, 词) 记下不想再看到的候选，组合时由 `filter::BlacklistFilter` 去掉；词典本身不改，删掉黑名单中的一条即恢复。
+//!
+//! - 编码同用户词频（见 `history`）：简拼、前缀补全取 comment 中的完整编码，其余为所覆盖的段拼接（如 `shurufa`）
+//! - 只能删除词典中的词：直查的候选（精确、前缀补全、简拼、模糊音）与只有一个词的组句；多词的组句、联想、注入的候选不能删除，
+//!   组句也不因含有删除的词而被去掉
+//! - 随会话保留（`reset` 不清空）；可导出为 TSV（`to_tsv`/`from_tsv_str`），core 不读写文件，落盘见 `rime_dict::blacklist`
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::model::{Candidate, CandidateSource, CandidateText};
+
+/// 删除的候选：(编码, 词) 的集合。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Blacklist {
+    /// 编码 -> 词；按编码分开存放，查询时不必构造 (编码, 词)
+    entries: HashMap<CandidateText, HashSet<CandidateText>>,
+}
+
+impl Blacklist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入 `key` 下的 `text`；已在黑名单中时返回 false。
+    pub fn insert(&mut self, key: &str, text: &str) -> bool {
+        if key.is_empty() || text.is_empty() {
+            return false;
+        }
+        self.entries.entry(key.into()).or_default().insert(text.into())
+    }
+
+    /// 移出 `key` 下的 `text`（恢复该候选）；不在黑名单中时返回 false。
+    pub fn remove(&mut self, key: &str, text: &str) -> bool {
+        let Some(texts) = self.entries.get_mut(key) else {
+            return false;
+        };
+        let removed = texts.remove(text);
+        if texts.is_empty() {
+            self.entries.remove(key);
+        }
+        removed
+    }
+
+    pub fn contains(&self, key: &str, text: &str) -> bool {
+        self.entries.get(key).is_some_and(|texts| texts.contains(text))
+    }
+
+    /// 记录了多少个 (编码, 词)。
+    pub fn len(&self) -> usize {
+        self.entries.values().map(HashSet::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 全部 (编码, 词)，按编码、词排序。
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        let mut entries: Vec<(&str, &str)> = self.entries.iter().flat_map(|(key, texts)| texts.iter().map(|text| (&**key, &**text))).collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// 导出为 TSV：每行 `key<TAB>text`，按 key、text 排序（同样的黑名单总是得到同样的文本）。
+    pub fn to_tsv(&self) -> String {
+        let mut out = String::new();
+        for (key, text) in self.entries() {
+            let _ = writeln!(out, "{key}\t{text}");
+        }
+        out
+    }
+
+    /// 从 `to_tsv` 的输出恢复；空行与 `#` 开头的行忽略，重复的行只算一条。
+    ///
+    /// 某行不是 `key<TAB>text` 时返回带行号的错误信息。
+    pub fn from_tsv_str(s: &str) -> Result<Self, String> {
+        let mut blacklist = Self::new();
+        for (idx, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let (Some(key), Some(text), None) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(format!("第 {} 行：应为 key、text 两列", idx + 1));
+            };
+            if key.is_empty() || text.is_empty() {
+                return Err(format!("第 {} 行：key、text 不能为空", idx + 1));
+            }
+            blacklist.insert(key, text);
+        }
+        Ok(blacklist)
+    }
+}
+
+/// 候选能否删除：词典直查的候选，以及只有一个词的组句（与直查的词相同，去重时可能保留的是组句）。
+pub(crate) fn forgettable(c: &Candidate) -> bool {
+    match c.source {
+        CandidateSource::Exact | CandidateSource::Prefix | CandidateSource::Initials | CandidateSource::Fuzzy => true,
+        CandidateSource::Compose => c.parts.len() == 1,
+        CandidateSource::Prediction | CandidateSource::External => false,
+    }
+}
+
+/// `c` 是否已删除：能删除（`forgettable`），且其 (编码, 词) 在 `blacklist` 中。
+pub(crate) fn blacklisted(blacklist: &Blacklist, c: &Candidate, segment: &[String]) -> bool {
+    forgettable(c) && candidate_key(c, segment).is_some_and(|key| blacklist.contains(&key, &c.text))
+}
+
+/// 候选的编码（同用户词频）：简拼、前缀补全取 comment 中的完整编码，其余为 `segment` 中所覆盖的段拼接；没有覆盖范围时为 None。
+pub(crate) fn candidate_key<'a>(c: &'a Candidate, segment: &[String]) -> Option<Cow<'a, str>> {
+    match (&c.source, &c.comment) {
+        (CandidateSource::Prefix | CandidateSource::Initials, Some(key)) => Some(Cow::Borrowed(key)),
+        _ => segment.get(c.segment_start..c.segment_end).filter(|span| !span.is_empty()).map(|span| Cow::Owned(span.concat())),
+    }
+}
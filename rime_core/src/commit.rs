@@ -0,0 +1,54 @@
+//! `commit`：上屏文本的后处理（不同的目标程序对上屏文本的要求略有不同）。
+//!
+//! 会话以 `Session::with_commit_transform` 注册 transform，按注册顺序依次作用于每个 `Action::Commit`
+//! （选词、回车、原串上屏都一样），在 `handle` 返回之前、繁体转换之后。
+//! 联想与排序策略（`RankingPolicy::on_commit`）看到的仍是变换前的文本。
+
+/// 上屏文本变换。闭包 `Fn(&str) -> String` 也可直接注册。
+pub trait CommitTransform: Send + Sync {
+    fn transform(&self, text: &str) -> String;
+}
+
+impl<F> CommitTransform for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn transform(&self, text: &str) -> String {
+        self(text)
+    }
+}
+
+/// 在末尾加一个空格。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AppendSpace;
+
+impl CommitTransform for AppendSpace {
+    fn transform(&self, text: &str) -> String {
+        format!("{text} ")
+    }
+}
+
+/// ASCII 数字转为全角数字（`2024` -> `２０２４`），其余字符不变。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FullWidthDigits;
+
+impl CommitTransform for FullWidthDigits {
+    fn transform(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| match c {
+                '0'..='9' => char::from_u32(u32::from(c) - u32::from('0') + 0xFF10).unwrap_or(c),
+                _ => c,
+            })
+            .collect()
+    }
+}
+
+/// 去掉首尾空白；变换后为空串时仍是一次上屏（`Action::Commit("")`）。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimWhitespace;
+
+impl CommitTransform for TrimWhitespace {
+    fn transform(&self, text: &str) -> String {
+        text.trim().to_string()
+    }
+}
@@ -0,0 +1,130 @@
+//! `config`：引擎可调参数。
+//!
+//! core 不读文件：配置文件的解析由上层（CLI 等）完成，这里只定义参数结构与默认值。
+
+/// 默认候选标签。
+pub const DEFAULT_SELECT_LABELS: &str = "123456789";
+
+/// 引擎参数（对应配置文件中的 `[engine]` 段）。
+///
+/// 取值范围由上层在解析时校验；越界值交给 `Engine::with_config` 会 panic。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineConfig {
+    /// 候选词数量（1-9）
+    pub candidate_limit: u8,
+    /// 组词时单个“词”最多覆盖多少个音节段（至少 1）
+    pub max_word_length: u8,
+    /// 组句时每个 span 最多取多少个词扩展路径（至少 1）
+    pub beam_span_limit: usize,
+    /// 组句时每个段位置保留多少条路径（至少 1）；None 为按所需条数自动取
+    pub beam_width: Option<usize>,
+    /// 单词候选每个 span 最多出多少条（至少 1）
+    pub word_candidate_limit: usize,
+    /// 候选总数上限（各页合计，至少 1）；None 为不限
+    pub final_limit: Option<usize>,
+    /// 单次组句最多查询词典多少次；None 为不限
+    pub max_lookups: Option<usize>,
+    /// 整段输入的前缀补全（码表方案宜关闭）
+    pub prefix_completion: bool,
+    /// 码表编码中的通配符（例如五笔的 `z`）；None 为不支持
+    pub wildcard: Option<char>,
+    /// 码表满码长度：码满且候选唯一时自动上屏，再输入时先上屏首选；None 为不自动上屏
+    pub auto_commit_length: Option<usize>,
+    /// 候选按来源分组展示（见 `UiState::groups`）；默认按分数交错排列
+    pub group_by_source: bool,
+    /// 候选去重按 (文本, 范围) 还是只按文本
+    pub dedup_mode: DedupMode,
+    /// 选择组句候选时确认整句还是只确认第一个词
+    pub sentence_select: SentenceSelect,
+    /// 单字候选的读音标注
+    pub annotation: Annotation,
+    /// 内嵌组合串（`UiState::composition`）的样式
+    pub preedit_style: PreeditStyle,
+    /// 简繁异体去重（需设置 `Engine::converter`）
+    pub variant_dedup: bool,
+    /// 候选带上分数组成（调试排序用，见 `explain`）
+    pub explain: bool,
+    /// 候选窗横排还是竖排（见 `UiState::layout`）
+    pub orientation: Orientation,
+    /// 候选标签，第 n 个字符标注第 n 个候选（见 `UiState::layout`）
+    pub select_labels: String,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            candidate_limit: 9,
+            max_word_length: 4,
+            beam_span_limit: 16,
+            beam_width: None,
+            word_candidate_limit: 16,
+            final_limit: None,
+            max_lookups: None,
+            prefix_completion: true,
+            wildcard: None,
+            auto_commit_length: None,
+            group_by_source: false,
+            dedup_mode: DedupMode::ByTextAndSpan,
+            sentence_select: SentenceSelect::WholeSentence,
+            annotation: Annotation::Off,
+            preedit_style: PreeditStyle::Pinyin,
+            variant_dedup: false,
+            explain: false,
+            orientation: Orientation::Vertical,
+            select_labels: DEFAULT_SELECT_LABELS.to_string(),
+        }
+    }
+}
+
+/// 选择组句候选（带 `Candidate::parts`）时的确认方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SentenceSelect {
+    /// 确认整句
+    #[default]
+    WholeSentence,
+    /// 只确认第一个词；句子的其余部分作为剩余段的首选候选，连续选择即逐词确认
+    FirstWord,
+}
+
+/// 候选去重（`filter::DedupSortTruncate`）时视为重复的条件。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupMode {
+    /// 文本与覆盖的段（`segment_start..segment_end`）都相同；同一个词覆盖不同的段时各保留一条
+    #[default]
+    ByTextAndSpan,
+    /// 文本相同即重复：只保留排在最前（分数最高）的一条，其范围与组句分词取同文本中覆盖段数最多的一条
+    ByTextOnly,
+}
+
+/// 单字候选的读音标注（`filter::AnnotationFilter`）：`comment` 填入产生该候选的编码。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Annotation {
+    /// 不标注
+    #[default]
+    Off,
+    /// 只标注有歧义的单字：列表中还有别的候选以同一个字开头
+    Ambiguous,
+    /// 标注全部单字候选
+    All,
+}
+
+/// 内嵌组合串（`UiState::composition`）的样式；经典的 `UiState::preedit` 不受影响。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreeditStyle {
+    /// 已确认文本 + 未确认部分的拼音
+    #[default]
+    Pinyin,
+    /// 预览上屏结果：已确认文本 + 当前页首选 + 首选未覆盖的拼音
+    Preview,
+}
+
+/// 候选窗的排列方向（前端的展示提示，引擎本身不受影响）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Orientation {
+    /// 每行一个候选
+    #[default]
+    Vertical,
+    /// 候选排成一行
+    Horizontal,
+}
@@ -4,7 +4,51 @@
 //! - `raw_input`：用户尚未上屏的输入串（全拼/简拼）
 //! - `analysis`：对 `raw_input` 的切分结果（`segment` + `preedit`）
 //! - `confirm/confirm_text`：已确认的段范围与对应文本（用于“逐段选词”）
-use crate::{engine::Analysis, key_event::Action, model::UiState, processor::EngineFacade};
+//! - 联想候选：上屏后、开始新的输入前展示（见 `prediction`）
+//! - 注入候选：异步来源为当前组合补充的候选，与本地候选一起排序后分页（见 `async_source`）
+//! - 逐词确认（`SentenceSelect::FirstWord`）：选组句候选只确认第一个词，句子其余部分置顶于剩余段的候选
+//! - 最近上屏记忆：会话内最近上屏的词再次输入时加分，与本地候选一起排序后分页（见 `recent`）；`reset` 不清空
+//! - 自动造词：记下本次输入的各次选词，分几次选完上屏时拼成词组（见 `learn`）；开关 `reset` 不清空
+//! - 用户词频：记下本次输入的各次选词，上屏时交给会话记入引擎的 `UserHistory`（见 `history`）；开关 `reset` 不清空
+//! - 删除的候选：会话级黑名单（见 `blacklist`），组合时在分页之前去掉其中的候选；`reset` 不清空
+//! - 输出繁体：会话级开关（见 `convert`），决定简繁异体去重保留哪种写法；`reset` 不清空
+//! - 输入方案：引擎有多个方案时，会话当前所用的方案（见 `schema`）；`reset` 不清空
+//! - 输入模式：拼音或英文直通（见 `InputMode`），英文模式下整段输入原样作为一段，第一个候选即原始输入；`reset` 不清空
+//! - 原样文本（临时英文）：`raw_input` 中不经切分器的部分，各占一个段；确认到它时直接以原文确认，不查词典（见 `start_literal`）
+//! - 强制切分点：`raw_input` 上的字节位置，两侧分别切分（见 `force_boundary`）；在末尾继续输入、删除都保留，删到它时作废
+use std::{ops::Range, sync::Arc};
+
+use crate::{
+    blacklist::{Blacklist, candidate_key, forgettable},
+    config::SentenceSelect,
+    engine::{Analysis, PageRequest, SegmentationKind},
+    key_event::{Action, RejectReason},
+    learn::{LearnedPhrase, Pick},
+    model::{Candidate, CandidateSource, CandidateText, UiState, source_groups},
+    processor::EngineFacade,
+    recent::RecentCommits,
+};
+
+/// 输入模式（`InputEvent::ToggleMode` 切换，见 `Context::set_mode`）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputMode {
+    /// 按输入方案切分
+    #[default]
+    Pinyin,
+    /// 英文直通：字符原样累积（保留大小写，标点也进入组合串），不经切分器；第一个候选即原始输入，回车或空格上屏
+    English,
+}
+
+/// 缓存的候选总数及其所对应的组合与词典版本（见 `Context::page_count`）。
+#[derive(Debug, Clone)]
+struct CountCache {
+    generation: u64,
+    raw_input: String,
+    segment: Arc<[String]>,
+    confirm: usize,
+    caret: usize,
+    count: usize,
+}
 
 /// 输入会话上下文：processor 链共享的唯一状态。
 #[derive(Debug, Clone)]
@@ -13,12 +57,61 @@ pub struct Context {
     pub raw_input: String,
     /// 切分结果（由 `EngineFacade::analyze` 产生）
     pub analysis: Analysis,
-    /// 光标所在段位置（第一版默认在末尾）
+    /// 光标所在段位置：候选只覆盖 [confirm, caret)；输入变化后回到末尾（有原样文本时见 `reanalyze`），可用 `move_caret` 移动
     pub caret: usize,
     /// 已确认段范围的结束位置：[0, confirm)
     pub confirm: usize,
     /// 已确认文本（内部 composition）
     pub confirm_text: String,
+    /// 候选页码（0 起）；输入变化或选词推进后回到第一页
+    pub page: usize,
+    /// 联想候选；非空即处于联想状态
+    predictions: Vec<Candidate>,
+    /// 异步来源为当前组合注入的候选
+    injected: Vec<Candidate>,
+    /// 逐词确认时，所选句子尚未确认的部分（覆盖 [confirm, caret)）
+    sentence_rest: Option<Candidate>,
+    /// 最近上屏记忆（None 即未开启）；跨输入保留，随会话结束
+    recent: Option<RecentCommits>,
+    /// 输出繁体（`InputEvent::ToggleTraditional`）；跨输入保留
+    traditional: bool,
+    /// 当前输入方案（`EngineFacade::schemas` 的下标）；跨输入保留
+    schema: usize,
+    /// 输入模式（`InputEvent::ToggleMode`）；跨输入保留
+    mode: InputMode,
+    /// 删除的候选（`InputEvent::DeleteCandidate`）；跨输入保留
+    blacklist: Blacklist,
+    /// 最近一次组句的快照及其所用的词典版本（见 `ui_state_cached`）
+    ui_cache: Option<(u64, UiState)>,
+    /// 当前组合的候选总数（见 `page_count`）；翻页不失效
+    count_cache: Option<CountCache>,
+    /// 已结束的原样文本在 `raw_input` 中的字节范围（按先后）
+    literals: Vec<Range<usize>>,
+    /// 正在输入的原样文本在 `raw_input` 中的起点（到末尾为止）
+    literal_open: Option<usize>,
+    /// 强制切分点在 `raw_input` 中的字节位置（升序，都在 (0, len) 内）
+    boundaries: Vec<usize>,
+    /// 有原样文本或强制切分点时，切分结果按来源分成的部分；没有时为空
+    chunks: Vec<Chunk>,
+    /// 本次事件中选中的候选排名（见 `mark_selected`），供会话统计取走
+    selected: Vec<usize>,
+    /// 本次输入的各次选词（None 即未开启自动造词）
+    picks: Option<Vec<Pick>>,
+    /// 上屏时拼成的词组（见 `take_learned`）
+    learned: Option<LearnedPhrase>,
+    /// 本次输入已选、尚未上屏的 (编码, 词)（None 即未开启用户词频）
+    choices: Option<Vec<(CandidateText, CandidateText)>>,
+    /// 上屏的选词（见 `take_chosen`）
+    chosen: Vec<(CandidateText, CandidateText)>,
+}
+
+/// 分部切分时切分结果的一部分（见 `Context::reanalyze`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chunk {
+    /// 一段原样文本（段下标）；`open` 即正在输入
+    Literal { segment: usize, open: bool },
+    /// 切分器给出的段 [start, end)
+    Analyzed { start: usize, end: usize, kind: SegmentationKind },
 }
 
 impl Default for Context {
@@ -26,82 +119,789 @@ impl Default for Context {
         Self {
             raw_input: String::new(),
             analysis: Analysis {
-                segment: Vec::new(),
+                segment: Arc::from([]),
                 preedit: String::new(),
+                fuzzy: Vec::new(),
+                kind: SegmentationKind::Syllables,
+                alternatives: Vec::new(),
             },
             caret: 0,
             confirm: 0,
             confirm_text: String::new(),
+            page: 0,
+            predictions: Vec::new(),
+            injected: Vec::new(),
+            sentence_rest: None,
+            recent: None,
+            traditional: false,
+            schema: 0,
+            mode: InputMode::Pinyin,
+            blacklist: Blacklist::new(),
+            ui_cache: None,
+            count_cache: None,
+            literals: Vec::new(),
+            literal_open: None,
+            boundaries: Vec::new(),
+            chunks: Vec::new(),
+            selected: Vec::new(),
+            picks: None,
+            learned: None,
+            choices: None,
+            chosen: Vec::new(),
         }
     }
 }
 
 impl Context {
-    /// 清空会话状态（等价于重新开始一次输入）。
+    /// 清空会话状态（等价于重新开始一次输入）；最近上屏记忆、繁体开关、输入方案与模式、删除的候选、自动造词与用户词频开关、本次事件选中的排名、
+    /// 拼成的词组与上屏的选词保留，本次已选未上屏的词不计入记忆。
     pub fn reset(&mut self) {
+        let mut recent = self.recent.take();
+        if let Some(recent) = &mut recent {
+            recent.discard();
+        }
+        let (traditional, schema, mode, selected, learned) = (self.traditional, self.schema, self.mode, std::mem::take(&mut self.selected), self.learned.take());
+        let (learning, history, chosen) = (self.picks.is_some(), self.choices.is_some(), std::mem::take(&mut self.chosen));
+        let blacklist = std::mem::take(&mut self.blacklist);
         *self = Self::default();
+        self.blacklist = blacklist;
+        self.recent = recent;
+        self.traditional = traditional;
+        self.schema = schema;
+        self.mode = mode;
+        self.selected = selected;
+        self.learned = learned;
+        self.chosen = chosen;
+        self.set_learning(learning);
+        self.set_history(history);
+    }
+
+    /// 开关自动造词（见 `learn`）：开启后记下每次选词，分几次选完上屏时拼成词组（`take_learned`）。
+    pub fn set_learning(&mut self, enabled: bool) {
+        if enabled != self.picks.is_some() {
+            self.picks = enabled.then(Vec::new);
+        }
+    }
+
+    /// 开关用户词频的记录（见 `history`）：开启后记下每次选词，上屏时交给 `take_chosen`。
+    pub fn set_history(&mut self, enabled: bool) {
+        if enabled != self.choices.is_some() {
+            self.choices = enabled.then(Vec::new);
+        }
+    }
+
+    /// 取走上屏的选词 (编码, 词)（`Session` 每个事件之后调用，记入 `Engine::history`）；未开启时总是为空。
+    pub fn take_chosen(&mut self) -> Vec<(CandidateText, CandidateText)> {
+        std::mem::take(&mut self.chosen)
+    }
+
+    /// 本次输入的选词随上屏计入用户词频。
+    fn commit_choices(&mut self) {
+        if let Some(choices) = &mut self.choices {
+            self.chosen.append(choices);
+        }
+    }
+
+    /// 取走最近一次上屏拼成的词组（`Session` 每个事件之后调用）；未开启自动造词时总是 None。
+    pub fn take_learned(&mut self) -> Option<LearnedPhrase> {
+        self.learned.take()
+    }
+
+    /// 记下选中了排名 `rank` 的候选（跨页从 0 计）；自定义 processor 选词时也应调用。
+    pub fn mark_selected(&mut self, rank: usize) {
+        self.selected.push(rank);
+    }
+
+    /// 取走自上次调用以来选中的排名（`Session` 每个事件之后调用）。
+    pub fn take_selected(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.selected)
+    }
+
+    /// 当前输入方案的下标（引擎只有一个方案时为 0）。
+    pub fn schema(&self) -> usize {
+        self.schema
+    }
+
+    /// 切换输入方案：正在输入的 `raw_input` 保留，按新方案重新切分；联想随之关闭。
+    pub fn set_schema(&mut self, engine: &dyn EngineFacade, schema: usize) {
+        self.schema = schema;
+        self.clear_predictions();
+        self.invalidate_ui();
+        if !self.raw_input.is_empty() {
+            self.reanalyze(engine);
+        }
+    }
+
+    /// 当前输入模式。
+    pub fn mode(&self) -> InputMode {
+        self.mode
+    }
+
+    /// 切换输入模式：正在输入的 `raw_input` 保留，按新模式重新切分（已确认的词随之作废）；
+    /// 原样文本与强制切分点丢弃，联想随之关闭。
+    pub fn set_mode(&mut self, engine: &dyn EngineFacade, mode: InputMode) {
+        if self.mode == mode {
+            return;
+        }
+        self.mode = mode;
+        self.literals.clear();
+        self.literal_open = None;
+        self.boundaries.clear();
+        self.clear_predictions();
+        self.invalidate_ui();
+        if !self.raw_input.is_empty() {
+            self.reanalyze(engine);
+        }
+    }
+
+    /// 是否输出繁体。
+    pub fn is_traditional(&self) -> bool {
+        self.traditional
+    }
+
+    /// 切换简体/繁体输出。
+    pub fn set_traditional(&mut self, traditional: bool) {
+        if self.traditional != traditional {
+            self.traditional = traditional;
+            self.invalidate_ui();
+        }
+    }
+
+    /// 删除的候选。
+    pub fn blacklist(&self) -> &Blacklist {
+        &self.blacklist
+    }
+
+    /// 替换删除的候选（例如读入上次保存的黑名单）。
+    pub fn set_blacklist(&mut self, blacklist: Blacklist) {
+        self.blacklist = blacklist;
+        self.invalidate_ui();
+    }
+
+    /// 删除（遗忘）当前页第 `index` 个候选：把它的 (编码, 词) 加入黑名单（见 `blacklist`），此后组合时不再出现。
+    ///
+    /// 只能删除词典中的词：多词的组句、注入的候选与英文模式的原始输入返回 `Action::Rejected(RejectReason::NotForgettable)`，状态不变。
+    /// 删除后当前页空了时退回上一页。
+    pub fn forget_candidate(&mut self, engine: &dyn EngineFacade, index: usize) -> Vec<Action> {
+        if self.raw_input.is_empty() {
+            return vec![Action::Rejected(RejectReason::NotComposing)];
+        }
+        let ui = self.ui_state_cached(engine);
+        let Some(cand) = ui.candidate_list.get(index) else {
+            return vec![Action::Rejected(RejectReason::NoSuchCandidate)];
+        };
+        let key = candidate_key(cand, &self.analysis.segment).filter(|_| forgettable(cand) && self.mode == InputMode::Pinyin);
+        let Some(key) = key else {
+            return vec![Action::Rejected(RejectReason::NotForgettable)];
+        };
+        self.blacklist.insert(&key, &cand.text);
+        self.invalidate_ui();
+        if self.page > 0 && self.ui_state_cached(engine).candidate_list.is_empty() {
+            self.page -= 1;
+        }
+        Vec::new()
+    }
+
+    /// 开启（Some）或关闭（None）最近上屏记忆。
+    pub fn set_recent(&mut self, recent: Option<RecentCommits>) {
+        self.recent = recent;
+        self.invalidate_ui();
+    }
+
+    /// 最近上屏记忆（未开启时为 None）。
+    pub fn recent(&self) -> Option<&RecentCommits> {
+        self.recent.as_ref()
     }
 
     /// 重新对 `raw_input` 进行切分，并同步更新 `caret/confirm` 的边界。
+    ///
+    /// 已确认的段若因重新切分而改变（或超出新的段数），确认作废，回到未选词状态。
+    ///
+    /// 有原样文本时分部切分：原样文本各占一段，其余部分各自交给切分器；`caret` 停在下一段原样文本之前，
+    /// 候选只覆盖到那里，已结束的原样文本到达 `confirm` 时直接确认。
+    /// 有强制切分点时，非原样文本的部分再在切分点处切开；已不在 `raw_input` 范围内的切分点丢弃。
+    ///
+    /// 英文模式不经切分器，整段输入作为一段（`Analysis::raw`）。
     pub fn reanalyze(&mut self, engine: &dyn EngineFacade) {
-        self.analysis = engine.analyze(&self.raw_input);
-        self.caret = self.analysis.segment.len();
-        if self.confirm > self.caret {
-            self.confirm = self.caret;
-            (&mut self.confirm_text).clear();
+        let len = self.raw_input.len();
+        self.boundaries.retain(|&at| at < len && self.raw_input.is_char_boundary(at));
+        let analysis = if self.mode == InputMode::English {
+            self.chunks.clear();
+            Analysis::raw(&self.raw_input)
+        } else if self.literals.is_empty() && self.literal_open.is_none() && self.boundaries.is_empty() {
+            self.chunks.clear();
+            engine.analyze_as(self.schema, &self.raw_input)
+        } else {
+            self.analyze_parts(engine)
+        };
+        let confirmed_kept = self.confirm <= analysis.segment.len() && analysis.segment.get(..self.confirm) == self.analysis.segment.get(..self.confirm);
+        self.analysis = analysis;
+        self.page = 0;
+        self.sentence_rest = None;
+        if !confirmed_kept {
+            self.confirm = 0;
+            self.confirm_text.clear();
+            if let Some(picks) = &mut self.picks {
+                picks.clear();
+            }
+        }
+        self.settle_literals();
+        self.caret = self.caret_end();
+    }
+
+    /// 分部切分（见 `reanalyze`）；输入变短后超出的原样文本随之截断或丢弃。
+    fn analyze_parts(&mut self, engine: &dyn EngineFacade) -> Analysis {
+        let len = self.raw_input.len();
+        self.literals.retain_mut(|r| {
+            r.end = r.end.min(len);
+            r.start < r.end
+        });
+        self.literal_open = self.literal_open.filter(|&start| start <= len);
+        let literals = self.literals.iter().map(|r| (r.clone(), false)).chain(self.literal_open.map(|start| (start..len, true)));
+        // (字节范围, 是否原样文本及是否正在输入)
+        let mut parts: Vec<(Range<usize>, Option<bool>)> = Vec::new();
+        // 非原样文本的部分在强制切分点处切开
+        let boundaries = &self.boundaries;
+        let analyzed = |parts: &mut Vec<(Range<usize>, Option<bool>)>, range: Range<usize>| {
+            let mut start = range.start;
+            for &at in boundaries.iter().filter(|&&at| range.start < at && at < range.end) {
+                parts.push((start..at, None));
+                start = at;
+            }
+            parts.push((start..range.end, None));
+        };
+        let mut pos = 0;
+        for (range, open) in literals {
+            if pos < range.start {
+                analyzed(&mut parts, pos..range.start);
+            }
+            pos = range.end;
+            if !range.is_empty() {
+                parts.push((range, Some(open)));
+            }
+        }
+        if pos < len {
+            analyzed(&mut parts, pos..len);
+        }
+        let mut segment: Vec<String> = Vec::new();
+        let mut preedit: Vec<String> = Vec::new();
+        let mut fuzzy: Vec<Vec<String>> = Vec::new();
+        self.chunks.clear();
+        for (range, literal) in parts {
+            let text = &self.raw_input[range];
+            if let Some(open) = literal {
+                self.chunks.push(Chunk::Literal { segment: segment.len(), open });
+                segment.push(text.to_string());
+                preedit.push(text.to_string());
+                fuzzy.push(Vec::new());
+                continue;
+            }
+            let analysis = engine.analyze_as(self.schema, text);
+            let start = segment.len();
+            if analysis.fuzzy.is_empty() {
+                fuzzy.resize(start + analysis.segment.len(), Vec::new());
+            } else {
+                fuzzy.extend(analysis.fuzzy);
+            }
+            segment.extend(analysis.segment.iter().cloned());
+            preedit.push(analysis.preedit);
+            self.chunks.push(Chunk::Analyzed { start, end: segment.len(), kind: analysis.kind });
+        }
+        if fuzzy.iter().all(Vec::is_empty) {
+            fuzzy.clear();
+        }
+        preedit.retain(|p| !p.is_empty());
+        Analysis {
+            segment: Arc::from(segment),
+            preedit: preedit.join(" "),
+            fuzzy,
+            kind: SegmentationKind::default(),
+            alternatives: Vec::new(),
+        }
+    }
+
+    /// 确认位于 `confirm` 的已结束原样文本，切分方式取 `confirm` 所在部分的。
+    fn settle_literals(&mut self) {
+        if self.chunks.is_empty() {
+            return;
+        }
+        while self.chunks.contains(&Chunk::Literal { segment: self.confirm, open: false }) {
+            self.confirm_text.push_str(&self.analysis.segment[self.confirm]);
+            self.confirm += 1;
+        }
+        if let Some(kind) = self.chunks.iter().find_map(|c| match *c {
+            Chunk::Analyzed { start, end, kind } if (start..end).contains(&self.confirm) => Some(kind),
+            _ => None,
+        }) {
+            self.analysis.kind = kind;
+        }
+    }
+
+    /// 光标最远能到的段位置：`confirm` 之后第一段原样文本之前，没有原样文本时为末尾。
+    fn caret_end(&self) -> usize {
+        self.chunks
+            .iter()
+            .find_map(|c| match *c {
+                Chunk::Literal { segment, .. } if segment >= self.confirm => Some(segment),
+                _ => None,
+            })
+            .unwrap_or(self.analysis.segment.len())
+    }
+
+    /// 光标左移（`left`）或右移一段：往左不越过 `confirm`（因而在 0 时不动），往右不越过 `caret_end`。返回光标是否移动。
+    ///
+    /// 候选只覆盖 [confirm, caret)，移动后回到第一页；选完光标前的部分后光标回到最远处。之后再输入字符时追加在末尾，光标也回到最远处。
+    pub fn move_caret(&mut self, left: bool) -> bool {
+        let caret = if left { self.caret.checked_sub(1).filter(|&c| c >= self.confirm) } else { Some(self.caret + 1).filter(|&c| c <= self.caret_end()) };
+        let Some(caret) = caret else {
+            return false;
+        };
+        self.caret = caret;
+        self.page = 0;
+        true
+    }
+
+    /// 各段在 `raw_input` 中的结束位置（字节）：第 n 项为前 n 段的结尾，首项为 0，末项为 `raw_input` 的长度（含末尾的 `'`）。
+    ///
+    /// 段的拼写与原文逐段对得上时（全拼、简拼、码表、原样文本，其间可隔着 `'`）直接对齐；
+    /// 对不上时（双拼、注音等换算过的段）取切分结果的前 n 段与当前相同的最短前缀。
+    fn segment_offsets(&self, engine: &dyn EngineFacade) -> Vec<usize> {
+        let (raw, segment) = (&self.raw_input, &self.analysis.segment);
+        let mut offsets = Vec::with_capacity(segment.len() + 1);
+        offsets.push(0);
+        let mut pos = 0;
+        for s in segment.iter() {
+            pos += raw[pos..].bytes().take_while(|&b| b == b'\'').count();
+            match raw.get(pos..pos + s.len()) {
+                Some(typed) if typed.eq_ignore_ascii_case(s) => pos += s.len(),
+                _ => break,
+            }
+            offsets.push(pos);
+        }
+        if offsets.len() == segment.len() + 1 && raw[pos..].bytes().all(|b| b == b'\'') {
+            offsets[segment.len()] = raw.len();
+            return offsets;
+        }
+        offsets.truncate(1);
+        let mut end = 0;
+        for n in 1..segment.len() {
+            end = (end + 1..raw.len()).filter(|&p| raw.is_char_boundary(p)).find(|&p| self.analyze_prefix(engine, p).get(..n) == segment.get(..n)).unwrap_or(raw.len());
+            offsets.push(end);
+        }
+        if !segment.is_empty() {
+            offsets.push(raw.len());
+        }
+        offsets
+    }
+
+    /// 按当前的原样文本、强制切分点与方案切分 `raw_input` 的前 `len` 个字节。
+    fn analyze_prefix(&self, engine: &dyn EngineFacade, len: usize) -> Arc<[String]> {
+        let mut probe = Self {
+            raw_input: self.raw_input[..len].to_string(),
+            schema: self.schema,
+            literals: self.literals.clone(),
+            literal_open: self.literal_open,
+            boundaries: self.boundaries.clone(),
+            ..Self::default()
+        };
+        probe.reanalyze(engine);
+        probe.analysis.segment
+    }
+
+    /// 在 `raw_input` 的字节位置 `at` 强制切开：两侧分别交给切分器（如 `xian` 在 2 处切开即 `xi`/`an`），`raw_input` 不变。
+    ///
+    /// 切分点按字节记在 `raw_input` 上而不是段下标：之后在末尾继续输入、删除都保留它，删到 `at` 时作废；
+    /// 落在原样文本内的切分点不起作用。`at` 须在 (0, len) 内且在字符边界上，否则不做任何事、返回 false。
+    pub fn force_boundary(&mut self, engine: &dyn EngineFacade, at: usize) -> bool {
+        if at == 0 || at >= self.raw_input.len() || !self.raw_input.is_char_boundary(at) {
+            return false;
+        }
+        if let Err(i) = self.boundaries.binary_search(&at) {
+            self.boundaries.insert(i, at);
+            self.reanalyze(engine);
+        }
+        true
+    }
+
+    /// 当前的强制切分点（`raw_input` 中的字节位置，升序）。
+    pub fn boundaries(&self) -> &[usize] {
+        &self.boundaries
+    }
+
+    /// 去掉全部强制切分点并重新切分。
+    pub fn clear_boundaries(&mut self, engine: &dyn EngineFacade) {
+        if !self.boundaries.is_empty() {
+            self.boundaries.clear();
+            self.reanalyze(engine);
+        }
+    }
+
+    /// 开始原样输入（临时英文）：此后输入的字符不经切分器，直到 `commit_literal`。已在原样输入中时不变。
+    pub fn start_literal(&mut self) {
+        if self.literal_open.is_none() {
+            self.literal_open = Some(self.raw_input.len());
+        }
+    }
+
+    /// 是否正在原样输入。
+    pub fn is_literal_open(&self) -> bool {
+        self.literal_open.is_some()
+    }
+
+    /// 结束原样输入：已输入的部分成为一段原样文本，确认到它时（前面的拼音选定后）以原文确认，之后的输入回到拼音。
+    ///
+    /// 整段输入都是临时英文（`SegmentationKind::Raw`）时，整段作为原样文本。返回是否有原样输入可结束。
+    pub fn commit_literal(&mut self, engine: &dyn EngineFacade) -> bool {
+        let start = match self.literal_open.take() {
+            Some(start) => start,
+            None if self.literals.is_empty() && self.analysis.kind == SegmentationKind::Raw => 0,
+            None => return false,
+        };
+        if start < self.raw_input.len() {
+            self.literals.push(start..self.raw_input.len());
+        }
+        self.reanalyze(engine);
+        true
+    }
+
+    /// 删除 `raw_input` 的最后一个字符并重新切分。
+    ///
+    /// 删空正在输入的原样文本即结束原样输入；删到已结束的原样文本则回到输入它的状态。
+    pub fn pop_input(&mut self, engine: &dyn EngineFacade) {
+        self.raw_input.pop();
+        let len = self.raw_input.len();
+        if self.literal_open.is_some_and(|start| start >= len) {
+            self.literal_open = None;
+        } else if self.literal_open.is_none()
+            && let Some(last) = self.literals.last()
+            && last.start < len
+            && last.end > len
+        {
+            self.literal_open = Some(last.start);
+            self.literals.pop();
+        }
+        self.reanalyze(engine);
+    }
+
+    /// 删除光标前的一段（连同它前面的 `'`）在 `raw_input` 中的原文并重新切分，光标停在删去的位置。
+    ///
+    /// 光标在最远处（未移动过，见 `move_caret`）时同 `pop_input`，只删最后一个字符；光标已在 `confirm` 处时不删除，返回 false。
+    pub fn delete_before_caret(&mut self, engine: &dyn EngineFacade) -> bool {
+        if self.caret == self.caret_end() {
+            self.pop_input(engine);
+            return true;
+        }
+        if self.caret <= self.confirm {
+            return false;
+        }
+        let offsets = self.segment_offsets(engine);
+        let (start, mut end) = (offsets[self.caret - 1], offsets[self.caret]);
+        // 删的是第一段时连同其后的 `'`，不留下打头的分隔符
+        if start == 0 {
+            end += self.raw_input[end..].bytes().take_while(|&b| b == b'\'').count();
+        }
+        self.raw_input.replace_range(start..end, "");
+        // 光标前只有拼音段，其后的原样文本与切分点随之前移
+        let shift = |p: &mut usize| {
+            if *p >= end {
+                *p -= end - start;
+            } else if *p > start {
+                *p = start;
+            }
+        };
+        for r in &mut self.literals {
+            shift(&mut r.start);
+            shift(&mut r.end);
+        }
+        if let Some(open) = &mut self.literal_open {
+            shift(open);
+        }
+        self.boundaries.iter_mut().for_each(shift);
+        self.boundaries.dedup();
+        self.boundaries.retain(|&b| b > 0);
+        self.reanalyze(engine);
+        let caret = self.segment_offsets(engine)[1..].iter().filter(|&&p| p <= start).count();
+        self.caret = caret.clamp(self.confirm, self.caret_end());
+        true
+    }
+
+    /// 是否处于联想状态（上屏后展示联想候选，尚未开始新的输入）。
+    pub fn is_predicting(&self) -> bool {
+        !self.predictions.is_empty()
+    }
+
+    /// 当前的联想候选。
+    pub fn predictions(&self) -> &[Candidate] {
+        &self.predictions
+    }
+
+    /// 以 `candidates` 进入联想状态（为空即不联想）；候选标为 `CandidateSource::Prediction`。
+    pub fn set_predictions(&mut self, mut candidates: Vec<Candidate>) {
+        for (rank, c) in candidates.iter_mut().enumerate() {
+            c.segment_start = 0;
+            c.segment_end = 0;
+            c.source = CandidateSource::Prediction;
+            c.rank = rank;
+        }
+        self.predictions = candidates;
+        self.invalidate_ui();
+    }
+
+    /// 退出联想状态。
+    pub fn clear_predictions(&mut self) {
+        if !self.predictions.is_empty() {
+            self.predictions.clear();
+            self.invalidate_ui();
+        }
+    }
+
+    /// 已注入的候选。
+    pub fn injected(&self) -> &[Candidate] {
+        &self.injected
+    }
+
+    /// 追加注入的候选（标为 `CandidateSource::External`）；组合变化后须 `clear_injected`。
+    pub fn add_injected(&mut self, candidates: impl IntoIterator<Item = Candidate>) {
+        let rank = self.injected.len();
+        self.injected.extend(candidates.into_iter().enumerate().map(|(i, c)| Candidate { source: CandidateSource::External, rank: rank + i, ..c }));
+        self.invalidate_ui();
+    }
+
+    /// 丢弃注入的候选。
+    pub fn clear_injected(&mut self) {
+        if !self.injected.is_empty() {
+            self.injected.clear();
+            self.invalidate_ui();
+        }
+    }
+
+    /// 最近上屏记忆中与当前组合匹配的词（联想时没有）。
+    fn recent_candidates(&self) -> Vec<Candidate> {
+        match &self.recent {
+            Some(recent) if !self.is_predicting() && !recent.is_empty() && self.confirm < self.caret => recent.candidates(&self.analysis.segment, self.confirm, self.caret),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 取当前页候选的请求：组句与统计总数用同一份，`recent` 取自 `recent_candidates`。
+    fn page_request<'a>(&'a self, recent: &'a [Candidate]) -> PageRequest<'a> {
+        let predicting = self.is_predicting();
+        // 逐词确认：句子的其余部分排在第一位
+        let rest = self.sentence_rest.as_ref().filter(|c| !predicting && c.segment_start == self.confirm && c.segment_end == self.caret);
+        PageRequest {
+            page: self.page,
+            blacklist: Some(&self.blacklist),
+            injected: if predicting { &[] } else { &self.injected },
+            recent,
+            sentence_rest: rest,
+            english: self.mode == InputMode::English && !predicting,
+            traditional: self.traditional,
         }
     }
 
     /// 生成 UI 层只读快照。
     pub fn ui_state(&self, engine: &dyn EngineFacade) -> UiState {
-        engine.compose_with_state(
-            &self.raw_input,
-            self.analysis.clone(),
-            self.confirm,
-            Some(self.caret),
-            self.confirm_text.clone(),
-        )
+        let recent = self.recent_candidates();
+        let mut ui = engine.compose_with_state(&self.raw_input, self.analysis.clone(), self.confirm, Some(self.caret), self.confirm_text.clone(), self.page_request(&recent));
+        if self.is_predicting() {
+            ui.candidate_list = self.predictions.clone();
+            ui.predicting = true;
+        }
+        if let Some(schemas) = engine.schemas() {
+            ui.schema = schemas.name(self.schema).to_string();
+        }
+        ui.injected = !self.is_predicting() && !self.injected.is_empty();
+        ui.english = self.mode == InputMode::English;
+        // 候选已变（联想）：重新分组，首选也可能变了
+        if self.is_predicting() {
+            if engine.group_by_source() {
+                ui.groups = source_groups(&ui.candidate_list);
+            }
+            ui.update_composition(engine.preedit_style());
+        }
+        ui
+    }
+
+    /// 缓存的快照仍对应当前状态与 `engine` 的词典版本时返回它。
+    ///
+    /// 切分只由 `raw_input` 决定，比较 raw_input/segment/caret/confirm/confirm_text/page 即可。
+    pub fn cached_ui(&self, engine: &dyn EngineFacade) -> Option<&UiState> {
+        self.ui_cache
+            .as_ref()
+            .filter(|(generation, _)| *generation == engine.generation())
+            .map(|(_, ui)| ui)
+            .filter(|ui| ui.raw_input == self.raw_input && ui.segment == self.analysis.segment && ui.caret == self.caret && ui.confirm == self.confirm && ui.confirm_text == self.confirm_text && ui.page == self.page)
     }
 
-    /// Enter 的默认行为：提交“已确认 + 原始输入”。
+    /// 同 `ui_state`，但状态未变时复用上次的快照，不再重复组句。
+    pub fn ui_state_cached(&mut self, engine: &dyn EngineFacade) -> UiState {
+        if let Some(ui) = self.cached_ui(engine) {
+            return ui.clone();
+        }
+        let ui = self.ui_state(engine);
+        self.ui_cache = Some((engine.generation(), ui.clone()));
+        ui
+    }
+
+    /// 丢弃缓存的快照（词典以外的引擎设置变化后须调用；词典修改由版本号自动识别）。
+    pub fn invalidate_ui(&mut self) {
+        self.ui_cache = None;
+        self.count_cache = None;
+    }
+
+    /// Enter 的默认行为：提交“已确认文本 + 未确认部分的原始输入”。
     pub fn commit_on_enter(&mut self) -> Vec<Action> {
         let mut actions = Vec::new();
-        if !self.raw_input.is_empty() || !self.confirm_text.is_empty() {
-            let mut s = String::new();
-            s.push_str(&self.confirm_text);
+        let mut s = std::mem::take(&mut self.confirm_text);
+        if self.confirm == 0 {
             s.push_str(&self.raw_input);
-            if !s.is_empty() {
-                actions.push(Action::Commit(s));
-            }
+        } else {
+            s.extend(self.analysis.segment[self.confirm..].iter().map(String::as_str));
+        }
+        if !s.is_empty() {
+            actions.push(Action::Commit(s));
+        }
+        // 已确认的词随之上屏
+        if let Some(recent) = &mut self.recent {
+            recent.commit();
         }
+        self.commit_choices();
         self.reset();
         actions
     }
 
+    /// 翻页：`forward` 为 true 时向后翻；到达首/尾页时停住。返回页码是否变化。
+    pub fn turn_page(&mut self, engine: &dyn EngineFacade, forward: bool) -> bool {
+        if forward {
+            if !self.ui_state_cached(engine).has_next_page {
+                return false;
+            }
+            self.page += 1;
+            true
+        } else if self.page > 0 {
+            self.page -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 当前候选共有几页；联想时为 1 页，没有候选时为 0。
+    ///
+    /// 与组句用同一份请求统计（并入、插入、去掉的候选都算在内），页数与翻页一致。须取出全部候选（见 `EngineFacade::candidate_count`），
+    /// 结果按组合缓存，翻页时不再重新统计。
+    pub fn page_count(&mut self, engine: &dyn EngineFacade) -> usize {
+        if self.is_predicting() {
+            return 1;
+        }
+        let generation = engine.generation();
+        let cached = self.count_cache.as_ref().filter(|c| c.generation == generation && c.raw_input == self.raw_input && c.segment == self.analysis.segment && c.confirm == self.confirm && c.caret == self.caret);
+        let count = match cached {
+            Some(cache) => cache.count,
+            None => {
+                let recent = self.recent_candidates();
+                let count = engine.candidate_count(&self.raw_input, &self.analysis, self.confirm, self.caret, self.page_request(&recent));
+                self.count_cache = Some(CountCache { generation, raw_input: self.raw_input.clone(), segment: Arc::clone(&self.analysis.segment), confirm: self.confirm, caret: self.caret, count });
+                count
+            }
+        };
+        count.div_ceil(engine.page_size().max(1))
+    }
+
     /// 选词推进 confirm；若全部确认则 Commit 并 reset。
+    ///
+    /// `index` 是当前页内的下标。无法选择时状态不变，返回 `Action::Rejected`；选中时记下其排名（见 `mark_selected`）。
+    ///
+    /// `SentenceSelect::FirstWord` 时，多词的组句候选只确认第一个词，其余部分成为下一次的首选候选。
     pub fn select_candidate(&mut self, engine: &dyn EngineFacade, index: usize) -> Vec<Action> {
-        if self.raw_input.is_empty() || self.confirm >= self.caret {
-            return Vec::new();
+        if self.raw_input.is_empty() {
+            return vec![Action::Rejected(RejectReason::NotComposing)];
         }
-        let ui = self.ui_state(engine);
+        if self.confirm >= self.caret {
+            return vec![Action::Rejected(RejectReason::NoSuchCandidate)];
+        }
+        let ui = self.ui_state_cached(engine);
         let Some(cand) = ui.candidate_list.get(index) else {
-            return Vec::new();
+            return vec![Action::Rejected(RejectReason::NoSuchCandidate)];
         };
-        if cand.segment_start != self.confirm {
-            return Vec::new();
+        if cand.segment_start != self.confirm || cand.segment_end <= cand.segment_start || cand.segment_end > self.caret {
+            return vec![Action::Rejected(RejectReason::SpanNotAtConfirm)];
+        }
+        self.selected.push(self.page * engine.page_size() + index);
+        match cand.parts.split_first() {
+            Some((first, rest)) if engine.sentence_select() == SentenceSelect::FirstWord && !rest.is_empty() => {
+                self.record_pick(engine, &first.text, first.segment_start..first.segment_end, None, cand.weight);
+                self.confirm_text.push_str(&first.text);
+                self.confirm = first.segment_end;
+                self.sentence_rest = Some(Candidate {
+                    text: rest.iter().map(|w| w.text.as_str()).collect::<String>().into(),
+                    segment_start: first.segment_end,
+                    parts: rest.to_vec(),
+                    ..cand.clone()
+                });
+            }
+            _ => {
+                // 简拼与前缀补全记下完整编码（comment），其余记下所选的段
+                let key = match (&cand.source, &cand.comment) {
+                    (CandidateSource::Prefix | CandidateSource::Initials, Some(key)) => Some(key),
+                    _ => None,
+                };
+                self.record_pick(engine, &cand.text, cand.segment_start..cand.segment_end, key, cand.weight);
+                self.confirm_text.push_str(&cand.text);
+                self.confirm = cand.segment_end;
+                self.sentence_rest = None;
+            }
         }
-        if cand.segment_end <= cand.segment_start || cand.segment_end > self.caret {
-            return Vec::new();
+        self.page = 0;
+        self.settle_literals();
+        if self.confirm >= self.caret {
+            self.caret = self.caret_end();
         }
-        self.confirm_text.push_str(&cand.text);
-        self.confirm = cand.segment_end;
 
-        if self.confirm == self.caret {
-            if !self.confirm_text.is_empty() {
-                return vec![Action::Commit(std::mem::take(&mut self.confirm_text))];
+        if self.confirm == self.analysis.segment.len() && self.literal_open.is_none() {
+            let text = std::mem::take(&mut self.confirm_text);
+            if let Some(recent) = &mut self.recent {
+                recent.commit();
+            }
+            self.commit_choices();
+            if let Some(picks) = &self.picks {
+                self.learned = LearnedPhrase::from_picks(picks, &text);
             }
             self.reset();
+            if !text.is_empty() {
+                return vec![Action::Commit(text)];
+            }
         }
         Vec::new()
     }
+
+    /// 为最近上屏记忆、自动造词与用户词频记下一次选词：`key` 为完整编码（简拼、前缀补全的 comment）时按它切分音节，否则取所选的段。
+    fn record_pick(&mut self, engine: &dyn EngineFacade, text: &CandidateText, span: Range<usize>, key: Option<&CandidateText>, weight: i32) {
+        // 英文模式的输入不是编码
+        if self.mode == InputMode::English || self.recent.is_none() && self.picks.is_none() && self.choices.is_none() {
+            return;
+        }
+        // 码表编码与简拼直接确认的字母不是音节
+        let known = match self.analysis.kind {
+            SegmentationKind::Codes | SegmentationKind::Raw => false,
+            SegmentationKind::Initials => key.is_some(),
+            SegmentationKind::Syllables => true,
+        };
+        let (key, syllables): (CandidateText, Vec<String>) = match key {
+            Some(key) => (key.clone(), engine.analyze(key).segment.to_vec()),
+            None => {
+                let syllables = &self.analysis.segment[span];
+                (syllables.concat().into(), syllables.to_vec())
+            }
+        };
+        if let Some(picks) = &mut self.picks {
+            picks.push(Pick { text: text.clone(), syllables: known.then(|| syllables.clone()) });
+        }
+        if let Some(choices) = &mut self.choices {
+            choices.push((key.clone(), text.clone()));
+        }
+        if let Some(recent) = &mut self.recent {
+            recent.select(text.clone(), key, syllables, weight);
+        }
+    }
 }
@@ -0,0 +1,35 @@
+//! `convert`：输出文本转换（简 -> 繁等）。
+//!
+//! 转换数据在构造引擎时交给 `Engine::converter`（只加载一次，多个会话共享），
+//! 每个会话用 `InputEvent::ToggleTraditional` 在运行时开关（默认关闭，状态见 `UiState::traditional`）。
+//!
+//! 转换只作用于输出：候选、已确认文本与上屏文本在会话内部始终是词典原文，
+//! 输出时才经 `filter::SimplifierFilter` 转换。因此输入中途切换时，已确认的部分也随之转换（两个方向都是），
+//! 联想（`prediction`）仍以原文查询。
+
+use std::sync::Arc;
+
+/// 文本转换器：把词典原文转换为输出文本。
+pub trait TextConverter: Send + Sync {
+    fn convert(&self, text: &str) -> String;
+
+    /// `c` 所属简繁异体组的代表字（通常是简体，例如 `裡`、`裏` -> `里`），用于识别互为异体的候选（`filter::VariantDedupFilter`）。
+    ///
+    /// 默认不归并，每个字自成一组。
+    fn base_char(&self, c: char) -> char {
+        c
+    }
+}
+
+impl<C> TextConverter for Arc<C>
+where
+    C: TextConverter + ?Sized,
+{
+    fn convert(&self, text: &str) -> String {
+        (**self).convert(text)
+    }
+
+    fn base_char(&self, c: char) -> char {
+        (**self).base_char(c)
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::model::Candidate;
 
 /// 词典抽象：core 不关心词典来自文件/内存/网络。
@@ -11,8 +13,92 @@ pub trait Dictionary: Send + Sync {
     /// - `limit`: 返回候选数量上限（实现可自行 clamp）
     fn lookup_span(&self, segment: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate>;
 
+    /// 惰性查询 `segment[start..end]`：与 `lookup_span` 同序逐条产出，调用方取多少算多少。
+    ///
+    /// 约定 `lookup_span(.., limit)` 等于本迭代器的前 `limit` 条。默认实现一次取出全部结果，
+    /// 词典较大时应覆盖为真正的惰性遍历。
+    fn lookup_iter<'a>(&'a self, segment: &'a [String], start: usize, end: usize) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        Box::new(self.lookup_span(segment, start, end, usize::MAX).into_iter())
+    }
+
+    /// 简拼查询：`initials` 为逐字母的段（如 `["b","j"]`），产出首字母串与之相同的词，comment 为完整拼音。
+    ///
+    /// 只在切分结果是简拼 fallback（`SegmentationKind::Initials`）时由 translator 调用；
+    /// `a`/`e`/`o` 这类单字母音节不走这里。默认没有简拼索引。
+    fn lookup_initials<'a>(&'a self, initials: &'a [String]) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        let _ = initials;
+        Box::new(std::iter::empty())
+    }
+
+    /// 通配符查询：`code` 中的 `wildcard` 匹配任一字符，产出等长编码的词条（来源为 `CandidateSource::Fuzzy`，comment 为实际编码）。
+    ///
+    /// 只在码表方案（`SegmentationKind::Codes`）开启通配符时由 translator 调用。默认不支持。
+    fn lookup_wildcard<'a>(&'a self, code: &'a str, wildcard: char) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        let _ = (code, wildcard);
+        Box::new(std::iter::empty())
+    }
+
+    /// 反查：`text` 的全部编码（多音字、多音词有多个），按权重降序；用于给没有编码的候选（联想等）标注读音，或由汉字查拼音。
+    ///
+    /// 默认不支持反查，返回空。
+    fn reverse_lookup(&self, text: &str) -> Vec<String> {
+        let _ = text;
+        Vec::new()
+    }
+
+    /// 内容版本号：每次修改后递增。缓存（例如会话的 UI 快照）记下构建时的版本，不一致即作废。
+    ///
+    /// 只读词典保持默认的 0 即可。
+    fn generation(&self) -> u64 {
+        0
+    }
+
     /// 查询整段输入（默认走 `lookup_span(0..len)`）。
     fn lookup(&self, segment: &[String], limit: usize) -> Vec<Candidate> {
         self.lookup_span(segment, 0, segment.len(), limit)
     }
 }
+
+/// 多个引擎共享同一份只读词典（服务端等场景）。
+impl<D> Dictionary for Arc<D>
+where
+    D: Dictionary + ?Sized,
+{
+    fn lookup_span(&self, segment: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate> {
+        (**self).lookup_span(segment, start, end, limit)
+    }
+
+    fn lookup_iter<'a>(&'a self, segment: &'a [String], start: usize, end: usize) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        (**self).lookup_iter(segment, start, end)
+    }
+
+    fn lookup_initials<'a>(&'a self, initials: &'a [String]) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        (**self).lookup_initials(initials)
+    }
+
+    fn lookup_wildcard<'a>(&'a self, code: &'a str, wildcard: char) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        (**self).lookup_wildcard(code, wildcard)
+    }
+
+    fn lookup(&self, segment: &[String], limit: usize) -> Vec<Candidate> {
+        (**self).lookup(segment, limit)
+    }
+
+    fn reverse_lookup(&self, text: &str) -> Vec<String> {
+        (**self).reverse_lookup(text)
+    }
+
+    fn generation(&self) -> u64 {
+        (**self).generation()
+    }
+}
+
+/// 可在运行时修改的词典（用户词典、CLI `:add` 等）。
+///
+/// 实现须在每次修改后递增 `Dictionary::generation`。
+pub trait MutableDictionary: Dictionary {
+    /// 插入词条 `key -> text`；同一 `(key, text)` 已存在时改为新的 weight。
+    ///
+    /// 返回 true 表示新增，false 表示更新了已有词条。
+    fn insert(&mut self, key: &str, text: &str, weight: i32) -> bool;
+}
@@ -1,24 +1,116 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::blacklist::{Blacklist, blacklisted};
+use crate::config::{Annotation, DEFAULT_SELECT_LABELS, DedupMode, EngineConfig, Orientation, PreeditStyle, SentenceSelect};
+use crate::convert::TextConverter;
 use crate::dictionary::Dictionary;
-use crate::filter::{DedupSortTruncate, Filter};
-use crate::model::Candidate;
-use crate::model::UiState;
+use crate::explain::{EXPLAIN_WEIGHT, note_filter};
+use crate::filter::{AnnotationFilter, BlacklistFilter, DedupSortTruncate, Filter, RecencyBoostFilter, VariantDedupFilter};
+use crate::{bigram::BigramModel, history::UserHistory, learn::LearnedPhrases};
+use crate::model::{Candidate, CandidateSource, CandidateText, Diagnostic, source_groups};
+use crate::model::{LayoutHints, UiState};
+use crate::ranking::{FrequencyFirst, RankingPolicy};
+use crate::schema::AnalyzerSet;
 use crate::segmenter::Segmenter;
-use crate::translator::DictTranslator;
+use crate::translator::{DictTranslator, Translator};
 
 /// 解析结果（segment + preedit）。
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Analysis {
-    /// 切分后的音节段（全拼：`["qi","shi"]`；简拼：`["q","s"]`）
-    pub segment: Vec<String>,
+    /// 切分后的音节段（全拼：`["qi","shi"]`；简拼：`["q","s"]`）；与 `UiState` 共享，克隆只增加引用计数
+    pub segment: Arc<[String]>,
     /// 展示用 preedit（例如 `"qi shi"` / `"q s"`）
     pub preedit: String,
+    /// 每个音节段的模糊音拼写（与 `segment` 等长，不含自身；未开启模糊音时为空）
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub fuzzy: Vec<Vec<String>>,
+    /// 切分方式：音节切分，或无法切分时退化的简拼
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: SegmentationKind,
+    /// 整段输入的其他切分方案（如 `xian` 之于 `["xi","an"]`），按切分器的评分降序，不含 `segment` 本身；
+    /// 引擎翻译整段时也查这些切分（见 `QuanpinPreeditor::max_alternatives`）。多数切分器不提供，为空
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub alternatives: Vec<Vec<String>>,
+}
+
+impl Analysis {
+    /// 整段输入原样作为一段（`SegmentationKind::Raw`）；输入为空时没有段。
+    pub fn raw(raw_input: &str) -> Self {
+        Self {
+            segment: if raw_input.is_empty() { Arc::from([]) } else { Arc::from([raw_input.to_string()]) },
+            preedit: raw_input.to_string(),
+            fuzzy: Vec::new(),
+            kind: SegmentationKind::Raw,
+            alternatives: Vec::new(),
+        }
+    }
+}
+
+/// `Engine::compose_with_state` 取哪一页候选。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PageRequest<'a> {
+    /// 页码（0 起），每页 `candidate_limit` 个
+    pub page: usize,
+    /// 会话删除的候选（见 `blacklist`）：翻页之前去掉，各页由其后的候选补足
+    pub blacklist: Option<&'a Blacklist>,
+    /// 异步来源为当前组合注入的候选（见 `async_source`）：与本地候选一起排序、去重之后再分页
+    pub injected: &'a [Candidate],
+    /// 最近上屏的词（见 `recent`）：同注入的候选，排序、去重之后再分页
+    pub recent: &'a [Candidate],
+    /// 逐词确认时句子的其余部分：排在第一位，与它重复的候选（按 `dedup_mode`）去掉
+    pub sentence_rest: Option<&'a Candidate>,
+    /// 英文模式（见 `Context::set_mode`）：原始输入排在第一位，与它相同的候选去掉
+    pub english: bool,
+    /// 当前输出繁体（见 `Session::is_traditional`）：开启 `variant_dedup` 时据此保留异体中的一种写法
+    pub traditional: bool,
+}
+
+/// `Analysis::segment` 是怎么得来的。
+///
+/// 单字母的段未必是简拼（`a`/`e`/`o` 本身就是音节），词典只凭段长无法区分，须由切分器标明。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SegmentationKind {
+    /// 按音节切分（每段是一个合法音节）
+    #[default]
+    Syllables,
+    /// 简拼 fallback：每段是一个首字母
+    Initials,
+    /// 码表：每段是一个完整编码（见 `rime_pinyin::TableAnalyzer`）；不查简拼
+    Codes,
+    /// 原样输入（临时英文、英文模式或无法切分的字母串）：整段输入作为一段，只由英文翻译器查询（见 `Engine::english_translator`）
+    Raw,
 }
 
 /// 纯接口：把 raw input 解析为音节段（segment）并给出 preedit 展示。
 ///
 /// 备注：当前 `rime_pinyin::QuanpinPreeditor` 同时承担“全拼切分 + 简拼 fallback”。
+///
+/// 运行时才确定方案（全拼/双拼等）时，使用 `Box<dyn Analyzer>` 作为 `Engine`/`Session` 的类型参数：
+/// `Box<dyn Analyzer>` 同样实现 `Analyzer`（因而也实现 `Segmenter`）。
 pub trait Analyzer: Send + Sync {
     fn analyze(&self, input: &str) -> Analysis;
+
+    /// 可在会话内切换的方案（见 `schema`）；普通 analyzer 没有。
+    fn schemas(&self) -> Option<&AnalyzerSet> {
+        None
+    }
+}
+
+impl<A> Analyzer for Box<A>
+where
+    A: Analyzer + ?Sized,
+{
+    fn analyze(&self, input: &str) -> Analysis {
+        (**self).analyze(input)
+    }
+
+    fn schemas(&self) -> Option<&AnalyzerSet> {
+        (**self).schemas()
+    }
 }
 
 /// 引擎：负责把输入状态（segment/caret/confirmed）转成 `UiState`。
@@ -30,12 +122,60 @@ pub struct Engine<D, A> {
     analyzer: A,
     /// 词典（TSV 或其他实现）
     dictionary: D,
-    /// 候选词数量（1-9）；超出范围时回退到默认值
+    /// 每页候选词数量（1-9）；builder 不接受范围外的值（见 `candidate_limit`）
     candidate_limit: u8,
     /// 组词时单个“词”最多覆盖多少个音节段
     max_word_length: u8,
-    /// 每个 span 查询最多取多少条（用于控制 beam search 扩展规模）
-    per_span_limit: usize,
+    /// 组句时每个 span 最多取多少个词扩展路径（beam search 的扩展规模）
+    beam_span_limit: usize,
+    /// 组句时每个段位置保留的路径数；None 为按所需条数自动取
+    beam_width: Option<usize>,
+    /// 单词候选每个 span 最多出多少条
+    word_candidate_limit: usize,
+    /// 候选总数上限（各页合计）；None 为不限
+    final_limit: Option<usize>,
+    /// 单次组句最多查询词典多少次；None 为不限
+    max_lookups: Option<usize>,
+    /// 整段输入的前缀补全
+    prefix_completion: bool,
+    /// 码表编码中的通配符
+    wildcard: Option<char>,
+    /// 码表满码长度（自动上屏）
+    auto_commit_length: Option<usize>,
+    /// 候选按来源分组展示
+    group_by_source: bool,
+    /// 候选去重的条件
+    dedup_mode: DedupMode,
+    /// 选择组句候选时的确认方式
+    sentence_select: SentenceSelect,
+    /// 单字候选的读音标注
+    annotation: Annotation,
+    /// 内嵌组合串的样式
+    preedit_style: PreeditStyle,
+    /// 候选窗排列方向（展示提示）
+    orientation: Orientation,
+    /// 候选标签（展示提示）
+    select_labels: Arc<str>,
+    /// 输出文本转换（简 -> 繁），会话内开关（见 `convert`）
+    converter: Option<Arc<dyn TextConverter>>,
+    /// 按 `converter` 合并简繁异体候选
+    variant_dedup: bool,
+    /// 候选在元数据中带上分数组成（见 `explain`）
+    explain: bool,
+    /// 候选排序策略
+    ranking: Arc<dyn RankingPolicy>,
+    /// 用户词频与每次选择的加分（见 `history`）
+    history: Option<(UserHistory, i32)>,
+    /// 自动造词学到的词（见 `learn`）
+    learned: LearnedPhrases,
+    /// 组句用的二元模型（见 `bigram`）
+    bigram: Option<Arc<BigramModel>>,
+    /// 英文单词补全（`SegmentationKind::Raw`）
+    english: Option<Arc<dyn Translator>>,
+    /// 额外的翻译器（候选与词典候选一起排序去重）
+    translators: Vec<Arc<dyn Translator>>,
+    /// 额外的候选后处理（在默认 filter 之后依次执行）
+    filters: Vec<Arc<dyn Filter>>,
 }
 
 impl<D, A> Engine<D, A>
@@ -49,35 +189,380 @@ where
             analyzer,
             candidate_limit: 9,
             max_word_length: 4,
-            per_span_limit: 16,
+            beam_span_limit: 16,
+            beam_width: None,
+            word_candidate_limit: 16,
+            final_limit: None,
+            max_lookups: None,
+            prefix_completion: true,
+            wildcard: None,
+            auto_commit_length: None,
+            group_by_source: false,
+            dedup_mode: DedupMode::ByTextAndSpan,
+            sentence_select: SentenceSelect::WholeSentence,
+            annotation: Annotation::Off,
+            preedit_style: PreeditStyle::Pinyin,
+            orientation: Orientation::Vertical,
+            select_labels: Arc::from(DEFAULT_SELECT_LABELS),
+            converter: None,
+            variant_dedup: false,
+            explain: false,
+            ranking: Arc::new(FrequencyFirst),
+            history: None,
+            learned: LearnedPhrases::new(),
+            bigram: None,
+            english: None,
+            translators: Vec::new(),
+            filters: Vec::new(),
         }
     }
 
-    /// 设置候选词数量上限（1..=9）；非法值会回退到 9。
+    /// 按 `EngineConfig` 创建引擎（等价于 `new` 后逐项调用 builder）。
+    ///
+    /// # Panics
+    ///
+    /// 参数越界时 panic，规则同各 builder。
+    pub fn with_config(dictionary: D, analyzer: A, config: &EngineConfig) -> Self {
+        Self::new(dictionary, analyzer)
+            .candidate_limit(config.candidate_limit)
+            .max_word_length(config.max_word_length)
+            .beam_span_limit(config.beam_span_limit)
+            .beam_width(config.beam_width)
+            .word_candidate_limit(config.word_candidate_limit)
+            .final_limit(config.final_limit)
+            .max_lookups(config.max_lookups)
+            .prefix_completion(config.prefix_completion)
+            .wildcard(config.wildcard)
+            .auto_commit_length(config.auto_commit_length)
+            .group_by_source(config.group_by_source)
+            .dedup_mode(config.dedup_mode)
+            .sentence_select(config.sentence_select)
+            .annotation(config.annotation)
+            .preedit_style(config.preedit_style)
+            .variant_dedup(config.variant_dedup)
+            .explain(config.explain)
+            .orientation(config.orientation)
+            .select_labels(&config.select_labels)
+    }
+
+    /// 设置每页候选词数量（1..=9）。
+    ///
+    /// # Panics
+    ///
+    /// `limit` 不在 1..=9 时 panic：选词键只有 1-9，超过 9 个的候选无法选择。
     pub fn candidate_limit(mut self, limit: u8) -> Self {
-        if limit < 2 || limit > 9 {
-            self.candidate_limit = 9;
-        } else {
-            self.candidate_limit = limit;
-        }
+        assert!((1..=9).contains(&limit), "candidate_limit 须在 1..=9 之间，实际为 {limit}");
+        self.candidate_limit = limit;
         self
     }
 
     /// 限制组词时单个词最多覆盖多少个音节段。
+    ///
+    /// # Panics
+    ///
+    /// `n` 为 0 时 panic。
     pub fn max_word_length(mut self, n: u8) -> Self {
-        self.max_word_length = n.max(1);
+        assert!(n > 0, "max_word_length 不能为 0");
+        self.max_word_length = n;
+        self
+    }
+
+    /// 同时设置 `beam_span_limit` 与 `word_candidate_limit`。
+    ///
+    /// # Panics
+    ///
+    /// `n` 为 0 时 panic。
+    pub fn per_span_limit(self, n: usize) -> Self {
+        self.beam_span_limit(n).word_candidate_limit(n)
+    }
+
+    /// 组句时每个 span 最多取多少个词扩展路径：越大组句越准，查询与组合越多。
+    ///
+    /// # Panics
+    ///
+    /// `n` 为 0 时 panic。
+    pub fn beam_span_limit(mut self, n: usize) -> Self {
+        assert!(n > 0, "beam_span_limit 不能为 0");
+        self.beam_span_limit = n;
+        self
+    }
+
+    /// 组句时每个段位置保留多少条路径（beam 宽度）：越大组句越准，组合越多；None（默认）为按所需条数取 8..=64。
+    ///
+    /// # Panics
+    ///
+    /// `n` 为 `Some(0)` 时 panic。
+    pub fn beam_width(mut self, n: Option<usize>) -> Self {
+        assert!(n != Some(0), "beam_width 不能为 0");
+        self.beam_width = n;
+        self
+    }
+
+    /// 单词候选（从光标处起、覆盖 1..=`max_word_length` 段的词）每个 span 最多出多少条。
+    ///
+    /// 单词候选多于所需条数时不再组句，调小它可让组句候选更早出现。
+    ///
+    /// # Panics
+    ///
+    /// `n` 为 0 时 panic。
+    pub fn word_candidate_limit(mut self, n: usize) -> Self {
+        assert!(n > 0, "word_candidate_limit 不能为 0");
+        self.word_candidate_limit = n;
+        self
+    }
+
+    /// 候选总数上限（各页合计，翻页到此为止）；None 为不限。
+    ///
+    /// # Panics
+    ///
+    /// `n` 为 `Some(0)` 时 panic。
+    pub fn final_limit(mut self, n: Option<usize>) -> Self {
+        assert!(n != Some(0), "final_limit 不能为 0");
+        self.final_limit = n;
+        self
+    }
+
+    /// 单次组句最多查询词典多少次（慢机器上以候选质量换延迟）；None 为不限。
+    ///
+    /// 按查询次数计，同样的输入总是得到同样的候选；预算用尽时 `UiState::truncated` 为 true。
+    pub fn max_lookups(mut self, n: Option<usize>) -> Self {
+        self.max_lookups = n;
+        self
+    }
+
+    /// 整段输入是否带前缀补全候选（`CandidateSource::Prefix`，comment 为完整 key）；默认开启。
+    ///
+    /// 补全按拼音音节边界筛选，对码表编码没有意义，码表方案（`SegmentationKind::Codes`）宜关闭。
+    pub fn prefix_completion(mut self, enabled: bool) -> Self {
+        self.prefix_completion = enabled;
+        self
+    }
+
+    /// 码表编码中的通配符（例如五笔的 `z`）：含通配符的编码匹配等长的编码，comment 为实际编码；默认不支持。
+    ///
+    /// 只对码表方案（`SegmentationKind::Codes`）的单个编码生效；通配符须是编码中不会出现的字符。
+    pub fn wildcard(mut self, wildcard: Option<char>) -> Self {
+        self.wildcard = wildcard;
+        self
+    }
+
+    /// 码表满码长度（例如五笔为 4）：码满且只有一个候选时自动上屏；已满码仍继续输入时，先上屏首选再开始下一个编码（见 `processor::EditingProcessor`）。
+    ///
+    /// 只对码表方案（`SegmentationKind::Codes`）生效；None（默认）为不自动上屏。
+    ///
+    /// # Panics
+    ///
+    /// 长度为 0 时 panic。
+    pub fn auto_commit_length(mut self, n: Option<usize>) -> Self {
+        assert!(n != Some(0), "auto_commit_length 不能为 0");
+        self.auto_commit_length = n;
+        self
+    }
+
+    /// 候选按来源分组展示（`UiState::groups` 给出各组范围），而不是按分数交错排列（默认）。
+    ///
+    /// 分组只改变排列顺序，`Select(n)` 仍按展示顺序取第 n 个。
+    pub fn group_by_source(mut self, enabled: bool) -> Self {
+        self.group_by_source = enabled;
+        self
+    }
+
+    /// 候选去重的条件（见 `DedupMode`）：默认文本与范围都相同才合并；`ByTextOnly` 时同一个词只出现一次。
+    pub fn dedup_mode(mut self, mode: DedupMode) -> Self {
+        self.dedup_mode = mode;
+        self
+    }
+
+    /// 选择组句候选时确认整句（默认）还是只确认第一个词（见 `SentenceSelect`）。
+    pub fn sentence_select(mut self, mode: SentenceSelect) -> Self {
+        self.sentence_select = mode;
+        self
+    }
+
+    /// 为单字候选标注读音（`comment` 填入编码）：全部、只标有歧义的，或不标注（默认）。
+    pub fn annotation(mut self, mode: Annotation) -> Self {
+        self.annotation = mode;
         self
     }
 
+    /// 内嵌组合串（`UiState::composition`）显示拼音（默认）还是预览上屏结果。
+    pub fn preedit_style(mut self, style: PreeditStyle) -> Self {
+        self.preedit_style = style;
+        self
+    }
+
+    /// 候选窗横排还是竖排（`UiState::layout`，只是给前端的提示）；默认竖排。
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// 候选标签（`UiState::layout`），第 n 个字符标注第 n 个候选；默认 `123456789`。
+    pub fn select_labels(mut self, labels: &str) -> Self {
+        self.select_labels = Arc::from(labels);
+        self
+    }
+
+    /// 按 `annotation` 设置为候选标注读音（见 `AnnotationFilter`）；`segment` 为候选范围所索引的切分结果。
+    pub fn annotate(&self, segment: &[String], candidates: Vec<Candidate>) -> Vec<Candidate> {
+        AnnotationFilter { mode: self.annotation, segment, dictionary: &self.dictionary }.apply(candidates)
+    }
+
+    /// 设置候选排序策略（见 `ranking`），默认 `FrequencyFirst`。
+    ///
+    /// 会话每次上屏都会通知策略（`RankingPolicy::on_commit`），共享此引擎的会话共用同一个策略。
+    pub fn ranking_policy(mut self, policy: Arc<dyn RankingPolicy>) -> Self {
+        self.ranking = policy;
+        self
+    }
+
+    /// 开启用户词频（见 `history`）：候选按它在 `history` 中被选过的次数加分，每次加 `boost`（按 `history` 的半衰期衰减）；默认关闭。
+    ///
+    /// 会话上屏时把所选的词记入 `history`，共享此引擎的会话共用同一份记录。
+    pub fn user_history(mut self, history: UserHistory, boost: i32) -> Self {
+        self.history = Some((history, boost));
+        self
+    }
+
+    /// 用户词频；未开启时为 None。
+    pub fn history(&self) -> Option<&UserHistory> {
+        self.history.as_ref().map(|(history, _)| history)
+    }
+
+    /// 换用 `phrases` 存放自动造词学到的词（见 `learn`），例如调用方读入的上次保存的结果；默认为空。
+    ///
+    /// 查词时排在词典之前；会话上屏时写入，共享此引擎的会话共用同一份。
+    pub fn phrase_store(mut self, phrases: LearnedPhrases) -> Self {
+        self.learned = phrases;
+        self
+    }
+
+    /// 自动造词学到的词。
+    pub fn learned_phrases(&self) -> &LearnedPhrases {
+        &self.learned
+    }
+
+    /// 组句时按二元模型给相邻两词加分（见 `bigram`）；默认没有，组句只看权重与长度。
+    pub fn bigram_model(mut self, model: Arc<BigramModel>) -> Self {
+        self.bigram = Some(model);
+        self
+    }
+
+    /// 设置输出文本转换（简 -> 繁）；会话用 `InputEvent::ToggleTraditional` 开关，默认关闭。
+    ///
+    /// 转换数据只在这里交入一次，由共享此引擎的会话共用。
+    pub fn converter(mut self, converter: Arc<dyn TextConverter>) -> Self {
+        self.converter = Some(converter);
+        self
+    }
+
+    /// 互为简繁异体的候选（如 `里`/`裡`/`裏`）只保留一个，按会话当前的输出保留简体或繁体写法，其余折叠进 comment（见 `VariantDedupFilter`）。
+    ///
+    /// 异体关系取自 `converter` 的转换表，未设置 `converter` 时不起作用；默认关闭。
+    pub fn variant_dedup(mut self, enabled: bool) -> Self {
+        self.variant_dedup = enabled;
+        self
+    }
+
+    /// 每个候选在元数据中带上分数的组成：词典权重、组句长度加成与二元加分、改动过它的 filter（见 `explain::Explanation`）；默认关闭。
+    ///
+    /// 只用于调试排序：开启后每次组合都要多分配元数据，并在 filter 前后比对候选。
+    pub fn explain(mut self, enabled: bool) -> Self {
+        self.explain = enabled;
+        self
+    }
+
+    /// 设置英文单词补全（例如 `rime_dict::EnglishTranslator`）；默认不设置。
+    ///
+    /// 只在以下情况接管整段输入（`SegmentationKind::Raw`），不与拼音候选混排：
+    ///
+    /// - 输入以大写字母开头（临时英文；会话须以 `InputPolicy::keep_case` 保留大小写）
+    /// - 输入全是字母与 `'`，但切分器无法切分
+    pub fn english_translator(mut self, translator: Arc<dyn Translator>) -> Self {
+        self.english = Some(translator);
+        self
+    }
+
+    /// 追加一个翻译器（例如脚本实现的触发式候选）：对每个候选范围查询，候选与词典候选一起排序、去重。
+    pub fn translator(mut self, translator: Arc<dyn Translator>) -> Self {
+        self.translators.push(translator);
+        self
+    }
+
+    /// 追加一个候选后处理：在默认的去重、排序之后按追加顺序执行，结果再截断到所需条数。
+    pub fn filter(mut self, filter: Arc<dyn Filter>) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// 输出文本转换；未设置时为 None。
+    pub fn text_converter(&self) -> Option<&dyn TextConverter> {
+        self.converter.as_deref()
+    }
+
+    /// 每页候选词数量。
+    pub fn page_size(&self) -> usize {
+        usize::from(self.candidate_limit)
+    }
+
+    /// 词典（只读）。
+    pub fn dictionary(&self) -> &D {
+        &self.dictionary
+    }
+
+    /// 词典内容版本（见 `Dictionary::generation`），学到新词、用户词频变化时也会变。
+    pub fn generation(&self) -> u64 {
+        self.dictionary.generation() + self.learned.generation() + self.history().map_or(0, UserHistory::generation)
+    }
+
+    /// 词典（可变，例如配合 `MutableDictionary` 在运行时加词）。
+    pub fn dictionary_mut(&mut self) -> &mut D {
+        &mut self.dictionary
+    }
+
     /// 将 raw_input 切分成 segment + preedit（不包含候选生成）。
     pub fn analyze(&self, raw_input: &str) -> Analysis {
-        self.analyzer.analyze(raw_input)
+        self.english_analysis(raw_input, self.analyzer.analyze(raw_input))
+    }
+
+    /// 设置了英文翻译器且输入应按英文处理时（见 `english_translator`），把切分结果换成整段原样输入；
+    /// 没有英文翻译器时，只有大写开头且无法切分的输入换成原样输入（候选只有原样的英文串，见 `compose_with_state`）。
+    fn english_analysis(&self, raw_input: &str, analysis: Analysis) -> Analysis {
+        let word = raw_input.starts_with(|c: char| c.is_ascii_alphabetic()) && raw_input.chars().all(|c| c.is_ascii_alphabetic() || c == '\'');
+        let capitalized = raw_input.starts_with(|c: char| c.is_ascii_uppercase());
+        let raw = match self.english {
+            Some(_) => capitalized || analysis.segment.is_empty(),
+            None => capitalized && analysis.segment.is_empty(),
+        };
+        if !word || !raw {
+            return analysis;
+        }
+        Analysis::raw(raw_input)
     }
 
     /// 快捷接口：从 raw_input 直接生成 `UiState`（默认 confirmed=0, caret=末尾）。
     pub fn compose(&self, raw_input: &str) -> UiState {
         let analysis: Analysis = self.analyze(raw_input);
-        self.compose_with_state(raw_input, analysis, 0, None, String::new())
+        self.compose_with_state(raw_input, analysis, 0, None, String::new(), PageRequest::default())
+    }
+
+    /// 无状态查询：整段输入的前 `limit` 个候选（不分页）。
+    pub fn query(&self, raw_input: &str, limit: usize) -> Vec<Candidate> {
+        self.query_with_diagnostics(raw_input, limit).0
+    }
+
+    /// 同 `query`，另返回诊断信息（见 `Diagnostic`）。
+    pub fn query_with_diagnostics(&self, raw_input: &str, limit: usize) -> (Vec<Candidate>, Vec<Diagnostic>) {
+        let analysis = self.analyze(raw_input);
+        if analysis.segment.is_empty() {
+            let diagnostics = if raw_input.is_empty() { Vec::new() } else { vec![Diagnostic::NoSegmentation] };
+            return (Vec::new(), diagnostics);
+        }
+        if limit == 0 {
+            return (Vec::new(), Vec::new());
+        }
+        let (candidates, diagnostics, _) = self.compose_from_segment(&analysis, 0, analysis.segment.len(), limit);
+        (candidates, diagnostics)
     }
 
     /// 面向 Session：给定 segment/caret/confirm，生成“下一段要选”的候选。
@@ -85,6 +570,11 @@ where
     /// - `confirm`: 已确认到哪个段位置（不含）
     /// - `caret`: 光标位置；None 表示末尾
     /// - `confirm_text`: 已确认文本（用于 UI 展示与最终 Commit 聚合）
+    /// - `request`: 候选页码（0 起，每页 `candidate_limit` 个）与要去掉的删除过的候选
+    ///
+    /// 输入以大写字母开头（会话须以 `InputPolicy::keep_case` 保留大小写）且候选覆盖整段时，原样的英文串排在第一页末尾
+    /// （`comment` 为 `"en"`，其后的候选顺延），已有同样文本的候选时不再加。
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(raw = raw_input, confirm = confirm, page = request.page)))]
     pub fn compose_with_state(
         &self,
         raw_input: &str,
@@ -92,48 +582,336 @@ where
         confirm: usize,
         caret: Option<usize>,
         confirm_text: String,
+        request: PageRequest<'_>,
     ) -> UiState {
-        let preedit: String = analysis.preedit;
-        let segment: Vec<String> = analysis.segment;
-        let caret: usize = caret.unwrap_or(segment.len()).min(segment.len());
+        let page = request.page;
+        let len = analysis.segment.len();
+        let mut diagnostics = Vec::new();
+        if len == 0 && !raw_input.is_empty() {
+            diagnostics.push(Diagnostic::NoSegmentation);
+        }
+        let requested_caret = caret.unwrap_or(len);
+        if requested_caret > len || confirm > requested_caret {
+            diagnostics.push(Diagnostic::SpanOutOfBounds { confirm, caret: requested_caret, len });
+        }
+        let caret: usize = requested_caret.min(len);
         let confirmed: usize = confirm.min(caret);
 
         // 只对 [confirmed, caret) 生成候选，便于“逐段确认”的交互模型。
-        let candidate_list = if segment.is_empty() || confirmed >= caret {
-            Vec::new()
+        let (candidate_list, has_next_page) = if len == 0 || confirmed >= caret {
+            (Vec::new(), false)
         } else {
-            self.compose_from_segment(&segment, confirmed, caret)
+            let english = english_slot(raw_input, confirmed, caret, len, request.english);
+            let (candidates, has_next_page, notes) = self.compose_page(&analysis, confirmed, caret, request, english);
+            diagnostics.extend(notes);
+            (self.annotate(&analysis.segment, candidates), has_next_page)
         };
-        UiState {
+        let truncated = diagnostics.contains(&Diagnostic::BudgetExhausted);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(span = ?(confirmed, caret), count = candidate_list.len(), has_next_page, top = %crate::model::summarize(&candidate_list, 3), "compose");
+        let groups = if self.group_by_source { source_groups(&candidate_list) } else { Vec::new() };
+        let mut ui = UiState {
             raw_input: raw_input.to_owned(),
-            preedit,
-            segment,
+            preedit: analysis.preedit,
+            segment: analysis.segment,
             caret,
             confirm: confirmed,
             confirm_text,
             candidate_list,
+            page,
+            has_next_page,
+            truncated,
+            predicting: false,
+            injected: false,
+            traditional: false,
+            english: false,
+            groups,
+            composition: String::new(),
+            composition_caret: 0,
+            schema: String::new(),
+            layout: LayoutHints {
+                page_size: usize::from(self.candidate_limit),
+                orientation: self.orientation,
+                labels: Arc::clone(&self.select_labels),
+                has_prev_page: page > 0,
+                has_next_page,
+            },
+            diagnostics,
+        };
+        ui.update_composition(self.preedit_style);
+        ui
+    }
+
+    /// `confirm..caret` 各页合计有多少个候选（按 `request` 并入、去掉候选，与 `compose_with_state` 一致；不看页码），用于展示总页数。
+    ///
+    /// 须取出全部候选（只受 `final_limit` 限制），比组合一页慢得多，只宜在展示总页数时调用。
+    pub fn candidate_count(&self, raw_input: &str, analysis: &Analysis, confirm: usize, caret: usize, request: PageRequest<'_>) -> usize {
+        let len = analysis.segment.len();
+        if confirm >= caret || caret > len {
+            return 0;
         }
+        let english = english_slot(raw_input, confirm, caret, len, request.english);
+        self.ranked_candidates(analysis, confirm, caret, usize::MAX, request, english).0.len()
     }
 
-    fn compose_from_segment(&self, segment: &[String], start: usize, end: usize) -> Vec<Candidate> {
-        // translator：负责查词与组句
-        let translator = DictTranslator {
-            dict: &self.dictionary,
-            max_word_length: self.max_word_length,
-            per_span_limit: self.per_span_limit,
+    /// 取第 `request.page` 页候选：多取一条用于判断是否还有下一页。返回 (候选, 是否有下一页, 诊断信息)。
+    fn compose_page(&self, analysis: &Analysis, start: usize, end: usize, request: PageRequest<'_>, english: Option<English<'_>>) -> (Vec<Candidate>, bool, Vec<Diagnostic>) {
+        let page_size = usize::from(self.candidate_limit);
+        let offset = request.page.saturating_mul(page_size);
+        let (mut all, diagnostics) = self.ranked_candidates(analysis, start, end, offset.saturating_add(page_size + 1), request, english);
+        let has_next_page = all.len() > offset + page_size;
+        let candidate_list = if offset < all.len() {
+            all.drain(offset..).take(page_size).collect()
+        } else {
+            Vec::new()
         };
-        let out = translator.translate_with_composition(
-            segment,
-            start,
-            end,
-            usize::from(self.candidate_limit),
-        );
+        (candidate_list, has_next_page, diagnostics)
+    }
+
+    /// 排好序的前 `limit` 个候选（分页之前）及诊断信息。
+    ///
+    /// `request` 中注入的候选、最近上屏的词在此并入第一页（见 `merge_into_first_page`）；原样的英文串（`english`）排在第一页末尾，
+    /// 英文模式下排在第一位；句子的其余部分（`request.sentence_rest`）排在第一位（英文模式下排在原始输入之后）。
+    /// 各页都从头取候选，并入、插入的位置每页一致。开启 `variant_dedup` 时互为异体的候选最后再折叠一次（并入、插入的也算在内）。
+    fn ranked_candidates(&self, analysis: &Analysis, start: usize, end: usize, limit: usize, request: PageRequest<'_>, english: Option<English<'_>>) -> (Vec<Candidate>, Vec<Diagnostic>) {
+        let page_size = usize::from(self.candidate_limit);
+        let blacklist = request.blacklist.filter(|b| !b.is_empty());
+        let (mut all, mut diagnostics) = self.refilled_candidates(analysis, start, end, limit, request);
+        if !request.injected.is_empty() || !request.recent.is_empty() {
+            all = self.merge_into_first_page(analysis, start..end, all, limit, request);
+            if !all.is_empty() {
+                diagnostics.retain(|d| *d != Diagnostic::NoCandidates { start, end });
+            }
+        }
+        // 只与第一页比较：每页都至少取到第一页
+        if let Some(English::Trailing(text)) = english
+            && !all.iter().take(page_size).any(|c| *c.text == *text)
+        {
+            let candidate = english_candidate(text, Some("en".into()), start, end);
+            if !blacklist.is_some_and(|b| blacklisted(b, &candidate, &analysis.segment)) {
+                all.insert(all.len().min(page_size.saturating_sub(1)), candidate);
+                diagnostics.retain(|d| *d != Diagnostic::NoCandidates { start, end });
+            }
+        }
+        if let Some(rest) = request.sentence_rest {
+            let dedup = self.dedup_mode;
+            all.retain(|c| dedup.key(c) != dedup.key(rest));
+            all.insert(0, rest.clone());
+            diagnostics.retain(|d| *d != Diagnostic::NoCandidates { start, end });
+        }
+        if let Some(English::Leading(text)) = english {
+            all.retain(|c| *c.text != *text);
+            all.insert(0, english_candidate(text, None, start, end));
+            diagnostics.retain(|d| *d != Diagnostic::NoCandidates { start, end });
+        }
+        if let Some(filter) = self.variant_filter(request.traditional) {
+            all = filter.apply(all);
+        }
+        (all, diagnostics)
+    }
+
+    /// 去掉 `request.blacklist` 中的候选、折叠简繁异体（开启 `variant_dedup` 时）之后的前 `limit` 个候选。
+    ///
+    /// 去重、删除或折叠掉的候选（在此去掉）使取到的不足 `limit` 条时，差几条就多取几条，直到取够或 translator 再也取不满，
+    /// 每页仍是满的（最后一页除外），有没有下一页也不会因此误判。
+    fn refilled_candidates(&self, analysis: &Analysis, start: usize, end: usize, limit: usize, request: PageRequest<'_>) -> (Vec<Candidate>, Vec<Diagnostic>) {
+        let blacklist = request.blacklist.filter(|b| !b.is_empty());
+        let variant = self.variant_filter(request.traditional);
+        let wanted = limit;
+        let mut limit = limit;
+        loop {
+            let (mut all, mut diagnostics, more) = self.compose_from_segment(analysis, start, end, limit);
+            if let Some(blacklist) = blacklist {
+                all = apply_filter(&BlacklistFilter { blacklist, segment: &analysis.segment }, all, &mut diagnostics);
+            }
+            if let Some(filter) = &variant {
+                all = filter.apply(all);
+            }
+            if all.len() >= wanted || !more {
+                return (all, diagnostics);
+            }
+            limit = limit.saturating_add(wanted - all.len());
+        }
+    }
+
+    /// 把注入的候选和最近上屏的词（去掉 `request.blacklist` 中的）并入第一页：与第一页的候选一起按 filter 的规则排序、去重，挤出第一页的候选顺延到后面的页。
+    ///
+    /// translator 取的条数不同时分数可能不同（条数多时单词候选也作为组句候选，带长度加成），第一页因此总以只取一页时的结果为准：
+    /// 不论取的是哪一页，并入的位置都一样。`all` 为取到的前 `limit` 个候选。
+    fn merge_into_first_page(&self, analysis: &Analysis, span: Range<usize>, all: Vec<Candidate>, limit: usize, request: PageRequest<'_>) -> Vec<Candidate> {
+        let page_size = usize::from(self.candidate_limit);
+        let extra: Vec<Candidate> = request.injected.iter().chain(request.recent).cloned().collect();
+        let extra = match request.blacklist.filter(|b| !b.is_empty()) {
+            Some(blacklist) => BlacklistFilter { blacklist, segment: &analysis.segment }.apply(extra),
+            None => extra,
+        };
+        let mut first = if limit == page_size + 1 { all.clone() } else { self.refilled_candidates(analysis, span.start, span.end, page_size + 1, request).0 };
+        first.truncate(page_size);
+        let dedup = self.dedup_mode;
+        let merged_keys: HashSet<_> = first.iter().chain(&extra).map(|c| dedup.key(c)).collect();
+        first.extend(extra);
+        let mut merged = DedupSortTruncate { limit: usize::MAX, group_by_source: self.group_by_source, ranking: &*self.ranking, dedup }.apply(first);
+        merged.extend(all.into_iter().filter(|c| !merged_keys.contains(&dedup.key(c))));
+        merged
+    }
+
+    /// 开启 `variant_dedup` 且有简繁转换器时，按 `traditional` 保留写法的异体折叠 filter。
+    fn variant_filter(&self, traditional: bool) -> Option<VariantDedupFilter<'_>> {
+        let converter = self.converter.as_deref().filter(|_| self.variant_dedup)?;
+        Some(VariantDedupFilter { converter, traditional })
+    }
+
+    /// 翻译并过滤出 `analysis.segment[start..end]` 的前 `limit` 个候选（不超过 `final_limit`）；另返回诊断信息（含查询预算是否用尽），
+    /// 以及再多取是否可能得到更多候选（translator 取满了 `limit` 条，且未到 `final_limit`；去重后可能不足 `limit` 条）。
+    ///
+    /// 翻译整段时也翻译 `Analysis::alternatives` 中的各种切分，只取覆盖整段的候选：范围记为整段，不带逐词切分（`parts`）。
+    fn compose_from_segment(&self, analysis: &Analysis, start: usize, end: usize, limit: usize) -> (Vec<Candidate>, Vec<Diagnostic>, bool) {
+        let segment = &analysis.segment[..];
+        let mut diagnostics = Vec::new();
+        let limit = self.final_limit.map_or(limit, |n| limit.min(n));
+        let (mut out, mut truncated) = if analysis.kind == SegmentationKind::Raw {
+            (self.english.as_ref().map(|english| english.translate(segment, start, end, limit)).unwrap_or_default(), false)
+        } else {
+            self.dict_translator(&analysis.fuzzy, analysis.kind).translate_with_budget(segment, start, end, limit)
+        };
+        if start == 0 && end == segment.len() && analysis.kind == SegmentationKind::Syllables {
+            for alternative in &analysis.alternatives {
+                let (candidates, exhausted) = self.dict_translator(&[], analysis.kind).translate_with_budget(alternative, 0, alternative.len(), limit);
+                truncated |= exhausted;
+                let whole = candidates.into_iter().filter(|c| c.segment_start == 0 && c.segment_end == alternative.len());
+                out.extend(whole.map(|c| Candidate { segment_start: start, segment_end: end, parts: Vec::new(), ..c }));
+            }
+        }
+        for translator in &self.translators {
+            out.extend(translator.translate(segment, start, end, limit));
+        }
+        if out.is_empty() {
+            diagnostics.push(Diagnostic::NoCandidates { start, end });
+        }
+        let more = out.len() >= limit && self.final_limit.is_none_or(|n| limit < n);
+        if truncated {
+            diagnostics.push(Diagnostic::BudgetExhausted);
+        }
         // filter：负责去重/排序/截断
-        DedupSortTruncate {
-            limit: self.candidate_limit,
+        let out = if self.explain {
+            self.filter_explained(out, segment, limit, &mut diagnostics)
+        } else {
+            let out = self.boost_history(out, segment);
+            let mut out = DedupSortTruncate { limit, group_by_source: self.group_by_source, ranking: &*self.ranking, dedup: self.dedup_mode }.apply(out);
+            if !self.filters.is_empty() {
+                out = self.filters.iter().fold(out, |out, filter| apply_filter(&**filter, out, &mut diagnostics));
+                out.truncate(limit);
+            }
+            out
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(span = ?(start, end), count = out.len(), top = %crate::model::summarize(&out, 3), "filter");
+        (out, diagnostics, more)
+    }
+
+    /// translator：负责查词与组句。
+    fn dict_translator<'a>(&'a self, fuzzy: &'a [Vec<String>], kind: SegmentationKind) -> DictTranslator<'a, D> {
+        DictTranslator {
+            dict: &self.dictionary,
+            learned: Some(&self.learned),
+            fuzzy,
+            kind,
+            max_word_length: self.max_word_length,
+            beam_span_limit: self.beam_span_limit,
+            beam_width: self.beam_width,
+            bigram: self.bigram.as_deref(),
+            word_candidate_limit: self.word_candidate_limit,
+            max_lookups: self.max_lookups,
+            prefix_completion: self.prefix_completion,
+            wildcard: self.wildcard,
+            explain: self.explain,
         }
-        .apply(out)
     }
+
+    /// 按用户词频加分（未开启时原样返回）。
+    fn boost_history(&self, out: Vec<Candidate>, segment: &[String]) -> Vec<Candidate> {
+        match &self.history {
+            Some((history, boost)) => RecencyBoostFilter { history, segment, boost: *boost }.apply(out),
+            None => out,
+        }
+    }
+
+    /// 同 `compose_from_segment` 的 filter 步骤，另在元数据中记下分数组成与改动过候选的 filter（见 `explain`）。
+    fn filter_explained(&self, mut out: Vec<Candidate>, segment: &[String], limit: usize, diagnostics: &mut Vec<Diagnostic>) -> Vec<Candidate> {
+        // 组句候选由 translator 记下组成，其余候选的分数即词典权重
+        for c in out.iter_mut().filter(|c| c.meta(EXPLAIN_WEIGHT).is_none()) {
+            let weight = c.effective_score().to_string();
+            c.meta.get_or_insert_with(Box::default).insert(EXPLAIN_WEIGHT.to_string(), weight);
+        }
+        let out = self.boost_history(out, segment);
+        let mut duplicates: HashMap<(CandidateText, usize, usize), usize> = HashMap::new();
+        for c in &out {
+            *duplicates.entry(self.dedup_mode.key(c)).or_default() += 1;
+        }
+        let mut out = DedupSortTruncate { limit, group_by_source: self.group_by_source, ranking: &*self.ranking, dedup: self.dedup_mode }.apply(out);
+        for c in &mut out {
+            let merged = duplicates[&self.dedup_mode.key(c)] - 1;
+            if merged > 0 {
+                note_filter(c, &format!("dedup×{merged}"));
+            }
+        }
+        if self.filters.is_empty() {
+            return out;
+        }
+        let key = |c: &Candidate| (c.text.clone(), c.segment_start, c.segment_end, c.comment.clone(), c.effective_score());
+        for (i, filter) in self.filters.iter().enumerate() {
+            let before: HashSet<_> = out.iter().map(key).collect();
+            out = apply_filter(&**filter, out, diagnostics);
+            for c in out.iter_mut().filter(|c| !before.contains(&key(c))) {
+                note_filter(c, &format!("filter#{}", i + 1));
+            }
+        }
+        out.truncate(limit);
+        out
+    }
+}
+
+/// 原样的英文串排在哪里（见 `Engine::ranked_candidates`）。
+#[derive(Debug, Clone, Copy)]
+enum English<'a> {
+    /// 输入以大写字母开头：排在第一页末尾
+    Trailing(&'a str),
+    /// 英文模式：排在第一位
+    Leading(&'a str),
+}
+
+/// 英文模式下总是原样输入排第一；否则输入以大写字母开头且候选覆盖整段时，原样的英文串排在第一页末尾（见 `Engine::compose_with_state`）。
+fn english_slot(raw_input: &str, confirm: usize, caret: usize, len: usize, english_mode: bool) -> Option<English<'_>> {
+    if english_mode {
+        return Some(English::Leading(raw_input));
+    }
+    (confirm == 0 && caret == len && raw_input.starts_with(|c: char| c.is_ascii_uppercase())).then_some(English::Trailing(raw_input))
+}
+
+/// 原样的英文串作为候选（覆盖 `start..end`）。
+fn english_candidate(text: &str, comment: Option<CandidateText>, start: usize, end: usize) -> Candidate {
+    Candidate {
+        text: text.into(),
+        comment,
+        weight: 0,
+        score: None,
+        segment_start: start,
+        segment_end: end,
+        source: CandidateSource::Exact,
+        rank: 0,
+        parts: Vec::new(),
+        meta: None,
+    }
+}
+
+/// 执行自定义 filter；它把非空的候选过滤为空时记下诊断。
+fn apply_filter(filter: &dyn Filter, candidates: Vec<Candidate>, diagnostics: &mut Vec<Diagnostic>) -> Vec<Candidate> {
+    let had_any = !candidates.is_empty();
+    let out = filter.apply(candidates);
+    if had_any && out.is_empty() {
+        diagnostics.push(Diagnostic::FilterRemovedAll { filter: filter.name().to_string() });
+    }
+    out
 }
 
 impl<D, A> crate::processor::EngineFacade for Engine<D, A>
@@ -145,6 +923,13 @@ where
         Engine::<D, A>::analyze(self, raw_input)
     }
 
+    fn analyze_as(&self, schema: usize, raw_input: &str) -> Analysis {
+        match self.analyzer.schemas() {
+            Some(schemas) => self.english_analysis(raw_input, schemas.analyze_as(schema, raw_input)),
+            None => Engine::<D, A>::analyze(self, raw_input),
+        }
+    }
+
     fn compose_with_state(
         &self,
         raw_input: &str,
@@ -152,6 +937,7 @@ where
         confirmed: usize,
         caret: Option<usize>,
         confirmed_text: String,
+        request: PageRequest<'_>,
     ) -> UiState {
         Engine::<D, A>::compose_with_state(
             self,
@@ -160,6 +946,49 @@ where
             confirmed,
             caret,
             confirmed_text,
+            request,
         )
     }
+
+    fn candidate_count(&self, raw_input: &str, analysis: &Analysis, confirmed: usize, caret: usize, request: PageRequest<'_>) -> usize {
+        Engine::<D, A>::candidate_count(self, raw_input, analysis, confirmed, caret, request)
+    }
+
+    fn generation(&self) -> u64 {
+        Engine::<D, A>::generation(self)
+    }
+
+    fn page_size(&self) -> usize {
+        Engine::<D, A>::page_size(self)
+    }
+
+    fn group_by_source(&self) -> bool {
+        self.group_by_source
+    }
+
+    fn dedup_mode(&self) -> DedupMode {
+        self.dedup_mode
+    }
+
+    fn sentence_select(&self) -> SentenceSelect {
+        self.sentence_select
+    }
+
+    fn preedit_style(&self) -> PreeditStyle {
+        self.preedit_style
+    }
+
+    fn ranking(&self) -> &dyn RankingPolicy {
+        &*self.ranking
+    }
+
+
+    fn schemas(&self) -> Option<&AnalyzerSet> {
+        self.analyzer.schemas()
+    }
+
+    fn auto_commit_length(&self) -> Option<usize> {
+        self.auto_commit_length
+    }
 }
+
@@ -0,0 +1,87 @@
+//! `explain`：候选分数的组成（`Engine::explain` 开启），用来排查“这个候选为什么排在这里”。
+//!
+//! 开启后引擎给每个候选的元数据（`Candidate::meta`）写入：
+//! - `EXPLAIN_WEIGHT`：词典权重；组句候选为路径上各词的权重之和
+//! - `EXPLAIN_LENGTH`：组句的长度加成（每段 1000）；其他候选没有
+//! - `EXPLAIN_BIGRAM`：组句路径上相邻词的二元模型加分之和（见 `bigram`）；没有模型时没有
+//! - `EXPLAIN_FILTERS`：改动过它的 filter，逗号分隔：`history×N`（用户词频中选过 N 次，加分计入权重，见 `history`；按时间衰减时为 `history×N×D`）、
+//!   `dedup×N`（去重时合并了 N 条同文本同范围的候选）、`filter#N`（第 N 个自定义 filter 改动或产出了它）
+//!
+//! 最近上屏的加成（见 `recent`）由 `RECENT_AGE` 识别，为分数减去其余各项。
+//! 各项之和即排序用的分数（`Candidate::effective_score`）；没有语言模型与按来源的加减分，来源只在同分时决定先后。
+
+use std::fmt;
+
+use crate::{model::Candidate, recent::RECENT_AGE};
+
+/// 候选元数据的键：词典权重。
+pub const EXPLAIN_WEIGHT: &str = "explain.weight";
+/// 候选元数据的键：组句的长度加成。
+pub const EXPLAIN_LENGTH: &str = "explain.length";
+/// 候选元数据的键：组句的二元模型加分。
+pub const EXPLAIN_BIGRAM: &str = "explain.bigram";
+/// 候选元数据的键：改动过候选的 filter。
+pub const EXPLAIN_FILTERS: &str = "explain.filters";
+
+/// 一个候选的分数组成。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Explanation {
+    pub weight: i64,
+    pub length: i64,
+    pub bigram: i64,
+    pub recency: i64,
+    pub filters: Vec<String>,
+}
+
+impl Explanation {
+    /// 从候选的元数据读出分数组成；引擎未开启 `explain`（且不是最近上屏的候选）时为 None。
+    pub fn of(candidate: &Candidate) -> Option<Self> {
+        let recent = candidate.meta(RECENT_AGE).is_some();
+        let parse = |key| candidate.meta(key).and_then(|v| v.parse::<i64>().ok());
+        let weight = match parse(EXPLAIN_WEIGHT) {
+            Some(weight) => weight,
+            None if recent => i64::from(candidate.weight),
+            None => return None,
+        };
+        let length = parse(EXPLAIN_LENGTH).unwrap_or(0);
+        let bigram = parse(EXPLAIN_BIGRAM).unwrap_or(0);
+        let recency = if recent { candidate.effective_score() - weight - length - bigram } else { 0 };
+        let filters = candidate.meta(EXPLAIN_FILTERS).map(|v| v.split(',').map(str::to_string).collect()).unwrap_or_default();
+        Some(Self { weight, length, bigram, recency, filters })
+    }
+
+    /// 各项之和，即排序用的分数。
+    pub fn total(&self) -> i64 {
+        self.weight + self.length + self.bigram + self.recency
+    }
+}
+
+/// `权重 80 + 长度 2000 = 2080；dedup×1`；为 0 的加成不列出。
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "权重 {}", self.weight)?;
+        if self.length != 0 {
+            write!(f, " + 长度 {}", self.length)?;
+        }
+        if self.bigram != 0 {
+            write!(f, " + 二元 {}", self.bigram)?;
+        }
+        if self.recency != 0 {
+            write!(f, " + 最近上屏 {}", self.recency)?;
+        }
+        write!(f, " = {}", self.total())?;
+        if !self.filters.is_empty() {
+            write!(f, "；{}", self.filters.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// 记下改动过 `candidate` 的 filter。
+pub(crate) fn note_filter(candidate: &mut Candidate, filter: &str) {
+    let filters = candidate.meta.get_or_insert_with(Box::default).entry(EXPLAIN_FILTERS.to_string()).or_default();
+    if !filters.is_empty() {
+        filters.push(',');
+    }
+    filters.push_str(filter);
+}
@@ -1,25 +1,278 @@
 //! `filter`：候选后处理（去重/排序/裁剪/过滤等）。
 
-use crate::model::Candidate;
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+use crate::{
+    blacklist::{Blacklist, blacklisted, candidate_key},
+    config::{Annotation, DedupMode},
+    convert::TextConverter,
+    dictionary::Dictionary,
+    explain::{EXPLAIN_WEIGHT, note_filter},
+    history::UserHistory,
+    model::{Candidate, CandidateSource, CandidateText},
+    ranking::RankingPolicy,
+};
 
 /// Filter：对候选列表做后处理（去重、排序、裁剪、字符集过滤等）。
 pub trait Filter: Send + Sync {
     fn apply(&self, candidates: Vec<Candidate>) -> Vec<Candidate>;
+
+    /// 名字，用于诊断信息（`Diagnostic::FilterRemovedAll`）；默认为类型名。
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
-/// 默认 filter：按 weight 倒序排序，按 (text, span) 去重，截断到 limit。
-pub struct DedupSortTruncate {
-    pub limit: u8,
+/// 默认 filter：按 `ranking` 排序、按 `dedup`（默认 (text, span)）去重、截断到 limit。
+///
+/// `group_by_source` 时先按 `source` 排序，同来源的候选连在一起，组内仍按 `ranking`。
+///
+/// 去重保留排序后最靠前的一条；`DedupMode::ByTextOnly` 时它的范围与 `parts` 改取同文本中覆盖段数最多的一条，
+/// 选中后 confirm 推进到所有重复项都覆盖到的位置。排序是稳定的：策略判为相等的候选保持输入顺序（translator 的产出顺序），
+/// 因此只要词典与策略是确定的，同样的输入在任何平台、任何进程中都得到同样的候选顺序。
+pub struct DedupSortTruncate<'a> {
+    pub limit: usize,
+    pub group_by_source: bool,
+    /// 排序策略（默认 `ranking::FrequencyFirst`）
+    pub ranking: &'a dyn RankingPolicy,
+    /// 视为重复的条件
+    pub dedup: DedupMode,
 }
 
-impl Filter for DedupSortTruncate {
+impl Filter for DedupSortTruncate<'_> {
     fn apply(&self, mut candidates: Vec<Candidate>) -> Vec<Candidate> {
-        let limit = usize::from(self.limit.max(1));
-        candidates.sort_by(|a, b| b.weight.cmp(&a.weight).then_with(|| a.text.cmp(&b.text)));
-        candidates.dedup_by(|a, b| {
-            a.text == b.text && a.segment_start == b.segment_start && a.segment_end == b.segment_end
+        let limit = self.limit.max(1);
+        let group = self.group_by_source;
+        candidates.sort_by(|a, b| {
+            let by_group = if group { a.source.cmp(&b.source) } else { std::cmp::Ordering::Equal };
+            by_group.then_with(|| self.ranking.compare(a, b))
         });
+        // 同 (text, span) 在排序后未必相邻（例如同一个词分别来自直查与组句、权重不同），不能用 dedup_by
+        if self.dedup == DedupMode::ByTextAndSpan {
+            let mut seen: HashSet<(CandidateText, usize, usize)> = HashSet::new();
+            candidates.retain(|c| seen.insert((c.text.clone(), c.segment_start, c.segment_end)));
+            candidates.truncate(limit);
+            return candidates;
+        }
+        // 文本 -> 保留的那一条（排序后第一次出现）的下标
+        let mut first: HashMap<CandidateText, usize> = HashMap::new();
+        let mut keep = vec![true; candidates.len()];
+        for i in 0..candidates.len() {
+            let j = match first.entry(candidates[i].text.clone()) {
+                Entry::Vacant(e) => {
+                    e.insert(i);
+                    continue;
+                }
+                Entry::Occupied(e) => *e.get(),
+            };
+            keep[i] = false;
+            let span = |c: &Candidate| c.segment_end.saturating_sub(c.segment_start);
+            if span(&candidates[i]) > span(&candidates[j]) {
+                let parts = std::mem::take(&mut candidates[i].parts);
+                let (start, end) = (candidates[i].segment_start, candidates[i].segment_end);
+                let kept = &mut candidates[j];
+                (kept.segment_start, kept.segment_end, kept.parts) = (start, end, parts);
+            }
+        }
+        let mut keep = keep.into_iter();
+        candidates.retain(|_| keep.next().unwrap_or(true));
         candidates.truncate(limit);
         candidates
     }
 }
+
+impl DedupMode {
+    /// 候选在此模式下的去重键：`ByTextOnly` 时范围一律记为 (0, 0)。
+    pub(crate) fn key(self, c: &Candidate) -> (CandidateText, usize, usize) {
+        match self {
+            Self::ByTextAndSpan => (c.text.clone(), c.segment_start, c.segment_end),
+            Self::ByTextOnly => (c.text.clone(), 0, 0),
+        }
+    }
+}
+
+/// 用户词频加成（见 `history`）：候选的分数加上 `boost` × 它在 `history` 中衰减后的选择次数；引擎在 `DedupSortTruncate` 之前执行。
+///
+/// 候选的编码同记录时：简拼、前缀补全取 comment 中的完整编码，其余为所覆盖的段拼接。
+/// 开启 `Engine::explain` 时，已带分数组成的候选（组句）一并计入权重，并记下 `history×N`（N 为次数；有衰减时为 `history×N×D`，D 为衰减系数）。
+pub struct RecencyBoostFilter<'a> {
+    pub history: &'a UserHistory,
+    /// 当前输入的切分（`Analysis::segment`）
+    pub segment: &'a [String],
+    /// 每选一次的加分
+    pub boost: i32,
+}
+
+impl Filter for RecencyBoostFilter<'_> {
+    fn apply(&self, mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+        if self.history.is_empty() {
+            return candidates;
+        }
+        let now = self.history.now();
+        for c in &mut candidates {
+            let Some(key) = candidate_key(c, self.segment) else { continue };
+            let (count, last_used) = (self.history.count(&key, &c.text), self.history.last_used(&key, &c.text).unwrap_or(now));
+            if count == 0 {
+                continue;
+            }
+            let decay = self.history.decay(last_used, now);
+            let bonus = (f64::from(self.boost) * f64::from(count) * decay).round().clamp(f64::from(i32::MIN), f64::from(i32::MAX)) as i32;
+            c.weight = c.weight.saturating_add(bonus);
+            if let Some(score) = &mut c.score {
+                *score = score.saturating_add(i64::from(bonus));
+            }
+            if let Some(weight) = c.meta(EXPLAIN_WEIGHT).and_then(|w| w.parse::<i64>().ok()) {
+                let weight = weight.saturating_add(i64::from(bonus)).to_string();
+                c.meta.get_or_insert_with(Box::default).insert(EXPLAIN_WEIGHT.to_string(), weight);
+                let note = if decay < 1.0 { format!("history×{count}×{decay:.2}") } else { format!("history×{count}") };
+                note_filter(c, &note);
+            }
+        }
+        candidates
+    }
+}
+
+/// 去掉删除过的候选（见 `blacklist`），其余顺序不变；引擎在翻页之前执行（见 `engine::PageRequest`），各页由其后的候选补足。
+///
+/// 只比对词典中的词（直查的候选与只有一个词的组句），编码同 `RecencyBoostFilter`；多词的组句、联想、注入的候选保留。
+pub struct BlacklistFilter<'a> {
+    pub blacklist: &'a Blacklist,
+    /// 当前输入的切分（`Analysis::segment`）
+    pub segment: &'a [String],
+}
+
+impl Filter for BlacklistFilter<'_> {
+    fn apply(&self, mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+        if self.blacklist.is_empty() {
+            return candidates;
+        }
+        candidates.retain(|c| !blacklisted(self.blacklist, c, self.segment));
+        candidates
+    }
+}
+
+/// 转换候选文本（简繁转换等，见 `convert`）：`text` 与 `parts` 一并转换。
+///
+/// 不排序也不去重：转换前后的候选一一对应，`Select(n)` 选中的仍是展示的第 n 个。
+pub struct SimplifierFilter<'a> {
+    pub converter: &'a dyn TextConverter,
+}
+
+impl Filter for SimplifierFilter<'_> {
+    fn apply(&self, mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+        for c in &mut candidates {
+            c.text = self.converter.convert(&c.text).into();
+            for part in &mut c.parts {
+                part.text = self.converter.convert(&part.text).into();
+            }
+        }
+        candidates
+    }
+}
+
+/// 简繁异体去重：同一范围内逐字互为异体（`TextConverter::base_char` 相同）的候选只保留一个，
+/// 其余的文本折叠进它的 `comment`（如 `裡/裏`，原有 comment 在前）。
+///
+/// - 繁体输出时保留繁体写法：先找 `convert` 给出的正体，再找已是繁体的写法；否则保留简体写法（逐字都是代表字）
+/// - 没有符合的写法时保留排在最前的；保留的候选占该组最靠前的位置，其余候选顺序不变
+pub struct VariantDedupFilter<'a> {
+    pub converter: &'a dyn TextConverter,
+    /// 当前输出繁体（见 `Session::is_traditional`）
+    pub traditional: bool,
+}
+
+impl VariantDedupFilter<'_> {
+    /// 逐字换成代表字。
+    fn base(&self, text: &str) -> String {
+        text.chars().map(|c| self.converter.base_char(c)).collect()
+    }
+
+    /// 从一组异体（非空）中选出保留的候选，折叠其余的文本。
+    fn fold(&self, mut group: Vec<Candidate>) -> Candidate {
+        if group.len() == 1 {
+            return group.swap_remove(0);
+        }
+        let base = self.base(&group[0].text);
+        let preferred = if self.traditional {
+            let standard = self.converter.convert(&base);
+            group.iter().position(|c| *c.text == *standard).or_else(|| group.iter().position(|c| *c.text != *base && self.converter.convert(&c.text) == *c.text))
+        } else {
+            group.iter().position(|c| *c.text == *base)
+        };
+        let mut kept = group.remove(preferred.unwrap_or(0));
+        let mut folded: Vec<&str> = Vec::new();
+        for c in &group {
+            if c.text != kept.text && !folded.contains(&&*c.text) {
+                folded.push(&c.text);
+            }
+        }
+        if !folded.is_empty() {
+            let folded = folded.join("/");
+            kept.comment = Some(match &kept.comment {
+                Some(comment) => format!("{comment} {folded}").into(),
+                None => folded.into(),
+            });
+        }
+        kept
+    }
+}
+
+impl Filter for VariantDedupFilter<'_> {
+    fn apply(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        // (范围, 代表字文本) -> 组号；组按首次出现的先后排列
+        let mut index: HashMap<(usize, usize, String), usize> = HashMap::new();
+        let mut groups: Vec<Vec<Candidate>> = Vec::new();
+        for c in candidates {
+            let key = (c.segment_start, c.segment_end, self.base(&c.text));
+            match index.get(&key) {
+                Some(&g) => groups[g].push(c),
+                None => {
+                    index.insert(key, groups.len());
+                    groups.push(vec![c]);
+                }
+            }
+        }
+        groups.into_iter().map(|group| self.fold(group)).collect()
+    }
+}
+
+/// 单字候选的读音标注：`comment` 填入产生该候选的编码（见 `Annotation`），顺序不变。
+///
+/// - 前缀补全、简拼、模糊音命中的 `comment` 本就是完整编码，保持不变；异步来源的 `comment` 也不改
+/// - 其余取所覆盖音节的拼接（精确匹配即其 key）
+/// - 没有覆盖范围的候选（联想）用 `Dictionary::reverse_lookup` 反查，取权重最高的编码，查不到则不标注
+pub struct AnnotationFilter<'a> {
+    pub mode: Annotation,
+    /// 候选范围所索引的切分结果
+    pub segment: &'a [String],
+    pub dictionary: &'a dyn Dictionary,
+}
+
+impl Filter for AnnotationFilter<'_> {
+    fn apply(&self, mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+        if self.mode == Annotation::Off {
+            return candidates;
+        }
+        let first_char = |c: &Candidate| c.text.chars().next();
+        let single = |c: &Candidate| c.text.chars().count() == 1;
+        let targets: Vec<usize> = (0..candidates.len())
+            .filter(|&i| single(&candidates[i]))
+            .filter(|&i| self.mode == Annotation::All || candidates.iter().enumerate().any(|(j, o)| j != i && first_char(o) == first_char(&candidates[i])))
+            .collect();
+        for i in targets {
+            let c = &mut candidates[i];
+            let keyed = c.comment.is_some() && matches!(c.source, CandidateSource::Prefix | CandidateSource::Initials | CandidateSource::Fuzzy | CandidateSource::External);
+            if keyed {
+                continue;
+            }
+            let key = match self.segment.get(c.segment_start..c.segment_end) {
+                Some(span) if !span.is_empty() => Some(span.concat()),
+                _ => self.dictionary.reverse_lookup(&c.text).into_iter().next(),
+            };
+            if let Some(key) = key {
+                c.comment = Some(key.into());
+            }
+        }
+        candidates
+    }
+}
@@ -0,0 +1,236 @@
+//! `history`：用户词频（`Engine::user_history` 开启）。
+//!
+//! 记录每个 (编码, 词) 被选中上屏的次数与最近一次上屏的时间，组合时由 `filter::RecencyBoostFilter` 按次数给候选加分：选得越多排得越靠前。
+//!
+//! - 编码同最近上屏记忆（见 `recent`）：简拼、前缀补全取 comment 中的完整编码，其余为所选的段拼接（如 `shurufa`）
+//! - 只有上屏时才计入（含回车上屏时已确认的词），放弃输入则不计
+//! - 加分时次数按距最近一次上屏的时长指数衰减（半衰期见 `with_half_life`）：很久没选的词不会一直压过近来常选的词；存下的次数本身不变
+//! - 时间取自 `Clock`（缺省为系统时间，以秒计），可注入固定的时间
+//! - 不随会话结束丢弃；可导出为 TSV（`to_tsv`/`from_tsv_str`），core 不读写文件，落盘见 `rime_dict::history`
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::model::CandidateText;
+
+/// `Engine::user_history` 每次选择加分的缺省值，按自带词典的权重（词频，常用字为百万量级）取定；其他词典宜按其权重量级调整。
+pub const DEFAULT_HISTORY_BOOST: i32 = 1_000_000;
+
+/// 次数衰减的缺省半衰期（秒）：30 天没选过，次数按一半计。
+pub const DEFAULT_HALF_LIFE: u64 = 30 * 24 * 60 * 60;
+
+/// 时间来源：自 Unix 纪元起的秒数。闭包 `Fn() -> u64` 也是 `Clock`。
+pub trait Clock: Send + Sync {
+    fn now(&self) -> u64;
+}
+
+/// 系统时间。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    }
+}
+
+impl<F: Fn() -> u64 + Send + Sync> Clock for F {
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+/// 一条记录（见 `UserHistory::entries`）。
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HistoryEntry {
+    pub key: CandidateText,
+    pub text: CandidateText,
+    /// 选择次数（未衰减）
+    pub count: u32,
+    /// 最近一次上屏的时间（`Clock::now`）
+    pub last_used: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stat {
+    count: u32,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    /// 编码 -> 词 -> 次数与时间
+    by_key: HashMap<CandidateText, HashMap<CandidateText, Stat>>,
+    /// 每次写入加一（见 `Dictionary::generation`）
+    generation: u64,
+}
+
+/// 用户词频；克隆得到的是同一份（例如共享同一个引擎的多个会话）。
+#[derive(Clone)]
+pub struct UserHistory {
+    inner: Arc<Mutex<Stats>>,
+    clock: Arc<dyn Clock>,
+    /// 半衰期（秒）；None 为不衰减
+    half_life: Option<u64>,
+}
+
+impl Default for UserHistory {
+    fn default() -> Self {
+        Self { inner: Arc::default(), clock: Arc::new(SystemClock), half_life: Some(DEFAULT_HALF_LIFE) }
+    }
+}
+
+impl fmt::Debug for UserHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserHistory").field("len", &self.len()).field("half_life", &self.half_life).finish_non_exhaustive()
+    }
+}
+
+impl UserHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置时间来源，默认 `SystemClock`。
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// 设置次数衰减的半衰期（秒），默认 `DEFAULT_HALF_LIFE`；None 为不衰减。
+    ///
+    /// # Panics
+    /// `Some(0)` 时 panic。
+    pub fn with_half_life(mut self, half_life: Option<u64>) -> Self {
+        assert!(half_life != Some(0), "half_life must be positive");
+        self.half_life = half_life;
+        self
+    }
+
+    pub fn half_life(&self) -> Option<u64> {
+        self.half_life
+    }
+
+    /// 当前时间（`Clock::now`）。
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    /// 记下一次选择：`key` 下的 `text` 次数加一，最近上屏时间记为现在。
+    pub fn record(&self, key: &str, text: &str) {
+        self.add(key, text, 1, self.now());
+    }
+
+    /// `key` 下的 `text` 次数加 `count`，最近上屏时间取原有的与 `last_used` 中较晚的（导入、合并用）。
+    pub fn add(&self, key: &str, text: &str, count: u32, last_used: u64) {
+        if key.is_empty() || text.is_empty() {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let texts = inner.by_key.entry(key.into()).or_default();
+        let stat = texts.entry(text.into()).or_insert(Stat { count: 0, last_used });
+        stat.count = stat.count.saturating_add(count);
+        stat.last_used = stat.last_used.max(last_used);
+        inner.generation += 1;
+    }
+
+    fn stat(&self, key: &str, text: &str) -> Option<Stat> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.by_key.get(key).and_then(|texts| texts.get(text)).copied()
+    }
+
+    /// `key` 下的 `text` 被选过几次（未衰减）。
+    pub fn count(&self, key: &str, text: &str) -> u32 {
+        self.stat(key, text).map_or(0, |s| s.count)
+    }
+
+    /// `key` 下的 `text` 最近一次上屏的时间；没选过时为 None。
+    pub fn last_used(&self, key: &str, text: &str) -> Option<u64> {
+        self.stat(key, text).map(|s| s.last_used)
+    }
+
+    /// 在 `now` 时刻的衰减系数：距最近上屏每过一个半衰期减半（0-1；不衰减时为 1）。
+    pub fn decay(&self, last_used: u64, now: u64) -> f64 {
+        match self.half_life {
+            Some(half_life) => 0.5f64.powf(now.saturating_sub(last_used) as f64 / half_life as f64),
+            None => 1.0,
+        }
+    }
+
+    /// `key` 下的 `text` 在 `now` 时刻衰减后的次数。
+    pub fn decayed_count(&self, key: &str, text: &str, now: u64) -> f64 {
+        self.stat(key, text).map_or(0.0, |s| f64::from(s.count) * self.decay(s.last_used, now))
+    }
+
+    /// 记录了多少个不同的 (编码, 词)。
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).by_key.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).by_key.is_empty()
+    }
+
+    /// 内容版本：每次写入加一（排序随词频变化，见 `Engine::generation`）。
+    pub fn generation(&self) -> u64 {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).generation
+    }
+
+    /// 全部记录，按 key、text 排序。
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries: Vec<HistoryEntry> = inner
+            .by_key
+            .iter()
+            .flat_map(|(key, texts)| texts.iter().map(|(text, s)| HistoryEntry { key: key.clone(), text: text.clone(), count: s.count, last_used: s.last_used }))
+            .collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// 导出为 TSV：每行 `key<TAB>text<TAB>count<TAB>last_used`，按 key、text 排序（同样的记录总是得到同样的文本）。
+    pub fn to_tsv(&self) -> String {
+        let mut out = String::new();
+        for e in self.entries() {
+            let _ = writeln!(out, "{}\t{}\t{}\t{}", e.key, e.text, e.count, e.last_used);
+        }
+        out
+    }
+
+    /// 从 `to_tsv` 的输出恢复；空行与 `#` 开头的行忽略，同一 (key, text) 出现多次时次数相加、时间取较晚的。
+    ///
+    /// 也接受没有时间列的旧格式 `key<TAB>text<TAB>count`，这样的行时间记为读入时（`SystemClock`），从此开始衰减。
+    /// 某行列数或数字不对时返回带行号的错误信息。
+    pub fn from_tsv_str(s: &str) -> Result<Self, String> {
+        let history = Self::new();
+        history.extend_from_tsv_str(s)?;
+        Ok(history)
+    }
+
+    /// 同 `from_tsv_str`，读入已有的 `UserHistory`（用它的 `Clock` 给旧格式的行记时间）。
+    pub fn extend_from_tsv_str(&self, s: &str) -> Result<(), String> {
+        let now = self.now();
+        for (idx, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let (key, text, count, last_used) = match fields[..] {
+                [key, text, count] => (key, text, count, None),
+                [key, text, count, last_used] => (key, text, count, Some(last_used)),
+                _ => return Err(format!("第 {} 行：应为 key、text、次数（、最近上屏时间）三或四列", idx + 1)),
+            };
+            let count = count.parse::<u32>().map_err(|_| format!("第 {} 行：无法解析的次数 `{count}`", idx + 1))?;
+            let last_used = match last_used {
+                Some(t) => t.parse::<u64>().map_err(|_| format!("第 {} 行：无法解析的时间 `{t}`", idx + 1))?,
+                None => now,
+            };
+            self.add(key, text, count, last_used);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! `host`：前端（IME 框架/CLI/GUI）与会话之间的约定。
+//!
+//! 前端实现 `Host`，每个按键交给 `drive`；`drive` 负责解释 `Action`/`UiState`，
+//! 按固定顺序回调前端：
+//!
+//! 1. 没有 processor 消费该按键、也没有上屏：`forward_key`（按键交还给应用；此前在展示联想候选时先 `hide_candidates`），结束
+//! 2. 依次处理动作：`Action::Commit` 调 `commit_text`，`Action::Rejected` 调 `rejected`
+//! 3. `update_preedit`（输入结束时收到的 `UiState.raw_input` 为空，表示清除 preedit）
+//! 4. 有候选：`show_candidates`；否则 `hide_candidates`
+
+use crate::{
+    dictionary::Dictionary,
+    engine::Analyzer,
+    key_event::{Action, InputEvent, RejectReason},
+    model::UiState,
+    segmenter::Segmenter,
+    session::Session,
+};
+
+/// 前端回调。
+pub trait Host {
+    /// 上屏文本
+    fn commit_text(&mut self, text: &str);
+    /// 更新 preedit（组合串）
+    fn update_preedit(&mut self, ui: &UiState);
+    /// 展示/刷新候选窗
+    fn show_candidates(&mut self, ui: &UiState);
+    /// 隐藏候选窗
+    fn hide_candidates(&mut self);
+    /// 会话未处理的按键，交还给应用
+    fn forward_key(&mut self, ev: &InputEvent);
+    /// 请求被拒绝（例如选择了不存在的候选），可在此提示用户；默认忽略
+    fn rejected(&mut self, _reason: RejectReason) {}
+}
+
+/// 把事件交给会话，并按约定顺序回调 `host`。
+pub fn drive<D, A>(session: &mut Session<D, A>, host: &mut dyn Host, event: InputEvent)
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    let was_predicting = session.is_predicting();
+    let (ui, actions) = session.handle(event.clone());
+    // 按键交还应用时不再提示拒绝（例如没有输入时按数字键，应用照常收到数字）
+    if !session.last_consumed() && !actions.iter().any(|a| matches!(a, Action::Commit(_))) {
+        // 联想中按下的其他键会关闭联想
+        if was_predicting {
+            host.hide_candidates();
+        }
+        host.forward_key(&event);
+        return;
+    }
+    for action in &actions {
+        match action {
+            Action::Commit(text) => host.commit_text(text),
+            Action::Rejected(reason) => host.rejected(*reason),
+        }
+    }
+    host.update_preedit(&ui);
+    if ui.candidate_list.is_empty() {
+        host.hide_candidates();
+    } else {
+        host.show_candidates(&ui);
+    }
+}
+
+/// `RecordingHost` 记录的一次回调。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostCall {
+    CommitText(String),
+    /// 记录 preedit 文本
+    UpdatePreedit(String),
+    /// 记录候选文本
+    ShowCandidates(Vec<String>),
+    HideCandidates,
+    ForwardKey(InputEvent),
+    Rejected(RejectReason),
+}
+
+/// 按顺序记录全部回调的 `Host`（测试与调试用）。
+#[derive(Debug, Default)]
+pub struct RecordingHost {
+    pub calls: Vec<HostCall>,
+}
+
+impl RecordingHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已上屏的文本（按顺序）。
+    pub fn commits(&self) -> Vec<&str> {
+        self.calls
+            .iter()
+            .filter_map(|c| match c {
+                HostCall::CommitText(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Host for RecordingHost {
+    fn commit_text(&mut self, text: &str) {
+        self.calls.push(HostCall::CommitText(text.to_string()));
+    }
+
+    fn update_preedit(&mut self, ui: &UiState) {
+        self.calls.push(HostCall::UpdatePreedit(ui.preedit.clone()));
+    }
+
+    fn show_candidates(&mut self, ui: &UiState) {
+        self.calls.push(HostCall::ShowCandidates(ui.candidate_list.iter().map(|c| c.text.to_string()).collect()));
+    }
+
+    fn hide_candidates(&mut self) {
+        self.calls.push(HostCall::HideCandidates);
+    }
+
+    fn forward_key(&mut self, ev: &InputEvent) {
+        self.calls.push(HostCall::ForwardKey(ev.clone()));
+    }
+
+    fn rejected(&mut self, reason: RejectReason) {
+        self.calls.push(HostCall::Rejected(reason));
+    }
+}
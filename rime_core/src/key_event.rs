@@ -4,10 +4,11 @@
 /// - `Session`/processor 只关心“语义事件”，不关心具体平台键值。
 /// - CLI/GUI 层负责把系统按键转换成这些事件。
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum InputEvent {
     /// 输入一个字符（通常是 a-z 或 `'`）
     Char(char),
-    /// 删除光标前一个字符
+    /// 删除光标前的一个字符；光标移到中间时删除光标前的一段（见 `Context::delete_before_caret`）
     Backspace,
     /// 空格（当前实现里等同于选择高亮候选）
     Space,
@@ -15,15 +16,82 @@ pub enum InputEvent {
     Enter,
     /// 清空当前会话（类似 Esc）
     Clear,
-    /// 选择候选词（1-9）
+    /// 选择候选词（当前页内的下标，0 起）
     Select(usize),
+    /// 删除（遗忘）候选词（当前页内的下标，0 起）：此后不再出现在候选中（见 `blacklist`）；多词的组句等不能删除的候选被拒绝
+    DeleteCandidate(usize),
+    /// 候选翻到下一页（已是最后一页时停住）
+    NextPage,
+    /// 候选翻到上一页（已是第一页时停住）
+    PrevPage,
+    /// 开关输出文本的简繁转换（会话级；引擎未设置转换时不消费，见 `convert`）
+    ToggleTraditional,
+    /// 光标左移一段（已在已确认位置时不动）；候选只覆盖光标之前未确认的段
+    MoveLeft,
+    /// 光标右移一段（已在末尾时不动）
+    MoveRight,
+    /// 切换到下一个输入方案（引擎只有一个方案时不消费，见 `schema`）
+    NextSchema,
+    /// 切换拼音/英文模式（见 `context::InputMode`）；正在输入的部分保留，按新模式重新切分
+    ToggleMode,
+    /// 临时英文：开始原样输入；正在原样输入时结束它，已输入的部分作为一段原样文本，确认到它时以原文确认（见 `Context::commit_literal`）
+    CommitLiteral,
     /// 退出（上层用；core 可忽略）
     Exit,
 }
 
+impl InputEvent {
+    /// 从按键名解析（名称沿用 DOM `KeyboardEvent.key`，供 wasm/Python 等绑定共用）。
+    ///
+    /// - `a`-`z` / `'`：输入；`1`-`9`：选择当前页第 n 个候选
+    /// - `" "`/`Space`、`Enter`、`Backspace`、`Escape`（清空）
+    /// - `PageDown`/`=` 下一页，`PageUp`/`-` 上一页
+    /// - `ArrowLeft`/`ArrowRight`：光标左移/右移一段
+    pub fn from_key_name(key: &str) -> Option<Self> {
+        Some(match key {
+            " " | "Space" => Self::Space,
+            "Enter" => Self::Enter,
+            "Backspace" => Self::Backspace,
+            "Escape" => Self::Clear,
+            "PageDown" | "=" => Self::NextPage,
+            "PageUp" | "-" => Self::PrevPage,
+            "ArrowLeft" => Self::MoveLeft,
+            "ArrowRight" => Self::MoveRight,
+            _ => {
+                let mut chars = key.chars();
+                let (Some(ch), None) = (chars.next(), chars.next()) else {
+                    return None;
+                };
+                match ch {
+                    '1'..='9' => Self::Select(ch as usize - '1' as usize),
+                    c if c.is_ascii_alphabetic() || c == '\'' => Self::Char(c),
+                    _ => return None,
+                }
+            }
+        })
+    }
+}
+
 /// 引擎输出动作（对 UI/宿主的“副作用”请求）。
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum Action {
     /// 提交文本（上屏）
     Commit(String),
+    /// 请求无法执行（例如选择了不存在的候选），前端可据此提示（如响铃）
+    Rejected(RejectReason),
+}
+
+/// `Action::Rejected` 的原因。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum RejectReason {
+    /// 没有正在输入的内容
+    NotComposing,
+    /// 当前页没有该下标的候选
+    NoSuchCandidate,
+    /// 候选存在，但它的 span 不从已确认位置开始（或越过了光标）
+    SpanNotAtConfirm,
+    /// 候选不能删除（多词的组句、注入的候选，或英文模式的原始输入，见 `blacklist`）
+    NotForgettable,
 }
@@ -0,0 +1,193 @@
+//! `learn`：自动造词（`Session::with_phrase_learning` 开启，默认关闭）。
+//!
+//! 一次输入分几次选词上屏时（如 `shurufa` 先选“输入”再选“法”），把所选各词按先后拼成一个词，
+//! 编码为各词音节的拼接，写入引擎的 `LearnedPhrases`；下次输入同一编码时它就是精确匹配的候选。
+//!
+//! - 只有全部段都由选词确认时才学（含原样文本、回车上屏原串时不学）；码表编码、简拼直接确认的字母不学
+//! - 初始权重为各部分的权重之和（组句时不低于由这几个词连成的路径）；已学过或词典中已有这个词时在原权重上加一
+//! - 音节数超过上限（`Session::with_phrase_learning` 的参数，通常取 `DEFAULT_MAX_SYLLABLES`）的不学
+//! - 学到的词与词典分开存放，共享同一个引擎的会话都能写入、都能查到；core 不落盘，调用方可用 `LearnedPhrases::to_tsv`/`from_tsv_str` 保存、恢复
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    ops::Range,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    dictionary::Dictionary,
+    model::{Candidate, CandidateSource, CandidateText},
+};
+
+/// 自动造词的音节数上限缺省值。
+pub const DEFAULT_MAX_SYLLABLES: usize = 6;
+
+/// 一次选词：所选的词与它的音节（未知时为 None，整个词组都不学）。
+#[derive(Debug, Clone)]
+pub(crate) struct Pick {
+    pub(crate) text: CandidateText,
+    pub(crate) syllables: Option<Vec<String>>,
+}
+
+/// 由多次选词拼成的词组（见 `Context::take_learned`）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LearnedPhrase {
+    pub text: String,
+    /// 编码的音节切分（如 `[shu, ru, fa]`）
+    pub syllables: Vec<String>,
+    /// 各次所选的词及其在 `syllables` 中的范围
+    pub parts: Vec<(String, Range<usize>)>,
+}
+
+impl LearnedPhrase {
+    /// 把本次输入的选词拼成词组：至少两次选词、各词音节都已知、拼接结果等于上屏文本时才有。
+    pub(crate) fn from_picks(picks: &[Pick], committed: &str) -> Option<Self> {
+        if picks.len() < 2 {
+            return None;
+        }
+        let mut phrase = Self { text: String::new(), syllables: Vec::new(), parts: Vec::with_capacity(picks.len()) };
+        for pick in picks {
+            let syllables = pick.syllables.as_ref()?;
+            let start = phrase.syllables.len();
+            phrase.text.push_str(&pick.text);
+            phrase.syllables.extend(syllables.iter().cloned());
+            phrase.parts.push((pick.text.to_string(), start..phrase.syllables.len()));
+        }
+        (phrase.text == committed).then_some(phrase)
+    }
+
+    /// 完整编码（音节直接相连，如 `shurufa`）。
+    pub fn key(&self) -> String {
+        self.syllables.concat()
+    }
+
+    /// 写入时的权重：`learned` 或 `dict` 中已有这个词时为原权重加一，否则为各部分的权重之和（查不到的部分计 0）。
+    pub fn weight_in(&self, dict: &impl Dictionary, learned: &LearnedPhrases) -> i32 {
+        let exact = |text: &str, range: Range<usize>| {
+            learned.weight(&self.syllables[range.clone()].concat(), text).or_else(|| {
+                dict.lookup_iter(&self.syllables, range.start, range.end).find(|c| c.source == CandidateSource::Exact && *c.text == *text).map(|c| c.weight)
+            })
+        };
+        match exact(&self.text, 0..self.syllables.len()) {
+            Some(weight) => weight.saturating_add(1),
+            None => self.parts.iter().map(|(text, range)| exact(text, range.clone()).unwrap_or(0)).fold(0, i32::saturating_add),
+        }
+    }
+}
+
+/// 自动造词学到的词；克隆得到的是同一份（例如共享同一个引擎的多个会话），引擎查词时排在词典之前（见 `Engine::learned_phrases`）。
+#[derive(Debug, Clone, Default)]
+pub struct LearnedPhrases {
+    inner: Arc<RwLock<Phrases>>,
+}
+
+#[derive(Debug, Default)]
+struct Phrases {
+    /// 编码 -> (词, 权重)，按权重降序
+    by_key: HashMap<String, Vec<(CandidateText, i32)>>,
+    /// 每次写入加一（见 `Dictionary::generation`）
+    generation: u64,
+}
+
+impl LearnedPhrases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 写入 `key -> text`；已有时改为新的 `weight`。
+    pub fn insert(&self, key: &str, text: &str, weight: i32) {
+        if key.is_empty() || text.is_empty() {
+            return;
+        }
+        let mut inner = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        let texts = inner.by_key.entry(key.to_string()).or_default();
+        match texts.iter_mut().find(|(t, _)| **t == *text) {
+            Some(entry) => entry.1 = weight,
+            None => texts.push((text.into(), weight)),
+        }
+        texts.sort_by_key(|(_, w)| std::cmp::Reverse(*w));
+        inner.generation += 1;
+    }
+
+    /// `key` 下学到的 `text` 的权重；没学过时为 None。
+    pub fn weight(&self, key: &str, text: &str) -> Option<i32> {
+        let inner = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        inner.by_key.get(key)?.iter().find(|(t, _)| **t == *text).map(|(_, w)| *w)
+    }
+
+    /// `segment[start..end]` 拼成的编码下学到的词：精确匹配，按权重降序。
+    pub fn lookup_span(&self, segment: &[String], start: usize, end: usize) -> Vec<Candidate> {
+        let Some(span) = segment.get(start..end).filter(|span| !span.is_empty()) else {
+            return Vec::new();
+        };
+        let inner = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let Some(texts) = inner.by_key.get(&span.concat()) else {
+            return Vec::new();
+        };
+        texts
+            .iter()
+            .map(|(text, weight)| Candidate {
+                text: text.clone(),
+                comment: None,
+                weight: *weight,
+                score: None,
+                segment_start: start,
+                segment_end: end,
+                source: CandidateSource::Exact,
+                rank: 0,
+                parts: Vec::new(),
+                meta: None,
+            })
+            .collect()
+    }
+
+    /// 全部词条 (key, text, weight)，按 key、text 排序。
+    pub fn entries(&self) -> Vec<(String, CandidateText, i32)> {
+        let inner = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let mut entries: Vec<(String, CandidateText, i32)> = inner.by_key.iter().flat_map(|(key, texts)| texts.iter().map(|(text, weight)| (key.clone(), text.clone(), *weight))).collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// 导出为 TSV：每行 `key<TAB>text<TAB>weight`，按 key、text 排序。
+    pub fn to_tsv(&self) -> String {
+        let mut out = String::new();
+        for (key, text, weight) in self.entries() {
+            let _ = writeln!(out, "{key}\t{text}\t{weight}");
+        }
+        out
+    }
+
+    /// 从 `to_tsv` 的输出恢复；空行与 `#` 开头的行忽略，同一 (key, text) 出现多次时以最后一行为准。
+    ///
+    /// 某行列数或权重不对时返回带行号的错误信息。
+    pub fn from_tsv_str(s: &str) -> Result<Self, String> {
+        let learned = Self::new();
+        for (idx, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let [key, text, weight] = line.split('\t').collect::<Vec<_>>()[..] else {
+                return Err(format!("第 {} 行：应为 key、text、权重三列", idx + 1));
+            };
+            let weight = weight.parse::<i32>().map_err(|_| format!("第 {} 行：无法解析的权重 `{weight}`", idx + 1))?;
+            learned.insert(key, text, weight);
+        }
+        Ok(learned)
+    }
+
+    /// 学到了多少个不同的 (编码, 词)。
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).by_key.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).by_key.is_empty()
+    }
+
+    /// 内容版本：每次写入加一。
+    pub fn generation(&self) -> u64 {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).generation
+    }
+}
@@ -4,13 +4,39 @@
 //! - **核心可复用**：CLI/GUI/服务端都能复用同一套逻辑
 //! - **分层清晰**：engine -> processor -> segmenter -> translator -> filter -> 输出（`UiState`）
 //! - **易演进**：先跑通最小功能，再逐步替换/扩展 processor 与 translator
+//!
+//! feature `serde`：为 `UiState`、`Candidate`、`Analysis`、`InputEvent`、`Action` 派生 `Serialize`/`Deserialize`。
+//! 字段名与 Rust 字段一致；枚举变体为 snake_case（如 `{"select":0}`、`"next_page"`）。
+//!
+//! feature `test-util`：随机事件生成与会话不变量检查（`test_util`）。
+//!
+//! feature `tracing`：在组合、翻译、会话处理处输出 debug 级 span/event（查询范围、结果数、前 3 个候选）；关闭时无开销。
+pub mod async_source;
+pub mod bigram;
+pub mod blacklist;
+pub mod commit;
+pub mod config;
 pub mod context;
+pub mod convert;
 pub mod dictionary;
 pub mod engine;
+pub mod explain;
 pub mod filter;
+pub mod history;
+pub mod host;
 pub mod key_event;
+pub mod learn;
+pub mod metrics;
 pub mod model;
+pub mod prediction;
 pub mod processor;
+pub mod punct;
+pub mod ranking;
+pub mod recent;
+pub mod schema;
 pub mod segmenter;
 pub mod session;
+pub mod session_manager;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod translator;
@@ -0,0 +1,129 @@
+//! `metrics`：会话的输入效率统计（按键数、每字按键数、选词排名分布、放弃的组合、组合长度等）。
+//!
+//! 由 `Session` 在每次处理事件后喂入结果（开启见 `Session::with_metrics`），
+//! 因此统计的是核心实际处理过的输入，而不是上层的猜测；选中的排名由 `Context::select_candidate` 给出。
+
+use std::time::{Duration, Instant};
+
+use crate::{key_event::Action, model::UiState};
+
+/// 会话统计累加器（`Session::metrics` 返回其快照）。
+#[derive(Debug, Clone)]
+pub struct SessionMetrics {
+    /// 送入会话的事件总数
+    pub keystrokes: usize,
+    /// 上屏次数
+    pub commits: usize,
+    /// 上屏的字符数（按 char 计，经 `CommitTransform` 变换之后）
+    pub committed_chars: usize,
+    /// 选词次数（逐词确认的每一步、联想选词都算一次）
+    pub selections: usize,
+    /// 选中各排名的次数：`ranks[i]` 为选中第 i 个候选（跨页从 0 计，即页码 × 每页数 + 页内下标）的次数
+    pub ranks: Vec<usize>,
+    /// 选词时展示的候选数之和
+    pub candidates_shown: usize,
+    /// 以上屏结束的组合数
+    pub compositions_committed: usize,
+    /// 没有上屏就结束的组合数（清空，或退格删完）
+    pub compositions_abandoned: usize,
+    /// 已结束的组合的长度之和（每个组合取过程中最长的原始输入，按 char 计）
+    pub composition_chars: usize,
+    /// 开始时间
+    started: Instant,
+    /// 上一次 `UiState` 的候选数（本次选词时展示了多少候选）
+    last_shown: usize,
+    /// 正在进行的组合过程中最长的原始输入；0 即没有组合
+    peak: usize,
+}
+
+impl Default for SessionMetrics {
+    fn default() -> Self {
+        Self {
+            keystrokes: 0,
+            commits: 0,
+            committed_chars: 0,
+            selections: 0,
+            ranks: Vec::new(),
+            candidates_shown: 0,
+            compositions_committed: 0,
+            compositions_abandoned: 0,
+            composition_chars: 0,
+            started: Instant::now(),
+            last_shown: 0,
+            peak: 0,
+        }
+    }
+}
+
+impl SessionMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次事件：`ui` 为处理后的快照，`actions` 为产生的动作，`selected` 为本次选中的候选排名。
+    pub fn record(&mut self, ui: &UiState, actions: &[Action], selected: &[usize]) {
+        self.keystrokes += 1;
+        for &rank in selected {
+            if self.ranks.len() <= rank {
+                self.ranks.resize(rank + 1, 0);
+            }
+            self.ranks[rank] += 1;
+            self.selections += 1;
+            self.candidates_shown += self.last_shown;
+        }
+        let mut committed = false;
+        for a in actions {
+            if let Action::Commit(text) = a {
+                self.commits += 1;
+                self.committed_chars += text.chars().count();
+                committed = true;
+            }
+        }
+        // 码表码满上屏时，新组合在同一个事件里开始：先结束旧组合，再按处理后的输入计新组合
+        if self.peak > 0 && (committed || ui.raw_input.is_empty()) {
+            if committed {
+                self.compositions_committed += 1;
+            } else {
+                self.compositions_abandoned += 1;
+            }
+            self.composition_chars += self.peak;
+            self.peak = 0;
+        }
+        self.peak = self.peak.max(ui.raw_input.chars().count());
+        self.last_shown = ui.candidate_list.len();
+    }
+
+    /// 选中首选（第 1 页第 1 个）的次数。
+    pub fn first_choice(&self) -> usize {
+        self.ranks.first().copied().unwrap_or(0)
+    }
+
+    /// 选中非首选的次数。
+    pub fn other_choice(&self) -> usize {
+        self.selections - self.first_choice()
+    }
+
+    /// 每次选词平均展示的候选数；尚未选词时为 0。
+    pub fn average_candidates(&self) -> f64 {
+        ratio(self.candidates_shown, self.selections)
+    }
+
+    /// 每上屏一个字符的按键数；尚未上屏时为 0。
+    pub fn keystrokes_per_char(&self) -> f64 {
+        ratio(self.keystrokes, self.committed_chars)
+    }
+
+    /// 已结束的组合的平均长度；尚无组合结束时为 0。
+    pub fn average_composition_len(&self) -> f64 {
+        ratio(self.composition_chars, self.compositions_committed + self.compositions_abandoned)
+    }
+
+    /// 自创建（或 `Session::reset_metrics`）以来经过的时间。
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 { 0.0 } else { a as f64 / b as f64 }
+}
@@ -1,18 +1,194 @@
+use std::{borrow::Borrow, collections::BTreeMap, fmt, ops::{Deref, Range}, sync::Arc};
+
+use crate::config::{Orientation, PreeditStyle};
+
+/// 候选文本：共享的不可变字符串，克隆只增加引用计数。
+///
+/// 解引用为 `str`，多数只读用法无需改动；需要 `String` 时用 `to_string()`。
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CandidateText(Arc<str>);
+
+impl CandidateText {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for CandidateText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for CandidateText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for CandidateText {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CandidateText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for CandidateText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for CandidateText {
+    fn from(s: &str) -> Self {
+        Self(Arc::from(s))
+    }
+}
+
+impl From<String> for CandidateText {
+    fn from(s: String) -> Self {
+        Self(Arc::from(s))
+    }
+}
+
+impl From<Arc<str>> for CandidateText {
+    fn from(s: Arc<str>) -> Self {
+        Self(s)
+    }
+}
+
+impl PartialEq<str> for CandidateText {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for CandidateText {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CandidateText {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CandidateText {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
 /// 候选词（可被 UI 展示与用户选择）。
 ///
 /// 注意：`segment_start/segment_end` 是**对当前 segment 切分结果的索引范围**，
 /// 用于 `Context` 推进 `confirmed`。
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Candidate {
     /// 候选展示文本（提交文本）
-    pub text: String,
+    pub text: CandidateText,
     /// 备注（例如来源 key、是否 compose 等）
-    pub comment: Option<String>,
+    pub comment: Option<CandidateText>,
     /// 权重（越大越靠前），由词典/模型决定
     pub weight: i32,
+    /// 未截断的 i64 分数（组句候选的路径分数可能超出 i32）；排序时优先于 `weight`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub score: Option<i64>,
     /// 覆盖的音节段范围：[segment_start, segment_end)
     pub segment_start: usize,
     pub segment_end: usize,
+    /// 候选来源（同权重时按来源优劣排序）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source: CandidateSource,
+    /// translator 产出的先后次序（同权重、同来源时保持上游顺序）；词典构造时填 0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rank: usize,
+    /// 组句候选的逐词切分（依次覆盖 `segment_start..segment_end`）；其他候选为空
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub parts: Vec<CandidatePart>,
+    /// 任意键值元数据（emoji 分类、云端置信度等），供自定义 translator 与前端传递额外信息。
+    ///
+    /// core 只写入自己定义的键（`recent::RECENT_AGE`、`explain` 的各键），不解读其他键；translator 合并与各 filter 原样保留。装箱使常见的空元数据只占一个指针。
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub meta: Option<Box<BTreeMap<String, String>>>,
+}
+
+/// 组句候选中的一个词：文本及其覆盖的段范围 [segment_start, segment_end)。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CandidatePart {
+    pub text: CandidateText,
+    pub segment_start: usize,
+    pub segment_end: usize,
+}
+
+/// 候选来源，按声明顺序由优到劣。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CandidateSource {
+    /// 词典精确匹配
+    #[default]
+    Exact,
+    /// 整段输入的前缀补全
+    Prefix,
+    /// 简拼（首字母）匹配
+    Initials,
+    /// 模糊音拼写或码表通配符命中
+    Fuzzy,
+    /// 组句
+    Compose,
+    /// 上屏后的联想（见 `prediction`）
+    Prediction,
+    /// 异步来源注入（见 `async_source`）
+    External,
+}
+
+impl Candidate {
+    /// 排序用分数：有 `score` 时取之，否则为 `weight`。
+    pub fn effective_score(&self) -> i64 {
+        self.score.unwrap_or(i64::from(self.weight))
+    }
+
+    /// 设置元数据 `key`（已有时覆盖）。
+    pub fn with_meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.meta.get_or_insert_with(Box::default).insert(key.into(), value.into());
+        self
+    }
+
+    /// 元数据 `key` 的值。
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.meta.as_ref()?.get(key).map(String::as_str)
+    }
+}
+
+/// 候选列表中来源相同的连续区间，依次为 (来源, 下标范围)；用于 `UiState::groups`。
+pub fn source_groups(candidates: &[Candidate]) -> Vec<(CandidateSource, Range<usize>)> {
+    let mut groups: Vec<(CandidateSource, Range<usize>)> = Vec::new();
+    for (i, c) in candidates.iter().enumerate() {
+        match groups.last_mut() {
+            Some((source, range)) if *source == c.source => range.end = i + 1,
+            _ => groups.push((c.source, i..i + 1)),
+        }
+    }
+    groups
+}
+
+/// 候选列表摘要：前 `n` 个 `text(weight)`，用于日志。
+pub fn summarize(candidates: &[Candidate], n: usize) -> String {
+    let top: Vec<String> = candidates.iter().take(n).map(|c| format!("{}({})", c.text, c.weight)).collect();
+    top.join(" ")
 }
 
 /// 引擎给 UI 的“快照视图”。
@@ -21,19 +197,262 @@ pub struct Candidate {
 /// - UI 层只读 `UiState`，不直接读写 `Context`
 /// - 便于 GUI/CLI 输出与调试
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UiState {
     /// 原始输入字符串（未上屏的拼音/简拼）
     pub raw_input: String,
     /// preedit 展示（例如 "ni hao ma"）
     pub preedit: String,
-    /// 音节段切分结果（用于组词、选词推进）
-    pub segment: Vec<String>,
+    /// 音节段切分结果（用于组词、选词推进）；与 `Context` 中的切分共享
+    pub segment: Arc<[String]>,
     /// 光标所在段位置（第一版默认在末尾）
     pub caret: usize,
     /// 已确认段范围的结束位置：[0, confirm)
     pub confirm: usize,
     /// 已确认文本（内部 composition）
     pub confirm_text: String,
-    /// 当前可选候选列表（通常是“从 confirm 开始”的候选）
+    /// 当前页的候选列表（通常是“从 confirm 开始”的候选）
     pub candidate_list: Vec<Candidate>,
+    /// 当前页码（0 起）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub page: usize,
+    /// 是否还有下一页
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub has_next_page: bool,
+    /// 候选是否因查询预算（`Engine::max_lookups`）用尽而不完整
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub truncated: bool,
+    /// 候选是否为上屏后的联想（此时没有输入，前端可用不同样式展示）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub predicting: bool,
+    /// 候选中是否已并入异步来源的结果（见 `async_source`）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub injected: bool,
+    /// 输出文本是否已转换为繁体（见 `convert`）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub traditional: bool,
+    /// 是否处于英文模式（见 `context::InputMode`）：输入原样累积，第一个候选即原始输入
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub english: bool,
+    /// 分组展示（`EngineConfig::group_by_source`）时，`candidate_list` 中同来源的连续区间；交错排列时为空。
+    ///
+    /// 只描述展示分段，`Select(n)` 仍是 `candidate_list` 的下标。
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub groups: Vec<(CandidateSource, Range<usize>)>,
+    /// 内嵌组合串（样式见 `PreeditStyle`）；没有输入时为空
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub composition: String,
+    /// 光标在 `composition` 中的位置（按字符计）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub composition_caret: usize,
+    /// 当前输入方案的名字（见 `schema`）；引擎只有一个方案时为空
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "String::is_empty"))]
+    pub schema: String,
+    /// 候选窗的展示提示（见 `LayoutHints`）
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub layout: LayoutHints,
+    /// 本次组合中值得注意的情况（见 `Diagnostic`），用来解释候选为什么为空或不完整；正常时为空
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// 组合时的诊断信息：候选为空或不完整的原因，由引擎、translator 与 filter 记下（见 `UiState::diagnostics`）。
+///
+/// 只是说明，不是错误：有诊断时候选照常产出（可能为空）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Diagnostic {
+    /// 有输入，但切分器切不出任何段（例如不是拼音的字母串）
+    NoSegmentation,
+    /// 请求的确认位置/光标超出切分结果（已按段数收紧）
+    SpanOutOfBounds { confirm: usize, caret: usize, len: usize },
+    /// 查询预算（`Engine::max_lookups`）用尽，候选不完整（同 `UiState::truncated`）
+    BudgetExhausted,
+    /// 词典与各 translator 对 `start..end` 都没有给出候选
+    NoCandidates { start: usize, end: usize },
+    /// 自定义 filter（`Filter::name`）把非空的候选列表过滤为空
+    FilterRemovedAll { filter: String },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSegmentation => write!(f, "输入无法切分"),
+            Self::SpanOutOfBounds { confirm, caret, len } => write!(f, "确认位置 {confirm} / 光标 {caret} 超出段数 {len}"),
+            Self::BudgetExhausted => write!(f, "查询预算用尽，候选不完整"),
+            Self::NoCandidates { start, end } => write!(f, "段 {start}..{end} 没有候选"),
+            Self::FilterRemovedAll { filter } => write!(f, "filter `{filter}` 过滤掉了全部候选"),
+        }
+    }
+}
+
+/// 候选窗的展示提示：引擎与会话的配置（`EngineConfig::orientation`/`select_labels`、`Session::with_layout`）加上翻页状态。
+///
+/// 不做任何配置的前端照此展示即可；有自己的配置的前端可以忽略。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutHints {
+    /// 每页候选数（`EngineConfig::candidate_limit`）
+    pub page_size: usize,
+    /// 横排还是竖排
+    pub orientation: Orientation,
+    /// 候选标签，第 n 个字符标注第 n 个候选；字符不够时多出的候选没有标签
+    pub labels: Arc<str>,
+    /// 是否有上一页（`page > 0`）
+    pub has_prev_page: bool,
+    /// 是否有下一页（同 `UiState::has_next_page`）
+    pub has_next_page: bool,
+}
+
+impl UiState {
+    /// 按 `style` 重新生成 `composition` 与 `composition_caret`；候选或已确认文本变化后须再次调用。
+    ///
+    /// 拼音部分的各段以空格分隔；还没有确认任何段时直接用 `preedit`。
+    pub fn update_composition(&mut self, style: PreeditStyle) {
+        if self.raw_input.is_empty() {
+            self.composition.clear();
+            self.composition_caret = 0;
+            return;
+        }
+        let mut out = std::mem::take(&mut self.composition);
+        out.clear();
+        out.push_str(&self.confirm_text);
+        // 拼音部分从哪一段开始
+        let pinyin_from = match self.candidate_list.first() {
+            Some(top) if style == PreeditStyle::Preview && top.segment_start == self.confirm && top.segment_end > self.confirm => {
+                out.push_str(&top.text);
+                top.segment_end
+            }
+            _ => self.confirm,
+        };
+        let head_chars = out.chars().count();
+        if pinyin_from == 0 {
+            out.push_str(&self.preedit);
+        } else {
+            for (i, seg) in self.segment.iter().enumerate().skip(pinyin_from) {
+                if i > pinyin_from {
+                    out.push(' ');
+                }
+                out.push_str(seg);
+            }
+        }
+        // 光标不在末尾时，光标前为 segment[pinyin_from..caret] 以空格连接
+        self.composition_caret = match self.segment.get(pinyin_from..self.caret) {
+            Some(before) if self.caret < self.segment.len() => head_chars + before.iter().map(|s| s.chars().count()).sum::<usize>() + before.len().saturating_sub(1),
+            _ => out.chars().count(),
+        };
+        self.composition = out;
+    }
+
+    /// 与上一次快照 `previous` 相比哪些部分变了，逐字段比较；前端据此只重绘变化的部分。
+    ///
+    /// 候选按展示内容比较（文本、注释、覆盖范围、来源），不看分数、权重与上游次序等不展示的字段。
+    pub fn diff(&self, previous: &UiState) -> UiDiff {
+        UiDiff {
+            preedit: self.raw_input != previous.raw_input
+                || self.preedit != previous.preedit
+                || self.segment != previous.segment
+                || self.caret != previous.caret
+                || self.composition != previous.composition
+                || self.composition_caret != previous.composition_caret,
+            candidates: changed_range(&previous.candidate_list, &self.candidate_list),
+            confirm: self.confirm != previous.confirm || self.confirm_text != previous.confirm_text,
+            status: self.page != previous.page
+                || self.has_next_page != previous.has_next_page
+                || self.truncated != previous.truncated
+                || self.predicting != previous.predicting
+                || self.injected != previous.injected
+                || self.traditional != previous.traditional
+                || self.english != previous.english
+                || self.schema != previous.schema
+                || self.groups != previous.groups
+                || self.layout != previous.layout
+                || self.diagnostics != previous.diagnostics,
+        }
+    }
+
+    /// 单行紧凑快照，例如：
+    ///
+    /// `raw=nihao | pre="ni hao" | seg=[ni,hao] | caret=2 confirm=0 "" | cands: 你好(nihao,100,0..2); 你(ni,50,0..1)`
+    ///
+    /// 输出只依赖字段内容，跨平台稳定，便于断言与贴进 bug 报告。
+    /// 候选的 key 取 `comment`（前缀补全/简拼时为完整拼音），否则为覆盖的音节拼接；
+    /// 不在首页或还有下一页时追加 `page=N`（有下一页再加 `+`）；查询预算用尽时追加 `truncated`；联想时追加 `predicting`，并入异步结果时追加 `injected`，繁体输出时追加 `traditional`，英文模式时追加 `english`，分组展示时追加 `groups=来源:范围,...`。
+    pub fn to_snapshot_string(&self) -> String {
+        let mut s = format!("raw={} | pre={:?} | seg=[{}] | caret={} confirm={} {:?}", self.raw_input, self.preedit, self.segment.join(","), self.caret, self.confirm, self.confirm_text);
+        if self.page > 0 || self.has_next_page {
+            s += &format!(" | page={}{}", self.page, if self.has_next_page { "+" } else { "" });
+        }
+        if self.truncated {
+            s += " | truncated";
+        }
+        if self.predicting {
+            s += " | predicting";
+        }
+        if self.injected {
+            s += " | injected";
+        }
+        if self.traditional {
+            s += " | traditional";
+        }
+        if self.english {
+            s += " | english";
+        }
+        if !self.schema.is_empty() {
+            s += &format!(" | schema={}", self.schema);
+        }
+        if !self.groups.is_empty() {
+            let groups: Vec<String> = self.groups.iter().map(|(source, r)| format!("{source:?}:{}..{}", r.start, r.end)).collect();
+            s += &format!(" | groups={}", groups.join(","));
+        }
+        s += " | cands:";
+        if self.candidate_list.is_empty() {
+            s += " -";
+        }
+        for (i, c) in self.candidate_list.iter().enumerate() {
+            let key = c.comment.as_ref().map_or_else(|| self.segment.get(c.segment_start..c.segment_end).map(|s| s.concat()).unwrap_or_default(), |k| k.to_string());
+            s += &format!("{} {}({},{},{}..{})", if i == 0 { "" } else { ";" }, c.text, key, c.weight, c.segment_start, c.segment_end);
+        }
+        s
+    }
+}
+
+/// 两次 `UiState` 之间的变化（见 `UiState::diff`）；各项都没变时 `is_empty`。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UiDiff {
+    /// 组合串变了：`raw_input`、`preedit`、`segment`、`caret`、`composition`、`composition_caret`
+    pub preedit: bool,
+    /// 候选变了：需要重绘的下标范围（新列表的下标；列表变短时包括已移除的位置）；没变时为 None
+    ///
+    /// 范围从第一个不同的候选开始；长度不变时到最后一个不同的候选为止，否则到较长列表的末尾。
+    pub candidates: Option<Range<usize>>,
+    /// 已确认部分变了：`confirm`、`confirm_text`
+    pub confirm: bool,
+    /// 状态变了：`page`、`has_next_page`、`truncated`、`predicting`、`injected`、`traditional`、`english`、`schema`、`groups`、`layout`、`diagnostics`
+    pub status: bool,
+}
+
+impl UiDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 候选的展示内容是否相同（见 `UiState::diff`）。
+fn same_display(a: &Candidate, b: &Candidate) -> bool {
+    a.text == b.text && a.comment == b.comment && a.segment_start == b.segment_start && a.segment_end == b.segment_end && a.source == b.source
+}
+
+/// 两个候选列表中需要重绘的下标范围；展示内容完全相同时为 None。
+fn changed_range(old: &[Candidate], new: &[Candidate]) -> Option<Range<usize>> {
+    let start = old.iter().zip(new).position(|(a, b)| !same_display(a, b)).unwrap_or(old.len().min(new.len()));
+    if old.len() != new.len() {
+        return Some(start..old.len().max(new.len()));
+    }
+    if start == new.len() {
+        return None;
+    }
+    let end = new.len() - old.iter().zip(new).rev().position(|(a, b)| !same_display(a, b)).unwrap_or(0);
+    Some(start..end)
 }
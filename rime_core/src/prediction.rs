@@ -0,0 +1,25 @@
+//! `prediction`：上屏后的联想（预测接下来要输入的词）。
+//!
+//! 会话开启联想（`Session::with_prediction`）后，每次上屏都以上屏文本查询 `PredictionSource`，
+//! 结果作为候选展示（`UiState::predicting` 为 true，候选来源为 `CandidateSource::Prediction`）：
+//! 选词即上屏并继续联想；输入字符则退出联想、开始正常输入（见 `processor::PredictionProcessor`）。
+
+use std::sync::Arc;
+
+use crate::model::Candidate;
+
+/// 联想来源：给定刚上屏的文本，给出接下来可能输入的词。
+pub trait PredictionSource: Send + Sync {
+    /// 按推荐顺序返回至多 `limit` 个候选；`segment_start/segment_end`、`source` 由会话填充。
+    fn predict(&self, previous: &str, limit: usize) -> Vec<Candidate>;
+}
+
+/// 多个会话共享同一份联想数据。
+impl<P> PredictionSource for Arc<P>
+where
+    P: PredictionSource + ?Sized,
+{
+    fn predict(&self, previous: &str, limit: usize) -> Vec<Candidate> {
+        (**self).predict(previous, limit)
+    }
+}
@@ -4,22 +4,37 @@
 //! 对 `Context` 做状态变更，并可产生 `Action`（例如 Commit）。
 //!
 //! 当前链路（`Session::new` 默认组装）：
-//! - `EditingProcessor`：编辑输入（Char/Backspace/Clear）并触发重新切分
-//! - `SelectionProcessor`：选词（Space/Select(n)）推进 confirmed
+//! - `PredictionProcessor`：联想状态下选择/关闭联想候选（未开启联想时不做任何事）
+//! - `PunctuationProcessor`：标点上屏为中文标点（未开启时映射表为空，不做任何事）
+//! - `EditingProcessor`：编辑输入（Char/Backspace/Clear）、移动光标（MoveLeft/MoveRight）、切换拼音/英文模式（ToggleMode）并触发重新切分；接受哪些字符由 `InputPolicy` 决定
+//! - `PagingProcessor`：候选翻页（NextPage/PrevPage）
+//! - `SelectionProcessor`：选词（Space/Select(n)）推进 confirmed；删除候选（DeleteCandidate(n)，见 `blacklist`）
 //! - `EnterCommitProcessor`：回车提交（confirmed_text + raw_input）
 
+use std::collections::BTreeMap;
+
 use crate::{
-    context::Context,
-    engine::Analysis,
-    key_event::{Action, InputEvent},
+    config::{DedupMode, PreeditStyle, SentenceSelect},
+    context::{Context, InputMode},
+    engine::{Analysis, PageRequest, SegmentationKind},
+    key_event::{Action, InputEvent, RejectReason},
     model::UiState,
+    ranking::{FrequencyFirst, RankingPolicy},
+    schema::AnalyzerSet,
 };
 
 /// 给 processors 的对象安全引擎接口（避免在 processors 层引入泛型爆炸）。
 pub trait EngineFacade {
     /// 切分输入：raw -> (segment + preedit)
     fn analyze(&self, raw_input: &str) -> Analysis;
-    /// 组合输出：根据 segment/caret/confirmed 生成 UiState（候选等）
+    /// 用第 `schema` 个方案切分（见 `schemas`）；只有一个方案时同 `analyze`
+    fn analyze_as(&self, schema: usize, raw_input: &str) -> Analysis {
+        match self.schemas() {
+            Some(schemas) => schemas.analyze_as(schema, raw_input),
+            None => self.analyze(raw_input),
+        }
+    }
+    /// 组合输出：根据 segment/caret/confirmed 与所取的页（`request`）生成 UiState（候选等）
     fn compose_with_state(
         &self,
         raw_input: &str,
@@ -27,13 +42,55 @@ pub trait EngineFacade {
         confirmed: usize,
         caret: Option<usize>,
         confirmed_text: String,
+        request: PageRequest<'_>,
     ) -> UiState;
+    /// `confirmed..caret` 各页合计的候选数（按 `request` 并入、去掉候选，不看页码），用于展示总页数；须取出全部候选，比组合一页慢得多
+    fn candidate_count(&self, raw_input: &str, analysis: &Analysis, confirmed: usize, caret: usize, request: PageRequest<'_>) -> usize;
+    /// 词典内容版本（见 `Dictionary::generation`），用于判断缓存的快照是否过期
+    fn generation(&self) -> u64 {
+        0
+    }
+    /// 每页候选数
+    fn page_size(&self) -> usize {
+        9
+    }
+    /// 候选是否按来源分组展示（见 `UiState::groups`）
+    fn group_by_source(&self) -> bool {
+        false
+    }
+    /// 候选去重的条件
+    fn dedup_mode(&self) -> DedupMode {
+        DedupMode::ByTextAndSpan
+    }
+    /// 选择组句候选时的确认方式
+    fn sentence_select(&self) -> SentenceSelect {
+        SentenceSelect::WholeSentence
+    }
+    /// 内嵌组合串的样式
+    fn preedit_style(&self) -> PreeditStyle {
+        PreeditStyle::Pinyin
+    }
+    /// 候选排序策略
+    fn ranking(&self) -> &dyn RankingPolicy {
+        &FrequencyFirst
+    }
+    /// 可在会话内切换的方案（见 `schema`）；None 即只有一个方案
+    fn schemas(&self) -> Option<&AnalyzerSet> {
+        None
+    }
+    /// 码表满码长度（自动上屏）；None 即不自动上屏
+    fn auto_commit_length(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Processor 执行结果：是否“消费”了本次事件。
 ///
 /// - `Consume`：本 processor 已处理该事件，后续 processor 不再执行
 /// - `Continue`：本 processor 不处理该事件，交给下一个 processor
+///
+/// 没有输入时，编辑/翻页/选词/提交类按键一律 `Continue`：整条链都不处理，前端据此把按键交还给应用（见 `host::drive`）。
+/// 其中选词另附 `Action::Rejected(RejectReason::NotComposing)`，便于调用方断言。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessStatus {
     Consume,
@@ -50,8 +107,160 @@ pub trait Processor: Send + Sync {
     ) -> (ProcessStatus, Vec<Action>);
 }
 
-/// 编辑输入的 processor（插入/退格/清空）。
-pub struct EditingProcessor;
+/// 组合串接受哪些字符、其余字符如何处理（由 `EditingProcessor` 查询）。
+///
+/// 默认只接受字母（转为小写）与 `'`，其余字符丢弃。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputPolicy {
+    /// 接受 ASCII 字母（`keep_case` 为 false 时统一转为小写）
+    pub letters: bool,
+    /// 保留字母大小写（大写开头的输入即临时英文，见 `Engine::english_translator`）
+    pub keep_case: bool,
+    /// 接受 ASCII 数字（例如声调）
+    pub digits: bool,
+    /// 另外接受的字符
+    pub extra: Vec<char>,
+    /// 不接受的字符如何处理
+    pub reject: RejectAction,
+}
+
+impl Default for InputPolicy {
+    fn default() -> Self {
+        Self {
+            letters: true,
+            keep_case: false,
+            digits: false,
+            extra: vec!['\''],
+            reject: RejectAction::Drop,
+        }
+    }
+}
+
+impl InputPolicy {
+    /// `ch` 是否进入组合串。
+    pub fn accepts(&self, ch: char) -> bool {
+        (self.letters && ch.is_ascii_alphabetic()) || (self.digits && ch.is_ascii_digit()) || self.extra.contains(&ch)
+    }
+}
+
+/// `InputPolicy` 不接受的字符如何处理。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RejectAction {
+    /// 输入中直接丢弃；没有输入时交还应用
+    #[default]
+    Drop,
+    /// 总是交给后续 processor，无人处理时由前端交还应用（输入中也一样）
+    Forward,
+}
+
+/// 联想状态下的按键处理（见 `prediction`）；没有联想候选时直接交给下一个 processor。
+///
+/// `Select(n)` 上屏第 n 个联想词（会话随即以它再次联想），`Clear` 关闭联想；
+/// 其余按键先关闭联想再交给下一个 processor：字符开始正常输入，空格、回车等交还应用。
+pub struct PredictionProcessor;
+
+impl Processor for PredictionProcessor {
+    fn process(
+        &mut self,
+        _engine: &dyn EngineFacade,
+        context: &mut Context,
+        input_event: &InputEvent,
+    ) -> (ProcessStatus, Vec<Action>) {
+        if !context.is_predicting() {
+            return (ProcessStatus::Continue, Vec::new());
+        }
+        match *input_event {
+            InputEvent::Select(i) => {
+                let Some(text) = context.predictions().get(i).map(|c| c.text.to_string()) else {
+                    return (ProcessStatus::Consume, vec![Action::Rejected(RejectReason::NoSuchCandidate)]);
+                };
+                context.clear_predictions();
+                context.mark_selected(i);
+                (ProcessStatus::Consume, vec![Action::Commit(text)])
+            }
+            InputEvent::Clear => {
+                context.clear_predictions();
+                (ProcessStatus::Consume, Vec::new())
+            }
+            _ => {
+                context.clear_predictions();
+                (ProcessStatus::Continue, Vec::new())
+            }
+        }
+    }
+}
+
+/// 标点的 processor（见 `punct`）：映射表中的字符上屏为对应的文本。
+///
+/// 没有输入时直接上屏标点；正在输入时先逐段选首选直到上屏（无候选时同回车，上屏已确认文本与剩余原串），再上屏标点，
+/// 即产生两个 `Action::Commit`。`InputPolicy` 接受的字符不在此处理，英文模式下也不处理，都交给 `EditingProcessor`。
+#[derive(Debug, Default)]
+pub struct PunctuationProcessor {
+    map: BTreeMap<char, String>,
+}
+
+impl PunctuationProcessor {
+    /// 以 `map` 为映射表，去掉其中 `policy` 接受的字符。
+    pub fn new(mut map: BTreeMap<char, String>, policy: &InputPolicy) -> Self {
+        map.retain(|&ch, _| !policy.accepts(ch));
+        Self { map }
+    }
+}
+
+impl Processor for PunctuationProcessor {
+    fn process(
+        &mut self,
+        engine: &dyn EngineFacade,
+        context: &mut Context,
+        input_event: &InputEvent,
+    ) -> (ProcessStatus, Vec<Action>) {
+        let InputEvent::Char(ch) = *input_event else {
+            return (ProcessStatus::Continue, Vec::new());
+        };
+        let Some(text) = self.map.get(&ch).filter(|_| context.mode() == InputMode::Pinyin) else {
+            return (ProcessStatus::Continue, Vec::new());
+        };
+        let mut actions = Vec::new();
+        // 每次选首选至少推进一段，必然结束
+        while !context.raw_input.is_empty() {
+            let step = context.select_candidate(engine, 0);
+            if step.iter().any(|a| matches!(a, Action::Rejected(_))) {
+                actions.extend(context.commit_on_enter());
+                break;
+            }
+            actions.extend(step);
+        }
+        actions.push(Action::Commit(text.clone()));
+        (ProcessStatus::Consume, actions)
+    }
+}
+
+/// 编辑输入的 processor（插入/退格/清空/移动光标）。
+///
+/// 字符是否进入组合串由 `InputPolicy` 决定。没有输入时，退格、清空、移动光标以及分隔符 `'` 等交给下一个 processor。
+///
+/// 英文模式（`InputEvent::ToggleMode` 切换，见 `context::InputMode`）下字符不经 `InputPolicy`，除控制字符外原样累积，不自动上屏。
+///
+/// 光标按段移动（见 `Context::move_caret`）；光标不在末尾时退格删除光标前的一段，新输入的字符总是追加在末尾。
+///
+/// 临时英文夹在拼音中间（见 `Context::start_literal`）：`InputEvent::CommitLiteral` 开始原样输入，再次按下即结束；
+/// `keep_case` 时组合中输入大写字母也开始原样输入。
+///
+/// `raw_input` 按原样保存用户的输入（含重复或末尾的 `'`），规整只发生在切分时。
+///
+/// 码表方案（`SegmentationKind::Codes`）码满后再输入：先上屏前一个编码的首选，新字符开始下一个编码；
+/// 前一个编码没有候选时不上屏，输入按多个编码保留。码满有两种来源：analyzer 切出了第二个编码，
+/// 或唯一的编码超出 `EngineFacade::auto_commit_length`；后者在编码恰好满码且只有一个候选时直接上屏。
+#[derive(Debug, Default)]
+pub struct EditingProcessor {
+    policy: InputPolicy,
+}
+
+impl EditingProcessor {
+    pub fn new(policy: InputPolicy) -> Self {
+        Self { policy }
+    }
+}
 
 impl Processor for EditingProcessor {
     fn process(
@@ -61,21 +270,133 @@ impl Processor for EditingProcessor {
         input_event: &InputEvent,
     ) -> (ProcessStatus, Vec<Action>) {
         match *input_event {
+            InputEvent::ToggleMode => {
+                let mode = match context.mode() {
+                    InputMode::Pinyin => InputMode::English,
+                    InputMode::English => InputMode::Pinyin,
+                };
+                context.set_mode(engine, mode);
+                (ProcessStatus::Consume, Vec::new())
+            }
+            InputEvent::Char(ch) if context.mode() == InputMode::English && !ch.is_control() => {
+                context.raw_input.push(ch);
+                context.reanalyze(engine);
+                (ProcessStatus::Consume, Vec::new())
+            }
+            // `'` 是音节分隔符，不能开始输入：没有输入时交还应用
+            InputEvent::Char('\'') if context.raw_input.is_empty() => (ProcessStatus::Continue, Vec::new()),
             InputEvent::Char(ch) => {
-                // 匹配输入字符是否( a-z | A-Z )
-                if ch.is_ascii_alphabetic() || ch == '\'' {
-                    (&mut context.raw_input).push(ch.to_ascii_lowercase());
-                    (&mut *context).reanalyze(engine);
+                if self.policy.accepts(ch) {
+                    // 组合中输入大写字母即开始临时英文（整段输入本身是临时英文或以大写字母开头时除外）
+                    let capitalized = context.raw_input.starts_with(|c: char| c.is_ascii_uppercase());
+                    if self.policy.keep_case && ch.is_ascii_uppercase() && !context.raw_input.is_empty() && !capitalized && context.analysis.kind != SegmentationKind::Raw {
+                        context.start_literal();
+                    }
+                    let codes = context.analysis.segment.len();
+                    context.raw_input.push(if self.policy.keep_case { ch } else { ch.to_ascii_lowercase() });
+                    context.reanalyze(engine);
+                    if context.analysis.kind == SegmentationKind::Codes && codes == 1 && context.analysis.segment.len() == 2 {
+                        return (ProcessStatus::Consume, auto_commit(engine, context));
+                    }
+                    if context.analysis.kind == SegmentationKind::Codes
+                        && let Some(n) = engine.auto_commit_length()
+                        && let [code] = &*context.analysis.segment
+                    {
+                        let len = code.chars().count();
+                        if len > n {
+                            return (ProcessStatus::Consume, auto_commit(engine, context));
+                        }
+                        if len == n {
+                            let ui = context.ui_state_cached(engine);
+                            if ui.candidate_list.len() == 1 && !ui.has_next_page {
+                                return (ProcessStatus::Consume, context.select_candidate(engine, 0));
+                            }
+                        }
+                    }
+                } else if context.raw_input.is_empty() || self.policy.reject == RejectAction::Forward {
+                    return (ProcessStatus::Continue, Vec::new());
                 }
                 (ProcessStatus::Consume, Vec::new())
             }
+            InputEvent::Backspace | InputEvent::Clear | InputEvent::MoveLeft | InputEvent::MoveRight if context.raw_input.is_empty() => (ProcessStatus::Continue, Vec::new()),
             InputEvent::Backspace => {
-                (&mut context.raw_input).pop();
-                (&mut *context).reanalyze(engine);
+                context.delete_before_caret(engine);
+                (ProcessStatus::Consume, Vec::new())
+            }
+            InputEvent::MoveLeft | InputEvent::MoveRight => {
+                context.move_caret(*input_event == InputEvent::MoveLeft);
+                (ProcessStatus::Consume, Vec::new())
+            }
+            // 英文模式下整段都是原样输入
+            InputEvent::CommitLiteral if context.mode() == InputMode::English => (ProcessStatus::Consume, Vec::new()),
+            InputEvent::CommitLiteral => {
+                if !context.commit_literal(engine) {
+                    context.start_literal();
+                }
                 (ProcessStatus::Consume, Vec::new())
             }
             InputEvent::Clear => {
-                (&mut *context).reset();
+                context.reset();
+                (ProcessStatus::Consume, Vec::new())
+            }
+            _ => (ProcessStatus::Continue, Vec::new()),
+        }
+    }
+}
+
+/// 码表码满：撤回刚输入的字符，上屏前一个编码的首选，再把该字符作为下一个编码的开头。
+fn auto_commit(engine: &dyn EngineFacade, context: &mut Context) -> Vec<Action> {
+    let Some(ch) = context.raw_input.pop() else {
+        return Vec::new();
+    };
+    context.reanalyze(engine);
+    let actions = context.select_candidate(engine, 0);
+    let committed = actions.iter().any(|a| matches!(a, Action::Commit(_)));
+    context.raw_input.push(ch);
+    context.reanalyze(engine);
+    if committed { actions } else { Vec::new() }
+}
+
+/// 切换输入方案的 processor（`InputEvent::NextSchema`，见 `schema`）。
+///
+/// 引擎只有一个方案时不处理，按键交还应用。
+pub struct SchemaProcessor;
+
+impl Processor for SchemaProcessor {
+    fn process(
+        &mut self,
+        engine: &dyn EngineFacade,
+        context: &mut Context,
+        input_event: &InputEvent,
+    ) -> (ProcessStatus, Vec<Action>) {
+        match (input_event, engine.schemas()) {
+            (InputEvent::NextSchema, Some(schemas)) if schemas.len() > 1 => {
+                context.set_schema(engine, (context.schema() + 1) % schemas.len());
+                (ProcessStatus::Consume, Vec::new())
+            }
+            _ => (ProcessStatus::Continue, Vec::new()),
+        }
+    }
+}
+
+/// 候选翻页的 processor。
+pub struct PagingProcessor;
+
+impl Processor for PagingProcessor {
+    fn process(
+        &mut self,
+        engine: &dyn EngineFacade,
+        context: &mut Context,
+        input_event: &InputEvent,
+    ) -> (ProcessStatus, Vec<Action>) {
+        match *input_event {
+            InputEvent::NextPage | InputEvent::PrevPage if context.raw_input.is_empty() => (ProcessStatus::Continue, Vec::new()),
+            InputEvent::NextPage => {
+                context.turn_page(engine, true);
+                (ProcessStatus::Consume, Vec::new())
+            }
+            InputEvent::PrevPage => {
+                context.turn_page(engine, false);
                 (ProcessStatus::Consume, Vec::new())
             }
             _ => (ProcessStatus::Continue, Vec::new()),
@@ -93,16 +414,21 @@ impl Processor for SelectionProcessor {
         input_event: &InputEvent,
     ) -> (ProcessStatus, Vec<Action>) {
         match *input_event {
+            InputEvent::Space if context.raw_input.is_empty() => (ProcessStatus::Continue, Vec::new()),
+            // 按键仍交还应用，另附拒绝原因（见 `host::drive`）
+            InputEvent::Select(_) | InputEvent::DeleteCandidate(_) if context.raw_input.is_empty() => (ProcessStatus::Continue, vec![Action::Rejected(RejectReason::NotComposing)]),
             // 输入的是空格键
             InputEvent::Space => {
-                let action: Vec<Action> = (&mut *context).select_candidate(engine, 0);
+                let action: Vec<Action> = context.select_candidate(engine, 0);
                 (ProcessStatus::Consume, action)
             }
-            // 输入的是1-9数字
+            // 选择当前页第 i 个候选
             InputEvent::Select(i) => {
-                let action: Vec<Action> = (&mut *context).select_candidate(engine, i);
+                let action: Vec<Action> = context.select_candidate(engine, i);
                 (ProcessStatus::Consume, action)
             }
+            // 删除当前页第 i 个候选
+            InputEvent::DeleteCandidate(i) => (ProcessStatus::Consume, context.forget_candidate(engine, i)),
             _ => (ProcessStatus::Continue, Vec::new()),
         }
     }
@@ -113,12 +439,13 @@ pub struct EnterCommitProcessor;
 impl Processor for EnterCommitProcessor {
     fn process(
         &mut self,
-        engine: &dyn EngineFacade,
+        _engine: &dyn EngineFacade,
         context: &mut Context,
         input_event: &InputEvent,
     ) -> (ProcessStatus, Vec<Action>) {
         match *input_event {
-            InputEvent::Enter => (ProcessStatus::Consume, (&mut *context).commit_on_enter()),
+            InputEvent::Enter if context.raw_input.is_empty() => (ProcessStatus::Continue, Vec::new()),
+            InputEvent::Enter => (ProcessStatus::Consume, context.commit_on_enter()),
             _ => (ProcessStatus::Continue, Vec::new()),
         }
     }
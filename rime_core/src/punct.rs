@@ -0,0 +1,61 @@
+//! `punct`：英文标点到中文标点的映射表（`Session::with_punctuation` 开启，由 `processor::PunctuationProcessor` 处理）。
+//!
+//! - 没有输入时按下表中的标点，直接上屏对应的中文标点；正在输入时先上屏首选，再上屏标点
+//! - 输入方案本身用到的按键（见 `processor::InputPolicy`，如注音的 `,`、微软双拼的 `;`）仍进入组合串，不作为标点
+//! - 只做一对一映射；引号、括号不配对
+//! - 可从 TSV 读入（`from_tsv_str`），core 不读写文件，读文件见 `rime_dict::punct`
+
+use std::collections::BTreeMap;
+
+/// 常用的中文标点。
+pub const CHINESE: &[(char, &str)] = &[
+    (',', "，"),
+    ('.', "。"),
+    ('?', "？"),
+    ('!', "！"),
+    (':', "："),
+    (';', "；"),
+    ('\\', "、"),
+    ('(', "（"),
+    (')', "）"),
+    ('[', "【"),
+    (']', "】"),
+    ('<', "《"),
+    ('>', "》"),
+    ('~', "～"),
+    ('$', "￥"),
+    ('^', "……"),
+    ('_', "——"),
+];
+
+/// `CHINESE` 的映射表。
+pub fn chinese() -> BTreeMap<char, String> {
+    CHINESE.iter().map(|&(ch, text)| (ch, text.to_string())).collect()
+}
+
+/// 从 TSV 读入：每行 `标点<TAB>上屏文本`，标点为单个字符；空行与 `#` 开头的行忽略（`#<TAB>…` 是 `#` 本身的映射），
+/// 同一标点出现多次时只保留第一条。
+///
+/// 某行格式不对时返回带行号的错误信息。
+pub fn from_tsv_str(s: &str) -> Result<BTreeMap<char, String>, String> {
+    let mut map = BTreeMap::new();
+    for (idx, line) in s.lines().enumerate() {
+        if line.trim().is_empty() || (line.starts_with('#') && !line.starts_with("#\t")) {
+            continue;
+        }
+        let mut fields = line.split('\t');
+        let (Some(key), Some(text), None) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(format!("第 {} 行：应为标点、上屏文本两列", idx + 1));
+        };
+        let mut chars = key.chars();
+        let (Some(ch), None) = (chars.next(), chars.next()) else {
+            return Err(format!("第 {} 行：标点应为单个字符，实际为 `{key}`", idx + 1));
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(format!("第 {} 行：缺少上屏文本", idx + 1));
+        }
+        map.entry(ch).or_insert_with(|| text.to_string());
+    }
+    Ok(map)
+}
@@ -0,0 +1,141 @@
+//! `ranking`：候选排序策略（`RankingPolicy`），由 `filter::DedupSortTruncate` 使用。
+//!
+//! 引擎用 `Engine::ranking_policy` 选择策略，默认 `FrequencyFirst`。
+//! 策略只决定进入 filter 的候选如何排列；每个 span 取哪些词仍由 translator 按权重决定。
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::model::{Candidate, CandidateText};
+
+/// 排序策略：`compare` 给出展示顺序（Less 排在前面）。
+pub trait RankingPolicy: Send + Sync {
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering;
+
+    /// 上屏后由会话调用（`text` 为转换前的上屏文本）；需要记住上屏历史的策略覆盖它。
+    fn on_commit(&self, text: &str) {
+        let _ = text;
+    }
+}
+
+impl<R> RankingPolicy for Arc<R>
+where
+    R: RankingPolicy + ?Sized,
+{
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        (**self).compare(a, b)
+    }
+
+    fn on_commit(&self, text: &str) {
+        (**self).on_commit(text)
+    }
+}
+
+/// 按频率（默认）。依次比较：
+/// 1. 分数降序（`Candidate::effective_score`：组句候选取未截断的路径分数，其余取 `weight`）
+/// 2. `source` 由优到劣（精确 → 前缀 → 简拼 → 模糊音 → 组句）
+/// 3. `rank` 升序，即 translator 的产出顺序
+/// 4. `text` 升序
+/// 5. 覆盖范围 `(segment_start, segment_end)` 升序
+///
+/// 以上全部相同的候选对去重而言是同一个，排序结果与平台、哈希种子无关。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrequencyFirst;
+
+impl RankingPolicy for FrequencyFirst {
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        b.effective_score()
+            .cmp(&a.effective_score())
+            .then_with(|| a.source.cmp(&b.source))
+            .then_with(|| a.rank.cmp(&b.rank))
+            .then_with(|| a.text.cmp(&b.text))
+            .then_with(|| (a.segment_start, a.segment_end).cmp(&(b.segment_start, b.segment_end)))
+    }
+}
+
+/// 长词优先：按文本字数降序，同长度再按 `FrequencyFirst`。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LengthThenFrequency;
+
+impl RankingPolicy for LengthThenFrequency {
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        b.text.chars().count().cmp(&a.text.chars().count()).then_with(|| FrequencyFirst.compare(a, b))
+    }
+}
+
+/// 最近上屏优先：上屏过的文本排在前面（越近越靠前），其余按 `FrequencyFirst`。
+#[derive(Debug, Clone, Default)]
+pub struct RecencyBoost(pub SessionMemory);
+
+impl RankingPolicy for RecencyBoost {
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        self.0.recency(&b.text).cmp(&self.0.recency(&a.text)).then_with(|| FrequencyFirst.compare(a, b))
+    }
+
+    fn on_commit(&self, text: &str) {
+        self.0.record(text);
+    }
+}
+
+/// 最近上屏的文本；克隆得到的是同一份（例如多个引擎或前端共用）。
+///
+/// 只保留最近 `capacity` 个不同的文本，超出时忘掉最久未上屏的。
+#[derive(Debug, Clone)]
+pub struct SessionMemory {
+    inner: Arc<Mutex<Recent>>,
+}
+
+#[derive(Debug)]
+struct Recent {
+    capacity: usize,
+    /// 单调递增的上屏序号
+    tick: u64,
+    /// text -> 最近一次上屏的序号
+    last: HashMap<CandidateText, u64>,
+}
+
+impl Default for SessionMemory {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl SessionMemory {
+    /// # Panics
+    ///
+    /// `capacity` 为 0 时 panic。
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "SessionMemory 的 capacity 不能为 0");
+        Self {
+            inner: Arc::new(Mutex::new(Recent {
+                capacity,
+                tick: 0,
+                last: HashMap::new(),
+            })),
+        }
+    }
+
+    /// 记下一次上屏。
+    pub fn record(&self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let mut recent = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        recent.tick += 1;
+        let tick = recent.tick;
+        recent.last.insert(text.into(), tick);
+        if recent.last.len() > recent.capacity
+            && let Some(oldest) = recent.last.iter().min_by_key(|(_, t)| **t).map(|(k, _)| k.clone())
+        {
+            recent.last.remove(&oldest);
+        }
+    }
+
+    /// `text` 最近一次上屏的序号（越大越近）；没有上屏过（或已被忘掉）时为 None。
+    pub fn recency(&self, text: &str) -> Option<u64> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).last.get(text).copied()
+    }
+}
@@ -0,0 +1,118 @@
+//! `recent`：会话内的最近上屏记忆（`Session::with_recency` 开启）。
+//!
+//! 只存在于会话内：不写词典、不持久化，会话结束即丢弃（`Context::reset` 不清空）。
+//!
+//! 1. 选词时记下所选词及其完整编码（简拼、前缀补全取 comment 中的完整拼音）；上屏时才计入记忆，放弃输入则不计
+//! 2. 之后输入同一编码（或其简拼、音节前缀，如 `sr`/`shuru` 之于 `shurufa`）时，该词并入第一页（挤出的候选顺延到后面的页，该词不再在后面的页重复出现），
+//!    分数为原权重加上随“距今上屏次数”线性衰减的加成；距今上屏次数记在元数据 `RECENT_AGE` 中（最近一次为 0）
+
+use std::collections::VecDeque;
+
+use crate::model::{Candidate, CandidateSource, CandidateText};
+
+/// 候选元数据（`Candidate::meta`）的键：距今上屏次数。
+pub const RECENT_AGE: &str = "recent_age";
+
+/// 一条最近上屏的词。
+#[derive(Debug, Clone)]
+struct RecentEntry {
+    text: CandidateText,
+    /// 完整编码（如 `shurufa`）
+    key: CandidateText,
+    /// 编码的音节切分（如 `[shu, ru, fa]`）
+    syllables: Vec<String>,
+    weight: i32,
+    /// 上屏序号（会话内递增）
+    seq: u64,
+}
+
+/// 最近上屏记忆：最多 `capacity` 条，同一 (text, key) 只保留最近一次。
+#[derive(Debug, Clone)]
+pub struct RecentCommits {
+    capacity: usize,
+    boost: i64,
+    seq: u64,
+    /// 按上屏先后排列，最近的在末尾
+    entries: VecDeque<RecentEntry>,
+    /// 本次输入已选、尚未上屏的词
+    pending: Vec<RecentEntry>,
+}
+
+impl RecentCommits {
+    /// 记住最近 `capacity` 次上屏；最近一次的加成为 `boost`，每多隔一次上屏减少 `boost / capacity`。
+    ///
+    /// # Panics
+    ///
+    /// `capacity` 为 0 时 panic。
+    pub fn new(capacity: usize, boost: i64) -> Self {
+        assert!(capacity > 0, "最近上屏记忆的 capacity 不能为 0");
+        Self {
+            capacity,
+            boost,
+            seq: 0,
+            entries: VecDeque::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// 记下一次选词（`syllables` 为 `key` 的音节切分）；上屏时（`commit`）才计入记忆。
+    pub fn select(&mut self, text: CandidateText, key: CandidateText, syllables: Vec<String>, weight: i32) {
+        self.pending.push(RecentEntry { text, key, syllables, weight, seq: 0 });
+    }
+
+    /// 本次输入已上屏：已选的词计入记忆。
+    pub fn commit(&mut self) {
+        for mut entry in std::mem::take(&mut self.pending) {
+            self.seq += 1;
+            entry.seq = self.seq;
+            self.entries.retain(|e| e.text != entry.text || e.key != entry.key);
+            self.entries.push_back(entry);
+            if self.entries.len() > self.capacity {
+                self.entries.pop_front();
+            }
+        }
+    }
+
+    /// 放弃本次输入：已选的词不计入。
+    pub fn discard(&mut self) {
+        self.pending.clear();
+    }
+
+    /// 与 `segment[start..end]` 匹配的记忆，作为带加成分数的候选（范围从 `start` 开始）。
+    ///
+    /// 覆盖 `start..j` 的候选要求音节与编码完全相同；覆盖到 `end` 时，各段只需依次是编码音节的前缀（简拼、输入到一半）。
+    pub fn candidates(&self, segment: &[String], start: usize, end: usize) -> Vec<Candidate> {
+        let mut out = Vec::new();
+        for (rank, entry) in self.entries.iter().rev().enumerate() {
+            let age = (self.seq - entry.seq) as i64;
+            let boost = self.boost * (self.capacity as i64 - age).max(0) / self.capacity as i64;
+            for j in (start + 1)..=end {
+                let span = &segment[start..j];
+                let exact = span == entry.syllables.as_slice();
+                let abbreviated = j == end && span.len() <= entry.syllables.len() && span.iter().zip(&entry.syllables).all(|(s, syllable)| syllable.starts_with(s.as_str()));
+                if !exact && !abbreviated {
+                    continue;
+                }
+                let candidate = Candidate {
+                    text: entry.text.clone(),
+                    comment: (!exact).then(|| entry.key.clone()),
+                    weight: entry.weight,
+                    score: Some(i64::from(entry.weight) + boost),
+                    segment_start: start,
+                    segment_end: j,
+                    source: if exact { CandidateSource::Exact } else { CandidateSource::Prefix },
+                    rank,
+                    parts: Vec::new(),
+                    meta: None,
+                };
+                out.push(candidate.with_meta(RECENT_AGE, age.to_string()));
+            }
+        }
+        out
+    }
+
+    /// 是否还没有任何记忆。
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,69 @@
+//! `schema`：一个会话内切换输入方案（全拼/双拼等）。
+//!
+//! 1. 引擎以 `AnalyzerSet`（若干具名的 analyzer）作为切分器；第一个方案为默认方案
+//! 2. 每个会话各自记录当前方案（`Context::schema`），`InputEvent::NextSchema` 切到下一个（见 `processor::SchemaProcessor`）
+//! 3. 切换时保留正在输入的 `raw_input`，按新方案重新切分；已确认的部分只在切分结果不变时保留（同 `Context::reanalyze`）
+//!
+//! 引擎本身不变，共享同一引擎的会话可以各用各的方案。
+
+use crate::engine::{Analysis, Analyzer};
+
+/// 可切换的一组方案：(名字, analyzer)，至少一个。
+///
+/// 自身也实现 `Analyzer`（按默认方案切分），可直接作为 `Engine` 的类型参数。
+pub struct AnalyzerSet {
+    schemas: Vec<(String, Box<dyn Analyzer>)>,
+}
+
+impl AnalyzerSet {
+    /// 以 `analyzer` 为默认方案。
+    pub fn new(name: impl Into<String>, analyzer: impl Analyzer + 'static) -> Self {
+        Self { schemas: vec![(name.into(), Box::new(analyzer))] }
+    }
+
+    /// 追加一个方案（排在已有方案之后）。
+    pub fn with_schema(mut self, name: impl Into<String>, analyzer: impl Analyzer + 'static) -> Self {
+        self.schemas.push((name.into(), Box::new(analyzer)));
+        self
+    }
+
+    /// 方案数。
+    pub fn len(&self) -> usize {
+        self.schemas.len()
+    }
+
+    /// 总是 false（至少有默认方案）。
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+
+    /// 第 `schema` 个方案的名字（越界时取默认方案）。
+    pub fn name(&self, schema: usize) -> &str {
+        &self.schemas.get(schema).unwrap_or(&self.schemas[0]).0
+    }
+
+    /// 各方案的名字，按切换顺序。
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.schemas.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// 名为 `name` 的方案的下标。
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.schemas.iter().position(|(n, _)| n == name)
+    }
+
+    /// 用第 `schema` 个方案切分（越界时用默认方案）。
+    pub fn analyze_as(&self, schema: usize, input: &str) -> Analysis {
+        self.schemas.get(schema).unwrap_or(&self.schemas[0]).1.analyze(input)
+    }
+}
+
+impl Analyzer for AnalyzerSet {
+    fn analyze(&self, input: &str) -> Analysis {
+        self.analyze_as(0, input)
+    }
+
+    fn schemas(&self) -> Option<&AnalyzerSet> {
+        Some(self)
+    }
+}
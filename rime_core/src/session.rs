@@ -5,61 +5,407 @@
 //! - 持有 processors 链（可插拔）
 //! - 把每次 `InputEvent` 依次交给 processors，直到被消费
 //! - 最后输出 `UiState` + `Action`
+//!
+//! 引擎经 `Arc` 持有：多个会话（可跨线程）共享同一个引擎与词典，每个会话只有自己的 `Context`。
+//! 词典只读；会话上屏时经 `&Engine` 写入的只有两份自带锁的共享记录：学到的词（`LearnedPhrases`，`RwLock`）
+//! 与用户词频（`UserHistory`，`Mutex`），写入即对共享引擎的所有会话可见（版本号变化使各会话的快照失效，见 `Engine::generation`）。
+
+use std::{collections::BTreeMap, sync::Arc};
 
 use crate::{
+    async_source::{AsyncCandidateSource, CandidateRequest, RequestId},
+    blacklist::Blacklist,
+    commit::CommitTransform,
+    config::Orientation,
     context::Context,
+    convert::TextConverter,
     dictionary::Dictionary,
     engine::Analyzer,
     engine::Engine,
+    filter::{Filter, SimplifierFilter},
     key_event::{Action, InputEvent},
-    model::UiState,
-    processor::{EditingProcessor, EnterCommitProcessor, ProcessStatus, Processor, SelectionProcessor},
+    learn::LearnedPhrase,
+    metrics::SessionMetrics,
+    model::{Candidate, UiState},
+    prediction::PredictionSource,
+    processor::{EditingProcessor, EngineFacade, EnterCommitProcessor, InputPolicy, PagingProcessor, PredictionProcessor, ProcessStatus, Processor, PunctuationProcessor, SchemaProcessor, SelectionProcessor},
+    recent::RecentCommits,
     segmenter::Segmenter,
 };
 
 /// 输入法会话（一次输入过程的状态机容器）。
 pub struct Session<D, P> {
     /// 引擎（包含词典、analyzer/segmentor、translator/filter 编排）
-    engine: Engine<D, P>,
+    engine: Arc<Engine<D, P>>,
     /// 会话上下文（processors 共享）
     ctx: Context,
     /// processors 链（可配置/可扩展）
     processors: Vec<Box<dyn Processor>>,
+    /// 会话统计（`with_metrics` 开启）
+    metrics: Option<SessionMetrics>,
+    /// 最近一个事件是否被某个 processor 消费
+    consumed: bool,
+    /// 联想来源（`with_prediction` 开启）
+    predictor: Option<Arc<dyn PredictionSource>>,
+    /// 异步候选来源（`with_async_source` 开启）
+    async_source: Option<Arc<dyn AsyncCandidateSource>>,
+    /// 上屏文本变换，按注册顺序作用（`with_commit_transform`）
+    commit_transforms: Vec<Box<dyn CommitTransform>>,
+    /// 覆盖引擎配置的候选窗排列方向与标签（`with_layout`）
+    layout: Option<(Orientation, Arc<str>)>,
+    /// 当前组合的编号与组合本身（raw_input, confirm, caret）；组合变化时编号加一
+    composition: (RequestId, String, usize, usize),
+    /// 自动造词的音节数上限（`with_phrase_learning`）；None 为关闭
+    learning: Option<usize>,
+    /// 组合串接受的字符（`with_input_policy`）
+    input_policy: InputPolicy,
+    /// 标点映射表（`with_punctuation`）
+    punctuation: BTreeMap<char, String>,
 }
 
+/// 默认链中 `PunctuationProcessor` 与 `EditingProcessor` 的位置（见 `Session::new`）。
+const PUNCTUATION_PROCESSOR: usize = 1;
+const EDITING_PROCESSOR: usize = 2;
+
 impl<D, P> Session<D, P>
 where
     D: Dictionary,
     P: Analyzer + Segmenter,
 {
     /// 创建会话，并组装默认 processors 链。
-    pub fn new(engine: Engine<D, P>) -> Self {
+    ///
+    /// 传入 `Engine` 时独占；传入 `Arc<Engine>` 时与其他会话共享。
+    pub fn new(engine: impl Into<Arc<Engine<D, P>>>) -> Self {
+        let engine = engine.into();
+        let mut ctx = Context::default();
+        ctx.set_history(engine.history().is_some());
         Self {
             engine,
-            ctx: Context::default(),
+            ctx,
             processors: vec![
-                Box::new(EditingProcessor),
+                Box::new(PredictionProcessor),
+                Box::new(PunctuationProcessor::default()),
+                Box::new(EditingProcessor::default()),
+                Box::new(PagingProcessor),
                 Box::new(SelectionProcessor),
                 Box::new(EnterCommitProcessor),
+                Box::new(SchemaProcessor),
             ],
+            metrics: None,
+            consumed: false,
+            predictor: None,
+            async_source: None,
+            commit_transforms: Vec::new(),
+            layout: None,
+            composition: (0, String::new(), 0, 0),
+            learning: None,
+            input_policy: InputPolicy::default(),
+            punctuation: BTreeMap::new(),
         }
     }
 
-    /// 获取当前 UI 快照（只读）。
+    /// 以 `policy` 决定哪些字符进入组合串（替换默认链中的 `EditingProcessor`）；它接受的字符不再作为标点。
+    pub fn with_input_policy(mut self, policy: InputPolicy) -> Self {
+        self.processors[EDITING_PROCESSOR] = Box::new(EditingProcessor::new(policy.clone()));
+        self.processors[PUNCTUATION_PROCESSOR] = Box::new(PunctuationProcessor::new(self.punctuation.clone(), &policy));
+        self.input_policy = policy;
+        self
+    }
+
+    /// 开启标点映射：`map` 中的字符上屏为对应的文本，例如 `punct::chinese()`（见 `punct`，默认关闭）。
+    pub fn with_punctuation(mut self, map: BTreeMap<char, String>) -> Self {
+        self.processors[PUNCTUATION_PROCESSOR] = Box::new(PunctuationProcessor::new(map.clone(), &self.input_policy));
+        self.punctuation = map;
+        self
+    }
+
+    /// 以 `blacklist` 作为删除的候选（例如读入上次保存的黑名单，见 `blacklist`）；`InputEvent::DeleteCandidate` 在此基础上增加。
+    pub fn with_blacklist(mut self, blacklist: Blacklist) -> Self {
+        self.ctx.set_blacklist(blacklist);
+        self
+    }
+
+    /// 开启联想：每次上屏后以上屏文本查询 `source`，结果作为候选展示（默认关闭）。
+    pub fn with_prediction(mut self, source: Arc<dyn PredictionSource>) -> Self {
+        self.predictor = Some(source);
+        self
+    }
+
+    /// 开启最近上屏记忆：会话内最近 `capacity` 次上屏的词再次输入（含简拼、前缀）时并入第一页（挤出的候选顺延到后面的页），
+    /// 分数加上 `boost`，随此后的上屏次数线性衰减（见 `recent`，默认关闭）。
+    pub fn with_recency(mut self, capacity: usize, boost: i64) -> Self {
+        self.ctx.set_recent(Some(RecentCommits::new(capacity, boost)));
+        self
+    }
+
+    /// 开启异步候选来源：正在输入时，组合每变化一次就向 `source` 发出一次请求（见 `async_source`）。
+    pub fn with_async_source(mut self, source: Arc<dyn AsyncCandidateSource>) -> Self {
+        self.async_source = Some(source);
+        self
+    }
+
+    /// 注册上屏文本变换（见 `commit`）：作用于此后每个 `Action::Commit`，多个 transform 按注册顺序依次作用。
+    pub fn with_commit_transform(mut self, transform: impl CommitTransform + 'static) -> Self {
+        self.commit_transforms.push(Box::new(transform));
+        self
+    }
+
+    /// 以 `orientation` 与 `labels` 覆盖引擎配置的候选窗展示提示（`UiState::layout`），例如前端有自己的选词键时。
+    pub fn with_layout(mut self, orientation: Orientation, labels: &str) -> Self {
+        self.layout = Some((orientation, Arc::from(labels)));
+        self
+    }
+
+    /// 并入异步来源对请求 `id` 的结果，返回更新后的 UI 快照（`UiState::injected` 为 true）。
+    ///
+    /// 以下情况不做任何事、返回 None：`id` 不是当前组合的编号（组合已变化，结果过期）；
+    /// 过滤后没有新候选（已注入过的同一候选、或范围不从 confirm 开始/越过 caret 的候选都会被丢弃）。
+    pub fn inject_candidates(&mut self, id: RequestId, candidates: Vec<Candidate>) -> Option<UiState> {
+        if id != self.composition.0 || self.ctx.raw_input.is_empty() {
+            return None;
+        }
+        let (confirm, caret) = (self.ctx.confirm, self.ctx.caret);
+        let mut fresh: Vec<Candidate> = Vec::new();
+        for c in candidates {
+            let in_range = c.segment_start == confirm && c.segment_end > confirm && c.segment_end <= caret;
+            let same = |o: &Candidate| o.text == c.text && o.segment_end == c.segment_end;
+            if in_range && !self.ctx.injected().iter().any(same) && !fresh.iter().any(same) {
+                fresh.push(c);
+            }
+        }
+        if fresh.is_empty() {
+            return None;
+        }
+        self.ctx.add_injected(self.engine.annotate(&self.ctx.analysis.segment, fresh));
+        let ui = self.ctx.ui_state_cached(&*self.engine);
+        Some(self.present(ui))
+    }
+
+    /// 开启自动造词（见 `learn`，默认关闭；上限可取 `DEFAULT_MAX_SYLLABLES`）：一次输入分几次选词上屏时，把拼成的词组写入引擎的 `LearnedPhrases`，
+    /// 下次输入同一编码即为精确匹配；超过 `max_syllables` 个音节的不学。
+    ///
+    /// 引擎被其他会话共享时同样写入，各会话都能查到：多个用户共用一个引擎的宿主（服务端、gRPC、FFI）须自行决定是否开启。
+    /// 写入的词不会自动持久化（见 `Engine::phrase_store`）。
+    pub fn with_phrase_learning(mut self, max_syllables: usize) -> Self {
+        self.learning = Some(max_syllables);
+        self.ctx.set_learning(true);
+        self
+    }
+
+    /// 开启会话统计（见 `metrics`）。
+    pub fn with_metrics(mut self) -> Self {
+        self.metrics = Some(SessionMetrics::new());
+        self
+    }
+
+    /// 会话统计的快照；未开启时为 None。
+    pub fn metrics(&self) -> Option<SessionMetrics> {
+        self.metrics.clone()
+    }
+
+    /// 统计清零并重新计时；未开启时不做任何事。
+    pub fn reset_metrics(&mut self) {
+        if let Some(metrics) = &mut self.metrics {
+            *metrics = SessionMetrics::new();
+        }
+    }
+
+    /// 共享的引擎（`Arc::clone` 后可用于创建新会话）。
+    pub fn engine(&self) -> &Arc<Engine<D, P>> {
+        &self.engine
+    }
+
+    /// 引擎（可变）；引擎被其他会话共享时返回 None。修改词典后，新的候选从下一次输入开始生效。
+    pub fn engine_mut(&mut self) -> Option<&mut Engine<D, P>> {
+        self.ctx.invalidate_ui();
+        Arc::get_mut(&mut self.engine)
+    }
+
+    /// 替换引擎（例如重新加载词典后）；未上屏的输入保留，新的候选从下一次输入开始生效。
+    pub fn set_engine(&mut self, engine: impl Into<Arc<Engine<D, P>>>) {
+        self.ctx.invalidate_ui();
+        self.engine = engine.into();
+        self.ctx.set_history(self.engine.history().is_some());
+    }
+
+    /// 是否有未上屏的输入。
+    pub fn is_composing(&self) -> bool {
+        !self.ctx.raw_input.is_empty()
+    }
+
+    /// 是否正在展示上屏后的联想候选（见 `with_prediction`）。
+    pub fn is_predicting(&self) -> bool {
+        self.ctx.is_predicting()
+    }
+
+    /// 删除的候选（`InputEvent::DeleteCandidate` 累积），可用 `Blacklist::to_tsv` 导出保存。
+    pub fn blacklist(&self) -> &Blacklist {
+        self.ctx.blacklist()
+    }
+
+    /// 输出是否转换为繁体（见 `convert`）。
+    pub fn is_traditional(&self) -> bool {
+        self.ctx.is_traditional()
+    }
+
+    /// 最近处理的事件是否被消费；为 false 时前端应把按键交还应用（见 `host::drive`）。
+    pub fn last_consumed(&self) -> bool {
+        self.consumed
+    }
+
+    /// 当前候选共有几页（见 `Context::page_count`）：须取出全部候选，比组合一页慢得多，只宜在展示“第 n/总页数”时调用；结果按组合缓存。
+    pub fn page_count(&mut self) -> usize {
+        self.ctx.page_count(&*self.engine)
+    }
+
+    /// 获取当前 UI 快照（只读）；紧跟在 `handle` 之后调用时复用其结果，不再组句。
     pub fn ui_state(&self) -> UiState {
-        self.ctx.ui_state(&self.engine)
+        let ui = match self.ctx.cached_ui(&*self.engine) {
+            Some(ui) => ui.clone(),
+            None => self.ctx.ui_state(&*self.engine),
+        };
+        self.present(ui)
     }
 
     /// 处理一个输入事件，返回最新 UI 快照与动作列表。
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
     pub fn handle(&mut self, ev: InputEvent) -> (UiState, Vec<Action>) {
+        let actions = self.run_processors(&ev);
+        let selected = self.ctx.take_selected();
+        let ui = self.ctx.ui_state_cached(&*self.engine);
+        let ui = self.present(ui);
+        if let Some(metrics) = &mut self.metrics {
+            metrics.record(&ui, &actions, &selected);
+        }
+        (ui, actions)
+    }
+
+    /// 依次处理多个事件，只为最终状态生成一次 UI 快照（开启统计时除外），返回它与全部动作。
+    ///
+    /// 适合整段输入（例如按行读入的拼音），中间状态不展示也就不必组句。
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn handle_all(&mut self, events: impl IntoIterator<Item = InputEvent>) -> (UiState, Vec<Action>) {
+        let mut actions = Vec::new();
+        for ev in events {
+            let mut a = self.run_processors(&ev);
+            let selected = self.ctx.take_selected();
+            if self.metrics.is_some() {
+                let ui = self.ctx.ui_state_cached(&*self.engine);
+                let ui = self.present(ui);
+                if let Some(metrics) = &mut self.metrics {
+                    metrics.record(&ui, &a, &selected);
+                }
+            }
+            actions.append(&mut a);
+        }
+        let ui = self.ctx.ui_state_cached(&*self.engine);
+        (self.present(ui), actions)
+    }
+
+    fn run_processors(&mut self, ev: &InputEvent) -> Vec<Action> {
         let mut actions = Vec::new();
+        self.consumed = false;
+        // 会话级开关，不经 processors 链
+        if *ev == InputEvent::ToggleTraditional {
+            self.consumed = self.engine.text_converter().is_some();
+            let traditional = self.ctx.is_traditional() ^ self.consumed;
+            self.ctx.set_traditional(traditional);
+            return actions;
+        }
         for p in &mut self.processors {
-            let (status, mut a) = p.process(&self.engine, &mut self.ctx, &ev);
+            let (status, mut a) = p.process(&*self.engine, &mut self.ctx, ev);
             actions.append(&mut a);
             if status == ProcessStatus::Consume {
+                self.consumed = true;
                 break;
             }
         }
-        (self.ctx.ui_state(&self.engine), actions)
+        // 上屏后（且没有新的输入）以最后一次上屏的文本联想
+        if let Some(predictor) = &self.predictor
+            && self.ctx.raw_input.is_empty()
+            && let Some(Action::Commit(text)) = actions.iter().rfind(|a| matches!(a, Action::Commit(_)))
+        {
+            self.ctx.set_predictions(self.engine.annotate(&[], predictor.predict(text, self.engine.page_size())));
+        }
+        self.track_composition();
+        if let Some(phrase) = self.ctx.take_learned() {
+            self.learn(phrase);
+        }
+        for a in &actions {
+            if let Action::Commit(text) = a {
+                EngineFacade::ranking(&*self.engine).on_commit(text);
+            }
+        }
+        let chosen = self.ctx.take_chosen();
+        if let Some(history) = self.engine.history() {
+            for (key, text) in &chosen {
+                history.record(key, text);
+            }
+        }
+        if let Some(converter) = self.converter() {
+            for a in &mut actions {
+                if let Action::Commit(text) = a {
+                    *text = converter.convert(text);
+                }
+            }
+        }
+        for a in &mut actions {
+            if let Action::Commit(text) = a {
+                *text = self.commit_transforms.iter().fold(std::mem::take(text), |text, t| t.transform(&text));
+            }
+        }
+        actions
+    }
+
+    /// 把拼成的词组写入引擎的 `LearnedPhrases`（权重见 `LearnedPhrase::weight_in`）；超过音节数上限时不写。
+    fn learn(&mut self, phrase: LearnedPhrase) {
+        if self.learning.is_none_or(|max_syllables| phrase.syllables.len() > max_syllables) {
+            return;
+        }
+        let learned = self.engine.learned_phrases();
+        learned.insert(&phrase.key(), &phrase.text, phrase.weight_in(self.engine.dictionary(), learned));
+    }
+
+    /// 繁体输出开启时的转换器。
+    fn converter(&self) -> Option<&dyn TextConverter> {
+        self.engine.text_converter().filter(|_| self.ctx.is_traditional())
+    }
+
+    /// 把会话内部的快照转换为输出：繁体输出开启时转换候选与已确认文本；`with_layout` 覆盖展示提示。
+    fn present(&self, mut ui: UiState) -> UiState {
+        if let Some((orientation, labels)) = &self.layout {
+            ui.layout.orientation = *orientation;
+            ui.layout.labels = Arc::clone(labels);
+        }
+        if let Some(converter) = self.converter() {
+            ui.candidate_list = SimplifierFilter { converter }.apply(ui.candidate_list);
+            ui.confirm_text = converter.convert(&ui.confirm_text);
+            ui.traditional = true;
+            ui.update_composition(EngineFacade::preedit_style(&*self.engine));
+        }
+        ui
+    }
+
+    /// 组合变化时编号加一、丢弃注入的候选，正在输入时向异步来源发出请求。
+    fn track_composition(&mut self) {
+        let Some(source) = &self.async_source else {
+            return;
+        };
+        let (_, raw, confirm, caret) = &self.composition;
+        if *raw == self.ctx.raw_input && *confirm == self.ctx.confirm && *caret == self.ctx.caret {
+            return;
+        }
+        let id = self.composition.0 + 1;
+        self.composition = (id, self.ctx.raw_input.clone(), self.ctx.confirm, self.ctx.caret);
+        self.ctx.clear_injected();
+        if self.ctx.confirm < self.ctx.caret {
+            source.request_candidates(CandidateRequest {
+                id,
+                raw_input: self.ctx.raw_input.clone(),
+                segment: Arc::clone(&self.ctx.analysis.segment),
+                confirm: self.ctx.confirm,
+                caret: self.ctx.caret,
+            });
+        }
     }
 }
@@ -0,0 +1,81 @@
+//! `SessionManager`：按 id 管理多个 `Session`（服务端/RPC 场景）。
+//!
+//! 所有会话共享同一个 `Engine`（经 `Arc`），各自的输入状态完全隔离。
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+    dictionary::Dictionary,
+    engine::{Analyzer, Engine},
+    key_event::{Action, InputEvent},
+    model::UiState,
+    segmenter::Segmenter,
+    session::Session,
+};
+
+/// 会话 id（由 `SessionManager::create` 分配，不复用）。
+pub type SessionId = u64;
+
+/// 多会话管理器。
+pub struct SessionManager<D, A> {
+    /// 所有会话共享的引擎
+    engine: Arc<Engine<D, A>>,
+    /// 存活的会话
+    sessions: BTreeMap<SessionId, Session<D, A>>,
+    /// 下一个分配的 id
+    next_id: SessionId,
+}
+
+impl<D, A> SessionManager<D, A>
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    pub fn new(engine: impl Into<Arc<Engine<D, A>>>) -> Self {
+        Self {
+            engine: engine.into(),
+            sessions: BTreeMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// 创建新会话，返回其 id。
+    pub fn create(&mut self) -> SessionId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.sessions.insert(id, Session::new(Arc::clone(&self.engine)));
+        id
+    }
+
+    /// 销毁会话；id 不存在时返回 false。
+    pub fn destroy(&mut self, id: SessionId) -> bool {
+        self.sessions.remove(&id).is_some()
+    }
+
+    /// 把事件交给指定会话处理；id 不存在时返回 None。
+    pub fn handle(&mut self, id: SessionId, ev: InputEvent) -> Option<(UiState, Vec<Action>)> {
+        self.sessions.get_mut(&id).map(|s| s.handle(ev))
+    }
+
+    /// 指定会话的 UI 快照；id 不存在时返回 None。
+    pub fn ui_state(&self, id: SessionId) -> Option<UiState> {
+        self.sessions.get(&id).map(Session::ui_state)
+    }
+
+    /// 替换所有会话（含此后创建的）共享的引擎；各会话的输入状态保留。
+    pub fn set_engine(&mut self, engine: impl Into<Arc<Engine<D, A>>>) {
+        self.engine = engine.into();
+        for session in self.sessions.values_mut() {
+            session.set_engine(Arc::clone(&self.engine));
+        }
+    }
+
+    /// 存活会话数量。
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
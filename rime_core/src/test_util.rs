@@ -0,0 +1,142 @@
+//! `test_util`（feature `test-util`）：随机事件序列与会话不变量检查，用于发现状态机漂移。
+//!
+//! ```ignore
+//! let mut session = Session::new(engine);
+//! run_random_events(&mut session, 42, 10_000).unwrap();
+//! ```
+
+use std::fmt;
+
+use crate::{
+    dictionary::Dictionary,
+    engine::Analyzer,
+    key_event::InputEvent,
+    model::UiState,
+    segmenter::Segmenter,
+    session::Session,
+};
+
+/// 可复现的伪随机数（splitmix64，同一 seed 产生同一序列）；测试数据生成共用。
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `[0, n)` 内的随机数（`n` 须大于 0）。
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// 可复现的随机事件生成器。
+#[derive(Debug, Clone)]
+pub struct EventGenerator {
+    rng: SplitMix64,
+}
+
+impl EventGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: SplitMix64::new(seed) }
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.rng.below(n)
+    }
+
+    /// 下一个事件：约 70% 为字符（a-z，偶尔 `'`），其余为退格/选词/空格/回车/清空/翻页。
+    pub fn next_event(&mut self) -> InputEvent {
+        match self.below(100) {
+            0..70 => {
+                if self.below(30) == 0 {
+                    InputEvent::Char('\'')
+                } else {
+                    InputEvent::Char(char::from(b'a' + self.below(26) as u8))
+                }
+            }
+            70..78 => InputEvent::Backspace,
+            78..86 => InputEvent::Select(self.below(9) as usize),
+            86..91 => InputEvent::Space,
+            91..94 => InputEvent::Enter,
+            94..96 => InputEvent::Clear,
+            96..98 => InputEvent::NextPage,
+            _ => InputEvent::PrevPage,
+        }
+    }
+}
+
+impl Iterator for EventGenerator {
+    type Item = InputEvent;
+
+    fn next(&mut self) -> Option<InputEvent> {
+        Some(self.next_event())
+    }
+}
+
+/// 检查 `UiState` 的不变量：
+///
+/// - `confirm <= caret <= segment.len()`
+/// - `confirm_text` 为空当且仅当 `confirm == 0`
+/// - 候选的段范围非空、在 `[confirm, caret]` 内且从 `confirm` 开始（联想候选除外：没有输入，范围为 0..0）
+/// - `raw_input` 只含 a-z 与 `'`
+pub fn check_invariants(ui: &UiState) -> Result<(), String> {
+    if ui.confirm > ui.caret || ui.caret > ui.segment.len() {
+        return Err(format!("confirm({}) <= caret({}) <= segment.len()({}) 不成立", ui.confirm, ui.caret, ui.segment.len()));
+    }
+    if ui.confirm_text.is_empty() != (ui.confirm == 0) {
+        return Err(format!("confirm_text `{}` 与 confirm({}) 不一致", ui.confirm_text, ui.confirm));
+    }
+    for c in ui.candidate_list.iter().filter(|_| !ui.predicting) {
+        if c.segment_start != ui.confirm || c.segment_end <= c.segment_start || c.segment_end > ui.caret {
+            return Err(format!("候选 `{}` 的范围 {}..{} 不在 confirm({})..caret({}) 内", c.text, c.segment_start, c.segment_end, ui.confirm, ui.caret));
+        }
+    }
+    if let Some(ch) = ui.raw_input.chars().find(|&c| !(c.is_ascii_lowercase() || c == '\'')) {
+        return Err(format!("raw_input `{}` 含非法字符 {ch:?}", ui.raw_input));
+    }
+    Ok(())
+}
+
+/// 不变量被破坏时的现场。
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub seed: u64,
+    /// 第几个事件（0 起）之后被破坏
+    pub step: usize,
+    pub event: InputEvent,
+    pub ui: UiState,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed {} 第 {} 步 {:?} 之后：{}（raw_input `{}`）", self.seed, self.step, self.event, self.message, self.ui.raw_input)
+    }
+}
+
+/// 用 `seed` 生成 `steps` 个事件喂给会话，每步之后检查不变量。
+pub fn run_random_events<D, A>(session: &mut Session<D, A>, seed: u64, steps: usize) -> Result<(), Box<Violation>>
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    for (step, event) in EventGenerator::new(seed).take(steps).enumerate() {
+        let (ui, _actions) = session.handle(event.clone());
+        if let Err(message) = check_invariants(&ui) {
+            return Err(Box::new(Violation { seed, step, event, ui, message }));
+        }
+    }
+    Ok(())
+}
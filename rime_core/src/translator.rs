@@ -1,12 +1,37 @@
 //! `translator`：把 segment 翻译成候选（查词、组句、联想）。
 //!
 //! 当前实现：
-//! - `DictTranslator`：基于 `Dictionary::lookup_span`，支持：
+//! - `DictTranslator`：基于 `Dictionary::lookup_iter`（按需取前 N 条），支持：
 //!   - 直查（start..end）
 //!   - 单词候选（从 start 起枚举 1..=max_word_len）
-//!   - 组句候选（beam search，覆盖 start..end）
+//!   - 组句候选（beam search，覆盖 start..end）；有二元模型（`bigram`）时相邻两词另按模型加分
+//!   - 简拼：切分结果为简拼 fallback 时，span 无精确（整段另含前缀）匹配则查 `Dictionary::lookup_initials`；
+//!     单个字母只在它就是要翻译的整段时才查
+//!   - 模糊音：span 含模糊拼写时，逐一组合查询并合并
+//!   - 码表：切分结果为编码（`SegmentationKind::Codes`）时不查简拼；前缀补全可关闭（`prefix_completion`），
+//!     含通配符（`wildcard`）的编码查 `Dictionary::lookup_wildcard`
+//!   - 查询预算：`max_lookups` 限制单次翻译的词典查询次数，用尽后以已有路径收尾
+//!   - 单次翻译内同一 span 只查一次（直查、单词枚举与组句共用查询结果）
+//!
+//! 条数：单词候选每个 span 取 `word_candidate_limit` 条，合计不足 `limit` 时才组句；组句时每个 span 取
+//! `beam_span_limit` 个词扩展路径，出剩余的条数。`limit` 由调用方给出（引擎为翻到的页多取一条，不超过 `final_limit`）。
+
+use std::collections::HashMap;
+
+use crate::{
+    bigram::BigramModel,
+    dictionary::Dictionary,
+    engine::SegmentationKind,
+    explain::{EXPLAIN_BIGRAM, EXPLAIN_LENGTH, EXPLAIN_WEIGHT},
+    learn::LearnedPhrases,
+    model::{Candidate, CandidatePart, CandidateSource, CandidateText},
+};
+
+/// 单个 span 最多尝试多少种模糊音组合（不含原拼写）。
+const MAX_FUZZY_COMBINATIONS: usize = 16;
 
-use crate::{dictionary::Dictionary, model::Candidate};
+/// 组句时每覆盖一段的加成：路径的分数为各词权重之和加上 段数 × 此值。
+const LENGTH_BONUS: i64 = 1_000;
 
 /// Translator：把某段 segment 转成候选。
 pub trait Translator: Send + Sync {
@@ -19,14 +44,61 @@ pub trait Translator: Send + Sync {
     ) -> Vec<Candidate>;
 }
 
-/// 词典翻译器（基于 Dictionary::lookup_span），并提供一个轻量“组句”能力。
+/// 词典翻译器（基于 Dictionary::lookup_iter），并提供一个轻量“组句”能力。
 pub struct DictTranslator<'a, D> {
     /// 词典引用（查词发生在这里）
     pub dict: &'a D,
+    /// 自动造词学到的词（见 `learn`），精确匹配排在词典之前；None 为不查
+    pub learned: Option<&'a LearnedPhrases>,
+    /// 每个音节段的模糊拼写（见 `Analysis::fuzzy`；可为空）
+    pub fuzzy: &'a [Vec<String>],
+    /// 切分方式；为简拼时，span 直查无结果再查简拼索引
+    pub kind: SegmentationKind,
     /// 单个词候选最多覆盖段数
     pub max_word_length: u8,
-    /// 每个 span 查询最多取多少条（控制组合规模）
-    pub per_span_limit: usize,
+    /// 组句时每个 span 最多取多少个词扩展路径（控制组合规模）
+    pub beam_span_limit: usize,
+    /// 组句时每个段位置保留多少条路径（beam 宽度）；None 为按所需条数取 8..=64
+    pub beam_width: Option<usize>,
+    /// 组句时给相邻两词加分的二元模型；None 时路径分数只有权重与长度加成
+    pub bigram: Option<&'a BigramModel>,
+    /// 单词候选每个 span 最多出多少条
+    pub word_candidate_limit: usize,
+    /// 单次翻译最多查询词典多少次（含模糊音组合）；None 为不限。按次数而非耗时计，同样的输入总是同样的结果
+    pub max_lookups: Option<usize>,
+    /// 整段输入是否带前缀补全（`CandidateSource::Prefix`）；关闭时只取精确匹配
+    pub prefix_completion: bool,
+    /// 码表编码中的通配符（例如五笔的 `z`）；None 为不支持
+    pub wildcard: Option<char>,
+    /// 组句候选在元数据中带上分数组成（见 `explain`）
+    pub explain: bool,
+}
+
+/// 单次翻译内已查过的 span：(start, end) -> (查询时的条数上限, 结果)。只按键查找、不遍历，顺序不影响结果。
+type SpanMemo = HashMap<(usize, usize), (usize, Vec<Candidate>)>;
+
+/// 单次翻译剩余的查询次数。
+struct Budget {
+    remaining: Option<usize>,
+    /// 是否有查询因预算用尽而被跳过
+    exhausted: bool,
+}
+
+impl Budget {
+    /// 消耗一次查询；预算已用尽时返回 false。
+    fn take(&mut self) -> bool {
+        match &mut self.remaining {
+            None => true,
+            Some(0) => {
+                self.exhausted = true;
+                false
+            }
+            Some(n) => {
+                *n -= 1;
+                true
+            }
+        }
+    }
 }
 
 impl<'a, D> DictTranslator<'a, D>
@@ -40,42 +112,148 @@ where
         end: usize,
         limit: usize,
     ) -> Vec<Candidate> {
+        self.translate_with_budget(segment, start, end, limit).0
+    }
+
+    /// 同 `translate_with_composition`，另返回查询预算（`max_lookups`）是否用尽、候选可能不完整。
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self, segment), fields(segment = ?segment.get(start..end))))]
+    pub fn translate_with_budget(
+        &self,
+        segment: &[String],
+        start: usize,
+        end: usize,
+        limit: usize,
+    ) -> (Vec<Candidate>, bool) {
         let limit: usize = limit.max(1);
         let mut out: Vec<Candidate> = Vec::new();
+        let mut budget = Budget {
+            remaining: self.max_lookups,
+            exhausted: false,
+        };
+
+        let word_limit = self.word_candidate_limit.max(1);
+        let mut memo = SpanMemo::new();
 
-        // 0) 直查 start..end
-        let mut direct: Vec<Candidate> = self.dict.lookup_span(segment, start, end, limit);
+        // 0) 直查 start..end；简拼只剩一个字母时（例如确认了前面的词之后）也查简拼索引
+        // start..end 也在单词枚举范围内时，按两者中较大的条数一次查完，枚举时不再重复查询
+        let max_j = (start + (self.max_word_length as usize).max(1)).min(end);
+        let direct_limit = if max_j == end { limit.max(word_limit) } else { limit };
+        let mut direct: Vec<Candidate> = self.span(&mut memo, &mut budget, segment, start, end, direct_limit).to_vec();
+        if direct.is_empty() && self.kind == SegmentationKind::Initials && end - start == 1 && budget.take() {
+            direct = self.dict.lookup_initials(&segment[start..end]).take(limit).collect();
+        }
         for c in &mut direct {
             c.segment_start = start;
             c.segment_end = end;
         }
-        (&mut out).append(&mut direct);
+        out.append(&mut direct);
 
-        // 1) 单词候选（从 start 开始，枚举长度 1..=max_word_len）
-        let max_j = (start + (self.max_word_length as usize).max(1)).min(end);
-        for j in (start + 1)..=max_j {
-            let mut cands = self
-                .dict
-                .lookup_span(segment, start, j, self.per_span_limit.max(1));
-            for c in &mut cands {
-                c.segment_start = start;
-                c.segment_end = j;
-            }
-            out.append(&mut cands);
+        // 1) 单词候选（从 start 开始，枚举长度 1..=max_word_len；start..end 已由直查覆盖）
+        for j in ((start + 1)..=max_j).filter(|&j| j != end) {
+            out.extend(self.span(&mut memo, &mut budget, segment, start, j, word_limit).iter().take(word_limit).cloned().map(|c| Candidate { segment_start: start, segment_end: j, ..c }));
         }
 
         // 2) 组句候选（覆盖 start..end）
         if out.len() < limit {
             let mut composed =
-                self.compose_sentence_candidates(segment, start, end, limit - out.len());
+                self.compose_sentence_candidates(&mut memo, &mut budget, segment, start, end, limit - out.len());
             out.append(&mut composed);
         }
 
+        for (rank, c) in out.iter_mut().enumerate() {
+            c.rank = rank;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(count = out.len(), truncated = budget.exhausted, top = %crate::model::summarize(&out, 3), "translate");
+        (out, budget.exhausted)
+    }
+
+    /// 同 `lookup_span`，但同一 span 在单次翻译内只查一次：已查过且条数够用时直接返回 `memo` 中的结果。
+    fn span<'m>(&self, memo: &'m mut SpanMemo, budget: &mut Budget, segment: &[String], start: usize, end: usize, limit: usize) -> &'m [Candidate] {
+        if memo.get(&(start, end)).is_none_or(|(n, _)| *n < limit) {
+            let found = self.lookup_span(budget, segment, start, end, limit);
+            memo.insert((start, end), (limit, found));
+        }
+        &memo[&(start, end)].1
+    }
+
+    /// 查询 `segment[start..end]` 的前 `limit` 条；若其中有模糊拼写，再按组合逐一查询并合并（模糊命中的 comment 为实际 key）。
+    ///
+    /// 每次词典查询消耗一次预算；预算用尽后跳过其余查询。
+    fn lookup_span(&self, budget: &mut Budget, segment: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate> {
+        if !budget.take() {
+            return Vec::new();
+        }
+        if self.kind == SegmentationKind::Codes
+            && let Some(wildcard) = self.wildcard
+            && end - start == 1
+            && segment[start].contains(wildcard)
+        {
+            return self.dict.lookup_wildcard(&segment[start], wildcard).take(limit).map(|c| Candidate { segment_start: start, segment_end: end, ..c }).collect();
+        }
+        if self.kind == SegmentationKind::Initials {
+            // 简拼的各段是字母而非音节：整段输入拼成一段查询，前缀补全不必落在字母边界上（只对整段输入补全）
+            let out: Vec<Candidate> = if start == 0 && end == segment.len() {
+                let joined = [segment.concat()];
+                self.lookup_iter(&joined, 0, 1).take(limit).collect()
+            } else {
+                self.lookup_iter(segment, start, end).take(limit).collect()
+            };
+            // 单个字母作简拼太宽泛（会被高频单字淹没），只在它就是要翻译的整段时才查（见 translate_with_budget）
+            let out = if out.is_empty() && end - start >= 2 { self.dict.lookup_initials(&segment[start..end]).take(limit).collect() } else { out };
+            return out.into_iter().map(|c| Candidate { segment_start: start, segment_end: end, ..c }).collect();
+        }
+        let mut out: Vec<Candidate> = self.lookup_iter(segment, start, end).take(limit).collect();
+        let Some(fuzzy) = self.fuzzy.get(start..end) else {
+            return out;
+        };
+        if fuzzy.iter().all(Vec::is_empty) {
+            return out;
+        }
+
+        // 里程表式枚举：每个位置 0 表示原拼写，k 表示第 k 个模糊拼写
+        let mut choice = vec![0usize; end - start];
+        let mut variant: Vec<String> = segment.to_vec();
+        for _ in 0..MAX_FUZZY_COMBINATIONS {
+            let mut pos = 0;
+            while pos < choice.len() {
+                choice[pos] += 1;
+                if choice[pos] <= fuzzy[pos].len() {
+                    break;
+                }
+                choice[pos] = 0;
+                pos += 1;
+            }
+            if pos == choice.len() || !budget.take() {
+                break;
+            }
+            for (k, &c) in choice.iter().enumerate() {
+                variant[start + k] = if c == 0 { segment[start + k].clone() } else { fuzzy[k][c - 1].clone() };
+            }
+            let mut key: Option<CandidateText> = None;
+            out.extend(self.lookup_iter(&variant, start, end).take(limit).map(|mut cand| {
+                if cand.comment.is_none() {
+                    cand.comment = Some(key.get_or_insert_with(|| variant[start..end].concat().into()).clone());
+                }
+                cand.source = cand.source.max(CandidateSource::Fuzzy);
+                cand
+            }));
+        }
         out
     }
 
+    /// 学到的词接上 `Dictionary::lookup_iter`；关闭前缀补全时滤掉补全候选（叠加词典的下层精确匹配排在上层补全之后，不能在第一个补全处截止）。
+    fn lookup_iter<'s>(&'s self, segment: &'s [String], start: usize, end: usize) -> impl Iterator<Item = Candidate> + 's {
+        let completion = self.prefix_completion;
+        let learned = self.learned.filter(|learned| !learned.is_empty()).map(|learned| learned.lookup_span(segment, start, end)).unwrap_or_default();
+        learned.into_iter().chain(self.dict.lookup_iter(segment, start, end).filter(move |c| completion || c.source != CandidateSource::Prefix))
+    }
+
     fn compose_sentence_candidates(
         &self,
+        memo: &mut SpanMemo,
+        budget: &mut Budget,
         segments: &[String],
         start: usize,
         end: usize,
@@ -85,63 +263,117 @@ where
             return Vec::new();
         }
 
-        #[derive(Clone)]
-        struct Path {
-            text: String,
+        /// 路径节点（扁平存放在 arena 里）：分数 + 前驱节点 + 最后一个词；文本到最后才沿前驱拼接
+        struct Node {
             score: i64,
+            /// 前驱节点下标；起点为 None
+            parent: Option<usize>,
+            /// 最后一个词：span i..j、词在该 span 查询结果（`memo[&(i, j)]`）中的下标
+            word: (usize, usize, usize),
         }
 
-        let beam_k = limit.max(8).min(64);
-        let mut beams: Vec<Vec<Path>> = vec![Vec::new(); end + 1];
-        beams[start].push(Path {
-            text: String::new(),
-            score: 0,
-        });
+        let max_len = (self.max_word_length as usize).max(1);
+        let per_span = self.beam_span_limit.max(1);
+
+        let beam_k = self.beam_width.unwrap_or_else(|| limit.clamp(8, 64)).max(1);
+        let mut arena: Vec<Node> = vec![Node { score: 0, parent: None, word: (0, 0, 0) }];
+        // beams[i]：停在段位置 i 的路径（arena 下标）
+        let mut beams: Vec<Vec<usize>> = vec![Vec::new(); end + 1];
+        beams[start].push(0);
 
         for i in start..end {
             if beams[i].is_empty() {
                 continue;
             }
-            beams[i].sort_by(|a, b| b.score.cmp(&a.score));
-            beams[i].truncate(beam_k);
-            let cur_paths = beams[i].clone();
-
-            let max_j = (i + (self.max_word_length as usize).max(1)).min(end);
-            for j in (i + 1)..=max_j {
-                let words = self
-                    .dict
-                    .lookup_span(segments, i, j, self.per_span_limit.max(1));
-                if words.is_empty() {
-                    continue;
-                }
-                let len_bonus = ((j - i) as i64) * 1_000;
-                for p in &cur_paths {
-                    for w in &words {
-                        let mut text = String::new();
-                        if p.text.is_empty() {
-                            text.push_str(&w.text);
-                        } else {
-                            text.push_str(&p.text);
-                            text.push_str(&w.text);
-                        }
-                        let score = p.score + (w.weight as i64) + len_bonus;
-                        beams[j].push(Path { text, score });
+            let mut cur_paths = std::mem::take(&mut beams[i]);
+            // 同分按生成先后（arena 下标），即 span 的先后与词典的产出顺序：截断结果不依赖排序算法
+            cur_paths.sort_unstable_by_key(|&p| (std::cmp::Reverse(arena[p].score), p));
+            cur_paths.truncate(beam_k);
+            // 走到 i 时才查从 i 出发的 span
+            for j in (i + 1)..=(i + max_len).min(end) {
+                self.span(memo, budget, segments, i, j, per_span);
+            }
+
+            for j in (i + 1)..=(i + max_len).min(end) {
+                let span_words = &memo[&(i, j)].1;
+                let len_bonus = ((j - i) as i64) * LENGTH_BONUS;
+                for &p in &cur_paths {
+                    let base = arena[p].score;
+                    // 路径的最后一个词（起点没有），与新词组成二元组
+                    let prev = self.bigram.zip(arena[p].parent.map(|_| {
+                        let (pi, pj, pk) = arena[p].word;
+                        &memo[&(pi, pj)].1[pk].text
+                    }));
+                    // 单词候选可能以更大的条数查过同一 span，只取前 per_span 个
+                    for (k, w) in span_words.iter().take(per_span).enumerate() {
+                        let bigram = prev.map_or(0, |(model, prev)| model.score(prev, &w.text));
+                        beams[j].push(arena.len());
+                        arena.push(Node {
+                            score: base + (w.weight as i64) + len_bonus + bigram,
+                            parent: Some(p),
+                            word: (i, j, k),
+                        });
                     }
                 }
             }
         }
 
-        let mut finals = beams[end].clone();
-        finals.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.text.cmp(&b.text)));
+        let parts_of = |mut node: usize| -> Vec<CandidatePart> {
+            let mut parts = Vec::new();
+            while let Some(parent) = arena[node].parent {
+                let (i, j, k) = arena[node].word;
+                parts.push(CandidatePart { text: memo[&(i, j)].1[k].text.clone(), segment_start: i, segment_end: j });
+                node = parent;
+            }
+            parts.reverse();
+            parts
+        };
+
+        // 先只按分数排序；只为前 limit 条（含与第 limit 条同分的）拼接文本，再按 (分数, 文本, 生成先后) 定序
+        let mut finals = std::mem::take(&mut beams[end]);
+        finals.sort_unstable_by_key(|&p| (std::cmp::Reverse(arena[p].score), p));
+        if let Some(cutoff) = finals.get(limit.saturating_sub(1)).map(|&p| arena[p].score) {
+            let keep = finals.iter().take_while(|&&p| arena[p].score >= cutoff).count();
+            finals.truncate(keep);
+        }
+        let mut finals: Vec<(String, Vec<CandidatePart>, i64, usize)> = finals
+            .into_iter()
+            .map(|p| {
+                let parts = parts_of(p);
+                (parts.iter().map(|w| w.text.as_str()).collect(), parts, arena[p].score, p)
+            })
+            .collect();
+        finals.sort_unstable_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)).then_with(|| a.3.cmp(&b.3)));
         finals.truncate(limit);
+        let comment = CandidateText::from("compose");
         finals
             .into_iter()
-            .map(|p| Candidate {
-                text: p.text,
-                comment: Some("compose".to_string()),
-                weight: (p.score.min(i64::from(i32::MAX))) as i32,
-                segment_start: start,
-                segment_end: end,
+            .map(|(text, parts, score, _)| {
+                let candidate = Candidate {
+                    text: text.into(),
+                    comment: Some(comment.clone()),
+                    weight: score.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32,
+                    score: Some(score),
+                    segment_start: start,
+                    segment_end: end,
+                    source: CandidateSource::Compose,
+                    rank: 0,
+                    parts,
+                    meta: None,
+                };
+                if !self.explain {
+                    return candidate;
+                }
+                // 完整路径覆盖 start..end，长度加成只取决于段数；二元加分按相邻的词重算，其余即各词权重之和
+                let length = (end - start) as i64 * LENGTH_BONUS;
+                let Some(model) = self.bigram else {
+                    return candidate.with_meta(EXPLAIN_WEIGHT, (score - length).to_string()).with_meta(EXPLAIN_LENGTH, length.to_string());
+                };
+                let bigram: i64 = candidate.parts.windows(2).map(|w| model.score(&w[0].text, &w[1].text)).sum();
+                candidate
+                    .with_meta(EXPLAIN_WEIGHT, (score - length - bigram).to_string())
+                    .with_meta(EXPLAIN_LENGTH, length.to_string())
+                    .with_meta(EXPLAIN_BIGRAM, bigram.to_string())
             })
             .collect()
     }
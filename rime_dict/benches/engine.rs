@@ -0,0 +1,154 @@
+//! 基准：切分（含无缓存的长输入）、词典加载、查词（含前缀补全随词典规模的变化）、组句（含稠密词典）与完整会话。`cargo bench -p rime_dict`
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rime_core::{
+    engine::{Analyzer, Engine, SegmentationKind},
+    key_event::InputEvent,
+    session::Session,
+    translator::DictTranslator,
+};
+use rime_dict::{MappedDictionary, TsvDictionary, test_util::generate_tsv};
+use rime_pinyin::QuanpinPreeditor;
+
+const SEED: u64 = 20240601;
+const ENTRIES: usize = 50_000;
+
+const INPUTS: &[(&str, &str)] = &[("short", "nihao"), ("medium", "womenyiqiqu"), ("long", "zhonghuarenmingongheguowansui")];
+
+fn segment(c: &mut Criterion) {
+    let analyzer = QuanpinPreeditor::new();
+    let mut group = c.benchmark_group("segment");
+    for (name, input) in INPUTS {
+        group.bench_with_input(BenchmarkId::from_parameter(name), input, |b, input| b.iter(|| analyzer.analyze(black_box(input))));
+    }
+    // 同一输入反复切分会命中 DP 缓存；交替两条无公共前缀的 40 字节输入，每次都完整计算 DP
+    let cold = ["zhonghuarenmingongheguowansuishijiedaman", "woxiangqubeijingtiananmenkanshengqiyixia"];
+    group.bench_function("cold_40x2", |b| b.iter(|| cold.map(|input| analyzer.analyze(black_box(input)))));
+    group.finish();
+}
+
+fn load(c: &mut Criterion) {
+    let tsv = generate_tsv(SEED, ENTRIES);
+    c.bench_function("from_tsv_str/50k", |b| b.iter(|| TsvDictionary::from_tsv_str(black_box(&tsv)).unwrap()));
+}
+
+fn lookup(c: &mut Criterion) {
+    use rime_core::dictionary::Dictionary;
+
+    let dict = TsvDictionary::from_tsv_str(&generate_tsv(SEED, ENTRIES)).unwrap();
+    let analyzer = QuanpinPreeditor::new();
+    let mut group = c.benchmark_group("lookup_span");
+    // 非整段查询只走精确匹配
+    let exact = analyzer.analyze("nihaoma").segment;
+    group.bench_function("exact", |b| b.iter(|| dict.lookup_span(black_box(&exact), 0, 2, 16)));
+    // 整段查询会追加前缀补全
+    let prefix = analyzer.analyze("zhong").segment;
+    group.bench_function("prefix", |b| b.iter(|| dict.lookup_span(black_box(&prefix), 0, 1, 16)));
+    // 同样的查询走编译词典
+    let mut bytes = Vec::new();
+    dict.write_compiled(&mut bytes).unwrap();
+    let mapped = MappedDictionary::from_bytes(bytes).unwrap();
+    group.bench_function("mapped_exact", |b| b.iter(|| mapped.lookup_span(black_box(&exact), 0, 2, 16)));
+    group.bench_function("mapped_prefix", |b| b.iter(|| mapped.lookup_span(black_box(&prefix), 0, 1, 16)));
+    group.finish();
+}
+
+/// 整段前缀补全随词典规模的变化：trie 只走前缀本身与其下的词条，10 万条与 1 万条词典耗时应相当。
+fn prefix_scaling(c: &mut Criterion) {
+    use rime_core::dictionary::Dictionary;
+
+    let analyzer = QuanpinPreeditor::new();
+    let mut group = c.benchmark_group("prefix_scaling");
+    for entries in [10_000, 100_000] {
+        let dict = TsvDictionary::from_tsv_str(&generate_tsv(SEED, entries)).unwrap();
+        for input in ["zhong", "zhongh"] {
+            let segment = analyzer.analyze(input).segment;
+            group.bench_with_input(BenchmarkId::new(input, entries), &segment, |b, segment| b.iter(|| dict.lookup_span(black_box(segment), 0, segment.len(), 16)));
+        }
+    }
+    group.finish();
+}
+
+fn translate(c: &mut Criterion) {
+    let dict = TsvDictionary::from_tsv_str(&generate_tsv(SEED, ENTRIES)).unwrap();
+    let translator = DictTranslator {
+        dict: &dict,
+        fuzzy: &[],
+        kind: SegmentationKind::Syllables,
+        max_word_length: 4,
+        beam_span_limit: 16,
+        beam_width: None,
+        bigram: None,
+        learned: None,
+        word_candidate_limit: 16,
+        max_lookups: None,
+        prefix_completion: true,
+        wildcard: None,
+        explain: false,
+    };
+    let mut group = c.benchmark_group("translate_with_composition");
+    let inputs: [&[&str]; 3] = [&["ni", "hao"], &["wo", "men", "yi", "qi"], &["zhong", "hua", "ren", "min", "gong", "he", "guo", "jia"]];
+    for input in inputs {
+        let segment: Vec<String> = input.iter().map(|s| s.to_string()).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(segment.len()), &segment, |b, segment| b.iter(|| translator.translate_with_composition(black_box(segment), 0, segment.len(), 10)));
+    }
+    group.finish();
+}
+
+/// 稠密词典：`segment` 每个 1..=4 段的 span 都有 `per_span` 个词，组句的 beam 始终是满的。
+/// 单词候选最多 4 × `per_span` 个，limit 须大于它才会走到组句。
+fn dense_tsv(segment: &[&str], per_span: usize) -> String {
+    let mut tsv = String::new();
+    let mut n = 0u32;
+    for i in 0..segment.len() {
+        for len in 1..=4.min(segment.len() - i) {
+            let key = segment[i..i + len].concat();
+            for k in 0..per_span {
+                n += 1;
+                let text: String = (0..len as u32).map(|x| char::from_u32(0x4E00 + (n * 7 + x * 13) % 20_000).unwrap()).collect();
+                tsv += &format!("{text}\t{key}\t{}\n", (k * 37 + n as usize * 11) % 1000);
+            }
+        }
+    }
+    tsv
+}
+
+fn compose_dense(c: &mut Criterion) {
+    let segment = ["zhong", "hua", "ren", "min", "gong", "he", "guo", "jia"];
+    let dict = TsvDictionary::from_tsv_str(&dense_tsv(&segment, 16)).unwrap();
+    let translator = DictTranslator {
+        dict: &dict,
+        fuzzy: &[],
+        kind: SegmentationKind::Syllables,
+        max_word_length: 4,
+        beam_span_limit: 16,
+        beam_width: None,
+        bigram: None,
+        learned: None,
+        word_candidate_limit: 16,
+        max_lookups: None,
+        prefix_completion: true,
+        wildcard: None,
+        explain: false,
+    };
+    let segment: Vec<String> = segment.iter().map(|s| s.to_string()).collect();
+    c.bench_function("compose_dense/8", |b| b.iter(|| translator.translate_with_composition(black_box(&segment), 0, segment.len(), 128)));
+}
+
+fn session(c: &mut Criterion) {
+    let dict = TsvDictionary::from_tsv_str(&generate_tsv(SEED, ENTRIES)).unwrap();
+    let mut session = Session::new(Engine::new(dict, QuanpinPreeditor::new()));
+    let input = "zhonghuarenmingonghe";
+    c.bench_function("session/20_keystrokes", |b| {
+        b.iter(|| {
+            session.handle(InputEvent::Clear);
+            for ch in input.chars() {
+                black_box(session.handle(InputEvent::Char(ch)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, segment, load, lookup, prefix_scaling, translate, compose_dense, session);
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+//! 二元联想数据（`BigramPredictor`）：由“前一个词 -> 下一个词”的计数给出上屏后的联想；
+//! 以及组句用的二元语言模型（`rime_core::bigram::BigramModel`）的文件读取。
+
+use std::{collections::HashMap, fs, io, path::Path};
+
+use rime_core::{
+    bigram::BigramModel,
+    model::{Candidate, CandidateSource, CandidateText},
+    prediction::PredictionSource,
+};
+
+use crate::{int_column, tsv_rows};
+
+/// 基于二元组计数的联想来源。
+///
+/// TSV 格式：
+///
+/// - `prev<TAB>next<TAB>count`
+/// - count 可省略，默认 0
+/// - 允许 `#` 开头注释行
+/// - 同一 `(prev, next)` 重复出现时只保留第一条
+///
+/// 查询时先按整个上屏文本匹配 `prev`，没有结果再用它的最后一个字。
+#[derive(Debug, Clone, Default)]
+pub struct BigramPredictor {
+    /// prev -> [(next, count)]，按 count 降序（同分按 next）
+    map: HashMap<String, Vec<(CandidateText, i32)>>,
+}
+
+impl BigramPredictor {
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let s = fs::read_to_string(path)?;
+        Self::from_tsv_str(&s)
+    }
+
+    pub fn from_tsv_str(s: &str) -> io::Result<Self> {
+        let mut map: HashMap<String, Vec<(CandidateText, i32)>> = HashMap::new();
+        for (idx, mut it) in tsv_rows(s) {
+            let prev = it.next().unwrap_or("").trim();
+            let next = it.next().unwrap_or("").trim();
+            if prev.is_empty() || next.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("联想 TSV 第 {} 行缺少 prev/next", idx + 1)));
+            }
+            let count = int_column(it.next());
+            let entries = map.entry(prev.to_string()).or_default();
+            if !entries.iter().any(|(t, _)| t == next) {
+                entries.push((next.into(), count));
+            }
+        }
+        for v in map.values_mut() {
+            v.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        }
+        Ok(Self { map })
+    }
+}
+
+impl PredictionSource for BigramPredictor {
+    fn predict(&self, previous: &str, limit: usize) -> Vec<Candidate> {
+        let last_char = previous.char_indices().last().map(|(i, _)| &previous[i..]);
+        let Some(found) = self.map.get(previous).or_else(|| self.map.get(last_char?)) else {
+            return Vec::new();
+        };
+        found
+            .iter()
+            .take(limit)
+            .map(|(text, count)| Candidate {
+                text: text.clone(),
+                comment: None,
+                weight: *count,
+                score: None,
+                segment_start: 0,
+                segment_end: 0,
+                source: CandidateSource::Prediction,
+                rank: 0,
+                parts: Vec::new(),
+                meta: None,
+            })
+            .collect()
+    }
+}
+
+/// 为 `BigramModel` 加上文件读取（`BigramModel::from_path(path)`），格式见 `BigramModel::from_tsv_str`。
+pub trait BigramModelFile: Sized {
+    fn from_path(path: impl AsRef<Path>) -> io::Result<Self>;
+}
+
+impl BigramModelFile for BigramModel {
+    fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let s = fs::read_to_string(path)?;
+        Self::from_tsv_str(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", path.display())))
+    }
+}
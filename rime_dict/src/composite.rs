@@ -0,0 +1,87 @@
+//! 组合词典（`CompositeDictionary`）：并列挂载多个词典，例如普通词典加一份 emoji 词典（`😄<TAB>kaixin`）。
+
+use rime_core::{dictionary::Dictionary, model::Candidate};
+
+/// 若干子词典，查询时按挂载顺序依次产出各自的结果（排序交给 filter）。
+///
+/// 与 `DictionaryStack` 不同，子词典之间没有覆盖关系，同样的词条各自产出，由去重合并。
+///
+/// translator 只取前若干条（`lookup_iter` 的前缀），排在前面的大词典可能占满名额，后面的小词典永远出不来；
+/// `balanced(n)` 先依次取每个子词典的前 n 条，再依次接上各子词典其余的结果，只要取到 `n × 子词典数` 条，每个子词典都至少有 n 条（不足 n 条时为全部）。
+#[derive(Default)]
+pub struct CompositeDictionary {
+    dictionaries: Vec<Box<dyn Dictionary>>,
+    /// 每个子词典保证的条数（`balanced`）；None 即按挂载顺序拼接
+    per_dictionary: Option<usize>,
+}
+
+impl CompositeDictionary {
+    pub fn new(dictionaries: Vec<Box<dyn Dictionary>>) -> Self {
+        Self { dictionaries, per_dictionary: None }
+    }
+
+    /// 在最后挂载一个子词典。
+    pub fn with_dictionary(mut self, dictionary: impl Dictionary + 'static) -> Self {
+        self.dictionaries.push(Box::new(dictionary));
+        self
+    }
+
+    /// 每个子词典的前 `limit_per_dict` 条排在其余结果之前（见类型说明）；为 0 时同按顺序拼接。
+    pub fn balanced(mut self, limit_per_dict: usize) -> Self {
+        self.per_dictionary = Some(limit_per_dict).filter(|&n| n > 0);
+        self
+    }
+
+    /// 各子词典。
+    pub fn dictionaries(&self) -> &[Box<dyn Dictionary>] {
+        &self.dictionaries
+    }
+
+    /// 依次拼接各子词典的结果；`balanced` 时先取出每个子词典的前 n 条，其余部分仍惰性产出。
+    fn merged<'a>(&'a self, query: impl Fn(&'a dyn Dictionary) -> Box<dyn Iterator<Item = Candidate> + 'a> + 'a) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        let Some(n) = self.per_dictionary.filter(|_| self.dictionaries.len() > 1) else {
+            return Box::new(self.dictionaries.iter().flat_map(move |d| query(&**d)));
+        };
+        let mut heads = Vec::new();
+        let mut rests = Vec::with_capacity(self.dictionaries.len());
+        for d in &self.dictionaries {
+            let mut found = query(&**d);
+            heads.extend(found.by_ref().take(n));
+            rests.push(found);
+        }
+        Box::new(heads.into_iter().chain(rests.into_iter().flatten()))
+    }
+}
+
+impl Dictionary for CompositeDictionary {
+    fn lookup_span(&self, segment: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate> {
+        self.lookup_iter(segment, start, end).take(limit.max(1)).collect()
+    }
+
+    fn lookup_iter<'a>(&'a self, segment: &'a [String], start: usize, end: usize) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        self.merged(move |d| d.lookup_iter(segment, start, end))
+    }
+
+    fn lookup_initials<'a>(&'a self, initials: &'a [String]) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        self.merged(move |d| d.lookup_initials(initials))
+    }
+
+    fn lookup_wildcard<'a>(&'a self, code: &'a str, wildcard: char) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        self.merged(move |d| d.lookup_wildcard(code, wildcard))
+    }
+
+    /// 各子词典的编码按挂载顺序排列，重复的编码只保留第一次出现。
+    fn reverse_lookup(&self, text: &str) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for key in self.dictionaries.iter().flat_map(|d| d.reverse_lookup(text)) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    fn generation(&self) -> u64 {
+        self.dictionaries.iter().map(|d| d.generation()).sum()
+    }
+}
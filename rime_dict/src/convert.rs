@@ -0,0 +1,100 @@
+//! 简繁转换表（`ConversionTable`）：OpenCC 文本格式的字/词映射，按最长匹配转换。
+
+use std::{collections::HashMap, fs, io, path::Path};
+
+use rime_core::convert::TextConverter;
+
+/// 字/词映射表（例如 OpenCC 的 `STCharacters.txt` 与 `STPhrases.txt`）。
+///
+/// 格式：
+///
+/// - `源<TAB>目标 [其他目标...]`，只取第一个目标
+/// - 允许 `#` 开头注释行
+/// - 同一个源重复出现时只保留第一条（先加载的文件优先）
+///
+/// 转换时从左到右取最长的匹配，没有匹配的字原样保留。
+///
+/// 单字条目的全部目标（含第一个以外的）都记为源字的异体（见 `TextConverter::base_char`）。
+#[derive(Debug, Clone, Default)]
+pub struct ConversionTable {
+    map: HashMap<String, String>,
+    /// 最长的源（按字符数），限制最长匹配的窗口
+    max_key_chars: usize,
+    /// 单字目标 -> 源字（同一目标以先出现者为准）
+    base: HashMap<char, char>,
+}
+
+impl ConversionTable {
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_paths(&[path])
+    }
+
+    /// 合并多个文件（通常是字表 + 词表）。
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> io::Result<Self> {
+        let mut table = Self::default();
+        for path in paths {
+            table.extend_from_str(&fs::read_to_string(path)?)?;
+        }
+        Ok(table)
+    }
+
+    pub fn from_tsv_str(s: &str) -> io::Result<Self> {
+        let mut table = Self::default();
+        table.extend_from_str(s)?;
+        Ok(table)
+    }
+
+    fn extend_from_str(&mut self, s: &str) -> io::Result<()> {
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (from, targets) = line.split_once('\t').unwrap_or((line, ""));
+            let to = targets.split_whitespace().next().unwrap_or("");
+            if from.is_empty() || to.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("转换表第 {} 行缺少源或目标", idx + 1)));
+            }
+            self.max_key_chars = self.max_key_chars.max(from.chars().count());
+            self.map.entry(from.to_string()).or_insert_with(|| to.to_string());
+            if let Some(base) = single_char(from) {
+                for target in targets.split_whitespace().filter_map(single_char).filter(|&t| t != base) {
+                    self.base.entry(target).or_insert(base);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `s` 恰为一个字时返回它。
+fn single_char(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    chars.next().filter(|_| chars.next().is_none())
+}
+
+impl TextConverter for ConversionTable {
+    fn convert(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(first) = rest.chars().next() {
+            // 候选窗口的字节边界，由长到短尝试
+            let ends: Vec<usize> = rest.char_indices().skip(1).map(|(i, _)| i).chain([rest.len()]).take(self.max_key_chars).collect();
+            match ends.iter().rev().find_map(|&end| self.map.get(&rest[..end]).map(|to| (end, to))) {
+                Some((end, to)) => {
+                    out.push_str(to);
+                    rest = &rest[end..];
+                }
+                None => {
+                    out.push(first);
+                    rest = &rest[first.len_utf8()..];
+                }
+            }
+        }
+        out
+    }
+
+    fn base_char(&self, c: char) -> char {
+        self.base.get(&c).copied().unwrap_or(c)
+    }
+}
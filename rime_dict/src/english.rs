@@ -0,0 +1,126 @@
+//! 英文单词补全（`EnglishTranslator`）：输入是词表中单词的前缀即给出该词，供临时英文与无法切分的输入使用。
+
+use std::{collections::BTreeMap, fs, io, ops::Bound, path::Path};
+
+use rime_core::{
+    model::{Candidate, CandidateSource, CandidateText},
+    translator::Translator,
+};
+
+use crate::{int_column, tsv_rows};
+
+/// 英文词表翻译器（见 `Engine::english_translator`）：前缀匹配不区分大小写，完整匹配的单词在前，其余按词频降序。
+///
+/// TSV 格式：
+///
+/// - `word<TAB>frequency`
+/// - frequency 可省略，默认 0
+/// - 允许 `#` 开头注释行
+/// - 单词须以字母开头；同一单词（区分大小写）重复出现时只保留第一条
+///
+/// 候选随输入的大小写调整：输入首字母大写时候选首字母大写，输入全大写（至少两个字母）时候选全大写，否则保持词表写法（如 `iPhone`）。
+#[derive(Debug, Clone, Default)]
+pub struct EnglishTranslator {
+    /// 小写单词 -> [(词表写法, 词频)]
+    words: BTreeMap<String, Vec<(CandidateText, i32)>>,
+}
+
+impl EnglishTranslator {
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let s = fs::read_to_string(path)?;
+        Self::from_tsv_str(&s)
+    }
+
+    pub fn from_tsv_str(s: &str) -> io::Result<Self> {
+        let mut words = Self::default();
+        words.parse_into(s, false)?;
+        Ok(words)
+    }
+
+    /// 同 `from_tsv_str`，但跳过不是单词的行而不是报错；返回词表与跳过的行数。
+    pub fn from_tsv_str_lenient(s: &str) -> (Self, usize) {
+        let mut words = Self::default();
+        let skipped = words.parse_into(s, true).expect("宽松模式不返回错误");
+        (words, skipped)
+    }
+
+    /// 解析 TSV 并追加单词；`lenient` 时跳过格式错误的行，返回跳过的行数。
+    fn parse_into(&mut self, s: &str, lenient: bool) -> io::Result<usize> {
+        let mut skipped = 0;
+        for (idx, mut it) in tsv_rows(s) {
+            let word = it.next().unwrap_or("").trim();
+            if !word.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                if lenient {
+                    skipped += 1;
+                    continue;
+                }
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("英文词表第 {} 行不是单词", idx + 1)));
+            }
+            let frequency = int_column(it.next());
+            let entries = self.words.entry(word.to_ascii_lowercase()).or_default();
+            if !entries.iter().any(|(w, _)| &**w == word) {
+                entries.push((word.into(), frequency));
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// 词表中的单词数。
+    pub fn len(&self) -> usize {
+        self.words.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// 按输入的大小写调整候选写法（规则见 `EnglishTranslator`）。
+fn case_like(typed: &str, word: &CandidateText) -> CandidateText {
+    let mut letters = typed.chars().filter(char::is_ascii_alphabetic);
+    let Some(first) = letters.next() else {
+        return word.clone();
+    };
+    if first.is_ascii_uppercase() && letters.clone().next().is_some() && letters.all(|c| c.is_ascii_uppercase()) {
+        return word.to_ascii_uppercase().into();
+    }
+    if first.is_ascii_uppercase() {
+        let mut chars = word.chars();
+        let head = chars.next().map(|c| c.to_ascii_uppercase());
+        return head.into_iter().chain(chars).collect::<String>().into();
+    }
+    word.clone()
+}
+
+impl Translator for EnglishTranslator {
+    fn translate(&self, segments: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate> {
+        let typed: String = segments[start..end].concat();
+        let prefix = typed.to_ascii_lowercase();
+        if prefix.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+        let mut found: Vec<(&str, &CandidateText, i32)> = self
+            .words
+            .range::<str, _>((Bound::Included(prefix.as_str()), Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .flat_map(|(key, entries)| entries.iter().map(move |(word, frequency)| (key.as_str(), word, *frequency)))
+            .collect();
+        found.sort_by(|a, b| (a.0 != prefix).cmp(&(b.0 != prefix)).then_with(|| b.2.cmp(&a.2)).then_with(|| a.0.cmp(b.0)));
+        found
+            .into_iter()
+            .take(limit)
+            .map(|(key, word, frequency)| Candidate {
+                text: case_like(&typed, word),
+                comment: None,
+                weight: frequency,
+                score: None,
+                segment_start: start,
+                segment_end: end,
+                source: if key == prefix { CandidateSource::Exact } else { CandidateSource::Prefix },
+                rank: 0,
+                parts: Vec::new(),
+                meta: None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,41 @@
+//! 用户词频（`rime_core::history::UserHistory`）的文件读写：TSV，每行 `key<TAB>text<TAB>count<TAB>last_used`（旧的三列文件照常读入）。
+
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
+
+use rime_core::history::UserHistory;
+
+/// 用户目录下用户词频的文件名。
+pub const HISTORY_FILE: &str = "history.tsv";
+
+/// 为 `UserHistory` 加上文件读写（`UserHistory::load_from(path)`、`history.save_to(path)`）。
+pub trait UserHistoryFile: Sized {
+    /// 从 `path` 读入；文件不存在时为空（首次运行）。
+    fn load_from(path: impl AsRef<Path>) -> io::Result<Self>;
+
+    /// 写到 `path`：先写到同目录的临时文件再改名替换，中途失败不会留下写了一半的文件。
+    fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()>;
+}
+
+impl UserHistoryFile for UserHistory {
+    fn load_from(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(s) => Self::from_tsv_str(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", path.display()))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tsv.tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(self.to_tsv().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+}
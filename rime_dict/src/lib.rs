@@ -1,128 +1,461 @@
-use std::{collections::BTreeMap, fs, io, path::Path};
+pub mod bigram;
+pub mod blacklist;
+pub mod composite;
+pub mod convert;
+pub mod english;
+pub mod history;
+pub mod learn;
+pub mod mapped;
+pub mod merge;
+pub mod normalize;
+pub mod profile;
+pub mod punct;
+pub mod stack;
+mod trie;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
-use rime_core::engine::Analyzer;
-use rime_core::{dictionary::Dictionary, model::Candidate};
+pub use bigram::{BigramModelFile, BigramPredictor};
+pub use blacklist::{BLACKLIST_FILE, BlacklistFile};
+pub use composite::CompositeDictionary;
+pub use convert::ConversionTable;
+pub use english::EnglishTranslator;
+pub use history::{HISTORY_FILE, UserHistoryFile};
+pub use learn::{LEARNED_FILE, LearnedPhrasesFile};
+pub use mapped::MappedDictionary;
+pub use merge::MergePolicy;
+pub use normalize::{KeyNormalization, Umlaut};
+pub use profile::{Profile, ProfilePaths};
+pub use stack::DictionaryStack;
+
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Write},
+    iter,
+    path::Path,
+    sync::{LazyLock, OnceLock},
+};
+
+use rime_core::engine::{Analyzer, SegmentationKind};
+use rime_core::{
+    dictionary::{Dictionary, MutableDictionary},
+    model::{Candidate, CandidateSource, CandidateText},
+};
 use rime_pinyin::QuanpinPreeditor;
 
+use crate::trie::Trie;
+
 #[derive(Debug, Clone)]
 struct Entry {
-    text: String,
+    /// 共享文本：查询结果只增加引用计数
+    text: CandidateText,
     weight: i32,
+    /// 规整前的 key（与规整后不同时），作为候选的 comment 展示
+    original: Option<CandidateText>,
 }
 
+/// 未指定 analyzer 时用于切分 key 的默认全拼切分器（进程内共享一份）。
+static DEFAULT_ANALYZER: LazyLock<QuanpinPreeditor> = LazyLock::new(QuanpinPreeditor::new);
+
 /// TSV 格式（简化版）：
 ///
 /// - `text<TAB>key<TAB>weight`
 /// - weight 可省略，默认 0
 /// - 允许 `#` 开头注释行
+/// - 同一 `(key, text)` 重复出现时只保留第一条
 ///
-/// key 建议用“无分隔的拼音串”（例如 `nihao`），与 CLI 输入一致。
+/// key 建议用“无分隔的拼音串”（例如 `nihao`），与 CLI 输入一致；其他写法可在加载时规整（`from_tsv_str_normalized`）。
+#[derive(Clone)]
 pub struct TsvDictionary {
-    map: BTreeMap<String, Vec<Entry>>,
-    initials_map: BTreeMap<String, Vec<(String, Entry)>>, // initials -> [(key, entry)]
+    /// key -> 词条（见 `trie`）：精确查询与前缀补全都只走 key 的长度
+    map: Trie<Vec<Entry>>,
+    initials_map: BTreeMap<String, Vec<(CandidateText, Entry)>>, // initials -> [(key, entry)]
+    /// 反查索引 text -> 全部 key（按权重降序，同分按字典序）；第一次反查时建立，`insert` 后作废
+    reverse: OnceLock<HashMap<CandidateText, Vec<CandidateText>>>,
+    /// 每次 `insert` 后递增（见 `Dictionary::generation`）
+    generation: u64,
+    /// 加载与 `insert` 时的 key 规整
+    keys: KeyNormalization,
 }
 
 impl TsvDictionary {
+    fn empty() -> Self {
+        Self {
+            map: Trie::default(),
+            initials_map: BTreeMap::new(),
+            reverse: OnceLock::new(),
+            generation: 0,
+            keys: KeyNormalization::default(),
+        }
+    }
+
     pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_path_with(path, &*DEFAULT_ANALYZER)
+    }
+
+    /// 同 `from_path`，用 `analyzer` 切分 key 以建立首字母索引（例如复用引擎的切分器）。
+    pub fn from_path_with(path: impl AsRef<Path>, analyzer: &dyn Analyzer) -> io::Result<Self> {
         let s = fs::read_to_string(path)?;
-        Self::from_tsv_str(&s)
+        Self::from_tsv_str_with(&s, analyzer)
     }
 
-    pub fn from_tsv_str(s: &str) -> io::Result<Self> {
-        let mut map: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
-        let mut initials_map: BTreeMap<String, Vec<(String, Entry)>> = BTreeMap::new();
-        let syllabifier = QuanpinPreeditor::new();
+    /// 按顺序加载多个词典文件，合并为一个词典。
+    ///
+    /// 排在前面的优先级更高：同一 `(key, text)` 只保留最先出现的词条。
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> io::Result<Self> {
+        Self::from_paths_with(paths, &*DEFAULT_ANALYZER)
+    }
 
-        for (idx, line) in s.lines().enumerate() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
+    /// 同 `from_paths`，用 `analyzer` 切分 key。
+    pub fn from_paths_with<P: AsRef<Path>>(paths: &[P], analyzer: &dyn Analyzer) -> io::Result<Self> {
+        Self::from_paths_normalized(paths, analyzer, KeyNormalization::default())
+    }
+
+    /// 同 `from_paths_with`，加载时按 `keys` 规整各词典的 key（见 `from_tsv_str_normalized`）。
+    ///
+    /// 以 `mapped::MAGIC` 开头的文件按编译词典读入（见 `from_compiled`），其 key 已在编译时规整，不再按 `keys` 处理。
+    pub fn from_paths_normalized<P: AsRef<Path>>(paths: &[P], analyzer: &dyn Analyzer, keys: KeyNormalization) -> io::Result<Self> {
+        let mut dict = Self::empty();
+        dict.keys = keys;
+        for path in paths {
+            let path = path.as_ref();
+            let context = |e: io::Error| io::Error::new(e.kind(), format!("{}: {e}", path.display()));
+            let bytes = fs::read(path)?;
+            if bytes.starts_with(mapped::MAGIC) {
+                let compiled = Self::from_compiled_bytes(bytes).map_err(context)?;
+                if dict.map.len() == 0 {
+                    dict = Self { keys: dict.keys, ..compiled };
+                } else {
+                    dict.append(compiled);
+                }
                 continue;
             }
-            let mut it = line.split('\t');
+            let s = String::from_utf8(bytes).map_err(|_| context(io::Error::new(io::ErrorKind::InvalidData, "词典不是有效的 UTF-8")))?;
+            dict.parse_into(&s, analyzer, false).map_err(context)?;
+        }
+        dict.sort_entries();
+        Ok(dict)
+    }
+
+    pub fn from_tsv_str(s: &str) -> io::Result<Self> {
+        Self::from_tsv_str_with(s, &*DEFAULT_ANALYZER)
+    }
+
+    /// 同 `from_tsv_str`，用 `analyzer` 切分 key（只影响首字母索引）。
+    pub fn from_tsv_str_with(s: &str, analyzer: &dyn Analyzer) -> io::Result<Self> {
+        Self::from_tsv_str_normalized(s, analyzer, KeyNormalization::default())
+    }
+
+    /// 同 `from_tsv_str_with`，加载时按 `keys` 规整 key（例如 `ni3 hao3`、`lü` 写法的词典配 `KeyNormalization::pinyin()`）。
+    ///
+    /// 规整后相同的 `(key, text)` 只保留第一条；规整改变了 key 的词条以原 key 作为候选的 comment。
+    /// 之后 `insert` 的 key 也按同样的规则规整。
+    pub fn from_tsv_str_normalized(s: &str, analyzer: &dyn Analyzer, keys: KeyNormalization) -> io::Result<Self> {
+        let mut dict = Self::empty();
+        dict.keys = keys;
+        dict.parse_into(s, analyzer, false)?;
+        dict.sort_entries();
+        Ok(dict)
+    }
+
+    /// 同 `from_tsv_str`，但跳过缺少 text/key 的行而不是报错；返回词典与跳过的行数。
+    ///
+    /// 适合用户词典、粘贴文本等不可信输入。
+    pub fn from_tsv_str_lenient(s: &str) -> (Self, usize) {
+        let mut dict = Self::empty();
+        let skipped = dict.parse_into(s, &*DEFAULT_ANALYZER, true).expect("宽松模式不返回错误");
+        dict.sort_entries();
+        (dict, skipped)
+    }
+
+    /// 解析 TSV 并追加词条；`lenient` 时跳过格式错误的行，返回跳过的行数。
+    fn parse_into(&mut self, s: &str, analyzer: &dyn Analyzer, lenient: bool) -> io::Result<usize> {
+        #[cfg(feature = "rayon")]
+        if s.len() >= PAR_MIN_BYTES && rayon::current_num_threads() > 1 {
+            return self.par_parse_into(s, analyzer, lenient);
+        }
+        self.parse_lines(s, 0, analyzer, lenient)
+    }
+
+    /// `parse_into` 的串行实现；`first_line` 为 `s` 首行在整个输入中的行号（从 0 起，用于报错）。
+    fn parse_lines(&mut self, s: &str, first_line: usize, analyzer: &dyn Analyzer, lenient: bool) -> io::Result<usize> {
+        let mut skipped = 0;
+        // 同一 key 的词条通常相邻（一音多字），与上一行 key 相同时不再规整与切分
+        let keys = self.keys;
+        let mut last: Option<(&str, Cow<str>, Option<String>)> = None;
+        for (idx, mut it) in tsv_rows(s) {
             let text = it.next().unwrap_or("").trim();
-            let key = it.next().unwrap_or("").trim();
+            let raw_key = it.next().unwrap_or("").trim();
+            let cached = last.take().filter(|(k, _, _)| *k == raw_key);
+            let (_, key, initials) = last.insert(cached.unwrap_or_else(|| {
+                let key = keys.normalize(raw_key);
+                let initials = initials_of(&key, analyzer);
+                (raw_key, key, initials)
+            }));
             if text.is_empty() || key.is_empty() {
+                if lenient {
+                    skipped += 1;
+                    continue;
+                }
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidData,
-                    format!("TSV 第 {} 行缺少 text/key", idx + 1),
+                    format!("TSV 第 {} 行缺少 text/key", first_line + idx + 1),
                 ));
             }
-            let weight = it
-                .next()
-                .map(str::trim)
-                .filter(|x| !x.is_empty())
-                .and_then(|x| x.parse::<i32>().ok())
-                .unwrap_or(0);
-            let entry = Entry {
-                text: text.to_string(),
-                weight,
-            };
-            map.entry(key.to_string()).or_default().push(entry.clone());
-
-            // 预计算：key(如 qishi) -> 音节段(如 [qi, shi]) -> initials(如 qs)
-            let analysis = syllabifier.analyze(key);
-            if !analysis.segment.is_empty() {
-                let mut initials = String::new();
-                for seg in &analysis.segment {
-                    if let Some(ch) = seg.chars().next() {
-                        initials.push(ch);
-                    }
-                }
-                if !initials.is_empty() {
-                    initials_map
-                        .entry(initials)
-                        .or_default()
-                        .push((key.to_string(), entry));
+            let weight = int_column(it.next());
+            let original = (raw_key != *key).then_some(raw_key);
+            self.add_entry(key, original, text, weight, initials.as_deref());
+        }
+
+        Ok(skipped)
+    }
+
+    /// `parse_into` 的并行版本：按行边界分块，各块并行解析成独立的（未排序）词典，再按块顺序并入。
+    ///
+    /// 并入顺序与行顺序一致，因此“先出现者优先”、词条次序与报错行号都与串行路径相同。
+    #[cfg(feature = "rayon")]
+    fn par_parse_into(&mut self, s: &str, analyzer: &dyn Analyzer, lenient: bool) -> io::Result<usize> {
+        use rayon::prelude::*;
+
+        let chunks = split_lines(s, rayon::current_num_threads() * 4);
+        // 各块首行的行号：块（除最后一块）都以换行结尾，行数即换行数
+        let first_lines = chunks.iter().scan(0, |line, chunk| {
+            let first = *line;
+            *line += chunk.bytes().filter(|&b| b == b'\n').count();
+            Some(first)
+        });
+        let jobs: Vec<(&str, usize)> = chunks.iter().copied().zip(first_lines).collect();
+        // reduce 保持块的先后顺序（左侧先并入），合并本身也按树形并行；最靠前的错误胜出
+        let merged = jobs
+            .into_par_iter()
+            .map(|(chunk, first_line)| {
+                let mut part = Self::empty();
+                part.keys = self.keys;
+                part.parse_lines(chunk, first_line, analyzer, lenient).map(|skipped| (part, skipped))
+            })
+            .reduce_with(|left, right| {
+                let (mut left, a) = left?;
+                let (right, b) = right?;
+                left.append(right);
+                Ok((left, a + b))
+            });
+        let Some(merged) = merged else { return Ok(0) };
+        let (part, skipped) = merged?;
+        self.append(part);
+        Ok(skipped)
+    }
+
+    /// 按顺序并入另一份词典，结果与对其词条逐条 `add_entry` 相同（之后须 `sort_entries`）。
+    fn append(&mut self, other: Self) {
+        use std::collections::HashSet;
+
+        // 在 self 中已存在的 (key, text)：other 里对应的首字母索引项也要丢弃
+        let mut dup: HashSet<(CandidateText, CandidateText)> = HashSet::new();
+        for (key, entries) in other.map.into_entries() {
+            let existing = self.map.get_or_default(&key);
+            for e in entries {
+                if existing.iter().any(|x| x.text == e.text) {
+                    dup.insert((CandidateText::from(&*key), e.text));
+                } else {
+                    existing.push(e);
                 }
             }
         }
+        for (initials, items) in other.initials_map {
+            let v = self.initials_map.entry(initials).or_default();
+            v.extend(items.into_iter().filter(|(k, e)| dup.is_empty() || !dup.contains(&(k.clone(), e.text.clone()))));
+        }
+    }
 
-        for v in map.values_mut() {
+    /// 追加一条词条（不排序）；同一 `(key, text)` 已存在时返回 false 且不做修改。
+    ///
+    /// `key` 为规整后的 key，`original` 为与之不同的规整前的 key；
+    /// `initials` 为 key 的首字母串（见 `initials_of`），None 时不进首字母索引。
+    fn add_entry(&mut self, key: &str, original: Option<&str>, text: &str, weight: i32, initials: Option<&str>) -> bool {
+        let entries = self.map.get_or_default(key);
+        if entries.iter().any(|e| e.text == text) {
+            return false;
+        }
+        let entry = Entry {
+            text: text.into(),
+            weight,
+            original: original.map(Into::into),
+        };
+        entries.push(entry.clone());
+        if let Some(initials) = initials {
+            self.initials_map.entry(initials.to_string()).or_default().push((key.into(), entry));
+        }
+        true
+    }
+
+    /// 同 key 的词条按 weight 降序（同分按 text）排列。
+    fn sort_entries(&mut self) {
+        for v in self.map.values_mut() {
             v.sort_by(|a, b| b.weight.cmp(&a.weight).then_with(|| a.text.cmp(&b.text)));
         }
 
-        for v in initials_map.values_mut() {
+        for v in self.initials_map.values_mut() {
             v.sort_by(|a, b| b.1.weight.cmp(&a.1.weight).then_with(|| a.1.text.cmp(&b.1.text)));
         }
+    }
 
-        Ok(Self { map, initials_map })
+    /// key 以 `prefix` 开头（不含 `prefix` 本身）且通过 `completion` 检查的词条，按 key 字典序。
+    ///
+    /// 在 trie 中找到 `prefix` 的结点后只遍历其下的 key，与词典大小无关。
+    fn prefix_entries<'a>(&'a self, prefix: String, completion: Completion<'a>) -> impl Iterator<Item = (&'a str, &'a Entry)> {
+        let len = prefix.len();
+        self.map
+            .prefixed(&prefix)
+            .filter(move |(key, _)| key.len() > len && completion.accepts(key))
+            .flat_map(|(key, entries)| entries.iter().map(move |e| (key, e)))
     }
 
-    fn prefix_candidates(
-        &self,
-        prefix: &str,
-        start: usize,
-        end: usize,
-        limit: usize,
-        out: &mut Vec<Candidate>,
-    ) {
-        if prefix.is_empty() || limit == 0 {
-            return;
-        }
-        for (key, entries) in self.map.range(prefix.to_string()..) {
-            if !key.starts_with(prefix) {
-                break;
-            }
-            if key == prefix {
-                continue;
-            }
+    /// 加载与 `insert` 时所用的 key 规整。
+    pub fn key_normalization(&self) -> KeyNormalization {
+        self.keys
+    }
+
+    /// 写出 TSV（`text<TAB>key<TAB>weight`，按 key 排列，key 为规整后的），可由 `from_path` 原样读回。
+    pub fn write_tsv(&self, mut w: impl Write) -> io::Result<()> {
+        for (key, entries) in &self.map {
             for e in entries {
-                out.push(Candidate {
-                    text: e.text.clone(),
-                    comment: Some(key.clone()),
-                    weight: e.weight,
-                    segment_start: start,
-                    segment_end: end,
-                });
-                if out.len() >= limit {
-                    return;
-                }
+                writeln!(w, "{}\t{key}\t{}", e.text, e.weight)?;
             }
         }
+        Ok(())
+    }
+}
+
+impl Entry {
+    /// `comment` 为查询方给出的注释（前缀补全、简拼时为完整 key）；词条有规整前的 key 时以它代替。
+    fn to_candidate(&self, source: CandidateSource, comment: Option<CandidateText>, start: usize, end: usize) -> Candidate {
+        Candidate {
+            text: self.text.clone(),
+            comment: self.original.clone().or(comment),
+            weight: self.weight,
+            score: None,
+            segment_start: start,
+            segment_end: end,
+            source,
+            rank: 0,
+            parts: Vec::new(),
+            meta: None,
+        }
     }
 }
 
+/// TSV 的有效行（跳过空行与 `#` 注释行）：(行号（从 0 起）, 按 TAB 切开的各列)。
+pub(crate) fn tsv_rows(s: &str) -> impl Iterator<Item = (usize, std::str::Split<'_, char>)> {
+    s.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(idx, line)| (idx, line.split('\t')))
+}
+
+/// 可省略的整数列（weight/count 等）：缺失、为空或不是整数时为 0。
+pub(crate) fn int_column(column: Option<&str>) -> i32 {
+    column.map(str::trim).filter(|x| !x.is_empty()).and_then(|x| x.parse::<i32>().ok()).unwrap_or(0)
+}
+
+/// 启用 `rayon` 时，输入不小于该字节数才走并行解析；更小的输入线程开销占主导。
+#[cfg(feature = "rayon")]
+const PAR_MIN_BYTES: usize = 256 * 1024;
+
+/// 按 `\n` 把 `s` 切成约 `n` 块；每块（除最后一块）以换行结尾，逐块 `lines()` 与整体 `lines()` 一致。
+#[cfg(feature = "rayon")]
+fn split_lines(s: &str, n: usize) -> Vec<&str> {
+    let step = s.len().div_ceil(n.max(1)).max(1);
+    let mut chunks = Vec::with_capacity(n);
+    let mut rest = s;
+    while !rest.is_empty() {
+        // 按字节找换行：`\n` 一定落在字符边界上
+        let cut = match rest.as_bytes().get(step..).and_then(|tail| tail.iter().position(|&b| b == b'\n')) {
+            Some(i) => step + i + 1,
+            None => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(cut);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/// 前缀补全的音节对齐检查。
+///
+/// 输入切分出的音节须与 key 的切分逐段相同，只有末尾可以是输入到一半的音节：
+/// `nihao`（ni hao）可补全为 `nihaoma`（ni hao ma），`jio`（ji o）可补全为 `jiong`，
+/// 而 `fangan`（fang an）不补全 `fanganren`（fan gan ren）。
+#[derive(Clone, Copy)]
+pub(crate) struct Completion<'a> {
+    span: &'a [String],
+    /// 输入没有可信的音节边界（例如简拼输入拼成的一整串），此时只按字节前缀匹配
+    unsegmented: bool,
+}
+
+impl<'a> Completion<'a> {
+    pub(crate) fn new(span: &'a [String]) -> Self {
+        let unsegmented = match span {
+            [only] => {
+                let analysis = DEFAULT_ANALYZER.analyze(only);
+                analysis.kind != SegmentationKind::Syllables || analysis.segment.len() != 1
+            }
+            _ => false,
+        };
+        Self { span, unsegmented }
+    }
+
+    /// `key`（以输入拼接串为前缀）能否作为补全：存在 m，使输入前 m 段与 key 的前 m 个音节相同，
+    /// 且其余各段拼起来是 key 第 m+1 个音节的前缀。key 本身无法切分时不作要求。
+    pub(crate) fn accepts(&self, key: &str) -> bool {
+        if self.unsegmented {
+            return true;
+        }
+        let analysis = DEFAULT_ANALYZER.analyze(key);
+        if analysis.kind != SegmentationKind::Syllables {
+            return true;
+        }
+        let segs = &analysis.segment;
+        let common = self.span.iter().zip(segs.iter()).take_while(|(a, b)| a == b).count();
+        (0..=common.min(self.span.len().saturating_sub(1))).rev().any(|m| {
+            segs.get(m).is_some_and(|syllable| {
+                let rest = &self.span[m..];
+                rest.iter().map(String::len).sum::<usize>() <= syllable.len() && concat_is_prefix(rest, syllable)
+            })
+        })
+    }
+}
+
+/// `rest` 各段拼接后是否为 `s` 的前缀（不分配）。
+fn concat_is_prefix(rest: &[String], s: &str) -> bool {
+    let mut pos = 0;
+    rest.iter().all(|seg| {
+        let ok = s[pos..].starts_with(seg.as_str());
+        pos += seg.len();
+        ok
+    })
+}
+
+/// 预计算：key(如 qishi) -> 音节段(如 [qi, shi]) -> initials(如 qs)；切分失败或 key 是码表编码（`SegmentationKind::Codes`）时为 None。
+fn initials_of(key: &str, analyzer: &dyn Analyzer) -> Option<String> {
+    let analysis = analyzer.analyze(key);
+    if analysis.kind == SegmentationKind::Codes {
+        return None;
+    }
+    let initials: String = analysis.segment.iter().filter_map(|seg| seg.chars().next()).collect();
+    (!initials.is_empty()).then_some(initials)
+}
+
+/// `key` 是否与含通配符的 `code` 等长且逐字符相同（通配符处任意）。
+pub(crate) fn wildcard_matches(key: &str, code: &str, wildcard: char) -> bool {
+    key.chars().count() == code.chars().count() && key.chars().zip(code.chars()).all(|(k, c)| c == wildcard || k == c)
+}
+
 impl Dictionary for TsvDictionary {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self, segments), fields(key = ?segments.get(start..end))))]
     fn lookup_span(
         &self,
         segments: &[String],
@@ -130,54 +463,94 @@ impl Dictionary for TsvDictionary {
         end: usize,
         limit: usize,
     ) -> Vec<Candidate> {
-        let limit = limit.max(1);
+        let out: Vec<Candidate> = self.lookup_iter(segments, start, end).take(limit.max(1)).collect();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(count = out.len(), top = %rime_core::model::summarize(&out, 3), "lookup");
+        out
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn reverse_lookup(&self, text: &str) -> Vec<String> {
+        let index = self.reverse.get_or_init(|| {
+            // 按 key 的字典序遍历，稳定排序后同分的 key 保持字典序
+            let mut all: HashMap<CandidateText, Vec<(CandidateText, i32)>> = HashMap::new();
+            for (key, entries) in &self.map {
+                let key = CandidateText::from(key);
+                for e in entries {
+                    all.entry(e.text.clone()).or_default().push((key.clone(), e.weight));
+                }
+            }
+            all.into_iter()
+                .map(|(text, mut keys)| {
+                    keys.sort_by_key(|&(_, weight)| Reverse(weight));
+                    (text, keys.into_iter().map(|(key, _)| key).collect())
+                })
+                .collect()
+        });
+        index.get(text).map_or_else(Vec::new, |keys| keys.iter().map(ToString::to_string).collect())
+    }
+
+    /// 依次产出：精确匹配 → 整段输入的前缀补全；只在 `next()` 时构造候选。
+    fn lookup_iter<'a>(&'a self, segments: &'a [String], start: usize, end: usize) -> Box<dyn Iterator<Item = Candidate> + 'a> {
         if start >= end || end > segments.len() {
-            return Vec::new();
+            return Box::new(iter::empty());
         }
 
         let key: String = segments[start..end].concat();
         if key.is_empty() {
-            return Vec::new();
-        }
-
-        let mut out = Vec::new();
-        if let Some(entries) = self.map.get(&key) {
-            for e in entries.iter().take(limit) {
-                out.push(Candidate {
-                    text: e.text.clone(),
-                    comment: None,
-                    weight: e.weight,
-                    segment_start: start,
-                    segment_end: end,
-                });
-            }
+            return Box::new(iter::empty());
         }
+        let whole = start == 0 && end == segments.len();
 
+        let exact = self.map.get(&key).map_or(&[][..], Vec::as_slice).iter().map(move |e| e.to_candidate(CandidateSource::Exact, None, start, end));
         // 仅对“整段输入”提供前缀补全（用于 CLI 输入体验）。
-        if start == 0 && end == segments.len() && out.len() < limit {
-            self.prefix_candidates(&key, start, end, limit - out.len(), &mut out);
-        }
-
-        // initials 查询：如果 segments 看起来是 ["q","s"] 这种单字母数组，则尝试用 initials_map。
-        if out.is_empty()
-            && start == 0
-            && end == segments.len()
-            && segments.iter().all(|s| s.len() == 1 && s.bytes().all(|b| b.is_ascii_lowercase()))
-        {
-            let initials: String = segments.concat();
-            if let Some(v) = self.initials_map.get(&initials) {
-                for (k, e) in v.iter().take(limit) {
-                    out.push(Candidate {
-                        text: e.text.clone(),
-                        comment: Some(k.clone()),
-                        weight: e.weight,
-                        segment_start: start,
-                        segment_end: end,
-                    });
-                }
+        let prefix = whole
+            .then(|| self.prefix_entries(key, Completion::new(segments)))
+            .into_iter()
+            .flatten()
+            .map(move |(k, e)| e.to_candidate(CandidateSource::Prefix, Some(k.into()), start, end));
+        Box::new(exact.chain(prefix))
+    }
+
+    fn lookup_initials<'a>(&'a self, initials: &'a [String]) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        let end = initials.len();
+        let found = self.initials_map.get(&initials.concat()).map_or(&[][..], Vec::as_slice);
+        Box::new(found.iter().map(move |(k, e)| e.to_candidate(CandidateSource::Initials, Some(k.clone()), 0, end)))
+    }
+
+    /// 遍历第一个通配符之前的前缀下的 key（按字典序）。
+    fn lookup_wildcard<'a>(&'a self, code: &'a str, wildcard: char) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        let prefix = code.split(wildcard).next().unwrap_or("");
+        Box::new(
+            self.map
+                .prefixed(prefix)
+                .filter(move |(key, _)| wildcard_matches(key, code, wildcard))
+                .flat_map(|(key, entries)| {
+                    let comment = CandidateText::from(key);
+                    entries.iter().map(move |e| e.to_candidate(CandidateSource::Fuzzy, Some(comment.clone()), 0, 1))
+                }),
+        )
+    }
+}
+
+impl MutableDictionary for TsvDictionary {
+    fn insert(&mut self, raw_key: &str, text: &str, weight: i32) -> bool {
+        let key = self.keys.normalize(raw_key);
+        let original = (raw_key != key).then_some(raw_key);
+        let added = self.add_entry(&key, original, text, weight, initials_of(&key, &*DEFAULT_ANALYZER).as_deref());
+        if !added {
+            let entries = self.map.get_mut(&key).into_iter().flatten();
+            let by_initials = self.initials_map.values_mut().flatten().filter(|(k, _)| **k == *key).map(|(_, e)| e);
+            for e in entries.chain(by_initials).filter(|e| e.text == text) {
+                e.weight = weight;
             }
         }
-
-        out
+        self.sort_entries();
+        self.reverse = OnceLock::new();
+        self.generation += 1;
+        added
     }
 }
@@ -0,0 +1,493 @@
+//! 编译后的二进制词典（`TsvDictionary::write_compiled`/`compile_to`），只读加载的 `MappedDictionary`，
+//! 以及读回为可修改的 `TsvDictionary`（`TsvDictionary::from_compiled`，不再解析 TSV、切分 key）。
+//!
+//! 文件布局（整数均为小端 u32，weight 为 i32）：
+//!
+//! | 段 | 记录 |
+//! |---|---|
+//! | 头 | `MAGIC`、`VERSION`、key 规整选项，随后依次是 keys/entries/initials/initial_entries 的 (偏移, 条数) 与字符串池的 (偏移, 字节数) |
+//! | keys | `(key, first, len)`：按 key 字节序；`first..first + len` 为 entries 下标 |
+//! | entries | `(text, weight, original)`：同 key 内按 weight 降序、text 升序 |
+//! | initials | `(initials, first, len)`：按首字母串字节序；指向 initial_entries |
+//! | initial_entries | `(key, text, weight, original)`：排序同 entries |
+//! | 字符串池 | 长度前缀（u32）的 UTF-8 串，相同的串只存一份 |
+//!
+//! 记录里的字符串字段都是字符串池内的偏移；`original` 为规整前的 key，没有时为 `NONE`。
+//! 同一份词典总是写出同样的字节（便于校验）。查询结果与加载同一份 TSV 的 `TsvDictionary` 一致。
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::Deref,
+    path::Path,
+    sync::{PoisonError, RwLock},
+};
+
+use rime_core::{
+    dictionary::Dictionary,
+    model::{Candidate, CandidateSource, CandidateText},
+};
+
+use crate::{Completion, Entry, KeyNormalization, TsvDictionary, trie::Trie, wildcard_matches};
+
+pub(crate) const MAGIC: &[u8; 8] = b"RIMEDICT";
+/// 格式版本；2 起带有规整前的 key 与 key 规整选项
+const VERSION: u32 = 2;
+/// 头部字节数：magic + 版本 + key 规整选项 + 4 张表各 (偏移, 条数) + 字符串池 (偏移, 字节数)
+const HEADER_LEN: usize = 8 + 4 + 4 + 5 * 8;
+/// 可选字符串字段为空
+const NONE: u32 = u32::MAX;
+
+/// 各表的记录字节数
+const KEY_LEN: usize = 12;
+const ENTRY_LEN: usize = 12;
+const INITIAL_ENTRY_LEN: usize = 16;
+
+impl TsvDictionary {
+    /// 写出编译后的二进制词典到 `path`（见 `write_compiled`）。
+    pub fn compile_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_compiled(&mut w)?;
+        w.flush()
+    }
+
+    /// 读入编译词典（`compile_to` 写出的文件）：直接还原词条与首字母索引，不解析 TSV、不切分 key。
+    ///
+    /// 得到的词典与编译前的相同（含规整前的 key 与 key 规整选项），可继续 `insert`。
+    /// 不是编译词典、版本不符或内容损坏时返回 `io::ErrorKind::InvalidData`。
+    pub fn from_compiled(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        Self::from_compiled_bytes(fs::read(path)?).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+
+    /// 同 `from_compiled`，从内存中的字节读入。
+    pub fn from_compiled_bytes(bytes: Vec<u8>) -> io::Result<Self> {
+        MappedDictionary::from_bytes(bytes)?.to_tsv_dictionary()
+    }
+
+    /// 写出编译后的二进制词典（格式见 `mapped` 模块），供 `MappedDictionary` 或 `from_compiled` 加载。
+    pub fn write_compiled(&self, mut w: impl Write) -> io::Result<()> {
+        let mut pool = Pool::default();
+        let mut keys = Vec::new();
+        let mut entries = Vec::new();
+        let mut n_entries = 0;
+        for (key, list) in &self.map {
+            push_u32(&mut keys, pool.intern(key)?);
+            push_u32(&mut keys, n_entries);
+            push_u32(&mut keys, to_u32(list.len())?);
+            for e in list {
+                push_u32(&mut entries, pool.intern(&e.text)?);
+                entries.extend_from_slice(&e.weight.to_le_bytes());
+                push_u32(&mut entries, pool.intern_opt(e.original.as_deref())?);
+            }
+            n_entries += to_u32(list.len())?;
+        }
+
+        let mut initials = Vec::new();
+        let mut initial_entries = Vec::new();
+        let mut n_initial_entries = 0;
+        for (ini, list) in &self.initials_map {
+            push_u32(&mut initials, pool.intern(ini)?);
+            push_u32(&mut initials, n_initial_entries);
+            push_u32(&mut initials, to_u32(list.len())?);
+            for (key, e) in list {
+                push_u32(&mut initial_entries, pool.intern(key)?);
+                push_u32(&mut initial_entries, pool.intern(&e.text)?);
+                initial_entries.extend_from_slice(&e.weight.to_le_bytes());
+                push_u32(&mut initial_entries, pool.intern_opt(e.original.as_deref())?);
+            }
+            n_initial_entries += to_u32(list.len())?;
+        }
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        push_u32(&mut header, VERSION);
+        push_u32(&mut header, self.keys.to_bits());
+        let mut offset = HEADER_LEN;
+        for (bytes, count) in [(&keys, self.map.len()), (&entries, n_entries as usize), (&initials, self.initials_map.len()), (&initial_entries, n_initial_entries as usize)] {
+            push_u32(&mut header, to_u32(offset)?);
+            push_u32(&mut header, to_u32(count)?);
+            offset += bytes.len();
+        }
+        push_u32(&mut header, to_u32(offset)?);
+        push_u32(&mut header, to_u32(pool.bytes.len())?);
+        to_u32(offset + pool.bytes.len())?;
+
+        for part in [&header, &keys, &entries, &initials, &initial_entries, &pool.bytes] {
+            w.write_all(part)?;
+        }
+        Ok(())
+    }
+}
+
+/// 写出时的字符串池：相同的串只写一次。
+#[derive(Default)]
+struct Pool<'a> {
+    bytes: Vec<u8>,
+    offsets: HashMap<&'a str, u32>,
+}
+
+impl<'a> Pool<'a> {
+    fn intern(&mut self, s: &'a str) -> io::Result<u32> {
+        if let Some(&off) = self.offsets.get(s) {
+            return Ok(off);
+        }
+        let off = to_u32(self.bytes.len())?;
+        push_u32(&mut self.bytes, to_u32(s.len())?);
+        self.bytes.extend_from_slice(s.as_bytes());
+        self.offsets.insert(s, off);
+        Ok(off)
+    }
+
+    /// 可选的串：None 时为 `NONE`。
+    fn intern_opt(&mut self, s: Option<&'a str>) -> io::Result<u32> {
+        s.map_or(Ok(NONE), |s| self.intern(s))
+    }
+}
+
+fn push_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn to_u32(n: usize) -> io::Result<u32> {
+    u32::try_from(n).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "词典过大：编译格式的偏移上限为 4 GiB"))
+}
+
+fn corrupt(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("编译词典已损坏：{what}"))
+}
+
+/// 词典文件的字节：映射的文件（feature `mmap`）或读入内存的副本。
+enum Storage {
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Owned(v) => v,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(m) => m,
+        }
+    }
+}
+
+/// 一张定长记录表在文件中的位置。
+#[derive(Debug, Clone, Copy)]
+struct Table {
+    offset: usize,
+    count: usize,
+}
+
+/// 只读的编译词典：查询时直接在文件字节上二分，不在加载时建索引。
+///
+/// 候选文本按字符串池偏移驻留为共享的 `CandidateText`：同一个词第一次被查到时分配一次，之后只增加引用计数。
+pub struct MappedDictionary {
+    data: Storage,
+    keys: Table,
+    entries: Table,
+    initials: Table,
+    initial_entries: Table,
+    /// 字符串池在文件中的起点与字节数
+    pool: (usize, usize),
+    /// 编译时的 key 规整选项
+    keys_normalization: KeyNormalization,
+    /// 字符串池偏移 -> 已驻留的文本
+    interned: RwLock<HashMap<u32, CandidateText>>,
+}
+
+impl MappedDictionary {
+    /// 打开编译词典；开启 feature `mmap` 时映射文件，否则读入内存。
+    ///
+    /// 映射期间文件不得被改写（与一般的 mmap 约定相同）。
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = fs::File::open(path)?;
+            // SAFETY: 只读映射；加载时校验全部偏移，之后只做有界读取。文件在映射期间被改写属于调用方违约。
+            let map = unsafe { memmap2::Mmap::map(&file)? };
+            Self::from_storage(Storage::Mapped(map))
+        }
+        #[cfg(not(feature = "mmap"))]
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// 从内存中的编译词典字节构建。
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<Self> {
+        Self::from_storage(Storage::Owned(bytes))
+    }
+
+    fn from_storage(data: Storage) -> io::Result<Self> {
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "不是编译词典（文件头不符）"));
+        }
+        let version = read_u32(&data, 8);
+        if version != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("编译词典版本 {version} 不受支持（需要 {VERSION}）")));
+        }
+        let keys_normalization = KeyNormalization::from_bits(read_u32(&data, 12)).ok_or_else(|| corrupt("未知的 key 规整选项"))?;
+        let field = |i: usize| read_u32(&data, 16 + i * 4) as usize;
+        let table = |i: usize, record_len: usize| -> io::Result<Table> {
+            let t = Table { offset: field(2 * i), count: field(2 * i + 1) };
+            t.count
+                .checked_mul(record_len)
+                .and_then(|n| n.checked_add(t.offset))
+                .filter(|&end| end <= data.len())
+                .ok_or_else(|| corrupt("数据表越界"))?;
+            Ok(t)
+        };
+        let keys = table(0, KEY_LEN)?;
+        let entries = table(1, ENTRY_LEN)?;
+        let initials = table(2, KEY_LEN)?;
+        let initial_entries = table(3, INITIAL_ENTRY_LEN)?;
+        let pool = (field(8), field(9));
+        if pool.0.checked_add(pool.1).is_none_or(|end| end > data.len()) {
+            return Err(corrupt("字符串池越界"));
+        }
+
+        let dict = Self {
+            data,
+            keys,
+            entries,
+            initials,
+            initial_entries,
+            pool,
+            keys_normalization,
+            interned: RwLock::new(HashMap::new()),
+        };
+        dict.validate()?;
+        Ok(dict)
+    }
+
+    /// 检查所有记录引用的字符串与下标区间，之后的查询不再做校验。
+    fn validate(&self) -> io::Result<()> {
+        let pool = &self.data[self.pool.0..self.pool.0 + self.pool.1];
+        let check_str = |off: u32| -> io::Result<()> {
+            let start = off as usize;
+            let body = start.checked_add(4).filter(|&e| e <= pool.len()).ok_or_else(|| corrupt("字符串偏移越界"))?;
+            let end = body.checked_add(read_u32(pool, start) as usize).filter(|&e| e <= pool.len()).ok_or_else(|| corrupt("字符串长度越界"))?;
+            std::str::from_utf8(&pool[body..end]).map(drop).map_err(|_| corrupt("字符串不是合法 UTF-8"))
+        };
+        for (index, target) in [(self.keys, self.entries), (self.initials, self.initial_entries)] {
+            for i in 0..index.count {
+                let rec = index.offset + i * KEY_LEN;
+                check_str(read_u32(&self.data, rec))?;
+                let (first, len) = (read_u32(&self.data, rec + 4) as usize, read_u32(&self.data, rec + 8) as usize);
+                if first.checked_add(len).is_none_or(|end| end > target.count) {
+                    return Err(corrupt("词条区间越界"));
+                }
+            }
+        }
+        let check_opt = |off: u32| if off == NONE { Ok(()) } else { check_str(off) };
+        for i in 0..self.entries.count {
+            let rec = self.entries.offset + i * ENTRY_LEN;
+            check_str(read_u32(&self.data, rec))?;
+            check_opt(read_u32(&self.data, rec + 8))?;
+        }
+        for i in 0..self.initial_entries.count {
+            let rec = self.initial_entries.offset + i * INITIAL_ENTRY_LEN;
+            check_str(read_u32(&self.data, rec))?;
+            check_str(read_u32(&self.data, rec + 4))?;
+            check_opt(read_u32(&self.data, rec + 12))?;
+        }
+        Ok(())
+    }
+
+    /// 字符串池中 `off` 处的串（不含长度前缀）。
+    fn bytes_at(&self, off: u32) -> &[u8] {
+        let start = self.pool.0 + off as usize;
+        let len = read_u32(&self.data, start) as usize;
+        &self.data[start + 4..start + 4 + len]
+    }
+
+    /// 驻留 `off` 处的串：每个偏移只分配一次。
+    fn intern(&self, off: u32) -> CandidateText {
+        if let Some(text) = self.interned.read().unwrap_or_else(PoisonError::into_inner).get(&off) {
+            return text.clone();
+        }
+        // 加载时已校验为合法 UTF-8，这里不会发生替换
+        let text = CandidateText::from(&*String::from_utf8_lossy(self.bytes_at(off)));
+        self.interned.write().unwrap_or_else(PoisonError::into_inner).entry(off).or_insert(text).clone()
+    }
+
+    /// 索引表（keys 或 initials）第 `i` 条：(串偏移, 词条区间)。
+    fn index_record(&self, table: Table, i: usize) -> (u32, usize, usize) {
+        let rec = table.offset + i * KEY_LEN;
+        (read_u32(&self.data, rec), read_u32(&self.data, rec + 4) as usize, read_u32(&self.data, rec + 8) as usize)
+    }
+
+    /// 索引表中第一条串不小于 `target`（各段拼接，不分配）的下标。
+    fn lower_bound(&self, table: Table, target: &[String]) -> usize {
+        let (mut lo, mut hi) = (0, table.count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (off, ..) = self.index_record(table, mid);
+            if cmp_concat(self.bytes_at(off), target) == Ordering::Less {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// 索引表中与 `target` 相等的记录的词条区间。
+    fn find(&self, table: Table, target: &[String]) -> Option<(usize, usize)> {
+        let i = self.lower_bound(table, target);
+        (i < table.count)
+            .then(|| self.index_record(table, i))
+            .filter(|&(off, ..)| cmp_concat(self.bytes_at(off), target) == Ordering::Equal)
+            .map(|(_, first, len)| (first, len))
+    }
+
+    /// `off` 为 `NONE` 时为 None，否则同 `intern`。
+    fn intern_opt(&self, off: u32) -> Option<CandidateText> {
+        (off != NONE).then(|| self.intern(off))
+    }
+
+    /// 同 `TsvDictionary`：词条有规整前的 key 时以它代替 `comment`。
+    fn entry_candidate(&self, i: usize, source: CandidateSource, comment: Option<CandidateText>, start: usize, end: usize) -> Candidate {
+        let rec = self.entries.offset + i * ENTRY_LEN;
+        Candidate {
+            text: self.intern(read_u32(&self.data, rec)),
+            comment: self.intern_opt(read_u32(&self.data, rec + 8)).or(comment),
+            weight: read_u32(&self.data, rec + 4) as i32,
+            score: None,
+            segment_start: start,
+            segment_end: end,
+            source,
+            rank: 0,
+            parts: Vec::new(),
+            meta: None,
+        }
+    }
+    /// 还原为 `TsvDictionary`：记录已按 key 排好序、同 key 内已按 weight 排好，逐条放回即可。
+    ///
+    /// 与解析 TSV 相同，每个词条的串各自分配（按偏移去重的查表比分配更慢）；keys 不是严格升序时视为损坏。
+    fn to_tsv_dictionary(&self) -> io::Result<TsvDictionary> {
+        let text = |off: u32| CandidateText::from(&*String::from_utf8_lossy(self.bytes_at(off)));
+        let entry = |text_off: u32, weight: u32, original: u32| Entry { text: text(text_off), weight: weight as i32, original: (original != NONE).then(|| text(original)) };
+        let mut entries: Vec<(Box<str>, Vec<Entry>)> = Vec::with_capacity(self.keys.count);
+        for k in 0..self.keys.count {
+            let (off, first, len) = self.index_record(self.keys, k);
+            let key = self.bytes_at(off);
+            if entries.last().is_some_and(|(prev, _)| prev.as_bytes() >= key) {
+                return Err(corrupt("keys 未按字节序排列"));
+            }
+            let list = (first..first + len)
+                .map(|i| {
+                    let rec = self.entries.offset + i * ENTRY_LEN;
+                    entry(read_u32(&self.data, rec), read_u32(&self.data, rec + 4), read_u32(&self.data, rec + 8))
+                })
+                .collect();
+            entries.push((String::from_utf8_lossy(key).into(), list));
+        }
+        let mut dict = TsvDictionary::empty();
+        dict.keys = self.keys_normalization;
+        dict.map = Trie::from_sorted(entries);
+        for k in 0..self.initials.count {
+            let (off, first, len) = self.index_record(self.initials, k);
+            let list = (first..first + len)
+                .map(|i| {
+                    let rec = self.initial_entries.offset + i * INITIAL_ENTRY_LEN;
+                    (text(read_u32(&self.data, rec)), entry(read_u32(&self.data, rec + 4), read_u32(&self.data, rec + 8), read_u32(&self.data, rec + 12)))
+                })
+                .collect();
+            dict.initials_map.insert(String::from_utf8_lossy(self.bytes_at(off)).into_owned(), list);
+        }
+        Ok(dict)
+    }
+}
+
+impl Dictionary for MappedDictionary {
+    fn lookup_span(&self, segments: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate> {
+        self.lookup_iter(segments, start, end).take(limit.max(1)).collect()
+    }
+
+    /// 与 `TsvDictionary` 相同的顺序：精确匹配 → 整段输入的前缀补全。
+    fn lookup_iter<'a>(&'a self, segments: &'a [String], start: usize, end: usize) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        if start >= end || end > segments.len() {
+            return Box::new(std::iter::empty());
+        }
+        let span = &segments[start..end];
+        let span_len: usize = span.iter().map(String::len).sum();
+        if span_len == 0 {
+            return Box::new(std::iter::empty());
+        }
+        let whole = start == 0 && end == segments.len();
+
+        let (first, len) = self.find(self.keys, span).unwrap_or((0, 0));
+        let exact = (first..first + len).map(move |i| self.entry_candidate(i, CandidateSource::Exact, None, start, end));
+        // 仅对“整段输入”提供前缀补全：从 lower_bound 起跳过相等的 key，取到前缀不再匹配为止
+        let prefix = whole
+            .then(|| {
+                let completion = Completion::new(span);
+                (self.lower_bound(self.keys, span)..self.keys.count)
+                    .map(move |k| self.index_record(self.keys, k))
+                    .take_while(move |&(off, ..)| {
+                        let key = self.bytes_at(off);
+                        key.len() >= span_len && cmp_concat(&key[..span_len], span) == Ordering::Equal
+                    })
+                    .filter(move |&(off, ..)| self.bytes_at(off).len() > span_len && str::from_utf8(self.bytes_at(off)).is_ok_and(|key| completion.accepts(key)))
+            })
+            .into_iter()
+            .flatten()
+            .flat_map(move |(off, first, len)| {
+                let comment = self.intern(off);
+                (first..first + len).map(move |i| self.entry_candidate(i, CandidateSource::Prefix, Some(comment.clone()), start, end))
+            });
+        Box::new(exact.chain(prefix))
+    }
+
+    fn lookup_initials<'a>(&'a self, initials: &'a [String]) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        let end = initials.len();
+        let (first, len) = self.find(self.initials, initials).unwrap_or((0, 0));
+        Box::new((first..first + len).map(move |i| {
+            let rec = self.initial_entries.offset + i * INITIAL_ENTRY_LEN;
+            Candidate {
+                text: self.intern(read_u32(&self.data, rec + 4)),
+                comment: self.intern_opt(read_u32(&self.data, rec + 12)).or_else(|| Some(self.intern(read_u32(&self.data, rec)))),
+                weight: read_u32(&self.data, rec + 8) as i32,
+                score: None,
+                segment_start: 0,
+                segment_end: end,
+                source: CandidateSource::Initials,
+                rank: 0,
+                parts: Vec::new(),
+                meta: None,
+            }
+        }))
+    }
+
+    /// 与 `TsvDictionary` 相同：从第一个通配符之前的前缀起按 key 顺序扫描。
+    fn lookup_wildcard<'a>(&'a self, code: &'a str, wildcard: char) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        let prefix = code.split(wildcard).next().unwrap_or("");
+        let first = self.lower_bound(self.keys, &[prefix.to_string()]);
+        Box::new(
+            (first..self.keys.count)
+                .map(move |k| self.index_record(self.keys, k))
+                .take_while(move |&(off, ..)| self.bytes_at(off).starts_with(prefix.as_bytes()))
+                .filter(move |&(off, ..)| str::from_utf8(self.bytes_at(off)).is_ok_and(|key| wildcard_matches(key, code, wildcard)))
+                .flat_map(move |(off, first, len)| {
+                    let comment = self.intern(off);
+                    (first..first + len).map(move |i| self.entry_candidate(i, CandidateSource::Fuzzy, Some(comment.clone()), 0, 1))
+                }),
+        )
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    let mut b = [0; 4];
+    b.copy_from_slice(&data[pos..pos + 4]);
+    u32::from_le_bytes(b)
+}
+
+/// 按字节序比较 `bytes` 与各段拼接后的串（不分配）。
+fn cmp_concat(bytes: &[u8], segments: &[String]) -> Ordering {
+    bytes.iter().copied().cmp(segments.iter().flat_map(|s| s.bytes()))
+}
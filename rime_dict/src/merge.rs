@@ -0,0 +1,77 @@
+//! 合并两份词典（`TsvDictionary::merge`）：同一 `(key, text)` 的权重按 `MergePolicy` 混合，其余词条取并集。
+
+use std::collections::BTreeMap;
+
+use rime_core::model::CandidateText;
+
+use crate::{Entry, TsvDictionary};
+
+/// 同一词条在两份词典中的权重如何合并。
+///
+/// 只在一份词典中出现的词条，另一份的权重按 0 计（`Max` 则原样保留）。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergePolicy {
+    /// 取较大者
+    Max,
+    /// 相加（饱和）
+    Sum,
+    /// `self_factor * a + other_factor * b`，四舍五入
+    Weighted { self_factor: f64, other_factor: f64 },
+}
+
+impl MergePolicy {
+    /// 合并权重；`None` 表示该词典中没有这个词条。
+    fn blend(self, a: Option<i32>, b: Option<i32>) -> i32 {
+        match self {
+            Self::Max => a.max(b).unwrap_or(0),
+            Self::Sum => a.unwrap_or(0).saturating_add(b.unwrap_or(0)),
+            // `as` 从 f64 转 i32 时饱和
+            Self::Weighted { self_factor, other_factor } => (self_factor * f64::from(a.unwrap_or(0)) + other_factor * f64::from(b.unwrap_or(0))).round() as i32,
+        }
+    }
+}
+
+/// 同一词条在 (self, other) 中的权重。
+type Pair = (Option<i32>, Option<i32>);
+
+impl TsvDictionary {
+    /// 合并 `self` 与 `other`，返回新词典（两者不变）。
+    ///
+    /// 词条按 `(key, text)` 对应，权重按 `policy` 合并；首字母索引沿用两份词典已有的切分，最后统一重建。
+    /// 规整前的 key 优先取 `self` 的；结果沿用 `self` 的 key 规整。
+    pub fn merge(&self, other: &TsvDictionary, policy: MergePolicy) -> TsvDictionary {
+        // key -> text -> (两边的权重, 规整前的 key)
+        let mut weights: BTreeMap<&str, BTreeMap<&str, (Pair, Option<&CandidateText>)>> = BTreeMap::new();
+        for (key, entries) in &self.map {
+            let texts = weights.entry(key).or_default();
+            for e in entries {
+                *texts.entry(e.text.as_str()).or_default() = ((Some(e.weight), None), e.original.as_ref());
+            }
+        }
+        for (key, entries) in &other.map {
+            let texts = weights.entry(key).or_default();
+            for e in entries {
+                let (pair, original) = texts.entry(e.text.as_str()).or_default();
+                pair.1 = Some(e.weight);
+                *original = original.or(e.original.as_ref());
+            }
+        }
+        // key -> initials：两份词典建索引时已经切分过，不再重复切分
+        let initials: BTreeMap<&str, &str> = [&self.initials_map, &other.initials_map]
+            .into_iter()
+            .flat_map(|index| index.iter().flat_map(|(ini, items)| items.iter().map(move |(key, _)| (key.as_str(), ini.as_str()))))
+            .collect();
+
+        let mut out = TsvDictionary::empty();
+        out.keys = self.keys;
+        for (key, texts) in weights {
+            let list: Vec<Entry> = texts.into_iter().map(|(text, ((a, b), original))| Entry { text: text.into(), weight: policy.blend(a, b), original: original.cloned() }).collect();
+            if let Some(ini) = initials.get(key) {
+                out.initials_map.entry(ini.to_string()).or_default().extend(list.iter().map(|e| (key.into(), e.clone())));
+            }
+            out.map.insert(key, list);
+        }
+        out.sort_entries();
+        out
+    }
+}
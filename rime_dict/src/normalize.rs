@@ -0,0 +1,98 @@
+//! `normalize`：词典 key 的规整（不同来源的拼音写法不一：`ü`/`v`、带声调数字、大写、以空格或 `'` 分隔音节）。
+//!
+//! 加载时把 key 规整为切分器产出的形式，查询才能命中；内置的全拼/双拼/注音切分器产出
+//! 无分隔的小写拼音、ü 写作 `v`，即 `KeyNormalization::pinyin()`。
+
+use std::borrow::Cow;
+
+/// `ü` 的写法。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Umlaut {
+    /// 不转换
+    #[default]
+    Keep,
+    /// `ü` -> `v`（内置切分器的写法）
+    ToV,
+    /// `v` -> `ü`（切分器产出 `ü` 时）
+    ToU,
+}
+
+/// key 规整选项；各项依次作用：去分隔 → 去声调数字 → 小写 → `ü`/`v`。默认不做任何规整。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyNormalization {
+    /// 大写转小写（`Ü` 转为 `ü`）
+    pub lowercase: bool,
+    /// 去掉声调数字（`ni3hao3` -> `nihao`）
+    pub strip_tones: bool,
+    /// `ü` 与 `v` 的转换
+    pub umlaut: Umlaut,
+    /// 去掉空白与 `'`，音节直接相连（`ni hao`、`xi'an` -> `nihao`、`xian`）
+    pub strip_separators: bool,
+}
+
+impl KeyNormalization {
+    /// 与内置拼音切分器一致的规整：全部开启，`ü` 写作 `v`。
+    pub fn pinyin() -> Self {
+        Self {
+            lowercase: true,
+            strip_tones: true,
+            umlaut: Umlaut::ToV,
+            strip_separators: true,
+        }
+    }
+
+    /// 规整 `key`；不需要改动时不分配。
+    pub fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        if !key.chars().any(|c| self.changes(c)) {
+            return Cow::Borrowed(key);
+        }
+        let mut out = String::with_capacity(key.len());
+        for c in key.chars() {
+            if self.strip_separators && (c.is_whitespace() || c == '\'') || self.strip_tones && c.is_ascii_digit() {
+                continue;
+            }
+            let c = if self.lowercase && c == 'Ü' { 'ü' } else if self.lowercase { c.to_ascii_lowercase() } else { c };
+            out.push(match (self.umlaut, c) {
+                (Umlaut::ToV, 'ü') => 'v',
+                (Umlaut::ToU, 'v') => 'ü',
+                _ => c,
+            });
+        }
+        Cow::Owned(out)
+    }
+
+    /// 编译词典头部的写法（见 `mapped`）：低 3 位依次为 lowercase、strip_tones、strip_separators，其上 2 位为 umlaut。
+    pub(crate) fn to_bits(self) -> u32 {
+        let umlaut = match self.umlaut {
+            Umlaut::Keep => 0,
+            Umlaut::ToV => 1,
+            Umlaut::ToU => 2,
+        };
+        u32::from(self.lowercase) | u32::from(self.strip_tones) << 1 | u32::from(self.strip_separators) << 2 | umlaut << 3
+    }
+
+    /// `to_bits` 的逆操作；有未定义的位时为 None。
+    pub(crate) fn from_bits(bits: u32) -> Option<Self> {
+        let umlaut = match bits >> 3 {
+            0 => Umlaut::Keep,
+            1 => Umlaut::ToV,
+            2 => Umlaut::ToU,
+            _ => return None,
+        };
+        Some(Self {
+            lowercase: bits & 1 != 0,
+            strip_tones: bits & 2 != 0,
+            strip_separators: bits & 4 != 0,
+            umlaut,
+        })
+    }
+
+    /// 字符 `c` 是否会被规整改变。
+    fn changes(&self, c: char) -> bool {
+        self.strip_separators && (c.is_whitespace() || c == '\'')
+            || self.strip_tones && c.is_ascii_digit()
+            || self.lowercase && (c.is_ascii_uppercase() || c == 'Ü')
+            || self.umlaut == Umlaut::ToV && c == 'ü'
+            || self.umlaut == Umlaut::ToU && c == 'v'
+    }
+}
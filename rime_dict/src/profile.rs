@@ -0,0 +1,92 @@
+//! 用户配置目录（`Profile`）：只读的系统词典 + 目录下可写的用户词典，组成 `DictionaryStack`。
+//!
+//! 只有 `open`/`save` 读写文件；查询与 `insert` 都在内存中进行，`save` 时才落盘。
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rime_core::{dictionary::Dictionary, engine::Analyzer};
+
+use crate::{DEFAULT_ANALYZER, DictionaryStack, TsvDictionary};
+
+/// 用户目录下用户词典的文件名。
+pub const USER_DICT_FILE: &str = "user.tsv";
+
+/// 配置涉及的文件。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfilePaths {
+    /// 系统词典（只读，按优先级排列）
+    pub system: Vec<PathBuf>,
+    /// 用户目录
+    pub user_dir: PathBuf,
+    /// 用户词典（`user_dir` 下的 `USER_DICT_FILE`）
+    pub user_dict: PathBuf,
+}
+
+/// 一个用户的词典配置：记录文件位置与用户层上次保存时的版本。
+///
+/// `open` 返回的 `DictionaryStack` 交给引擎使用（`insert` 写入用户层），需要落盘时把它传回 `save`。
+#[derive(Debug, Clone)]
+pub struct Profile {
+    paths: ProfilePaths,
+    /// 用户层上次读入/保存时的 `generation`
+    saved: u64,
+}
+
+impl Profile {
+    /// 加载系统词典与 `user_dir` 下的用户词典；目录或用户词典不存在时创建（首次运行）。
+    pub fn open(system_dict_path: impl AsRef<Path>, user_dir: impl AsRef<Path>) -> io::Result<(Self, DictionaryStack)> {
+        Self::open_with(&[system_dict_path], user_dir, &*DEFAULT_ANALYZER)
+    }
+
+    /// 同 `open`，可有多个系统词典（排在前面的优先），并用 `analyzer` 切分 key。
+    pub fn open_with<P: AsRef<Path>>(system_dict_paths: &[P], user_dir: impl AsRef<Path>, analyzer: &dyn Analyzer) -> io::Result<(Self, DictionaryStack)> {
+        let user_dir = user_dir.as_ref().to_path_buf();
+        let user_dict = user_dir.join(USER_DICT_FILE);
+        let system = TsvDictionary::from_paths_with(system_dict_paths, analyzer)?;
+        fs::create_dir_all(&user_dir)?;
+        if !user_dict.exists() {
+            File::create(&user_dict)?;
+        }
+        let user = TsvDictionary::from_path_with(&user_dict, analyzer).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", user_dict.display())))?;
+        let profile = Self {
+            paths: ProfilePaths {
+                system: system_dict_paths.iter().map(|p| p.as_ref().to_path_buf()).collect(),
+                user_dir,
+                user_dict,
+            },
+            saved: user.generation(),
+        };
+        Ok((profile, DictionaryStack::new(user).with_layer(Arc::new(system))))
+    }
+
+    /// 各文件的位置。
+    pub fn paths(&self) -> &ProfilePaths {
+        &self.paths
+    }
+
+    /// `dict` 的用户层自上次读入/保存后是否有修改。
+    pub fn has_changes(&self, dict: &DictionaryStack) -> bool {
+        dict.user().generation() != self.saved
+    }
+
+    /// 把 `dict` 的用户层写回用户词典；没有修改时不写，返回 false。
+    ///
+    /// 先写到同目录的临时文件再改名替换，中途失败不会留下写了一半的用户词典。
+    pub fn save(&mut self, dict: &DictionaryStack) -> io::Result<bool> {
+        if !self.has_changes(dict) {
+            return Ok(false);
+        }
+        let tmp = self.paths.user_dict.with_extension("tsv.tmp");
+        let mut w = BufWriter::new(File::create(&tmp)?);
+        dict.user().write_tsv(&mut w)?;
+        w.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+        fs::rename(&tmp, &self.paths.user_dict)?;
+        self.saved = dict.user().generation();
+        Ok(true)
+    }
+}
@@ -0,0 +1,12 @@
+//! 标点映射表（`rime_core::punct`）的文件读取：TSV，每行 `标点<TAB>上屏文本`。
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use rime_core::punct;
+
+/// 从 `path` 读入标点映射表，格式见 `rime_core::punct::from_tsv_str`。
+pub fn from_path(path: impl AsRef<Path>) -> io::Result<BTreeMap<char, String>> {
+    let path = path.as_ref();
+    let s = fs::read_to_string(path)?;
+    punct::from_tsv_str(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", path.display())))
+}
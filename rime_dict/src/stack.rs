@@ -0,0 +1,99 @@
+//! 分层词典（`DictionaryStack`）：可写的用户层叠在只读的系统层之上。
+
+use std::{collections::HashSet, sync::Arc};
+
+use rime_core::{
+    dictionary::{Dictionary, MutableDictionary},
+    model::{Candidate, CandidateText},
+};
+
+use crate::TsvDictionary;
+
+/// 用户层（`TsvDictionary`，可 `insert`）+ 若干只读层，查询时按层依次产出。
+///
+/// - 上层的结果排在前面；同一范围内与上层同文本、同编码（comment）的词条被上层覆盖
+/// - `insert` 只写用户层；版本号为各层之和
+/// - 只读层经 `Arc` 持有，多个 stack（例如多个用户）可共享同一份系统词典
+#[derive(Clone)]
+pub struct DictionaryStack {
+    user: TsvDictionary,
+    layers: Vec<Arc<dyn Dictionary>>,
+}
+
+impl DictionaryStack {
+    /// 只有用户层的 stack。
+    pub fn new(user: TsvDictionary) -> Self {
+        Self { user, layers: Vec::new() }
+    }
+
+    /// 在最下面加一个只读层（优先级低于已有各层）。
+    pub fn with_layer(mut self, layer: Arc<dyn Dictionary>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// 用户层。
+    pub fn user(&self) -> &TsvDictionary {
+        &self.user
+    }
+
+    /// 用户层（可修改；修改后版本号随之递增）。
+    pub fn user_mut(&mut self) -> &mut TsvDictionary {
+        &mut self.user
+    }
+
+    /// 各层（用户层除外）按优先级排列。
+    pub fn layers(&self) -> &[Arc<dyn Dictionary>] {
+        &self.layers
+    }
+
+    /// 先取用户层的全部结果（用户词典通常很小），再依次接上各只读层中未被覆盖的词条；没有只读层时原样惰性产出。
+    fn stacked<'a>(&'a self, user: Box<dyn Iterator<Item = Candidate> + 'a>, lower: impl Fn(&'a dyn Dictionary) -> Box<dyn Iterator<Item = Candidate> + 'a> + 'a) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        if self.layers.is_empty() {
+            return user;
+        }
+        let user: Vec<Candidate> = user.collect();
+        let shadowed: HashSet<(CandidateText, Option<CandidateText>)> = user.iter().map(|c| (c.text.clone(), c.comment.clone())).collect();
+        let lower = self.layers.iter().flat_map(move |layer| lower(&**layer)).filter(move |c| shadowed.is_empty() || !shadowed.contains(&(c.text.clone(), c.comment.clone())));
+        Box::new(user.into_iter().chain(lower))
+    }
+}
+
+impl Dictionary for DictionaryStack {
+    fn lookup_span(&self, segment: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate> {
+        self.lookup_iter(segment, start, end).take(limit.max(1)).collect()
+    }
+
+    fn lookup_iter<'a>(&'a self, segment: &'a [String], start: usize, end: usize) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        self.stacked(self.user.lookup_iter(segment, start, end), move |layer| layer.lookup_iter(segment, start, end))
+    }
+
+    fn lookup_initials<'a>(&'a self, initials: &'a [String]) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        self.stacked(self.user.lookup_initials(initials), move |layer| layer.lookup_initials(initials))
+    }
+
+    fn lookup_wildcard<'a>(&'a self, code: &'a str, wildcard: char) -> Box<dyn Iterator<Item = Candidate> + 'a> {
+        self.stacked(self.user.lookup_wildcard(code, wildcard), move |layer| layer.lookup_wildcard(code, wildcard))
+    }
+
+    /// 各层的编码依次排列（用户层在前，每层内按权重降序），重复的编码只保留第一次出现。
+    fn reverse_lookup(&self, text: &str) -> Vec<String> {
+        let mut keys = self.user.reverse_lookup(text);
+        for key in self.layers.iter().flat_map(|layer| layer.reverse_lookup(text)) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    fn generation(&self) -> u64 {
+        self.user.generation() + self.layers.iter().map(|layer| layer.generation()).sum::<u64>()
+    }
+}
+
+impl MutableDictionary for DictionaryStack {
+    fn insert(&mut self, key: &str, text: &str, weight: i32) -> bool {
+        self.user.insert(key, text, weight)
+    }
+}
@@ -0,0 +1,25 @@
+//! `test_util`（feature `test-util`）：可复现的测试/基准词典。
+
+use rime_core::test_util::SplitMix64;
+use rime_pinyin::SYLLABARY;
+
+/// 生成 `entries` 行 TSV 词典：key 为 1-4 个随机音节，text 为等长的随机汉字，weight 随机。
+///
+/// 同一 `seed` 总是生成同一份内容。
+pub fn generate_tsv(seed: u64, entries: usize) -> String {
+    let mut rng = SplitMix64::new(seed);
+    let mut out = String::with_capacity(entries * 24);
+    for _ in 0..entries {
+        let len = 1 + rng.below(4) as usize;
+        let mut key = String::new();
+        let mut text = String::new();
+        for _ in 0..len {
+            key.push_str(SYLLABARY[rng.below(SYLLABARY.len() as u64) as usize].0);
+            // CJK 统一汉字常用区
+            text.push(char::from_u32(0x4E00 + rng.below(0x5000) as u32).expect("CJK 区间内"));
+        }
+        let weight = rng.below(1_000_000);
+        out.push_str(&format!("{text}\t{key}\t{weight}\n"));
+    }
+    out
+}
@@ -0,0 +1,236 @@
+//! `TsvDictionary` 的 key 索引：按字节展开、单支路径压缩成一条边的 trie（radix tree）。
+//!
+//! 精确查询与前缀查询都只走 key 本身的长度，前缀下的词条再按深度优先遍历产出，
+//! 与词典大小无关；遍历按 key 的字典序（与 `BTreeMap<String, _>` 相同），各 key 下的词条保持插入后排好的顺序（weight 降序）。
+//! 压缩后结点数不超过 key 数的两倍。
+
+/// 压缩 trie：每个 key 对应一个值，key 本身存在其结点上（遍历时不必拼接）。
+#[derive(Debug, Clone)]
+pub(crate) struct Trie<V> {
+    root: TrieNode<V>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct TrieNode<V> {
+    /// 子结点：(边的首字节, 通向它的边, 子结点)，按首字节升序；同一结点下各边的首字节互不相同。
+    /// 首字节单独存放，二分查找时不必读边
+    children: Vec<(u8, Box<[u8]>, TrieNode<V>)>,
+    /// 以此结点结尾的 key 及其值
+    value: Option<(Box<str>, V)>,
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self { root: TrieNode::default(), len: 0 }
+    }
+}
+
+impl<V> Default for TrieNode<V> {
+    fn default() -> Self {
+        Self { children: Vec::new(), value: None }
+    }
+}
+
+impl<V> TrieNode<V> {
+    /// 首字节为 `b` 的边的下标；没有时为应插入的位置。
+    fn edge(&self, b: u8) -> Result<usize, usize> {
+        self.children.binary_search_by_key(&b, |(first, ..)| *first)
+    }
+
+    /// `key` 所在的结点；`partial` 时 `key` 可止于某条边的中途，取该边通向的结点（其下的 key 都以 `key` 开头）。
+    fn find(&self, key: &[u8], partial: bool) -> Option<&Self> {
+        let mut node = self;
+        let mut rest = key;
+        while let Some(&b) = rest.first() {
+            let (_, label, child) = &node.children[node.edge(b).ok()?];
+            if let Some(tail) = rest.strip_prefix(&**label) {
+                rest = tail;
+            } else if partial && label.starts_with(rest) {
+                rest = &[];
+            } else {
+                return None;
+            }
+            node = child;
+        }
+        Some(node)
+    }
+
+    fn find_mut(&mut self, key: &[u8]) -> Option<&mut Self> {
+        let mut node = self;
+        let mut rest = key;
+        while let Some(&b) = rest.first() {
+            let i = node.edge(b).ok()?;
+            let (_, label, child) = &mut node.children[i];
+            rest = rest.strip_prefix(&**label)?;
+            node = child;
+        }
+        Some(node)
+    }
+
+    /// 由 `slots`（按 key 排序，都以同样的 `depth` 个字节开头）建立其公共前缀处的结点；`slots` 为空时为空结点。
+    fn build(slots: &mut [Option<(Box<str>, V)>], depth: usize) -> Self {
+        let mut node = Self::default();
+        let mut start = 0;
+        if slots.first().is_some_and(|slot| Self::slot_key(slot).len() == depth) {
+            node.value = slots[0].take();
+            start = 1;
+        }
+        while start < slots.len() {
+            let first = Self::slot_key(&slots[start])[depth];
+            let end = start + slots[start..].partition_point(|slot| Self::slot_key(slot)[depth] == first);
+            let (lo, hi) = (Self::slot_key(&slots[start]), Self::slot_key(&slots[end - 1]));
+            let common = lo.iter().zip(hi).take_while(|(a, b)| a == b).count();
+            let label: Box<[u8]> = lo[depth..common].into();
+            node.children.push((first, label, Self::build(&mut slots[start..end], common)));
+            start = end;
+        }
+        node
+    }
+
+    /// `build` 中尚未取走的 key；`build` 只读取未取走的位置。
+    fn slot_key(slot: &Option<(Box<str>, V)>) -> &[u8] {
+        slot.as_ref().map_or(&[], |(key, _)| key.as_bytes())
+    }
+
+    /// `key` 所在的结点，没有时插入：沿途的边与 `key` 只有部分相同时在分歧处把边切成两段。
+    fn find_or_insert(&mut self, key: &[u8]) -> &mut Self {
+        let mut node = self;
+        let mut rest = key;
+        while let Some(&b) = rest.first() {
+            let i = match node.edge(b) {
+                Ok(i) => i,
+                Err(i) => {
+                    node.children.insert(i, (b, rest.into(), Self::default()));
+                    return &mut node.children[i].2;
+                }
+            };
+            let (_, label, child) = &mut node.children[i];
+            let common = label.iter().zip(rest).take_while(|(a, b)| a == b).count();
+            if common < label.len() {
+                let lower = std::mem::take(child);
+                child.children.push((label[common], label[common..].into(), lower));
+                *label = label[..common].into();
+            }
+            rest = &rest[common..];
+            node = child;
+        }
+        node
+    }
+}
+
+impl<V> Trie<V> {
+    /// 由按字典序排列、互不相同的 (key, 值) 建立：逐层按字节分组，边一次建成，不必像逐个 `insert` 那样反复切分。
+    pub(crate) fn from_sorted(entries: Vec<(Box<str>, V)>) -> Self {
+        let len = entries.len();
+        let mut slots: Vec<Option<(Box<str>, V)>> = entries.into_iter().map(Some).collect();
+        Self { root: TrieNode::build(&mut slots, 0), len }
+    }
+
+    /// 不同 key 的个数。
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&V> {
+        self.root.find(key.as_bytes(), false)?.value.as_ref().map(|(_, v)| v)
+    }
+
+    pub(crate) fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.root.find_mut(key.as_bytes())?.value.as_mut().map(|(_, v)| v)
+    }
+
+    /// `key` 的值，没有时先插入 `V::default()`。
+    pub(crate) fn get_or_default(&mut self, key: &str) -> &mut V
+    where
+        V: Default,
+    {
+        let node = self.root.find_or_insert(key.as_bytes());
+        if node.value.is_none() {
+            self.len += 1;
+        }
+        &mut node.value.get_or_insert_with(|| (key.into(), V::default())).1
+    }
+
+    /// 设置 `key` 的值，返回原有的值。
+    pub(crate) fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let node = self.root.find_or_insert(key.as_bytes());
+        let old = node.value.replace((key.into(), value)).map(|(_, v)| v);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// 全部 (key, 值)，按 key 的字典序。
+    pub(crate) fn iter(&self) -> Iter<'_, V> {
+        Iter { stack: vec![&self.root] }
+    }
+
+    /// 以 `prefix` 开头的 (key, 值)（含 `prefix` 本身），按 key 的字典序。
+    pub(crate) fn prefixed(&self, prefix: &str) -> Iter<'_, V> {
+        Iter { stack: self.root.find(prefix.as_bytes(), true).into_iter().collect() }
+    }
+
+    /// 全部值（可变），顺序同 `iter`。
+    pub(crate) fn values_mut(&mut self) -> ValuesMut<'_, V> {
+        ValuesMut { stack: vec![&mut self.root] }
+    }
+
+    /// 取出全部 (key, 值)，按 key 的字典序。
+    pub(crate) fn into_entries(mut self) -> Vec<(Box<str>, V)> {
+        let mut out = Vec::with_capacity(self.len);
+        let mut stack = vec![std::mem::take(&mut self.root)];
+        while let Some(node) = stack.pop() {
+            out.extend(node.value);
+            stack.extend(node.children.into_iter().rev().map(|(.., child)| child));
+        }
+        out
+    }
+}
+
+impl<'a, V> IntoIterator for &'a Trie<V> {
+    type Item = (&'a str, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Iter<'a, V> {
+        self.iter()
+    }
+}
+
+/// 深度优先的先序遍历：结点自身的 key 先于其子结点，子结点按边的首字节升序，即 key 的字典序。
+pub(crate) struct Iter<'a, V> {
+    stack: Vec<&'a TrieNode<V>>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a str, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            self.stack.extend(node.children.iter().rev().map(|(.., child)| child));
+            if let Some((key, value)) = &node.value {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+pub(crate) struct ValuesMut<'a, V> {
+    stack: Vec<&'a mut TrieNode<V>>,
+}
+
+impl<'a, V> Iterator for ValuesMut<'a, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(TrieNode { children, value }) = self.stack.pop() {
+            self.stack.extend(children.iter_mut().rev().map(|(.., child)| child));
+            if let Some((_, value)) = value {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,26 @@
+//! feature `header`：用 cbindgen 生成 `rime.h`，写入 `OUT_DIR`；
+//! 设置了 `RIME_FFI_HEADER_DIR` 时再写一份到该目录（相对路径以 `rime_ffi/` 为基准，例如 `include`）。
+
+fn main() {
+    #[cfg(feature = "header")]
+    generate_header();
+}
+
+#[cfg(feature = "header")]
+fn generate_header() {
+    use std::{env, path::PathBuf};
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=RIME_FFI_HEADER_DIR");
+
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).expect("读取 cbindgen.toml 失败");
+    let bindings = cbindgen::generate_with_config(&crate_dir, config).expect("cbindgen 生成头文件失败");
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("rime.h");
+    bindings.write_to_file(&out);
+    if let Some(dir) = env::var_os("RIME_FFI_HEADER_DIR") {
+        bindings.write_to_file(PathBuf::from(dir).join("rime.h"));
+    }
+}
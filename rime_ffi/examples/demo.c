@@ -0,0 +1,46 @@
+/*
+ * 输入 nihao -> 查看候选 -> 选首选直到上屏。
+ *
+ *   cargo build -p rime_ffi
+ *   cc rime_ffi/examples/demo.c -Irime_ffi/include -Ltarget/debug -lrime_ffi -o demo
+ *   LD_LIBRARY_PATH=target/debug ./demo rime_cli/asset/dict.tsv
+ */
+#include <stdio.h>
+
+#include "rime.h"
+
+int main(int argc, char **argv) {
+    if (rime_api_version() != RIME_API_VERSION) {
+        fprintf(stderr, "rime_ffi 版本 %u 与头文件 %u 不匹配\n", rime_api_version(), RIME_API_VERSION);
+        return 1;
+    }
+    RimeEngine *engine = rime_engine_new(argc > 1 ? argv[1] : "rime_cli/asset/dict.tsv");
+    if (!engine) {
+        fprintf(stderr, "加载词典失败\n");
+        return 1;
+    }
+    RimeSession *session = rime_session_new(engine);
+
+    for (const char *p = "nihao"; *p; p++) {
+        rime_session_process_key(session, (RimeKeyEvent){RIME_KEY_CHAR, (uint32_t)*p});
+    }
+
+    RimeUiState *ui = rime_session_get_ui(session);
+    printf("preedit: %s\n", ui->preedit);
+    for (size_t i = 0; i < ui->candidate_count; i++) {
+        printf("%zu. %s\n", i + 1, ui->candidates[i].text);
+    }
+    rime_ui_free(ui);
+
+    char *commit = NULL;
+    for (int i = 0; i < 8 && !commit; i++) {
+        rime_session_process_key(session, (RimeKeyEvent){RIME_KEY_SELECT, 0});
+        commit = rime_session_take_commit(session);
+    }
+    printf("commit: %s\n", commit ? commit : "(none)");
+    rime_string_free(commit);
+
+    rime_session_free(session);
+    rime_engine_free(engine);
+    return commit ? 0 : 1;
+}
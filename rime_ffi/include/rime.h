@@ -0,0 +1,139 @@
+/* rime.h：rime_rs 的 C 接口（由 cbindgen 生成，勿手改）。所有权约定见 rime_ffi/src/lib.rs。 */
+
+#ifndef RIME_H
+#define RIME_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// C 接口版本；不兼容的改动时递增。
+#define RIME_API_VERSION 1
+
+#define RIME_KEY_CHAR 0
+
+#define RIME_KEY_BACKSPACE 1
+
+#define RIME_KEY_SPACE 2
+
+#define RIME_KEY_ENTER 3
+
+#define RIME_KEY_CLEAR 4
+
+#define RIME_KEY_SELECT 5
+
+#define RIME_KEY_NEXT_PAGE 6
+
+#define RIME_KEY_PREV_PAGE 7
+
+#define RIME_KEY_MOVE_LEFT 8
+
+#define RIME_KEY_MOVE_RIGHT 9
+
+// 引擎句柄：持有已加载的词典，所有会话共享同一份。
+typedef struct RimeEngine RimeEngine;
+
+// 会话句柄。
+typedef struct RimeSession RimeSession;
+
+// 按键事件：`kind` 取 `RIME_KEY_*`；`value` 在 CHAR 时为 Unicode 码点，SELECT 时为页内下标，其余忽略。
+typedef struct RimeKeyEvent {
+  uint32_t kind;
+  uint32_t value;
+} RimeKeyEvent;
+
+// 候选（字符串归所属 `RimeUiState` 所有）。
+typedef struct RimeCandidate {
+  char *text;
+  // 无注释时为 null
+  char *comment;
+  int32_t weight;
+} RimeCandidate;
+
+// UI 快照（扁平结构）；整体用 `rime_ui_free` 释放。
+typedef struct RimeUiState {
+  char *raw_input;
+  char *preedit;
+  char *confirm_text;
+  struct RimeCandidate *candidates;
+  size_t candidate_count;
+  size_t page;
+  bool has_next_page;
+} RimeUiState;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 本库的 C 接口版本（`RIME_API_VERSION`）；前端可据此检查与头文件是否匹配。
+uint32_t rime_api_version(void);
+
+// 从 TSV 词典文件创建引擎；失败（路径为 null、非 UTF-8、读取/解析失败）返回 null。
+//
+// 返回值归调用方所有，用 `rime_engine_free` 释放；须在它创建的所有会话之后释放或之前均可（会话持有引擎的引用计数）。
+//
+// # Safety
+// `dict_path` 须为 null 或有效的 NUL 结尾字符串。
+struct RimeEngine *rime_engine_new(const char *dict_path);
+
+// 释放引擎；null 时忽略。
+//
+// # Safety
+// `engine` 须为 null 或 `rime_engine_new` 的返回值，且只释放一次。
+void rime_engine_free(struct RimeEngine *engine);
+
+// 在引擎上创建会话；`engine` 为 null 时返回 null。
+//
+// 返回值归调用方所有，用 `rime_session_free` 释放。
+//
+// # Safety
+// `engine` 须为 null 或有效的引擎指针。
+struct RimeSession *rime_session_new(const struct RimeEngine *engine);
+
+// 释放会话；null 时忽略。
+//
+// # Safety
+// `session` 须为 null 或 `rime_session_new` 的返回值，且只释放一次。
+void rime_session_free(struct RimeSession *session);
+
+// 处理一个按键；事件非法、`session` 为 null 或内部出错时返回 false。
+//
+// 产生上屏时文本暂存在会话中，用 `rime_session_take_commit` 取走。
+//
+// # Safety
+// `session` 须为 null 或有效的会话指针，且没有被其他线程同时使用。
+bool rime_session_process_key(struct RimeSession *session, struct RimeKeyEvent event);
+
+// 取走尚未读取的上屏文本；没有时返回 null。
+//
+// 返回值归调用方所有，用 `rime_string_free` 释放。
+//
+// # Safety
+// `session` 须为 null 或有效的会话指针。
+char *rime_session_take_commit(struct RimeSession *session);
+
+// 当前 UI 快照；`session` 为 null 或内部出错时返回 null。
+//
+// 返回值归调用方所有，用 `rime_ui_free` 释放。
+//
+// # Safety
+// `session` 须为 null 或有效的会话指针。
+struct RimeUiState *rime_session_get_ui(const struct RimeSession *session);
+
+// 释放 UI 快照及其中的全部字符串；null 时忽略。
+//
+// # Safety
+// `ui` 须为 null 或 `rime_session_get_ui` 的返回值，且只释放一次。
+void rime_ui_free(struct RimeUiState *ui);
+
+// 释放本库返回的字符串；null 时忽略。
+//
+// # Safety
+// `s` 须为 null 或本库返回的字符串，且只释放一次。
+void rime_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RIME_H */
@@ -0,0 +1,312 @@
+//! `rime_ffi`：C ABI 封装，供 C/C++/Objective-C 前端嵌入。
+//!
+//! 约定：
+//! - 字符串一律为 UTF-8、以 NUL 结尾
+//! - `*_new` / `rime_session_get_ui` / `rime_session_take_commit` 返回的指针归调用方所有，
+//!   必须用对应的 `*_free` / `rime_ui_free` / `rime_string_free` 释放
+//! - 传入的指针只在调用期间借用，函数不会保存
+//! - 所有入口都捕获 panic：出错时返回 null / false，不会跨越 FFI 边界 unwind
+//!
+//! ABI 稳定性：
+//! - 引擎与会话对 C 只暴露不透明指针，内部布局可随意调整
+//! - 跨边界的结构体均为 `#[repr(C)]`，只增不改；不兼容的改动须递增 `RIME_API_VERSION`
+//!
+//! 头文件 `include/rime.h` 由 cbindgen 生成：
+//! `RIME_FFI_HEADER_DIR=include cargo build -p rime_ffi --features header`。
+use std::{
+    ffi::{CStr, CString, c_char},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::Arc,
+};
+
+use rime_core::{
+    engine::Engine,
+    key_event::{Action, InputEvent},
+    model::UiState,
+    session::Session,
+};
+use rime_dict::TsvDictionary;
+use rime_pinyin::QuanpinPreeditor;
+
+type SharedEngine = Engine<TsvDictionary, QuanpinPreeditor>;
+
+/// 引擎句柄：持有已加载的词典，所有会话共享同一份。
+pub struct RimeEngine {
+    engine: Arc<SharedEngine>,
+}
+
+/// 会话句柄。
+pub struct RimeSession {
+    session: Session<TsvDictionary, QuanpinPreeditor>,
+    /// 最近一次上屏、尚未被取走的文本
+    commit: Option<String>,
+}
+
+/// C 接口版本；不兼容的改动时递增。
+pub const RIME_API_VERSION: u32 = 1;
+
+pub const RIME_KEY_CHAR: u32 = 0;
+pub const RIME_KEY_BACKSPACE: u32 = 1;
+pub const RIME_KEY_SPACE: u32 = 2;
+pub const RIME_KEY_ENTER: u32 = 3;
+pub const RIME_KEY_CLEAR: u32 = 4;
+pub const RIME_KEY_SELECT: u32 = 5;
+pub const RIME_KEY_NEXT_PAGE: u32 = 6;
+pub const RIME_KEY_PREV_PAGE: u32 = 7;
+pub const RIME_KEY_MOVE_LEFT: u32 = 8;
+pub const RIME_KEY_MOVE_RIGHT: u32 = 9;
+
+/// 按键事件：`kind` 取 `RIME_KEY_*`；`value` 在 CHAR 时为 Unicode 码点，SELECT 时为页内下标，其余忽略。
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RimeKeyEvent {
+    pub kind: u32,
+    pub value: u32,
+}
+
+/// 候选（字符串归所属 `RimeUiState` 所有）。
+#[repr(C)]
+pub struct RimeCandidate {
+    pub text: *mut c_char,
+    /// 无注释时为 null
+    pub comment: *mut c_char,
+    pub weight: i32,
+}
+
+/// UI 快照（扁平结构）；整体用 `rime_ui_free` 释放。
+#[repr(C)]
+pub struct RimeUiState {
+    pub raw_input: *mut c_char,
+    pub preedit: *mut c_char,
+    pub confirm_text: *mut c_char,
+    pub candidates: *mut RimeCandidate,
+    pub candidate_count: usize,
+    pub page: usize,
+    pub has_next_page: bool,
+}
+
+impl RimeKeyEvent {
+    fn to_input_event(self) -> Option<InputEvent> {
+        Some(match self.kind {
+            RIME_KEY_CHAR => InputEvent::Char(char::from_u32(self.value)?),
+            RIME_KEY_BACKSPACE => InputEvent::Backspace,
+            RIME_KEY_SPACE => InputEvent::Space,
+            RIME_KEY_ENTER => InputEvent::Enter,
+            RIME_KEY_CLEAR => InputEvent::Clear,
+            RIME_KEY_SELECT => InputEvent::Select(self.value as usize),
+            RIME_KEY_NEXT_PAGE => InputEvent::NextPage,
+            RIME_KEY_PREV_PAGE => InputEvent::PrevPage,
+            RIME_KEY_MOVE_LEFT => InputEvent::MoveLeft,
+            RIME_KEY_MOVE_RIGHT => InputEvent::MoveRight,
+            _ => return None,
+        })
+    }
+}
+
+/// 本库的 C 接口版本（`RIME_API_VERSION`）；前端可据此检查与头文件是否匹配。
+#[unsafe(no_mangle)]
+pub extern "C" fn rime_api_version() -> u32 {
+    RIME_API_VERSION
+}
+
+/// 执行 `f`，panic 时返回 `fallback`。
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// 字符串中的 NUL 会被丢弃（C 字符串无法表示）。
+fn c_string(s: &str) -> *mut c_char {
+    CString::new(s.replace('\0', "")).expect("已去除 NUL").into_raw()
+}
+
+/// 释放 `c_string` 分配的字符串；null 时忽略。
+unsafe fn drop_c_string(p: *mut c_char) {
+    if !p.is_null() {
+        // SAFETY: p 由 `CString::into_raw` 产生且只释放一次
+        drop(unsafe { CString::from_raw(p) });
+    }
+}
+
+impl RimeUiState {
+    fn from_ui(ui: &UiState) -> Self {
+        let candidates: Box<[RimeCandidate]> = ui
+            .candidate_list
+            .iter()
+            .map(|c| RimeCandidate {
+                text: c_string(&c.text),
+                comment: c.comment.as_deref().map_or(ptr::null_mut(), c_string),
+                weight: c.weight,
+            })
+            .collect();
+        let candidate_count = candidates.len();
+        Self {
+            raw_input: c_string(&ui.raw_input),
+            preedit: c_string(&ui.preedit),
+            confirm_text: c_string(&ui.confirm_text),
+            candidates: Box::into_raw(candidates).cast(),
+            candidate_count,
+            page: ui.page,
+            has_next_page: ui.has_next_page,
+        }
+    }
+}
+
+/// 从 TSV 词典文件创建引擎；失败（路径为 null、非 UTF-8、读取/解析失败）返回 null。
+///
+/// 返回值归调用方所有，用 `rime_engine_free` 释放；须在它创建的所有会话之后释放或之前均可（会话持有引擎的引用计数）。
+///
+/// # Safety
+/// `dict_path` 须为 null 或有效的 NUL 结尾字符串。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_engine_new(dict_path: *const c_char) -> *mut RimeEngine {
+    if dict_path.is_null() {
+        return ptr::null_mut();
+    }
+    // SAFETY: 调用方保证 dict_path 有效
+    let path = unsafe { CStr::from_ptr(dict_path) };
+    guard(ptr::null_mut(), || {
+        let Ok(path) = path.to_str() else {
+            return ptr::null_mut();
+        };
+        match TsvDictionary::from_path(path) {
+            Ok(dict) => Box::into_raw(Box::new(RimeEngine {
+                engine: Arc::new(Engine::new(dict, QuanpinPreeditor::new())),
+            })),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// 释放引擎；null 时忽略。
+///
+/// # Safety
+/// `engine` 须为 null 或 `rime_engine_new` 的返回值，且只释放一次。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_engine_free(engine: *mut RimeEngine) {
+    if !engine.is_null() {
+        // SAFETY: 调用方保证 engine 来自 Box::into_raw 且未释放
+        guard((), || drop(unsafe { Box::from_raw(engine) }));
+    }
+}
+
+/// 在引擎上创建会话；`engine` 为 null 时返回 null。
+///
+/// 返回值归调用方所有，用 `rime_session_free` 释放。
+///
+/// # Safety
+/// `engine` 须为 null 或有效的引擎指针。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_session_new(engine: *const RimeEngine) -> *mut RimeSession {
+    // SAFETY: 调用方保证 engine 有效
+    let Some(engine) = (unsafe { engine.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    guard(ptr::null_mut(), || {
+        let session = Session::new(Arc::clone(&engine.engine));
+        Box::into_raw(Box::new(RimeSession { session, commit: None }))
+    })
+}
+
+/// 释放会话；null 时忽略。
+///
+/// # Safety
+/// `session` 须为 null 或 `rime_session_new` 的返回值，且只释放一次。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_session_free(session: *mut RimeSession) {
+    if !session.is_null() {
+        // SAFETY: 调用方保证 session 来自 Box::into_raw 且未释放
+        guard((), || drop(unsafe { Box::from_raw(session) }));
+    }
+}
+
+/// 处理一个按键；事件非法、`session` 为 null 或内部出错时返回 false。
+///
+/// 产生上屏时文本暂存在会话中，用 `rime_session_take_commit` 取走。
+///
+/// # Safety
+/// `session` 须为 null 或有效的会话指针，且没有被其他线程同时使用。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_session_process_key(session: *mut RimeSession, event: RimeKeyEvent) -> bool {
+    // SAFETY: 调用方保证 session 有效且独占
+    let Some(session) = (unsafe { session.as_mut() }) else {
+        return false;
+    };
+    let Some(ev) = event.to_input_event() else {
+        return false;
+    };
+    guard(false, || {
+        let (_ui, actions) = session.session.handle(ev);
+        for action in actions {
+            if let Action::Commit(text) = action {
+                session.commit.get_or_insert_with(String::new).push_str(&text);
+            }
+        }
+        true
+    })
+}
+
+/// 取走尚未读取的上屏文本；没有时返回 null。
+///
+/// 返回值归调用方所有，用 `rime_string_free` 释放。
+///
+/// # Safety
+/// `session` 须为 null 或有效的会话指针。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_session_take_commit(session: *mut RimeSession) -> *mut c_char {
+    // SAFETY: 调用方保证 session 有效且独占
+    let Some(session) = (unsafe { session.as_mut() }) else {
+        return ptr::null_mut();
+    };
+    guard(ptr::null_mut(), || session.commit.take().map_or(ptr::null_mut(), |s| c_string(&s)))
+}
+
+/// 当前 UI 快照；`session` 为 null 或内部出错时返回 null。
+///
+/// 返回值归调用方所有，用 `rime_ui_free` 释放。
+///
+/// # Safety
+/// `session` 须为 null 或有效的会话指针。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_session_get_ui(session: *const RimeSession) -> *mut RimeUiState {
+    // SAFETY: 调用方保证 session 有效
+    let Some(session) = (unsafe { session.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    guard(ptr::null_mut(), || Box::into_raw(Box::new(RimeUiState::from_ui(&session.session.ui_state()))))
+}
+
+/// 释放 UI 快照及其中的全部字符串；null 时忽略。
+///
+/// # Safety
+/// `ui` 须为 null 或 `rime_session_get_ui` 的返回值，且只释放一次。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_ui_free(ui: *mut RimeUiState) {
+    if ui.is_null() {
+        return;
+    }
+    guard((), || {
+        // SAFETY: ui 来自 Box::into_raw；candidates 来自长度为 candidate_count 的 Box<[_]>
+        unsafe {
+            let ui = Box::from_raw(ui);
+            let candidates = Box::from_raw(ptr::slice_from_raw_parts_mut(ui.candidates, ui.candidate_count));
+            for c in candidates.iter() {
+                drop_c_string(c.text);
+                drop_c_string(c.comment);
+            }
+            drop_c_string(ui.raw_input);
+            drop_c_string(ui.preedit);
+            drop_c_string(ui.confirm_text);
+        }
+    });
+}
+
+/// 释放本库返回的字符串；null 时忽略。
+///
+/// # Safety
+/// `s` 须为 null 或本库返回的字符串，且只释放一次。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rime_string_free(s: *mut c_char) {
+    // SAFETY: 调用方保证 s 来自本库
+    guard((), || unsafe { drop_c_string(s) });
+}
@@ -0,0 +1,12 @@
+//! feature `grpc`：用 tonic-prost-build 从 `proto/rime.proto` 生成代码（protoc 取自 protoc-bin-vendored）。
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/rime.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("找不到内置 protoc");
+        // SAFETY: build 脚本是单线程的
+        unsafe { std::env::set_var("PROTOC", protoc) };
+        tonic_prost_build::compile_protos("proto/rime.proto").expect("生成 gRPC 代码失败");
+    }
+}
@@ -0,0 +1,51 @@
+//! 进程内往返：在随机端口启动服务，用生成的客户端走一遍 Convert 与会话流程。
+//!
+//! `cargo run -p rime_grpc --features grpc --example round_trip`
+use rime_core::{engine::Engine, key_event::InputEvent};
+use rime_dict::TsvDictionary;
+use rime_grpc::{
+    RimeService,
+    pb::{self, rime_client::RimeClient},
+};
+use rime_pinyin::QuanpinPreeditor;
+use tonic::transport::{Server, server::TcpIncoming};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dict = TsvDictionary::from_tsv_str("你好\tnihao\t100\n你\tni\t50\n好\thao\t40\n")?;
+    let service = RimeService::new(Engine::new(dict, QuanpinPreeditor::new()));
+
+    let incoming = TcpIncoming::bind("127.0.0.1:0".parse()?)?;
+    let addr = incoming.local_addr()?;
+    tokio::spawn(Server::builder().add_service(service.into_server()).serve_with_incoming(incoming));
+
+    let mut client = RimeClient::connect(format!("http://{addr}")).await?;
+
+    let convert = client.convert(pb::ConvertRequest { raw_input: "nihao".into(), limit: 3 }).await?.into_inner();
+    let texts: Vec<&str> = convert.candidates.iter().map(|c| c.text.as_str()).collect();
+    println!("Convert(nihao) -> {texts:?}");
+    assert_eq!(texts.first(), Some(&"你好"));
+
+    // 两个会话互不影响
+    let a = client.create_session(pb::CreateSessionRequest {}).await?.into_inner().session;
+    let b = client.create_session(pb::CreateSessionRequest {}).await?.into_inner().session;
+    for ch in "nihao".chars() {
+        client.process_key(pb::ProcessKeyRequest { session: a, event: Some(InputEvent::Char(ch).into()) }).await?;
+    }
+    let ui_b = client.get_ui(pb::SessionRef { session: b }).await?.into_inner().ui.unwrap_or_default();
+    assert!(ui_b.raw_input.is_empty());
+
+    let resp = client.process_key(pb::ProcessKeyRequest { session: a, event: Some(InputEvent::Space.into()) }).await?.into_inner();
+    let commits: Vec<_> = resp.actions.into_iter().filter_map(|a| match a.action? {
+        pb::action::Action::Commit(text) => Some(text),
+        pb::action::Action::Rejected(_) => None,
+    }).collect();
+    println!("会话 {a} 上屏 {commits:?}");
+    assert_eq!(commits, ["你好"]);
+
+    client.destroy_session(pb::SessionRef { session: a }).await?;
+    let err = client.get_ui(pb::SessionRef { session: a }).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    println!("ok");
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! `rime_grpc`：gRPC 服务（tonic/prost），定义见 `proto/rime.proto`。
+//!
+//! - `Convert` 直接走共享引擎的无状态查询，不占用会话锁，可完全并发
+//! - 会话按 id 隔离，各有各的锁：会话表只在查找、增删时短暂加锁，按键处理与组句在 `spawn_blocking` 中只锁所属的会话，
+//!   不同会话可并发处理，也不阻塞异步运行时
+//! - 消息字段名与 serde JSON 协议一致
+//!
+//! 整个 crate 需要 feature `grpc`。
+#![cfg(feature = "grpc")]
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use rime_core::{
+    dictionary::Dictionary,
+    engine::{Analyzer, Engine},
+    key_event::{Action, InputEvent, RejectReason},
+    model::{Candidate, UiState},
+    segmenter::Segmenter,
+    session::Session,
+    session_manager::SessionId,
+};
+use tonic::{Request, Response, Status};
+
+/// 由 `proto/rime.proto` 生成的消息与服务。
+pub mod pb {
+    tonic::include_proto!("rime.v1");
+}
+
+use pb::rime_server::{Rime, RimeServer};
+
+/// `Convert` 的 limit 为 0 时的默认值
+const DEFAULT_LIMIT: usize = 10;
+/// `Convert` 的 limit 上限
+const MAX_LIMIT: usize = 1000;
+
+/// gRPC 服务实现：所有会话共享同一个引擎。
+pub struct RimeService<D, A> {
+    engine: Arc<Engine<D, A>>,
+    sessions: Mutex<Sessions<D, A>>,
+}
+
+/// 存活的会话；每个会话单独加锁（见 `RimeService::session`）。
+struct Sessions<D, A> {
+    by_id: HashMap<SessionId, Arc<Mutex<Session<D, A>>>>,
+    /// 下一个分配的 id（不复用）
+    next_id: SessionId,
+}
+
+impl<D, A> RimeService<D, A>
+where
+    D: Dictionary + Send + Sync + 'static,
+    A: Analyzer + Segmenter + Send + Sync + 'static,
+{
+    pub fn new(engine: impl Into<Arc<Engine<D, A>>>) -> Self {
+        Self {
+            engine: engine.into(),
+            sessions: Mutex::new(Sessions { by_id: HashMap::new(), next_id: 1 }),
+        }
+    }
+
+    /// 包装成可挂到 `tonic::transport::Server` 上的服务。
+    pub fn into_server(self) -> RimeServer<Self> {
+        RimeServer::new(self)
+    }
+
+    fn sessions(&self) -> MutexGuard<'_, Sessions<D, A>> {
+        lock(&self.sessions)
+    }
+
+    /// 取出会话（只在查找时持有会话表的锁）。
+    fn session(&self, id: SessionId) -> Result<Arc<Mutex<Session<D, A>>>, Status> {
+        self.sessions().by_id.get(&id).cloned().ok_or_else(|| unknown_session(id))
+    }
+
+    /// 在阻塞线程池中锁住会话执行 `f`：组句可能较慢，不占用异步运行时的线程。
+    async fn with_session<T: Send + 'static>(&self, id: SessionId, f: impl FnOnce(&mut Session<D, A>) -> T + Send + 'static) -> Result<T, Status> {
+        let session = self.session(id)?;
+        tokio::task::spawn_blocking(move || f(&mut lock(&session))).await.map_err(|e| Status::internal(format!("会话 {id} 处理失败：{e}")))
+    }
+}
+
+/// 加锁；会话处理 panic 时锁会中毒，其余状态仍可用，继续服务。
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[tonic::async_trait]
+impl<D, A> Rime for RimeService<D, A>
+where
+    D: Dictionary + Send + Sync + 'static,
+    A: Analyzer + Segmenter + Send + Sync + 'static,
+{
+    async fn convert(&self, request: Request<pb::ConvertRequest>) -> Result<Response<pb::ConvertResponse>, Status> {
+        let req = request.into_inner();
+        let limit = match req.limit as usize {
+            0 => DEFAULT_LIMIT,
+            n => n.min(MAX_LIMIT),
+        };
+        let candidates = self.engine.query(&req.raw_input, limit).iter().map(pb::Candidate::from).collect();
+        Ok(Response::new(pb::ConvertResponse { candidates }))
+    }
+
+    async fn create_session(&self, _request: Request<pb::CreateSessionRequest>) -> Result<Response<pb::SessionRef>, Status> {
+        let session = Session::new(Arc::clone(&self.engine));
+        let mut sessions = self.sessions();
+        let id = sessions.next_id;
+        sessions.next_id += 1;
+        sessions.by_id.insert(id, Arc::new(Mutex::new(session)));
+        Ok(Response::new(pb::SessionRef { session: id }))
+    }
+
+    async fn destroy_session(&self, request: Request<pb::SessionRef>) -> Result<Response<pb::SessionRef>, Status> {
+        let session = request.into_inner().session;
+        if self.sessions().by_id.remove(&session).is_none() {
+            return Err(unknown_session(session));
+        }
+        Ok(Response::new(pb::SessionRef { session }))
+    }
+
+    async fn process_key(&self, request: Request<pb::ProcessKeyRequest>) -> Result<Response<pb::ProcessKeyResponse>, Status> {
+        let req = request.into_inner();
+        let event = req.event.ok_or_else(|| Status::invalid_argument("缺少 event"))?.try_into()?;
+        let (ui, actions) = self.with_session(req.session, move |s| s.handle(event)).await?;
+        Ok(Response::new(pb::ProcessKeyResponse {
+            ui: Some((&ui).into()),
+            actions: actions.iter().map(pb::Action::from).collect(),
+        }))
+    }
+
+    async fn get_ui(&self, request: Request<pb::SessionRef>) -> Result<Response<pb::GetUiResponse>, Status> {
+        let session = request.into_inner().session;
+        let ui = self.with_session(session, |s| s.ui_state()).await?;
+        Ok(Response::new(pb::GetUiResponse { ui: Some((&ui).into()) }))
+    }
+}
+
+fn unknown_session(id: SessionId) -> Status {
+    Status::not_found(format!("会话 {id} 不存在"))
+}
+
+impl From<&Candidate> for pb::Candidate {
+    fn from(c: &Candidate) -> Self {
+        Self {
+            text: c.text.to_string(),
+            comment: c.comment.as_ref().map(ToString::to_string),
+            weight: c.weight,
+            segment_start: c.segment_start as u64,
+            segment_end: c.segment_end as u64,
+        }
+    }
+}
+
+impl From<&UiState> for pb::UiState {
+    fn from(ui: &UiState) -> Self {
+        Self {
+            raw_input: ui.raw_input.clone(),
+            preedit: ui.preedit.clone(),
+            segment: ui.segment.to_vec(),
+            caret: ui.caret as u64,
+            confirm: ui.confirm as u64,
+            confirm_text: ui.confirm_text.clone(),
+            candidate_list: ui.candidate_list.iter().map(pb::Candidate::from).collect(),
+            page: ui.page as u64,
+            has_next_page: ui.has_next_page,
+            truncated: ui.truncated,
+            predicting: ui.predicting,
+            injected: ui.injected,
+            traditional: ui.traditional,
+            english: ui.english,
+            composition: ui.composition.clone(),
+            composition_caret: ui.composition_caret as u64,
+            schema: ui.schema.clone(),
+            diagnostics: ui.diagnostics.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl From<&Action> for pb::Action {
+    fn from(action: &Action) -> Self {
+        let action = match action {
+            Action::Commit(text) => pb::action::Action::Commit(text.clone()),
+            Action::Rejected(reason) => pb::action::Action::Rejected(pb::RejectReason::from(*reason).into()),
+        };
+        Self { action: Some(action) }
+    }
+}
+
+impl From<RejectReason> for pb::RejectReason {
+    fn from(reason: RejectReason) -> Self {
+        match reason {
+            RejectReason::NotComposing => Self::NotComposing,
+            RejectReason::NoSuchCandidate => Self::NoSuchCandidate,
+            RejectReason::SpanNotAtConfirm => Self::SpanNotAtConfirm,
+            RejectReason::NotForgettable => Self::NotForgettable,
+        }
+    }
+}
+
+impl From<InputEvent> for pb::InputEvent {
+    fn from(ev: InputEvent) -> Self {
+        use pb::input_event::Event;
+
+        let event = match ev {
+            InputEvent::Char(c) => Event::Char(c.to_string()),
+            InputEvent::Backspace => Event::Backspace(pb::Empty {}),
+            InputEvent::Space => Event::Space(pb::Empty {}),
+            InputEvent::Enter => Event::Enter(pb::Empty {}),
+            InputEvent::Clear => Event::Clear(pb::Empty {}),
+            InputEvent::Select(i) => Event::Select(i as u64),
+            InputEvent::DeleteCandidate(i) => Event::DeleteCandidate(i as u64),
+            InputEvent::NextPage => Event::NextPage(pb::Empty {}),
+            InputEvent::PrevPage => Event::PrevPage(pb::Empty {}),
+            InputEvent::ToggleTraditional => Event::ToggleTraditional(pb::Empty {}),
+            InputEvent::NextSchema => Event::NextSchema(pb::Empty {}),
+            InputEvent::ToggleMode => Event::ToggleMode(pb::Empty {}),
+            InputEvent::CommitLiteral => Event::CommitLiteral(pb::Empty {}),
+            InputEvent::MoveLeft => Event::MoveLeft(pb::Empty {}),
+            InputEvent::MoveRight => Event::MoveRight(pb::Empty {}),
+            // 服务端会话没有“退出”语义
+            InputEvent::Exit => Event::Clear(pb::Empty {}),
+        };
+        Self { event: Some(event) }
+    }
+}
+
+impl TryFrom<pb::InputEvent> for InputEvent {
+    type Error = Status;
+
+    fn try_from(ev: pb::InputEvent) -> Result<Self, Status> {
+        use pb::input_event::Event;
+
+        Ok(match ev.event.ok_or_else(|| Status::invalid_argument("event 为空"))? {
+            Event::Char(s) => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => InputEvent::Char(c),
+                    _ => return Err(Status::invalid_argument(format!("char 须为单个字符，实际为 `{s}`"))),
+                }
+            }
+            Event::Backspace(_) => InputEvent::Backspace,
+            Event::Space(_) => InputEvent::Space,
+            Event::Enter(_) => InputEvent::Enter,
+            Event::Clear(_) => InputEvent::Clear,
+            Event::Select(i) => InputEvent::Select(usize::try_from(i).map_err(|_| Status::invalid_argument("select 超出范围"))?),
+            Event::DeleteCandidate(i) => InputEvent::DeleteCandidate(usize::try_from(i).map_err(|_| Status::invalid_argument("delete_candidate 超出范围"))?),
+            Event::NextPage(_) => InputEvent::NextPage,
+            Event::PrevPage(_) => InputEvent::PrevPage,
+            Event::ToggleTraditional(_) => InputEvent::ToggleTraditional,
+            Event::NextSchema(_) => InputEvent::NextSchema,
+            Event::ToggleMode(_) => InputEvent::ToggleMode,
+            Event::CommitLiteral(_) => InputEvent::CommitLiteral,
+            Event::MoveLeft(_) => InputEvent::MoveLeft,
+            Event::MoveRight(_) => InputEvent::MoveRight,
+        })
+    }
+}
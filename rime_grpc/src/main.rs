@@ -0,0 +1,46 @@
+use std::{env, io, path::PathBuf};
+
+use rime_core::engine::Engine;
+use rime_dict::TsvDictionary;
+use rime_grpc::RimeService;
+use rime_pinyin::{FuzzyRules, QuanpinPreeditor};
+use tonic::transport::Server;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut addr = String::from("127.0.0.1:50051");
+    let mut dict_paths = Vec::new();
+    let mut fuzzy = FuzzyRules::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        let mut value = || args.next().ok_or_else(|| invalid_arg(format!("{a} 缺少参数值")));
+        match a.as_str() {
+            "--addr" => addr = value()?,
+            "--dict" => dict_paths.push(PathBuf::from(value()?)),
+            "--fuzzy" => fuzzy = FuzzyRules::parse(&value()?).map_err(|e| invalid_arg(e.to_string()))?,
+            "--help" | "-h" => print_help(),
+            _ => return Err(invalid_arg(format!("未知参数 `{a}`")).into()),
+        }
+    }
+    if dict_paths.is_empty() {
+        dict_paths.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../rime_cli/asset/dict.tsv"));
+    }
+    let addr = addr.parse().map_err(|e| invalid_arg(format!("无效的地址 `{addr}`：{e}")))?;
+
+    let analyzer = QuanpinPreeditor::new().with_fuzzy(fuzzy);
+    let dict = TsvDictionary::from_paths_with(&dict_paths, &analyzer)?;
+    let service = RimeService::new(Engine::new(dict, analyzer));
+    eprintln!("rime_grpc 监听 {addr}");
+    Server::builder().add_service(service.into_server()).serve(addr).await?;
+    Ok(())
+}
+
+fn invalid_arg(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn print_help() -> ! {
+    println!("用法：rime_grpc [--addr 127.0.0.1:50051] [--dict <path>]... [--fuzzy a=b,...]\n服务定义见 rime_grpc/proto/rime.proto");
+    std::process::exit(0);
+}
@@ -0,0 +1,153 @@
+//! 模糊音规则（例如 z=zh、n=l、an=ang）。
+//!
+//! 规则是对称的：`z=zh` 表示输入 `zong` 时也按 `zhong` 查词，反之亦然。
+//! 一条规则的两边必须同为声母或同为韵母。
+
+use std::fmt;
+
+/// 声母（按长度降序，便于最长匹配）。
+const INITIALS: &[&str] = &["zh", "ch", "sh", "b", "p", "m", "f", "d", "t", "n", "l", "g", "k", "h", "j", "q", "x", "r", "z", "c", "s", "y", "w"];
+
+/// 韵母。
+const FINALS: &[&str] = &[
+    "a", "o", "e", "i", "u", "v", "ai", "ei", "ao", "ou", "an", "en", "ang", "eng", "ong", "er", "ia", "ie", "iao", "iu", "ian", "in", "iang", "ing", "iong", "ua", "uo", "uai", "ui", "uan", "un", "uang", "ue", "ve",
+];
+
+/// 默认模糊音集合（`--fuzzy` 不带参数时使用）。
+pub const DEFAULT_FUZZY_RULES: &str = "z=zh,c=ch,s=sh,n=l,an=ang,en=eng,in=ing";
+
+/// 规则片段的类别。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Initial,
+    Final,
+}
+
+fn part_of(fragment: &str) -> Option<Part> {
+    if INITIALS.contains(&fragment) {
+        Some(Part::Initial)
+    } else if FINALS.contains(&fragment) {
+        Some(Part::Final)
+    } else {
+        None
+    }
+}
+
+/// 解析规则失败的原因。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyRuleError {
+    /// 出错的规则原文
+    pub rule: String,
+    /// 原因
+    pub reason: String,
+}
+
+impl fmt::Display for FuzzyRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "模糊音规则 `{}` 无效：{}", self.rule, self.reason)
+    }
+}
+
+impl std::error::Error for FuzzyRuleError {}
+
+/// 模糊音规则集合。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzyRules {
+    /// 声母等价对
+    initials: Vec<(String, String)>,
+    /// 韵母等价对
+    finals: Vec<(String, String)>,
+}
+
+impl FuzzyRules {
+    /// 从 `a=b` 对创建规则集；两边须同为声母或同为韵母。
+    pub fn new<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Self, FuzzyRuleError> {
+        let mut rules = Self::default();
+        for (a, b) in pairs {
+            let err = |reason: String| FuzzyRuleError {
+                rule: format!("{a}={b}"),
+                reason,
+            };
+            if a == b {
+                return Err(err("两边相同".to_string()));
+            }
+            let pa = part_of(a).ok_or_else(|| err(format!("`{a}` 不是已知的声母或韵母")))?;
+            let pb = part_of(b).ok_or_else(|| err(format!("`{b}` 不是已知的声母或韵母")))?;
+            if pa != pb {
+                return Err(err(format!("`{a}` 与 `{b}` 须同为声母或同为韵母")));
+            }
+            let pair = (a.to_string(), b.to_string());
+            match pa {
+                Part::Initial => rules.initials.push(pair),
+                Part::Final => rules.finals.push(pair),
+            }
+        }
+        Ok(rules)
+    }
+
+    /// 解析逗号分隔的 `a=b` 列表，例如 `z=zh,n=l,an=ang`。
+    pub fn parse(s: &str) -> Result<Self, FuzzyRuleError> {
+        let mut pairs = Vec::new();
+        for item in s.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let Some((a, b)) = item.split_once('=') else {
+                return Err(FuzzyRuleError {
+                    rule: item.to_string(),
+                    reason: "缺少 `=`".to_string(),
+                });
+            };
+            pairs.push((a.trim(), b.trim()));
+        }
+        Self::new(pairs)
+    }
+
+    /// 默认规则集（见 `DEFAULT_FUZZY_RULES`）。
+    pub fn default_set() -> Self {
+        Self::parse(DEFAULT_FUZZY_RULES).expect("默认模糊音规则合法")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.initials.is_empty() && self.finals.is_empty()
+    }
+
+    /// 音节 `syllable` 的模糊拼写（不含自身，未做音节表校验）。
+    pub fn variants(&self, syllable: &str) -> Vec<String> {
+        let (initial, fin) = split_syllable(syllable);
+        let initials = expand(initial, &self.initials);
+        let finals = expand(fin, &self.finals);
+
+        let mut out = Vec::new();
+        for i in &initials {
+            for f in &finals {
+                let s = format!("{i}{f}");
+                if s != syllable && !out.contains(&s) {
+                    out.push(s);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// 把音节拆成 (声母, 韵母)；零声母音节的声母为空串。
+fn split_syllable(syllable: &str) -> (&str, &str) {
+    for ini in INITIALS {
+        if let Some(rest) = syllable.strip_prefix(ini)
+            && !rest.is_empty()
+        {
+            return (&syllable[..ini.len()], rest);
+        }
+    }
+    ("", syllable)
+}
+
+fn expand<'a>(part: &'a str, pairs: &'a [(String, String)]) -> Vec<&'a str> {
+    let mut out = vec![part];
+    for (a, b) in pairs {
+        if part == a && !out.contains(&b.as_str()) {
+            out.push(b);
+        } else if part == b && !out.contains(&a.as_str()) {
+            out.push(a);
+        }
+    }
+    out
+}
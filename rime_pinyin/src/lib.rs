@@ -1,125 +1,424 @@
 //! 全拼（quanpin）相关：第一版只做“切分 + preedit 展示”。
+//!
+//! 另有双拼（`ShuangpinPreeditor`）、注音（`ZhuyinPreeditor`，均换算为拼音音节）与码表方案（`TableAnalyzer`）的切分器。
 
-use rime_core::engine::{Analysis, Analyzer};
+mod fuzzy;
+mod shuangpin;
+mod table;
+mod zhuyin;
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs, io, mem,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use rime_core::engine::{Analysis, Analyzer, SegmentationKind};
+
+pub use fuzzy::{DEFAULT_FUZZY_RULES, FuzzyRuleError, FuzzyRules};
+pub use shuangpin::{ShuangpinPreeditor, ShuangpinScheme};
+pub use table::TableAnalyzer;
+pub use zhuyin::ZhuyinPreeditor;
 
 include!(concat!(env!("OUT_DIR"), "/syllabary_gen.rs"));
 
+/// `QuanpinPreeditor` 默认可切分输入的最大字节数。
+pub const DEFAULT_MAX_INPUT_LEN: usize = 128;
+
+/// 切分时每个模糊音节（只经模糊规则才成立的拼写）的扣分：大于任何精确切分的频次之和，
+/// 因此只要存在精确切分就不会选模糊切分，多条模糊切分中选模糊音节最少的。
+const FUZZY_SYLLABLE_PENALTY: i64 = 1 << 40;
+
+/// 拼写 `s` 作为一个音节的得分：结构分（优先长音节）加频次，模糊拼写另扣 `FUZZY_SYLLABLE_PENALTY`；不是音节时为 None。
+fn syllable_score(s: &str, syllables: &Syllables, fuzzy: &HashMap<String, i32>) -> Option<i64> {
+    let (freq, penalty) = match (syllables.get(s), fuzzy.get(s)) {
+        (Some(&freq), _) => (freq, 0),
+        (None, Some(&freq)) => (freq, FUZZY_SYLLABLE_PENALTY),
+        (None, None) => return None,
+    };
+    Some((s.len() as i64) * 10_000 + i64::from(freq) - penalty)
+}
+
+/// 音节表：音节 -> 频次；内置表借用 `SYLLABARY`，从文件读入的音节自有。
+type Syllables = HashMap<Cow<'static, str>, i32>;
+
+/// 声调数字（见 `QuanpinPreeditor::tone_digits`）：阴平、阳平、上声、去声、轻声。
+const TONE_DIGITS: [char; 5] = ['1', '2', '3', '4', '5'];
+
+/// 规整 `'`：连续的 `'` 视为一个，首尾的 `'` 去掉。
+fn normalize_separators(input: &str) -> Cow<'_, str> {
+    if input.starts_with('\'') || input.ends_with('\'') || input.contains("''") {
+        Cow::Owned(input.split('\'').filter(|chunk| !chunk.is_empty()).collect::<Vec<_>>().join("'"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// 单个 chunk（`'` 之间的一段）的切分 DP。
+///
+/// `best[j]`/`prev[j]` 只依赖 `text[..j]`：输入追加或删除字符时，公共前缀部分原样保留，只需补算后面的列。
+#[derive(Debug, Clone)]
+struct ChunkDp {
+    text: String,
+    /// `text[..j]` 的最优切分得分；无法切分时为 None
+    best: Vec<Option<i64>>,
+    /// 最优切分的最后一个音节的起点
+    prev: Vec<Option<usize>>,
+}
+
+impl Default for ChunkDp {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            best: vec![Some(0)],
+            prev: vec![None],
+        }
+    }
+}
+
+impl ChunkDp {
+    /// 把 DP 更新到 `chunk`（须只含 a-z）：保留与旧输入的公共前缀，其余列重新计算。
+    ///
+    /// `fuzzy` 为只经模糊规则才成立的拼写（见 `QuanpinPreeditor::with_fuzzy`），每个扣 `FUZZY_SYLLABLE_PENALTY`。
+    fn update(&mut self, chunk: &str, syllables: &Syllables, fuzzy: &HashMap<String, i32>, max_len: usize) {
+        let common = self.text.bytes().zip(chunk.bytes()).take_while(|(a, b)| a == b).count();
+        self.best.truncate(common + 1);
+        self.prev.truncate(common + 1);
+        for j in common + 1..=chunk.len() {
+            let (mut best, mut prev) = (None, None);
+            // 起点从小到大、严格大于才替换：同分时保留起点最靠前的切分
+            for i in j.saturating_sub(max_len)..j {
+                let Some(base) = self.best[i] else { continue };
+                let Some(score) = syllable_score(&chunk[i..j], syllables, fuzzy) else { continue };
+                let score = base + score;
+                if best.is_none_or(|b| score > b) {
+                    best = Some(score);
+                    prev = Some(i);
+                }
+            }
+            self.best.push(best);
+            self.prev.push(prev);
+        }
+        self.text.truncate(common);
+        self.text.push_str(&chunk[common..]);
+    }
+
+    /// 回溯出整段的最优切分（实际输入的拼写）；无法完整切分时返回 None。
+    fn backtrack(&self) -> Option<Vec<String>> {
+        let mut out = Vec::new();
+        let mut cur = self.text.len();
+        self.best[cur]?;
+        while cur > 0 {
+            let p = self.prev[cur]?;
+            out.push(self.text[p..cur].to_string());
+            cur = p;
+        }
+        out.reverse();
+        Some(out)
+    }
+}
+
 pub struct QuanpinPreeditor {
-    syllables: Vec<(&'static str, i32)>,
+    /// 音节 -> 频次（默认为内置表，见 `from_syllabary_file`）
+    syllables: Syllables,
+    /// 最长音节的字节数
+    max_len: usize,
+    /// 无法切分成合法音节时，是否退化为“按字母段”的简拼模式
+    initials_fallback: bool,
+    /// 模糊音规则（默认为空，即关闭）
+    fuzzy: FuzzyRules,
+    /// 不在音节表中、经模糊规则才成立的拼写 -> 对应音节的最大频次（例如 in=ing 时的 `din`）
+    fuzzy_syllables: HashMap<String, i32>,
+    /// 超过此字节数的输入不切分，原样作为 preedit（防止超长粘贴拖慢每次按键）
+    max_input_len: usize,
+    /// `Analysis::alternatives` 最多给出多少种其他切分（默认 0，不给出）
+    max_alternatives: usize,
+    /// 接受声调数字 `1`-`5`（默认关闭，见 `tone_digits`）
+    tone_digits: bool,
+    /// 上一次输入各 chunk 的 DP，逐键输入时增量复用；只依赖音节表，与词典版本无关。引擎跨线程共享，故加锁
+    dp_cache: Mutex<Vec<ChunkDp>>,
 }
 
 impl Default for QuanpinPreeditor {
     fn default() -> Self {
-        // 按频次降序，遇到同长/同分时更稳定；
-        // 但 DP 里我们仍会对“长音节”给更高的结构性分数。
-        let mut syllables = SYLLABARY.to_vec();
-        syllables.sort_by(|a, b| {
-            b.1.cmp(&a.1)
-                .then_with(|| b.0.len().cmp(&a.0.len()))
-                .then_with(|| a.0.cmp(b.0))
-        });
-        Self { syllables }
+        Self::with_syllables(SYLLABARY.iter().map(|&(s, freq)| (Cow::Borrowed(s), freq)).collect())
     }
 }
 
 impl QuanpinPreeditor {
+    /// 以 `syllables` 为音节表，其余设置为默认值。
+    fn with_syllables(syllables: Syllables) -> Self {
+        Self {
+            max_len: syllables.keys().map(|s| s.len()).max().unwrap_or(0),
+            syllables,
+            initials_fallback: true,
+            fuzzy: FuzzyRules::default(),
+            fuzzy_syllables: HashMap::new(),
+            max_input_len: DEFAULT_MAX_INPUT_LEN,
+            max_alternatives: 0,
+            tone_digits: false,
+            dp_cache: Mutex::new(Vec::new()),
+        }
+    }
+
     pub fn new() -> Self {
         Self::default()
     }
 
-    fn segment_chunk(&self, chunk: &str) -> Option<Vec<&'static str>> {
-        if chunk.is_empty() {
-            return Some(Vec::new());
+    /// 以文件中的音节表代替内置表（例如增删方言音节），切分规则不变：长音节优先，同长按频次。
+    ///
+    /// 格式见 `from_syllabary_str`；出错时的信息带上文件路径。
+    pub fn from_syllabary_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let s = fs::read_to_string(path)?;
+        Self::from_syllabary_str(&s).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+
+    /// 从 TSV 读入音节表：每行 `syllable<TAB>freq`，freq 可省略（默认 0）；空行与 `#` 开头的行忽略，同一音节只保留第一条。
+    ///
+    /// 音节只能包含 a-z，freq 须为整数，否则返回带行号的错误；没有任何音节时也返回错误（否则任何输入都无法切分）。
+    pub fn from_syllabary_str(s: &str) -> io::Result<Self> {
+        let invalid = |idx: usize, message: String| io::Error::new(io::ErrorKind::InvalidData, format!("音节表第 {} 行：{message}", idx + 1));
+        let mut syllables = Syllables::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t').map(str::trim);
+            let (Some(syllable), freq, None) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(invalid(idx, "应为音节、频次两列".to_string()));
+            };
+            if syllable.is_empty() || !syllable.bytes().all(|b| b.is_ascii_lowercase()) {
+                return Err(invalid(idx, format!("音节只能包含 a-z，实际为 `{syllable}`")));
+            }
+            let freq = match freq.filter(|f| !f.is_empty()) {
+                Some(f) => f.parse::<i32>().map_err(|_| invalid(idx, format!("无法解析的频次 `{f}`")))?,
+                None => 0,
+            };
+            syllables.entry(Cow::Owned(syllable.to_string())).or_insert(freq);
         }
-        if !chunk.bytes().all(|b| b.is_ascii_lowercase()) {
-            return None;
+        if syllables.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "音节表为空"));
         }
+        Ok(Self::with_syllables(syllables))
+    }
 
-        let n = chunk.len();
-        let mut best_score: Vec<Option<i64>> = vec![None; n + 1];
-        let mut prev: Vec<Option<(usize, &'static str, i32)>> = vec![None; n + 1];
-        best_score[0] = Some(0);
+    /// 开关简拼 fallback（默认开启）。
+    pub fn initials_fallback(mut self, enabled: bool) -> Self {
+        self.initials_fallback = enabled;
+        self
+    }
 
-        for i in 0..n {
-            let Some(base) = best_score[i] else { continue };
-            let rest = &chunk[i..];
-            // 遍历所有可能音节：第一版简单暴力；n 一般很小。
-            for &(sy, freq) in &self.syllables {
-                if !rest.starts_with(sy) {
-                    continue;
-                }
-                let j = i + sy.len();
-                // 结构分：优先长音节，辅以频次
-                let score = base + (sy.len() as i64) * 10_000 + (freq as i64);
-                if best_score[j].is_none() || score > best_score[j].unwrap() {
-                    best_score[j] = Some(score);
-                    prev[j] = Some((i, sy, freq));
+    /// 开启模糊音：`Analysis::fuzzy` 中给出每个音节在音节表内的模糊拼写。
+    ///
+    /// 不在音节表中、但按规则等价于某个音节的拼写（例如 in=ing 时的 `din`、z=zh 时的 `zua`）也可切分，
+    /// `Analysis::segment` 记录实际输入的拼写，`Analysis::fuzzy` 给出可查词的音节；
+    /// 这样的拼写只在没有精确切分时才用（`shi` 不会因 s=sh 被切成别的样子）。
+    pub fn with_fuzzy(mut self, rules: FuzzyRules) -> Self {
+        let mut fuzzy_syllables: HashMap<String, i32> = HashMap::new();
+        for (sy, &freq) in &self.syllables {
+            for variant in rules.variants(sy).into_iter().filter(|v| !self.syllables.contains_key(v.as_str())) {
+                let best = fuzzy_syllables.entry(variant).or_insert(freq);
+                *best = (*best).max(freq);
+            }
+        }
+        self.max_len = self.max_len.max(fuzzy_syllables.keys().map(String::len).max().unwrap_or(0));
+        self.fuzzy_syllables = fuzzy_syllables;
+        self.fuzzy = rules;
+        self
+    }
+
+    /// 可切分输入的最大字节数（默认 `DEFAULT_MAX_INPUT_LEN`）；更长的输入不切分、不出候选。
+    pub fn max_input_len(mut self, n: usize) -> Self {
+        self.max_input_len = n;
+        self
+    }
+
+    /// 整段输入另给出至多 `n` 种切分（`Analysis::alternatives`，引擎翻译整段时一并查询），例如 `xian` 的 `["xi","an"]`；默认 0。
+    ///
+    /// 每次切分多做一遍不复用的 n-best DP，只在需要时开启。
+    pub fn max_alternatives(mut self, n: usize) -> Self {
+        self.max_alternatives = n;
+        self
+    }
+
+    /// 接受声调数字（默认关闭）：音节后的 `1`-`5` 同 `'` 一样断开音节，声调本身忽略（词典不分声调），例如 `xi1an1` 切成 `xi an`。
+    ///
+    /// 会话须接受数字（`InputPolicy::digits`），此时数字不再用来选词。
+    pub fn tone_digits(mut self, enabled: bool) -> Self {
+        self.tone_digits = enabled;
+        self
+    }
+
+    /// 开启 `tone_digits` 时把声调数字换成 `'`。
+    fn tones_as_separators<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        if self.tone_digits && input.contains(TONE_DIGITS) {
+            Cow::Owned(input.replace(TONE_DIGITS, "'"))
+        } else {
+            Cow::Borrowed(input)
+        }
+    }
+
+    /// 输入的前 `k` 种切分（实际输入的拼写），按得分降序；第一种即 `analyze` 的切分。
+    ///
+    /// `'` 是强制断点：各 chunk 分别切分再组合，所有方案都在这些位置断开。各方案的切分点互不相同；
+    /// 输入含 a-z 与 `'` 以外的字符、超过 `max_input_len` 或无法切分时为空。不做简拼 fallback。
+    pub fn segment_nbest(&self, input: &str, k: usize) -> Vec<Vec<String>> {
+        let input = input.to_ascii_lowercase();
+        if input.len() > self.max_input_len {
+            return Vec::new();
+        }
+        self.nbest(&normalize_separators(&self.tones_as_separators(&input)), k)
+    }
+
+    /// `segment_nbest` 的主体；`input` 已规整。
+    fn nbest(&self, input: &str, k: usize) -> Vec<Vec<String>> {
+        if k == 0 || input.is_empty() || !input.bytes().all(|b| b.is_ascii_lowercase() || b == b'\'') {
+            return Vec::new();
+        }
+        let mut combined: Vec<(i64, Vec<String>)> = vec![(0, Vec::new())];
+        for chunk in input.split('\'') {
+            let options = self.chunk_nbest(chunk, k);
+            let mut next = Vec::with_capacity(combined.len() * options.len());
+            for (head_score, head) in &combined {
+                for (score, tail) in &options {
+                    next.push((head_score + score, head.iter().chain(tail).cloned().collect()));
                 }
             }
+            // 稳定排序：同分时保持 (前面的方案, 本 chunk 的方案) 的先后
+            next.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+            next.truncate(k);
+            combined = next;
         }
+        combined.into_iter().map(|(_, segs)| segs).collect()
+    }
 
-        if best_score[n].is_none() {
-            return None;
+    /// 单个 chunk 的前 `k` 种切分及得分，按得分降序；同分时与 `ChunkDp` 一样起点靠前的在先，因此第一种即 DP 的最优切分。
+    fn chunk_nbest(&self, chunk: &str, k: usize) -> Vec<(i64, Vec<String>)> {
+        // paths[j]：覆盖 chunk[..j] 的前 k 条路径 (得分, 最后一个音节的起点 i, 前驱在 paths[i] 中的下标)；paths[0] 为起点
+        let mut paths: Vec<Vec<(i64, usize, usize)>> = vec![vec![(0, 0, 0)]];
+        for j in 1..=chunk.len() {
+            let mut here = Vec::new();
+            for i in j.saturating_sub(self.max_len)..j {
+                let Some(score) = syllable_score(&chunk[i..j], &self.syllables, &self.fuzzy_syllables) else { continue };
+                here.extend(paths[i].iter().enumerate().map(|(r, &(base, _, _))| (base + score, i, r)));
+            }
+            here.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+            here.truncate(k);
+            paths.push(here);
         }
+        paths[chunk.len()]
+            .iter()
+            .map(|&(score, start, rank)| {
+                let mut segs = Vec::new();
+                let (mut end, mut start, mut rank) = (chunk.len(), start, rank);
+                while end > 0 {
+                    segs.push(chunk[start..end].to_string());
+                    end = start;
+                    (_, start, rank) = paths[end][rank];
+                }
+                segs.reverse();
+                (score, segs)
+            })
+            .collect()
+    }
 
-        // 回溯
-        let mut out = Vec::new();
-        let mut cur = n;
-        while cur > 0 {
-            let Some((p, sy, _freq)) = prev[cur] else {
-                return None;
-            };
-            out.push(sy);
-            cur = p;
+    fn fuzzy_variants(&self, segs: &[String]) -> Vec<Vec<String>> {
+        if self.fuzzy.is_empty() {
+            return Vec::new();
         }
-        out.reverse();
-        Some(out)
+        segs.iter()
+            .map(|sy| {
+                let mut v = self.fuzzy.variants(sy);
+                v.retain(|x| self.syllables.contains_key(x.as_str()));
+                v
+            })
+            .collect()
     }
 
-    fn segment(&self, input: &str) -> Option<Vec<&'static str>> {
+    fn segment(&self, input: &str) -> Option<Vec<String>> {
         // 支持用 `'` 强制断开（Rime 常用来消歧/断词）。
-        let mut out = Vec::new();
-        for chunk in input.split('\'') {
-            let mut seg = self.segment_chunk(chunk)?;
-            out.append(&mut seg);
+        if !input.bytes().all(|b| b.is_ascii_lowercase() || b == b'\'') {
+            return None;
         }
-        Some(out)
+        // 其他线程正占用缓存时不等待，直接从头计算
+        let mut cache = self.dp_cache.try_lock().ok();
+        let dps: Vec<ChunkDp> = input
+            .split('\'')
+            .enumerate()
+            .map(|(k, chunk)| {
+                let mut dp = cache.as_mut().and_then(|c| c.get_mut(k)).map(mem::take).unwrap_or_default();
+                dp.update(chunk, &self.syllables, &self.fuzzy_syllables, self.max_len);
+                dp
+            })
+            .collect();
+        let out = dps.iter().map(ChunkDp::backtrack).collect::<Option<Vec<_>>>().map(|v| v.into_iter().flatten().collect());
+        // 切分失败（例如输入到一半的 `zh`）也保留 DP，下一键继续复用
+        if let Some(cache) = &mut cache {
+            **cache = dps;
+        }
+        out
     }
 }
 
 impl Analyzer for QuanpinPreeditor {
+    /// 输入中的 `'` 按分隔符规整后再切分：连续的 `'` 视为一个，开头的 `'` 忽略；
+    /// 末尾的 `'` 是尚未输入下一段的分隔符，不产生空段，只在 preedit 末尾显示为 `'`。
     fn analyze(&self, input: &str) -> Analysis {
-        if input.is_empty() {
-            return Analysis {
-                segment: Vec::new(),
-                preedit: String::new(),
-            };
-        }
+        let empty = |preedit: String| Analysis {
+            segment: Arc::from([]),
+            preedit,
+            fuzzy: Vec::new(),
+            kind: SegmentationKind::Syllables,
+            alternatives: Vec::new(),
+        };
         let input = input.to_ascii_lowercase();
-        match self.segment(&input) {
-            Some(segs) if !segs.is_empty() => Analysis {
-                preedit: segs.join(" "),
-                segment: segs.iter().map(|s| (*s).to_string()).collect(),
-            },
+        if input.len() > self.max_input_len {
+            return empty(input);
+        }
+        // 末尾的声调数字结束了音节，不是待输入下一段的分隔符
+        let pending = if input.ends_with('\'') { "'" } else { "" };
+        let toned = self.tones_as_separators(&input);
+        let normalized = normalize_separators(&toned);
+        if normalized.is_empty() {
+            return empty(String::new());
+        }
+        match self.segment(&normalized) {
+            Some(segs) if !segs.is_empty() => {
+                let alternatives = match self.max_alternatives {
+                    0 => Vec::new(),
+                    n => self.nbest(&normalized, n + 1).into_iter().filter(|alt| *alt != segs).take(n).collect(),
+                };
+                Analysis {
+                    preedit: segs.join(" ") + pending,
+                    fuzzy: self.fuzzy_variants(&segs),
+                    segment: Arc::from(segs),
+                    kind: SegmentationKind::Syllables,
+                    alternatives,
+                }
+            }
             _ => {
                 // initials 模式：当无法切分成合法音节时，退化为“按字母段”。
                 // 例如输入 `qs` -> segments ["q", "s"]，便于词典做首字母检索。
-                let letters_only = input.chars().all(|c| c.is_ascii_lowercase() || c == '\'');
-                if letters_only && (1..=6).contains(&input.len()) {
-                    let segments: Vec<String> = input
+                let letters_only = normalized.chars().all(|c| c.is_ascii_lowercase() || c == '\'');
+                if self.initials_fallback && letters_only && (1..=6).contains(&normalized.len()) {
+                    let segments: Arc<[String]> = normalized
                         .chars()
                         .filter(|&c| c != '\'')
                         .map(|c| c.to_string())
                         .collect();
                     Analysis {
-                        preedit: segments.join(" "),
+                        preedit: segments.join(" ") + pending,
                         segment: segments,
+                        fuzzy: Vec::new(),
+                        kind: SegmentationKind::Initials,
+                        alternatives: Vec::new(),
                     }
                 } else {
-                    Analysis {
-                        segment: Vec::new(),
-                        preedit: input,
-                    }
+                    empty(input)
                 }
             }
         }
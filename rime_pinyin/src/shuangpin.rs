@@ -0,0 +1,177 @@
+//! 双拼（shuangpin）：每两个按键为一个音节（声母键 + 韵母键），换算为全拼音节，词典仍按拼音查询。
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+};
+
+use rime_core::{
+    engine::{Analysis, Analyzer, SegmentationKind},
+    processor::InputPolicy,
+};
+
+use crate::SYLLABARY;
+
+/// 双拼方案。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShuangpinScheme {
+    /// 小鹤双拼
+    #[default]
+    Flypy,
+    /// 自然码
+    Ziranma,
+    /// 微软双拼
+    Microsoft,
+}
+
+/// 小鹤双拼：韵母键 -> 韵母（一键多个韵母时按声母取合法的音节）。
+const FLYPY: [(char, &[&str]); 26] = [
+    ('q', &["iu"]), ('w', &["ei"]), ('r', &["uan"]), ('t', &["ue", "ve"]), ('y', &["un"]),
+    ('o', &["uo", "o"]), ('p', &["ie"]), ('s', &["iong", "ong"]), ('d', &["ai"]), ('f', &["en"]),
+    ('g', &["eng"]), ('h', &["ang"]), ('j', &["an"]), ('k', &["uai", "ing"]), ('l', &["iang", "uang"]),
+    ('z', &["ou"]), ('x', &["ia", "ua"]), ('c', &["ao"]), ('v', &["v", "ui"]), ('b', &["in"]),
+    ('n', &["iao"]), ('m', &["ian"]), ('a', &["a"]), ('e', &["e"]), ('i', &["i"]), ('u', &["u"]),
+];
+
+/// 自然码。
+const ZIRANMA: [(char, &[&str]); 26] = [
+    ('q', &["iu"]), ('w', &["ia", "ua"]), ('r', &["uan"]), ('t', &["ue", "ve"]), ('y', &["ing", "uai"]),
+    ('o', &["uo", "o"]), ('p', &["un"]), ('s', &["iong", "ong"]), ('d', &["iang", "uang"]), ('f', &["en"]),
+    ('g', &["eng"]), ('h', &["ang"]), ('j', &["an"]), ('k', &["ao"]), ('l', &["ai"]),
+    ('z', &["ei"]), ('x', &["ie"]), ('c', &["iao"]), ('v', &["v", "ui"]), ('b', &["ou"]),
+    ('n', &["in"]), ('m', &["ian"]), ('a', &["a"]), ('e', &["e"]), ('i', &["i"]), ('u', &["u"]),
+];
+
+/// 微软双拼：`;` 为 ing，零声母音节以 `o` 起头（例如 `oj` 即 an、`or` 即 er）。
+const MICROSOFT: [(char, &[&str]); 27] = [
+    ('q', &["iu"]), ('w', &["ia", "ua"]), ('r', &["uan", "er"]), ('t', &["ue"]), ('y', &["uai", "v"]),
+    ('o', &["uo", "o"]), ('p', &["un"]), ('s', &["iong", "ong"]), ('d', &["iang", "uang"]), ('f', &["en"]),
+    ('g', &["eng"]), ('h', &["ang"]), ('j', &["an"]), ('k', &["ao"]), ('l', &["ai"]),
+    ('z', &["ei"]), ('x', &["ie"]), ('c', &["iao"]), ('v', &["ui", "ve"]), ('b', &["ou"]),
+    ('n', &["in"]), ('m', &["ian"]), (';', &["ing"]), ('a', &["a"]), ('e', &["e"]), ('i', &["i"]),
+    ('u', &["u"]),
+];
+
+/// 音节 -> 频次（用于校验换算出的拼音，一键多韵母时取频次高的）。
+static SYLLABLES: LazyLock<HashMap<&'static str, i32>> = LazyLock::new(|| SYLLABARY.iter().copied().collect());
+
+impl ShuangpinScheme {
+    fn finals(self) -> &'static [(char, &'static [&'static str])] {
+        match self {
+            Self::Flypy => &FLYPY,
+            Self::Ziranma => &ZIRANMA,
+            Self::Microsoft => &MICROSOFT,
+        }
+    }
+
+    /// 零声母音节的起头键：小鹤与自然码以韵母首字母起头（`aa`、`ai`、`ah` 即 a、ai、ang），微软双拼为 `o`。
+    fn zero_initial_keys(self) -> &'static [char] {
+        match self {
+            Self::Flypy | Self::Ziranma => &['a', 'e', 'o'],
+            Self::Microsoft => &['o'],
+        }
+    }
+}
+
+/// 声母键 -> 声母：三种方案都是 `v`/`i`/`u` 为 zh/ch/sh，其余声母即本身的字母。
+fn initial(key: char) -> Option<&'static str> {
+    const INITIALS: &str = "bpmfdtnlgkhjqxrzcsyw";
+    match key {
+        'v' => Some("zh"),
+        'i' => Some("ch"),
+        'u' => Some("sh"),
+        _ => INITIALS.find(key).map(|i| &INITIALS[i..=i]),
+    }
+}
+
+/// 双拼切分器：`Analysis::segment` 为换算后的全拼音节（例如小鹤 `nihc` -> `["ni","hao"]`），拼音词典照常可用。
+///
+/// - 每两个按键换算为一个音节：声母键 + 韵母键，一键多个韵母（如小鹤 `k` 为 uai/ing）时取合法且频次高的音节
+/// - 零声母音节按方案的写法输入（小鹤/自然码 `aa`、`ai`、`ah`，微软双拼 `oa`、`ol`、`oh`）
+/// - 奇数个按键时最后一个键是正在输入的音节，给出其声母（零声母起头键给出该字母），词典对整段输入做前缀补全
+/// - `'` 结束当前音节，其后重新两两配对
+///
+/// 有任何一对按键换算不出合法音节时不切分（`segment` 为空）。微软双拼用到 `;`，会话须以 `input_policy` 接受它。
+#[derive(Debug, Clone, Default)]
+pub struct ShuangpinPreeditor {
+    scheme: ShuangpinScheme,
+}
+
+impl ShuangpinPreeditor {
+    pub fn new(scheme: ShuangpinScheme) -> Self {
+        Self { scheme }
+    }
+
+    /// 该方案的输入策略（见 `Session::with_input_policy`）：微软双拼另接受 `;`。
+    pub fn input_policy(&self) -> InputPolicy {
+        let mut policy = InputPolicy::default();
+        if self.scheme == ShuangpinScheme::Microsoft {
+            policy.extra.push(';');
+        }
+        policy
+    }
+
+    /// 一对按键换算为全拼音节；不是合法音节时为 None。
+    fn syllable(&self, first: char, second: char) -> Option<String> {
+        let finals = self.scheme.finals().iter().find(|(k, _)| *k == second).map_or(&[][..], |(_, f)| *f);
+        let candidates: Vec<String> = if self.scheme.zero_initial_keys().contains(&first) {
+            // 零声母：微软双拼 `o` + 韵母键；小鹤/自然码首字母重复（`aa`）、两字母韵母全拼（`ai`）或首字母 + 韵母键（`ah`）
+            let spelled = match self.scheme {
+                ShuangpinScheme::Microsoft => Vec::new(),
+                _ if first == second => vec![first.to_string()],
+                _ => vec![format!("{first}{second}")],
+            };
+            let keyed = finals.iter().filter(|f| self.scheme == ShuangpinScheme::Microsoft || f.starts_with(first)).map(|f| f.to_string());
+            spelled.into_iter().chain(keyed).filter(|s| !s.starts_with(['i', 'u', 'v'])).collect()
+        } else {
+            let initial = initial(first)?;
+            // ü 在 j/q/x/y 后写作 u，在 n/l 后写作 v（同音节表）
+            let umlaut = if matches!(initial, "j" | "q" | "x" | "y") { "u" } else { "v" };
+            finals.iter().map(|f| format!("{initial}{}", f.replace('v', umlaut))).collect()
+        };
+        candidates.into_iter().filter_map(|s| SYLLABLES.get(s.as_str()).map(|&freq| (freq, s))).max_by_key(|(freq, _)| *freq).map(|(_, s)| s)
+    }
+
+    /// 正在输入的单个按键：声母键给出声母，零声母起头键给出该字母。
+    fn partial(&self, key: char) -> Option<String> {
+        if self.scheme.zero_initial_keys().contains(&key) {
+            return Some(key.to_string());
+        }
+        initial(key).map(str::to_string)
+    }
+}
+
+impl Analyzer for ShuangpinPreeditor {
+    fn analyze(&self, input: &str) -> Analysis {
+        let failed = |preedit: String| Analysis {
+            segment: Arc::from([]),
+            preedit,
+            fuzzy: Vec::new(),
+            kind: SegmentationKind::Syllables,
+            alternatives: Vec::new(),
+        };
+        let input = input.to_ascii_lowercase();
+        let mut segment = Vec::new();
+        for chunk in input.split('\'') {
+            let keys: Vec<char> = chunk.chars().collect();
+            for pair in keys.chunks(2) {
+                let syllable = match *pair {
+                    [first, second] => self.syllable(first, second),
+                    [key] => self.partial(key),
+                    _ => None,
+                };
+                match syllable {
+                    Some(s) => segment.push(s),
+                    None => return failed(input),
+                }
+            }
+        }
+        Analysis {
+            preedit: segment.join(" "),
+            segment: Arc::from(segment),
+            fuzzy: Vec::new(),
+            kind: SegmentationKind::Syllables,
+            alternatives: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! 码表方案（仓颉、五笔等）：编码直接对应字词，不按音节切分。
+
+use std::sync::Arc;
+
+use rime_core::engine::{Analysis, Analyzer, SegmentationKind};
+
+/// 码表切分器：整段输入作为一个编码（`SegmentationKind::Codes`）。
+///
+/// - `'` 显式分隔编码（同全拼的音节分隔符），例如 `ab'dd` -> `["ab", "dd"]`
+/// - 设置 `max_code_length` 后，超出长度的部分开始下一个编码，例如最长 2 时 `abdd` -> `["ab", "dd"]`；
+///   会话中码满后再输入即先上屏前一个编码的首选（见 `processor::EditingProcessor`）
+///
+/// 简拼与按音节的前缀补全对编码没有意义：前者不会触发，后者宜以 `Engine::prefix_completion(false)` 关闭。
+#[derive(Debug, Clone, Default)]
+pub struct TableAnalyzer {
+    /// 单个编码的最大长度（字符数）；None 为不限
+    max_code_length: Option<usize>,
+}
+
+impl TableAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 单个编码的最大长度（例如仓颉为 5）；`n` 须大于 0。
+    pub fn max_code_length(mut self, n: usize) -> Self {
+        assert!(n > 0, "max_code_length 必须大于 0");
+        self.max_code_length = Some(n);
+        self
+    }
+}
+
+impl Analyzer for TableAnalyzer {
+    /// 末尾的 `'` 是尚未输入下一个编码的分隔符，只在 preedit 末尾显示。
+    fn analyze(&self, input: &str) -> Analysis {
+        let input = input.to_ascii_lowercase();
+        let mut segment: Vec<String> = Vec::new();
+        for chunk in input.split('\'').filter(|chunk| !chunk.is_empty()) {
+            let chars: Vec<char> = chunk.chars().collect();
+            let n = self.max_code_length.unwrap_or(chars.len());
+            segment.extend(chars.chunks(n).map(|code| code.iter().collect::<String>()));
+        }
+        let pending = if input.ends_with('\'') && !segment.is_empty() { "'" } else { "" };
+        Analysis {
+            preedit: segment.join(" ") + pending,
+            segment: Arc::from(segment),
+            fuzzy: Vec::new(),
+            kind: SegmentationKind::Codes,
+            alternatives: Vec::new(),
+        }
+    }
+}
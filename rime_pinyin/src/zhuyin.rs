@@ -0,0 +1,262 @@
+//! 注音（bopomofo）：输入注音符号或大千键盘按键，切分为拼音音节，词典仍按拼音查询。
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, LazyLock},
+};
+
+use rime_core::{
+    engine::{Analysis, Analyzer, SegmentationKind},
+    processor::InputPolicy,
+};
+
+use crate::SYLLABARY;
+
+/// 大千（标准）键盘：按键 -> 注音符号或声调。
+const DACHEN: [(char, char); 41] = [
+    ('1', 'ㄅ'), ('q', 'ㄆ'), ('a', 'ㄇ'), ('z', 'ㄈ'),
+    ('2', 'ㄉ'), ('w', 'ㄊ'), ('s', 'ㄋ'), ('x', 'ㄌ'),
+    ('e', 'ㄍ'), ('d', 'ㄎ'), ('c', 'ㄏ'),
+    ('r', 'ㄐ'), ('f', 'ㄑ'), ('v', 'ㄒ'),
+    ('5', 'ㄓ'), ('t', 'ㄔ'), ('g', 'ㄕ'), ('b', 'ㄖ'),
+    ('y', 'ㄗ'), ('h', 'ㄘ'), ('n', 'ㄙ'),
+    ('u', 'ㄧ'), ('j', 'ㄨ'), ('m', 'ㄩ'),
+    ('8', 'ㄚ'), ('i', 'ㄛ'), ('k', 'ㄜ'), (',', 'ㄝ'),
+    ('9', 'ㄞ'), ('o', 'ㄟ'), ('l', 'ㄠ'), ('.', 'ㄡ'),
+    ('0', 'ㄢ'), ('p', 'ㄣ'), (';', 'ㄤ'), ('/', 'ㄥ'), ('-', 'ㄦ'),
+    ('6', 'ˊ'), ('3', 'ˇ'), ('4', 'ˋ'), ('7', '˙'),
+];
+
+/// 声母 -> 拼音。
+const INITIALS: [(char, &str); 21] = [
+    ('ㄅ', "b"), ('ㄆ', "p"), ('ㄇ', "m"), ('ㄈ', "f"),
+    ('ㄉ', "d"), ('ㄊ', "t"), ('ㄋ', "n"), ('ㄌ', "l"),
+    ('ㄍ', "g"), ('ㄎ', "k"), ('ㄏ', "h"),
+    ('ㄐ', "j"), ('ㄑ', "q"), ('ㄒ', "x"),
+    ('ㄓ', "zh"), ('ㄔ', "ch"), ('ㄕ', "sh"), ('ㄖ', "r"),
+    ('ㄗ', "z"), ('ㄘ', "c"), ('ㄙ', "s"),
+];
+
+/// 介音。
+const MEDIALS: [char; 3] = ['ㄧ', 'ㄨ', 'ㄩ'];
+
+/// 韵母 -> 拼音（单独出现时）。
+const FINALS: [(char, &str); 13] = [
+    ('ㄚ', "a"), ('ㄛ', "o"), ('ㄜ', "e"), ('ㄝ', "ê"),
+    ('ㄞ', "ai"), ('ㄟ', "ei"), ('ㄠ', "ao"), ('ㄡ', "ou"),
+    ('ㄢ', "an"), ('ㄣ', "en"), ('ㄤ', "ang"), ('ㄥ', "eng"), ('ㄦ', "er"),
+];
+
+/// 声调：一声（通常不标）、二声、三声、四声、轻声。
+const TONES: [char; 5] = ['ˉ', 'ˊ', 'ˇ', 'ˋ', '˙'];
+
+/// 音节表（用于校验换算出的拼音）。
+static SYLLABLES: LazyLock<HashSet<&'static str>> = LazyLock::new(|| SYLLABARY.iter().map(|(s, _)| *s).collect());
+
+/// 一个注音音节：声母 + 介音 + 韵母 + 声调，各部分都可省略。
+#[derive(Debug, Default, Clone, Copy)]
+struct Syllable {
+    initial: Option<char>,
+    medial: Option<char>,
+    rime: Option<char>,
+    tone: Option<char>,
+}
+
+impl Syllable {
+    fn is_empty(&self) -> bool {
+        self.initial.is_none() && self.medial.is_none() && self.rime.is_none()
+    }
+
+    /// 展示用：按输入顺序的注音符号（含声调）。
+    fn bopomofo(&self) -> String {
+        [self.initial, self.medial, self.rime, self.tone].into_iter().flatten().collect()
+    }
+
+    /// 换算为拼音（ü 写作 `v`，同音节表）；不是合法音节时为 None。
+    ///
+    /// 只有声母时视为正在输入的音节，给出声母本身（`ㄓㄔㄕㄖㄗㄘㄙ` 则是完整音节 `zhi` 等）。
+    fn pinyin(&self) -> Option<String> {
+        let rime = match (self.medial, self.rime) {
+            (None, None) => "",
+            (None, Some(r)) => FINALS.iter().find(|(c, _)| *c == r).map(|(_, s)| *s)?,
+            (Some('ㄧ'), r) => match r {
+                None => "i",
+                Some('ㄚ') => "ia",
+                Some('ㄛ') => "io",
+                Some('ㄝ') => "ie",
+                Some('ㄞ') => "iai",
+                Some('ㄠ') => "iao",
+                Some('ㄡ') => "iu",
+                Some('ㄢ') => "ian",
+                Some('ㄣ') => "in",
+                Some('ㄤ') => "iang",
+                Some('ㄥ') => "ing",
+                _ => return None,
+            },
+            (Some('ㄨ'), r) => match r {
+                None => "u",
+                Some('ㄚ') => "ua",
+                Some('ㄛ') => "uo",
+                Some('ㄞ') => "uai",
+                Some('ㄟ') => "ui",
+                Some('ㄢ') => "uan",
+                Some('ㄣ') => "un",
+                Some('ㄤ') => "uang",
+                Some('ㄥ') => "ong",
+                _ => return None,
+            },
+            (Some(_), r) => match r {
+                None => "ü",
+                Some('ㄝ') => "üe",
+                Some('ㄢ') => "üan",
+                Some('ㄣ') => "ün",
+                Some('ㄥ') => "iong",
+                _ => return None,
+            },
+        };
+        let pinyin = match self.initial {
+            Some(i) => {
+                let initial = INITIALS.iter().find(|(c, _)| *c == i).map(|(_, s)| *s)?;
+                if rime.is_empty() {
+                    let whole = matches!(initial, "zh" | "ch" | "sh" | "r" | "z" | "c" | "s");
+                    return Some(if whole { format!("{initial}i") } else { initial.to_string() });
+                }
+                // ü 在 j/q/x 后写作 u，在 n/l 后写作 v
+                let u = if matches!(initial, "j" | "q" | "x") { "u" } else { "v" };
+                format!("{initial}{}", rime.replace('ü', u))
+            }
+            None => match rime {
+                "" => return None,
+                "i" | "in" | "ing" => format!("y{rime}"),
+                "iu" => "you".to_string(),
+                "iong" => "yong".to_string(),
+                "u" => "wu".to_string(),
+                "ui" => "wei".to_string(),
+                "un" => "wen".to_string(),
+                "ong" => "weng".to_string(),
+                _ if rime.starts_with('ü') => rime.replace('ü', "yu"),
+                _ if rime.starts_with('i') => format!("y{}", &rime[1..]),
+                _ if rime.starts_with('u') => format!("w{}", &rime[1..]),
+                _ => rime.to_string(),
+            },
+        };
+        SYLLABLES.contains(pinyin.as_str()).then_some(pinyin)
+    }
+}
+
+/// 注音切分器：`Analysis::segment` 为拼音音节（拼音词典照常可用），preedit 显示注音。
+///
+/// - 接受注音符号本身，或大千键盘的按键（例如 `su3cl3` 即 `ㄋㄧˇ ㄏㄠˇ`）；两者可混用
+/// - 声调（大千键盘 `6`/`3`/`4`/`7`）结束当前音节，不参与查询；`'` 同样结束当前音节
+/// - 不标声调时按注音结构断开：声母总是开始新音节，介音、韵母在当前音节已有同类或更后的部分时开始新音节
+/// - 只有声母的音节（通常是正在输入的最后一个音节）给出声母本身，词典对整段输入做前缀补全
+///
+/// 大千键盘用到数字与 `,./;-`，会话须以 `input_policy` 接受这些字符。
+#[derive(Debug, Clone, Default)]
+pub struct ZhuyinPreeditor;
+
+impl ZhuyinPreeditor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 接受大千键盘按键与注音符号的输入策略（见 `Session::with_input_policy`）。
+    pub fn input_policy() -> InputPolicy {
+        let keys = DACHEN.iter().map(|(k, _)| *k).filter(|k| !k.is_ascii_alphanumeric());
+        let symbols = DACHEN.iter().map(|(_, s)| *s).chain(['ˉ', '\'']);
+        InputPolicy { letters: true, digits: true, extra: keys.chain(symbols).collect(), ..InputPolicy::default() }
+    }
+
+    /// 按键换算为注音符号（`'` 原样保留）；不认识的字符为 None。
+    fn symbol(ch: char) -> Option<char> {
+        if ch == '\'' || TONES.contains(&ch) || DACHEN.iter().any(|(_, s)| *s == ch) {
+            return Some(ch);
+        }
+        let ch = ch.to_ascii_lowercase();
+        DACHEN.iter().find(|(k, _)| *k == ch).map(|(_, s)| *s)
+    }
+
+    /// 把注音符号串断成音节。
+    fn syllables(symbols: &[char]) -> Vec<Syllable> {
+        let mut out = Vec::new();
+        let mut cur = Syllable::default();
+        for &c in symbols {
+            let part = Part::of(c);
+            let starts_new = match part {
+                Part::Initial => !cur.is_empty(),
+                Part::Medial => cur.medial.is_some() || cur.rime.is_some(),
+                Part::Rime => cur.rime.is_some(),
+                Part::End => false,
+            };
+            if starts_new {
+                out.push(std::mem::take(&mut cur));
+            }
+            match part {
+                Part::Initial => cur.initial = Some(c),
+                Part::Medial => cur.medial = Some(c),
+                Part::Rime => cur.rime = Some(c),
+                // 没有音节可结束的声调忽略
+                Part::End if !cur.is_empty() => {
+                    cur.tone = Some(c).filter(|&c| c != '\'');
+                    out.push(std::mem::take(&mut cur));
+                }
+                Part::End => {}
+            }
+        }
+        if !cur.is_empty() {
+            out.push(cur);
+        }
+        out
+    }
+}
+
+/// 注音符号在音节中的位置。
+#[derive(Debug, Clone, Copy)]
+enum Part {
+    Initial,
+    Medial,
+    Rime,
+    /// 声调或 `'`：结束当前音节
+    End,
+}
+
+impl Part {
+    fn of(c: char) -> Self {
+        if INITIALS.iter().any(|(i, _)| *i == c) {
+            Self::Initial
+        } else if MEDIALS.contains(&c) {
+            Self::Medial
+        } else if c == '\'' || TONES.contains(&c) {
+            Self::End
+        } else {
+            Self::Rime
+        }
+    }
+}
+
+impl Analyzer for ZhuyinPreeditor {
+    fn analyze(&self, input: &str) -> Analysis {
+        let failed = |preedit: String| Analysis {
+            segment: Arc::from([]),
+            preedit,
+            fuzzy: Vec::new(),
+            kind: SegmentationKind::Syllables,
+            alternatives: Vec::new(),
+        };
+        let Some(symbols) = input.chars().map(Self::symbol).collect::<Option<Vec<char>>>() else {
+            return failed(input.to_string());
+        };
+        let syllables = Self::syllables(&symbols);
+        let preedit = syllables.iter().map(Syllable::bopomofo).collect::<Vec<_>>().join(" ");
+        match syllables.iter().map(Syllable::pinyin).collect::<Option<Vec<String>>>() {
+            Some(segment) => Analysis {
+                segment: Arc::from(segment),
+                preedit,
+                fuzzy: Vec::new(),
+                kind: SegmentationKind::Syllables,
+                alternatives: Vec::new(),
+            },
+            None => failed(preedit),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! `rime_py`：PyO3 绑定（feature `python`），Python 模块名 `rime_rs`。
+//!
+//! ```python
+//! import rime_rs
+//! ime = rime_rs.Engine("dict.tsv")
+//! ime.query("nihao", 5)        # [{"text": ..., "comment": ..., "weight": ..., ...}, ...]
+//! s = rime_rs.Session(ime)
+//! s.process_key("n"); s.process_key("i"); s.process_key(" ")
+//! s.take_commits()             # ["你"]
+//! ```
+//!
+//! 构建：`maturin develop -m rime_py/Cargo.toml`。Rust 侧 panic 由 PyO3 转为 Python 的 `PanicException`。
+#![cfg(feature = "python")]
+
+use std::sync::Arc;
+
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+    types::{PyDict, PyList},
+};
+use rime_core::{
+    engine::{Analysis, Engine as CoreEngine},
+    key_event::{Action, InputEvent},
+    model::{Candidate, UiState},
+    session::Session as CoreSession,
+};
+use rime_dict::TsvDictionary;
+use rime_pinyin::QuanpinPreeditor;
+
+type PyEngineInner = CoreEngine<TsvDictionary, QuanpinPreeditor>;
+
+/// 引擎：持有词典，可直接查询，也可创建会话（会话共享同一引擎）。
+#[pyclass]
+struct Engine {
+    engine: Arc<PyEngineInner>,
+}
+
+#[pymethods]
+impl Engine {
+    /// `Engine(path)` 或 `Engine(tsv="...")`。
+    #[new]
+    #[pyo3(signature = (path = None, *, tsv = None))]
+    fn new(path: Option<&str>, tsv: Option<&str>) -> PyResult<Self> {
+        let dict = match (path, tsv) {
+            (Some(path), None) => TsvDictionary::from_path(path),
+            (None, Some(tsv)) => TsvDictionary::from_tsv_str(tsv),
+            _ => return Err(PyValueError::new_err("需要且只能给出 path 或 tsv 之一")),
+        }
+        .map_err(|e| PyIOError::new_err(e.to_string()))?;
+        Ok(Self {
+            engine: Arc::new(CoreEngine::new(dict, QuanpinPreeditor::new())),
+        })
+    }
+
+    /// 整段输入的前 `limit` 个候选。
+    #[pyo3(signature = (input, limit = 5))]
+    fn query<'py>(&self, py: Python<'py>, input: &str, limit: usize) -> PyResult<Bound<'py, PyList>> {
+        let items = self.engine.query(input, limit).iter().map(|c| candidate_to_dict(py, c)).collect::<PyResult<Vec<_>>>()?;
+        PyList::new(py, items)
+    }
+
+    /// 切分结果：`{"segment": [...], "preedit": "..."}`。
+    fn analyze<'py>(&self, py: Python<'py>, input: &str) -> PyResult<Bound<'py, PyDict>> {
+        analysis_to_dict(py, &self.engine.analyze(input))
+    }
+}
+
+/// 输入会话。
+#[pyclass]
+struct Session {
+    session: CoreSession<TsvDictionary, QuanpinPreeditor>,
+    commits: Vec<String>,
+}
+
+#[pymethods]
+impl Session {
+    #[new]
+    fn new(engine: &Engine) -> Self {
+        Self {
+            session: CoreSession::new(Arc::clone(&engine.engine)),
+            commits: Vec::new(),
+        }
+    }
+
+    /// 处理一个按键（按键名见 `InputEvent::from_key_name`），返回最新 UI 快照。
+    fn process_key<'py>(&mut self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyDict>> {
+        let ev = InputEvent::from_key_name(key).ok_or_else(|| PyValueError::new_err(format!("不支持的按键 `{key}`")))?;
+        let (ui, actions) = self.session.handle(ev);
+        for action in actions {
+            if let Action::Commit(text) = action {
+                self.commits.push(text);
+            }
+        }
+        ui_to_dict(py, &ui)
+    }
+
+    /// 当前 UI 快照。
+    fn ui_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        ui_to_dict(py, &self.session.ui_state())
+    }
+
+    /// 取走尚未读取的上屏文本。
+    fn take_commits(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.commits)
+    }
+}
+
+fn candidate_to_dict<'py>(py: Python<'py>, c: &Candidate) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new(py);
+    d.set_item("text", c.text.as_str())?;
+    d.set_item("comment", c.comment.as_deref())?;
+    d.set_item("weight", c.weight)?;
+    d.set_item("segment_start", c.segment_start)?;
+    d.set_item("segment_end", c.segment_end)?;
+    Ok(d)
+}
+
+fn analysis_to_dict<'py>(py: Python<'py>, a: &Analysis) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new(py);
+    d.set_item("segment", &*a.segment)?;
+    d.set_item("preedit", &a.preedit)?;
+    Ok(d)
+}
+
+fn ui_to_dict<'py>(py: Python<'py>, ui: &UiState) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new(py);
+    d.set_item("raw_input", &ui.raw_input)?;
+    d.set_item("preedit", &ui.preedit)?;
+    d.set_item("segment", &*ui.segment)?;
+    d.set_item("caret", ui.caret)?;
+    d.set_item("confirm", ui.confirm)?;
+    d.set_item("confirm_text", &ui.confirm_text)?;
+    let candidates = ui.candidate_list.iter().map(|c| candidate_to_dict(py, c)).collect::<PyResult<Vec<_>>>()?;
+    d.set_item("candidate_list", candidates)?;
+    d.set_item("page", ui.page)?;
+    d.set_item("has_next_page", ui.has_next_page)?;
+    d.set_item("truncated", ui.truncated)?;
+    d.set_item("diagnostics", ui.diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>())?;
+    Ok(d)
+}
+
+#[pymodule]
+fn rime_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Engine>()?;
+    m.add_class::<Session>()?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! 经引擎运行 `scripts/` 下的示例脚本，并检查结果。
+
+use std::sync::Arc;
+
+use rime_core::{engine::Engine, key_event::InputEvent, session::Session};
+use rime_dict::TsvDictionary;
+use rime_pinyin::QuanpinPreeditor;
+use rime_script::{ScriptFilter, ScriptTranslator};
+
+const DICT: &str = "日\tri\t100\n如\tru\t90\n日期\triqi\t80\n";
+
+fn main() -> std::io::Result<()> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/scripts");
+    let date = Arc::new(ScriptTranslator::from_path(format!("{dir}/date.rhai"))?);
+    let strip = Arc::new(ScriptFilter::from_path(format!("{dir}/strip_comments.rhai"))?);
+    let dict = TsvDictionary::from_tsv_str(DICT)?;
+
+    // date.rhai：rq 的首选是今天的日期，其余输入不受影响
+    let engine = Engine::new(dict.clone(), QuanpinPreeditor::new()).translator(date.clone());
+    let top = engine.query("rq", 5);
+    println!("rq -> {:?}", top.iter().map(|c| c.text.as_str()).collect::<Vec<_>>());
+    assert!(top[0].text.len() == "2024-01-01".len() && top[0].text.as_bytes()[4] == b'-');
+    assert!(!engine.query("ri", 5).iter().any(|c| c.comment.as_deref() == Some("日期")));
+    let mut session = Session::new(engine);
+    session.handle_all("rq".chars().map(InputEvent::Char));
+    let (_, actions) = session.handle(InputEvent::Space);
+    println!("space -> {actions:?}");
+
+    // strip_comments.rhai：候选的注释（例如组句标记）被去掉
+    let plain = Engine::new(dict.clone(), QuanpinPreeditor::new());
+    assert!(plain.query("ri", 5).iter().any(|c| c.comment.is_some()));
+    let stripped = Engine::new(dict, QuanpinPreeditor::new()).filter(strip);
+    let ri = stripped.query("ri", 5);
+    println!("ri -> {:?}", ri.iter().map(|c| (c.text.as_str(), c.comment.as_deref())).collect::<Vec<_>>());
+    assert!(ri.iter().all(|c| c.comment.is_none()));
+
+    // 出错的脚本不影响会话：filter 原样返回，translator 不给出候选
+    let broken = Arc::new(ScriptFilter::from_source("fn filter(c) { c[100].text }")?);
+    let engine = Engine::new(TsvDictionary::from_tsv_str(DICT)?, QuanpinPreeditor::new()).filter(broken);
+    assert_eq!(engine.query("ri", 5).len(), 2);
+    let looping = Arc::new(ScriptTranslator::from_source("fn translate(input) { loop {} }")?);
+    let engine = Engine::new(TsvDictionary::from_tsv_str(DICT)?, QuanpinPreeditor::new()).translator(looping);
+    assert_eq!(engine.query("ri", 5).len(), 2);
+    assert!(ScriptFilter::from_source("fn translate(input) { [] }").is_err());
+    println!("ok");
+    Ok(())
+}
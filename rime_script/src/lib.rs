@@ -0,0 +1,206 @@
+//! `rime_script`：用 rhai 脚本写候选后处理与触发式候选，改动无需重新编译（整个 crate 需要 feature `rhai`）。
+//!
+//! - `ScriptFilter`：脚本定义 `filter(candidates)`，返回新的候选数组（可改写、删除、重排、增加）；以 `Engine::filter` 加入引擎
+//! - `ScriptTranslator`：脚本定义 `translate(input)`，按待选部分的输入给出候选（例如 `rq` 给出日期）；以 `Engine::translator` 加入引擎
+//!
+//! 脚本中的候选是对象 `#{ text, comment, weight }`，comment 为 `()` 或空串即没有注释。
+//! 除 rhai 标准库外，脚本可调用 `today()`，返回当天（UTC）的 `#{ year, month, day }`。
+//!
+//! 脚本出错（运行时错误、超出操作数上限、返回值不是数组）不影响会话：以 `tracing` 记录后跳过，
+//! filter 原样返回输入，translator 不给出候选；数组中不是候选的元素忽略。
+//!
+//! 示例脚本见 `scripts/`，经引擎运行：`cargo run --manifest-path rime_script/Cargo.toml --features rhai --example scripts`。
+#![cfg(feature = "rhai")]
+
+use std::{
+    fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rhai::{AST, Array, Dynamic, Engine as Rhai, INT, Map, Scope};
+use rime_core::{
+    filter::Filter,
+    model::{Candidate, CandidateSource},
+    translator::Translator,
+};
+
+/// 单次调用最多执行的操作数：脚本死循环时以错误结束，而不是卡住会话。
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// 编译好的脚本及其入口函数。
+struct Script {
+    engine: Rhai,
+    ast: AST,
+    /// 入口函数名（`filter` / `translate`）
+    entry: &'static str,
+}
+
+impl Script {
+    fn from_path(path: &Path, entry: &'static str) -> io::Result<Self> {
+        let source = fs::read_to_string(path)?;
+        Self::compile(&source, entry).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+
+    fn compile(source: &str, entry: &'static str) -> io::Result<Self> {
+        let mut engine = Rhai::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.register_fn("today", today);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("脚本解析失败：{e}")))?;
+        if !ast.iter_functions().any(|f| f.name == entry && f.params.len() == 1) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("脚本没有定义 `{entry}(..)`")));
+        }
+        Ok(Self { engine, ast, entry })
+    }
+
+    /// 调用入口函数；出错时记录并返回 None。
+    fn call(&self, arg: Dynamic) -> Option<Array> {
+        match self.engine.call_fn::<Array>(&mut Scope::new(), &self.ast, self.entry, (arg,)) {
+            Ok(out) => Some(out),
+            Err(e) => {
+                tracing::warn!(entry = self.entry, error = %e, "脚本出错，已跳过");
+                None
+            }
+        }
+    }
+}
+
+/// 脚本实现的候选后处理（`filter(candidates) -> candidates`）。
+///
+/// 返回的候选按文本对应回输入的候选，保留其范围与来源；新增的候选沿用首选的范围与来源。
+/// 改了 weight 的候选以新 weight 为分数；返回的顺序即最终顺序。
+pub struct ScriptFilter {
+    script: Script,
+}
+
+impl ScriptFilter {
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self { script: Script::from_path(path.as_ref(), "filter")? })
+    }
+
+    pub fn from_source(source: &str) -> io::Result<Self> {
+        Ok(Self { script: Script::compile(source, "filter")? })
+    }
+}
+
+impl Filter for ScriptFilter {
+    fn apply(&self, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        let Some(top) = candidates.first() else {
+            return candidates;
+        };
+        let top = Candidate { score: None, parts: Vec::new(), ..top.clone() };
+        let input: Array = candidates.iter().map(to_object).collect();
+        let Some(output) = self.script.call(Dynamic::from_array(input)) else {
+            return candidates;
+        };
+        let mut originals: Vec<Option<Candidate>> = candidates.into_iter().map(Some).collect();
+        output
+            .into_iter()
+            .filter_map(from_object)
+            .map(|(text, comment, weight)| {
+                let matched = originals.iter_mut().find(|c| c.as_ref().is_some_and(|c| c.text.as_str() == text)).and_then(Option::take);
+                let base = matched.unwrap_or_else(|| top.clone());
+                let score = match weight {
+                    Some(w) if w != base.weight => None,
+                    _ => base.score,
+                };
+                Candidate {
+                    text: text.into(),
+                    comment: comment.map(Into::into),
+                    weight: weight.unwrap_or(base.weight),
+                    score,
+                    ..base
+                }
+            })
+            .collect()
+    }
+}
+
+/// 脚本实现的翻译器（`translate(input) -> candidates`）：`input` 为候选范围内各段原样连接（例如 `rq`）。
+///
+/// 候选覆盖整个范围，来源为 `CandidateSource::Exact`，weight 省略时为 0。
+pub struct ScriptTranslator {
+    script: Script,
+}
+
+impl ScriptTranslator {
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self { script: Script::from_path(path.as_ref(), "translate")? })
+    }
+
+    pub fn from_source(source: &str) -> io::Result<Self> {
+        Ok(Self { script: Script::compile(source, "translate")? })
+    }
+}
+
+impl Translator for ScriptTranslator {
+    fn translate(&self, segments: &[String], start: usize, end: usize, limit: usize) -> Vec<Candidate> {
+        let input: String = segments[start..end].concat();
+        let Some(output) = self.script.call(Dynamic::from(input)) else {
+            return Vec::new();
+        };
+        output
+            .into_iter()
+            .filter_map(from_object)
+            .take(limit)
+            .enumerate()
+            .map(|(rank, (text, comment, weight))| Candidate {
+                text: text.into(),
+                comment: comment.map(Into::into),
+                weight: weight.unwrap_or(0),
+                score: None,
+                segment_start: start,
+                segment_end: end,
+                source: CandidateSource::Exact,
+                rank,
+                parts: Vec::new(),
+                meta: None,
+            })
+            .collect()
+    }
+}
+
+/// 候选 -> 脚本对象 `#{ text, comment, weight }`。
+fn to_object(c: &Candidate) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("text".into(), Dynamic::from(c.text.to_string()));
+    map.insert("comment".into(), c.comment.as_ref().map_or(Dynamic::UNIT, |s| Dynamic::from(s.to_string())));
+    map.insert("weight".into(), Dynamic::from(INT::from(c.weight)));
+    Dynamic::from_map(map)
+}
+
+/// 脚本对象 -> (text, comment, weight)；不是对象或 text 不是非空字符串时为 None。
+fn from_object(value: Dynamic) -> Option<(String, Option<String>, Option<i32>)> {
+    let map = value.try_cast::<Map>()?;
+    let text = map.get("text")?.clone().into_string().ok().filter(|t| !t.is_empty())?;
+    let comment = map.get("comment").and_then(|c| c.clone().into_string().ok()).filter(|c| !c.is_empty());
+    let weight = map.get("weight").and_then(|w| w.as_int().ok()).map(|w| w.clamp(INT::from(i32::MIN), INT::from(i32::MAX)) as i32);
+    Some((text, comment, weight))
+}
+
+/// 脚本函数 `today()`：当天（UTC）的 `#{ year, month, day }`。
+fn today() -> Map {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days(i64::try_from(secs / 86_400).unwrap_or(0));
+    let mut map = Map::new();
+    map.insert("year".into(), Dynamic::from(year));
+    map.insert("month".into(), Dynamic::from(month));
+    map.insert("day".into(), Dynamic::from(day));
+    map
+}
+
+/// 1970-01-01 起的天数 -> 公历 (年, 月, 日)。
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
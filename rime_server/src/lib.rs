@@ -0,0 +1,212 @@
+//! `rime_server`：多客户端会话服务。
+//!
+//! - 监听 unix socket 或 TCP，每个连接一个线程，协议见 `protocol`（与 `rime_cli serve` 相同）
+//! - 每个连接有独立的 `SessionManager`：会话按 (连接, 会话 id) 隔离，连接断开时一并销毁
+//! - 引擎（含词典）只创建一次，经 `Arc` 在所有连接与会话间共享
+//! - 任一客户端发送 `shutdown` 后停止接受新连接
+//! - `run_shared`：引擎可在运行中替换（热加载，见 `SharedEngine`），各连接在两个请求之间切换
+pub mod protocol;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+use std::{
+    io::{self, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use rime_core::{
+    dictionary::Dictionary,
+    engine::{Analyzer, Engine},
+    segmenter::Segmenter,
+    session_manager::SessionManager,
+};
+
+use protocol::Flow;
+
+/// 监听地址。
+#[derive(Debug, Clone)]
+pub enum Bind {
+    /// TCP，例如 `127.0.0.1:7878`
+    Tcp(String),
+    /// unix socket 路径
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+/// 双向字节流（TCP / unix socket）。
+trait Stream: Read + Write + Send {
+    fn try_clone_box(&self) -> io::Result<Box<dyn Stream>>;
+}
+
+impl Stream for TcpStream {
+    fn try_clone_box(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(Box::new(self.try_clone()?))
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn try_clone_box(&self) -> io::Result<Box<dyn Stream>> {
+        Ok(Box::new(self.try_clone()?))
+    }
+}
+
+impl Listener {
+    fn bind(bind: &Bind) -> io::Result<Self> {
+        match bind {
+            Bind::Tcp(addr) => Ok(Self::Tcp(TcpListener::bind(addr)?)),
+            #[cfg(unix)]
+            Bind::Unix(path) => {
+                // 上次异常退出残留的 socket 文件
+                if path.exists() && UnixStream::connect(path).is_err() {
+                    std::fs::remove_file(path)?;
+                }
+                Ok(Self::Unix(UnixListener::bind(path)?, path.clone()))
+            }
+        }
+    }
+
+    fn accept(&self) -> io::Result<Box<dyn Stream>> {
+        match self {
+            Self::Tcp(l) => Ok(Box::new(l.accept()?.0)),
+            #[cfg(unix)]
+            Self::Unix(l, _) => Ok(Box::new(l.accept()?.0)),
+        }
+    }
+
+    /// 连接自身一次，唤醒阻塞在 `accept` 上的主循环。
+    fn waker(&self) -> io::Result<Box<dyn Fn() + Send + Sync>> {
+        match self {
+            Self::Tcp(l) => {
+                let addr = l.local_addr()?;
+                Ok(Box::new(move || drop(TcpStream::connect(addr))))
+            }
+            #[cfg(unix)]
+            Self::Unix(_, path) => {
+                let path = path.clone();
+                Ok(Box::new(move || drop(UnixStream::connect(&path))))
+            }
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Self::Unix(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// 运行中可替换的引擎（见 `run_shared`）。
+///
+/// 替换后，各连接在处理下一个请求之前切换到新引擎（会话的输入状态保留），正在处理的请求仍用原引擎。
+pub struct SharedEngine<D, A> {
+    /// (版本号, 引擎)；每次替换版本号加一
+    current: Mutex<(u64, Arc<Engine<D, A>>)>,
+}
+
+impl<D, A> SharedEngine<D, A> {
+    pub fn new(engine: impl Into<Arc<Engine<D, A>>>) -> Self {
+        Self { current: Mutex::new((0, engine.into())) }
+    }
+
+    /// 替换引擎（例如重新加载词典后）。
+    pub fn replace(&self, engine: impl Into<Arc<Engine<D, A>>>) {
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        *current = (current.0 + 1, engine.into());
+    }
+
+    /// 当前的版本号与引擎。
+    pub fn current(&self) -> (u64, Arc<Engine<D, A>>) {
+        let current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        (current.0, Arc::clone(&current.1))
+    }
+}
+
+/// 运行服务直到收到 `shutdown`；所有会话共享 `engine`。
+///
+/// 单个连接的 I/O 错误或 panic 只影响该连接。
+pub fn run<D, A>(bind: &Bind, engine: Arc<Engine<D, A>>) -> io::Result<()>
+where
+    D: Dictionary + 'static,
+    A: Analyzer + Segmenter + 'static,
+{
+    run_shared(bind, Arc::new(SharedEngine::new(engine)))
+}
+
+/// 同 `run`，但引擎可经 `engine.replace` 在运行中替换；替换后各连接先发出 `reloaded` 通知（见 `protocol`）。
+pub fn run_shared<D, A>(bind: &Bind, engine: Arc<SharedEngine<D, A>>) -> io::Result<()>
+where
+    D: Dictionary + 'static,
+    A: Analyzer + Segmenter + 'static,
+{
+    let listener = Listener::bind(bind)?;
+    let wake: Arc<dyn Fn() + Send + Sync> = Arc::from(listener.waker()?);
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    while !shutdown.load(Ordering::SeqCst) {
+        let stream = match listener.accept() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("accept 失败：{e}");
+                continue;
+            }
+        };
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+        let (engine, shutdown, wake) = (Arc::clone(&engine), Arc::clone(&shutdown), Arc::clone(&wake));
+        thread::spawn(move || {
+            let (mut version, current) = engine.current();
+            let mut manager = SessionManager::new(current);
+            let reload = |manager: &mut SessionManager<D, A>| {
+                let (latest, current) = engine.current();
+                if latest == version {
+                    return false;
+                }
+                version = latest;
+                manager.set_engine(current);
+                true
+            };
+            match handle_connection(&mut manager, stream, reload) {
+                Ok(Flow::Shutdown) => {
+                    shutdown.store(true, Ordering::SeqCst);
+                    wake();
+                }
+                Ok(Flow::Continue) => {}
+                Err(e) => eprintln!("连接异常断开：{e}"),
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection<D, A>(
+    manager: &mut SessionManager<D, A>,
+    stream: Box<dyn Stream>,
+    reload: impl FnMut(&mut SessionManager<D, A>) -> bool,
+) -> io::Result<Flow>
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    let reader = BufReader::new(stream.try_clone_box()?);
+    protocol::serve_with(manager, reader, stream, reload)
+}
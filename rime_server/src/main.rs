@@ -0,0 +1,86 @@
+use std::{env, io, path::PathBuf, sync::Arc};
+
+use rime_core::{dictionary::Dictionary, engine::Engine};
+use rime_dict::{MappedDictionary, TsvDictionary};
+use rime_pinyin::{FuzzyRules, QuanpinPreeditor};
+use rime_server::{Bind, SharedEngine};
+
+fn main() -> io::Result<()> {
+    let mut bind = None;
+    let mut dict_paths = Vec::new();
+    let mut compiled = None;
+    let mut fuzzy = FuzzyRules::default();
+    let mut watch = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        let mut value = || args.next().ok_or_else(|| invalid_arg(format!("{a} 缺少参数值")));
+        match a.as_str() {
+            "--tcp" => bind = Some(Bind::Tcp(value()?)),
+            #[cfg(unix)]
+            "--unix" => bind = Some(Bind::Unix(PathBuf::from(value()?))),
+            "--dict" => dict_paths.push(PathBuf::from(value()?)),
+            "--compiled" => compiled = Some(PathBuf::from(value()?)),
+            "--fuzzy" => fuzzy = FuzzyRules::parse(&value()?).map_err(|e| invalid_arg(e.to_string()))?,
+            "--watch" if cfg!(feature = "watch") => watch = true,
+            "--help" | "-h" => print_help(),
+            _ => return Err(invalid_arg(format!("未知参数 `{a}`"))),
+        }
+    }
+    let bind = bind.ok_or_else(|| invalid_arg("需要 --unix <path> 或 --tcp <addr>".to_string()))?;
+    if let Some(path) = compiled {
+        if !dict_paths.is_empty() {
+            return Err(invalid_arg("--compiled 与 --dict 不能同时使用".to_string()));
+        }
+        let watched = watch.then(|| vec![path.clone()]);
+        return serve(&bind, watched, move || Ok(Engine::new(MappedDictionary::open(&path)?, QuanpinPreeditor::new().with_fuzzy(fuzzy.clone()))));
+    }
+    if dict_paths.is_empty() {
+        dict_paths.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../rime_cli/asset/dict.tsv"));
+    }
+
+    let watched = watch.then(|| dict_paths.clone());
+    serve(&bind, watched, move || {
+        let analyzer = QuanpinPreeditor::new().with_fuzzy(fuzzy.clone());
+        let dict = TsvDictionary::from_paths_with(&dict_paths, &analyzer)?;
+        Ok(Engine::new(dict, analyzer))
+    })
+}
+
+/// 以 `load` 构造引擎并运行服务；`watched` 为 Some 时，这些文件变化后再次 `load` 并替换引擎（失败时保留原引擎）。
+fn serve<D>(bind: &Bind, watched: Option<Vec<PathBuf>>, load: impl Fn() -> io::Result<Engine<D, QuanpinPreeditor>> + Send + 'static) -> io::Result<()>
+where
+    D: Dictionary + Send + Sync + 'static,
+{
+    let engine = Arc::new(SharedEngine::new(load()?));
+    #[cfg(feature = "watch")]
+    if let Some(paths) = watched {
+        let engine = Arc::clone(&engine);
+        std::thread::spawn(move || {
+            let watcher = rime_server::watch::FileWatcher::spawn(paths, std::time::Duration::from_millis(300));
+            while let Some(changed) = watcher.wait_changed() {
+                let names: Vec<_> = changed.iter().map(|p| p.display().to_string()).collect();
+                match load() {
+                    Ok(e) => {
+                        engine.replace(e);
+                        eprintln!("已重新加载：{}", names.join(", "));
+                    }
+                    Err(e) => eprintln!("重新加载失败，继续使用原词典：{e}"),
+                }
+            }
+        });
+    }
+    #[cfg(not(feature = "watch"))]
+    debug_assert!(watched.is_none(), "--watch 需要 feature watch");
+    eprintln!("rime_server 监听 {bind:?}");
+    rime_server::run_shared(bind, engine)
+}
+
+fn invalid_arg(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn print_help() -> ! {
+    println!("用法：rime_server (--unix <path> | --tcp <addr>) [--dict <path>... | --compiled <path>] [--fuzzy a=b,...] [--watch]\n协议：按行分隔的 JSON-RPC（同 rime_cli serve），另有 shutdown 方法停止服务\n--compiled：加载 rime_cli compile 生成的二进制词典（只读）\n--watch（需以 feature watch 构建）：词典文件变化后重新加载，各连接在下一个请求之前切换并收到 reloaded 通知");
+    std::process::exit(0);
+}
@@ -0,0 +1,186 @@
+//! 按行分隔的 JSON-RPC 协议（`rime_cli serve` 与 `rime_server` 共用）。
+//!
+//! 每行一个请求：`{"id": 1, "method": "process_key", "params": {"session": 1, "event": {"char": "n"}}}`；
+//! 每行一个响应：`{"jsonrpc": "2.0", "id": 1, "result": {...}}` 或 `{"jsonrpc": "2.0", "id": 1, "error": {"code": ..., "message": ...}}`。
+//!
+//! 方法：
+//! - `create_session` -> `{"session": id}`
+//! - `process_key {session, event}` -> `{"ui": UiState, "actions": [Action]}`
+//! - `get_ui {session}` -> `{"ui": UiState}`
+//! - `destroy_session {session}` -> `{"session": id}`
+//! - `shutdown` -> `{"shutdown": true}`：响应后停止服务
+//!
+//! 热加载（`--watch`）替换引擎后，下一个响应之前先发出一行通知 `{"jsonrpc": "2.0", "method": "reloaded"}`（没有 id）。
+//!
+//! 格式错误的请求只会得到错误响应，不会终止进程；超过 `MAX_LINE_LEN` 的行回复 `-32600` 后断开连接。
+use std::io::{self, BufRead, Read, Write};
+
+use rime_core::{
+    dictionary::Dictionary,
+    engine::Analyzer,
+    key_event::InputEvent,
+    segmenter::Segmenter,
+    session_manager::{SessionId, SessionManager},
+};
+use serde_json::{Value, json};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// 会话不存在
+const UNKNOWN_SESSION: i64 = -32000;
+
+/// 一行请求的最大字节数（不含换行）：不换行的输入最多读入这么多，不会无限占用内存。
+pub const MAX_LINE_LEN: usize = 64 * 1024;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// 处理完一个请求后服务的去向。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    /// 收到 `shutdown`
+    Shutdown,
+}
+
+/// 逐行读取请求并写出响应，直到 EOF 或 `shutdown`。
+pub fn serve<D, A>(manager: &mut SessionManager<D, A>, input: impl BufRead, output: impl Write) -> io::Result<Flow>
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    serve_with(manager, input, output, |_| false)
+}
+
+/// 同 `serve`，但每个请求之前先调用 `before`（在两个请求之间替换引擎）；`before` 返回 true 时先发出 `reloaded` 通知。
+///
+/// 某行超过 `MAX_LINE_LEN` 时回复 `INVALID_REQUEST`（id 为 null）后返回，不再读后面的输入。
+pub fn serve_with<D, A>(
+    manager: &mut SessionManager<D, A>,
+    mut input: impl BufRead,
+    mut output: impl Write,
+    mut before: impl FnMut(&mut SessionManager<D, A>) -> bool,
+) -> io::Result<Flow>
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        // 多读一个字节：读满仍没有换行即超长
+        let n = input.by_ref().take(MAX_LINE_LEN as u64 + 1).read_until(b'\n', &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if n > MAX_LINE_LEN && buf.last() != Some(&b'\n') {
+            let error = RpcError::new(INVALID_REQUEST, format!("请求超过 {MAX_LINE_LEN} 字节"));
+            writeln!(output, "{}", error_response(Value::Null, error))?;
+            output.flush()?;
+            return Ok(Flow::Continue);
+        }
+        let line = std::str::from_utf8(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let line = line.strip_suffix('\n').map_or(line, |l| l.strip_suffix('\r').unwrap_or(l));
+        if line.trim().is_empty() {
+            continue;
+        }
+        if before(manager) {
+            writeln!(output, "{}", json!({ "jsonrpc": "2.0", "method": "reloaded" }))?;
+        }
+        let (response, flow) = handle_request(manager, line);
+        writeln!(output, "{response}")?;
+        output.flush()?;
+        if flow == Flow::Shutdown {
+            return Ok(flow);
+        }
+    }
+    Ok(Flow::Continue)
+}
+
+/// 处理一行请求，返回响应对象与服务去向。
+pub fn handle_request<D, A>(manager: &mut SessionManager<D, A>, line: &str) -> (Value, Flow)
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    let request: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return (error_response(Value::Null, RpcError::new(PARSE_ERROR, format!("无法解析 JSON：{e}"))), Flow::Continue),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    if request.get("method").and_then(Value::as_str) == Some("shutdown") {
+        return (json!({ "jsonrpc": "2.0", "id": id, "result": { "shutdown": true } }), Flow::Shutdown);
+    }
+    let response = match dispatch(manager, &request) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error_response(id, e),
+    };
+    (response, Flow::Continue)
+}
+
+fn dispatch<D, A>(manager: &mut SessionManager<D, A>, request: &Value) -> Result<Value, RpcError>
+where
+    D: Dictionary,
+    A: Analyzer + Segmenter,
+{
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_REQUEST, "缺少 method"))?;
+    let params = request.get("params").unwrap_or(&Value::Null);
+
+    match method {
+        "create_session" => Ok(json!({ "session": manager.create() })),
+        "process_key" => {
+            let id = session_param(params)?;
+            let event: InputEvent = params
+                .get("event")
+                .cloned()
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "缺少 params.event"))
+                .and_then(|v| serde_json::from_value(v).map_err(|e| RpcError::new(INVALID_PARAMS, format!("无效的 event：{e}"))))?;
+            let (ui, actions) = manager.handle(id, event).ok_or_else(|| unknown_session(id))?;
+            Ok(json!({ "ui": ui, "actions": actions }))
+        }
+        "get_ui" => {
+            let id = session_param(params)?;
+            let ui = manager.ui_state(id).ok_or_else(|| unknown_session(id))?;
+            Ok(json!({ "ui": ui }))
+        }
+        "destroy_session" => {
+            let id = session_param(params)?;
+            if !manager.destroy(id) {
+                return Err(unknown_session(id));
+            }
+            Ok(json!({ "session": id }))
+        }
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("未知方法 `{method}`"))),
+    }
+}
+
+fn session_param(params: &Value) -> Result<SessionId, RpcError> {
+    params
+        .get("session")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "缺少 params.session（非负整数）"))
+}
+
+fn unknown_session(id: SessionId) -> RpcError {
+    RpcError::new(UNKNOWN_SESSION, format!("会话 {id} 不存在"))
+}
+
+fn error_response(id: Value, e: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } })
+}
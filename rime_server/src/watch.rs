@@ -0,0 +1,98 @@
+//! 文件变化监视（feature `watch`）：供 `rime_server --watch` 与 `rime_cli --watch` 热加载词典与配置。
+//!
+//! 只用 std：后台线程按固定间隔比较各文件的 (修改时间, 长度)，不依赖平台的文件通知。
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// 轮询间隔。
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 文件的 (修改时间, 长度)；文件不存在或读不到元数据时为 None。
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// 监视一组文件：有文件变化、且此后 `debounce` 内不再变化时，报告一次变化过的文件。
+///
+/// 编辑器保存时的连续写入（截断再写、写临时文件再改名）合并为一次；文件被删除或重新创建也算变化。
+/// drop 时停止后台线程。
+#[derive(Debug)]
+pub struct FileWatcher {
+    changed: Receiver<Vec<PathBuf>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    pub fn spawn(paths: Vec<PathBuf>, debounce: Duration) -> Self {
+        let (tx, changed) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        thread::spawn(move || {
+            let mut stamps: Vec<Stamp> = paths.iter().map(|p| stamp(p)).collect();
+            // 尚未报告的变化文件，及最近一次变化的时间
+            let mut pending: Vec<PathBuf> = Vec::new();
+            let mut last_change = Instant::now();
+            while !stopped.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+                for (path, old) in paths.iter().zip(&mut stamps) {
+                    let new = stamp(path);
+                    if new != *old {
+                        *old = new;
+                        last_change = Instant::now();
+                        if !pending.contains(path) {
+                            pending.push(path.clone());
+                        }
+                    }
+                }
+                if !pending.is_empty() && last_change.elapsed() >= debounce && tx.send(std::mem::take(&mut pending)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { changed, stop }
+    }
+
+    /// 尚未取走的变化文件（多次报告合并，不阻塞）；没有变化时为 None。
+    pub fn try_changed(&self) -> Option<Vec<PathBuf>> {
+        let mut out: Vec<PathBuf> = Vec::new();
+        while let Ok(batch) = self.changed.try_recv() {
+            merge(&mut out, batch);
+        }
+        (!out.is_empty()).then_some(out)
+    }
+
+    /// 阻塞直到有文件变化，返回变化过的文件（同 `try_changed`）；后台线程已退出时为 None。
+    pub fn wait_changed(&self) -> Option<Vec<PathBuf>> {
+        let mut out = self.changed.recv().ok()?;
+        if let Some(more) = self.try_changed() {
+            merge(&mut out, more);
+        }
+        Some(out)
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn merge(out: &mut Vec<PathBuf>, batch: Vec<PathBuf>) {
+    for path in batch {
+        if !out.contains(&path) {
+            out.push(path);
+        }
+    }
+}
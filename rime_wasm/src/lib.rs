@@ -0,0 +1,56 @@
+//! `rime_wasm`：wasm-bindgen 封装，供浏览器 demo 使用。
+//!
+//! 词典从字符串加载（浏览器里没有文件系统），按键名沿用 DOM `KeyboardEvent.key`。
+//! 示例页面见 `examples/index.html`。
+use rime_core::{
+    engine::Engine,
+    key_event::{Action, InputEvent},
+    session::Session,
+};
+use rime_dict::TsvDictionary;
+use rime_pinyin::QuanpinPreeditor;
+use wasm_bindgen::prelude::*;
+
+/// 一个输入会话（引擎 + 会话 + 待取走的上屏文本）。
+#[wasm_bindgen]
+pub struct WasmIme {
+    session: Session<TsvDictionary, QuanpinPreeditor>,
+    commits: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl WasmIme {
+    /// 从 TSV 文本创建（格式同 `TsvDictionary`）。
+    #[wasm_bindgen(constructor)]
+    pub fn new(dict_tsv: &str) -> Result<WasmIme, JsError> {
+        let dict = TsvDictionary::from_tsv_str(dict_tsv)?;
+        Ok(Self {
+            session: Session::new(Engine::new(dict, QuanpinPreeditor::new())),
+            commits: Vec::new(),
+        })
+    }
+
+    /// 处理一个按键（按键名见 `InputEvent::from_key_name`），返回最新 UiState（JS 对象）。
+    ///
+    /// 其他按键返回错误，调用方可交给浏览器默认处理。
+    pub fn process_key(&mut self, key: &str) -> Result<JsValue, JsError> {
+        let ev = InputEvent::from_key_name(key).ok_or_else(|| JsError::new(&format!("unsupported key `{key}`")))?;
+        let (ui, actions) = self.session.handle(ev);
+        for action in actions {
+            if let Action::Commit(text) = action {
+                self.commits.push(text);
+            }
+        }
+        Ok(serde_wasm_bindgen::to_value(&ui)?)
+    }
+
+    /// 当前 UiState（JS 对象）。
+    pub fn ui_state(&self) -> Result<JsValue, JsError> {
+        Ok(serde_wasm_bindgen::to_value(&self.session.ui_state())?)
+    }
+
+    /// 取走自上次调用以来的上屏文本。
+    pub fn commit_events(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.commits)
+    }
+}